@@ -0,0 +1,2387 @@
+;
+; 標準命令・関数などのデータベース (HSPがインストールされていない環境で使う)
+;
+; このファイルは `ham gen-builtin` で生成されたものです。直接編集しないでください。
+; hsphelp/*.hs のうち、標準命令などのヘルプファイルの %index, %prm, %inst, %note を抜き出しています。
+; (同梱のものは data/hsphelp/*.hs から `ham --hsp data gen-builtin > data/builtin.hs` で生成しています。)
+;
+
+%index
+M_PI
+円周率
+%note
+標準定義マクロ
+
+%index
+dir_cur
+カレントディレクトリ
+%note
+標準定義マクロ
+
+%index
+dir_exe
+実行ファイルがあるディレクトリ
+%note
+標準定義マクロ
+
+%index
+dir_win
+Windowsディレクトリ
+%note
+標準定義マクロ
+
+%index
+dir_sys
+Windowsのシステムディレクトリ
+%note
+標準定義マクロ
+
+%index
+dir_cmdline
+コマンドライン文字列
+%note
+標準定義マクロ
+
+%index
+dir_desktop
+デスクトップディレクトリ
+%note
+標準定義マクロ
+
+%index
+dir_mydoc
+マイドキュメントディレクトリ
+%note
+標準定義マクロ
+
+%index
+dir_tv
+HSPTVディレクトリ
+%note
+標準定義マクロ
+
+%index
+ginfo_mx
+スクリーン上のマウスカーソルX座標
+%note
+標準定義マクロ
+
+%index
+ginfo_my
+スクリーン上のマウスカーソルY座標
+%note
+標準定義マクロ
+
+%index
+ginfo_act
+アクティブなウィンドウID
+%note
+標準定義マクロ
+
+%index
+ginfo_sel
+操作先ウィンドウID
+%note
+標準定義マクロ
+
+%index
+ginfo_wx1
+ウィンドウの左上X座標
+%note
+標準定義マクロ
+
+%index
+ginfo_wy1
+ウィンドウの左上Y座標
+%note
+標準定義マクロ
+
+%index
+ginfo_wx2
+ウィンドウの右下X座標
+%note
+標準定義マクロ
+
+%index
+ginfo_wy2
+ウィンドウの右下Y座標
+%note
+標準定義マクロ
+
+%index
+ginfo_vx
+ウィンドウのスクロールX座標
+%note
+標準定義マクロ
+
+%index
+ginfo_vy
+ウィンドウのスクロールY座標
+%note
+標準定義マクロ
+
+%index
+ginfo_sizex
+ウィンドウ全体のXサイズ
+%note
+標準定義マクロ
+
+%index
+ginfo_sizey
+ウィンドウ全体のYサイズ
+%note
+標準定義マクロ
+
+%index
+ginfo_winx
+画面の描画エリアXサイズ
+%note
+標準定義マクロ
+
+%index
+ginfo_winy
+画面の描画エリアYサイズ
+%note
+標準定義マクロ
+
+%index
+ginfo_mesx
+メッセージの出力Xサイズ
+%note
+標準定義マクロ
+
+%index
+ginfo_mesy
+メッセージの出力Yサイズ
+%note
+標準定義マクロ
+
+%index
+ginfo_r
+現在設定されているカラーコード (R)
+%note
+標準定義マクロ
+
+%index
+ginfo_g
+現在設定されているカラーコード (G)
+%note
+標準定義マクロ
+
+%index
+ginfo_b
+現在設定されているカラーコード (B)
+%note
+標準定義マクロ
+
+%index
+ginfo_paluse
+デスクトップのカラーモード
+%note
+標準定義マクロ
+
+%index
+ginfo_dispx
+デスクトップ全体のXサイズ
+%note
+標準定義マクロ
+
+%index
+ginfo_dispy
+デスクトップ全体のYサイズ
+%note
+標準定義マクロ
+
+%index
+ginfo_cx
+カレントポジションのX座標
+%note
+標準定義マクロ
+
+%index
+ginfo_cy
+カレントポジションのY座標
+%note
+標準定義マクロ
+
+%index
+ginfo_intid
+メッセージ割り込み時のウィンドウID
+%note
+標準定義マクロ
+
+%index
+ginfo_newid
+未使用ウィンドウID
+%note
+標準定義マクロ
+
+%index
+ginfo_sx
+画面の初期化Xサイズ
+%note
+標準定義マクロ
+
+%index
+ginfo_sy
+画面の初期化Yサイズ
+%note
+標準定義マクロ
+
+%index
+objinfo_mode
+モード情報
+%prm
+(p1)
+%note
+標準定義マクロ
+
+%index
+objinfo_bmscr
+オブジェクトが配置されているBMSCR構造体のポインタ
+%prm
+(p1)
+%note
+標準定義マクロ
+
+%index
+objinfo_hwnd
+ウィンドウオブジェクトのハンドル
+%prm
+(p1)
+%note
+標準定義マクロ
+
+%index
+notemax
+メモリノートパッドの行数
+%note
+標準定義マクロ
+
+%index
+notesize
+メモリノートパッドの文字数
+%note
+標準定義マクロ
+
+%index
+msgothic
+ＭＳ ゴシック
+%note
+標準定義マクロ
+
+%index
+msmincho
+ＭＳ 明朝
+%note
+標準定義マクロ
+
+%index
+font_normal
+通常のスタイル
+%note
+標準定義マクロ
+
+%index
+font_bold
+太文字
+%note
+標準定義マクロ
+
+%index
+font_italic
+イタリック体
+%note
+標準定義マクロ
+
+%index
+font_underline
+下線
+%note
+標準定義マクロ
+
+%index
+font_strikeout
+打ち消し線
+%note
+標準定義マクロ
+
+%index
+font_antialias
+アンチエイリアス
+%note
+標準定義マクロ
+
+%index
+screen_normal
+通常のウィンドウ
+%note
+標準定義マクロ
+
+%index
+screen_palette
+パレットモード
+%note
+標準定義マクロ
+
+%index
+screen_hide
+非表示のウィンドウ
+%note
+標準定義マクロ
+
+%index
+screen_fixedsize
+サイズ固定ウィンドウ
+%note
+標準定義マクロ
+
+%index
+screen_tool
+ツールウィンドウ
+%note
+標準定義マクロ
+
+%index
+screen_frame
+深い縁のあるウィンドウ
+%note
+標準定義マクロ
+
+%index
+gmode_gdi
+通常のコピー
+%note
+標準定義マクロ
+
+%index
+gmode_mem
+メモリ間コピー
+%note
+標準定義マクロ
+
+%index
+gmode_rgb0
+透明色付きコピー
+%note
+標準定義マクロ
+
+%index
+gmode_alpha
+半透明合成コピー
+%note
+標準定義マクロ
+
+%index
+gmode_rgb0alpha
+透明色付き半透明合成コピー
+%note
+標準定義マクロ
+
+%index
+gmode_add
+色加算合成コピー
+%note
+標準定義マクロ
+
+%index
+gmode_sub
+色減算合成コピー
+%note
+標準定義マクロ
+
+%index
+gmode_pixela
+ピクセルアルファブレンドコピー
+%note
+標準定義マクロ
+
+%index
+switch
+比較ブロック開始
+%prm
+p1
+%note
+標準定義マクロ
+
+%index
+case
+比較値指定
+%prm
+p1
+%note
+標準定義マクロ
+
+%index
+default
+デフォルト比較指定
+%note
+標準定義マクロ
+
+%index
+swbreak
+比較実行脱出指定
+%note
+標準定義マクロ
+
+%index
+swend
+比較ブロック終了
+%note
+標準定義マクロ
+
+%index
+while
+while～wendの繰り返し
+%prm
+p1
+%note
+標準定義マクロ
+
+%index
+wend
+while～wendの繰り返し
+%note
+標準定義マクロ
+
+%index
+do
+do～untilの繰り返し
+%note
+標準定義マクロ
+
+%index
+until
+do～untilの繰り返し
+%prm
+p1
+%note
+標準定義マクロ
+
+%index
+for
+for～nextの繰り返し
+%prm
+p1, p2, p3, p4
+%note
+標準定義マクロ
+
+%index
+next
+for～nextの繰り返し
+%note
+標準定義マクロ
+
+%index
+_break
+ループから抜ける
+%note
+標準定義マクロ
+
+%index
+_continue
+ループをやり直す
+%note
+標準定義マクロ
+
+%index
+goto
+指定ラベルにジャンプ
+%prm
+*label
+%note
+標準命令
+
+%index
+gosub
+指定ラベルにサブルーチンジャンプ
+%prm
+*label
+%note
+標準命令
+
+%index
+return
+サブルーチンから復帰
+%prm
+p1
+%note
+標準命令
+
+%index
+end
+プログラム終了
+%prm
+p1
+%note
+標準命令
+
+%index
+stop
+プログラム中断
+%note
+標準命令
+
+%index
+wait
+実行を一定時間中断する
+%prm
+p1
+%note
+標準命令
+
+%index
+await
+一定の時間で待つ
+%prm
+p1
+%note
+標準命令
+
+%index
+repeat
+ループの始まりの場所を示す
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+loop
+ループの始まりに戻る
+%note
+標準命令
+
+%index
+break
+ループから抜ける
+%note
+標準命令
+
+%index
+continue
+ループをやり直す
+%prm
+p1
+%note
+標準命令
+
+%index
+foreach
+配列変数の要素数だけ繰り返す
+%prm
+p1
+%note
+標準命令
+
+%index
+if
+条件を満たしていればその行の命令を実行
+%prm
+p1
+%note
+標準命令
+
+%index
+else
+条件を満たしていなければその行の命令を実行
+%note
+標準命令
+
+%index
+on
+数値による分岐
+%prm
+p1 goto/gosub *label1, *label2, ...
+%note
+標準命令
+
+%index
+onexit
+終了時にジャンプ
+%prm
+goto/gosub *label
+%note
+標準命令
+
+%index
+onerror
+エラー発生時にジャンプ
+%prm
+goto/gosub *label
+%note
+標準命令
+
+%index
+onkey
+キー割り込み実行を指定
+%prm
+goto/gosub *label
+%note
+標準命令
+
+%index
+onclick
+クリック割り込み実行を指定
+%prm
+goto/gosub *label
+%note
+標準命令
+
+%index
+oncmd
+Windowsメッセージ割り込み実行を指定
+%prm
+goto/gosub *label, p1
+%note
+標準命令
+
+%index
+exgoto
+指定条件によるジャンプ
+%prm
+p1, p2, p3, *label
+%note
+標準命令
+
+%index
+run
+指定したファイルに制御を移す
+%prm
+"filename", "cmdline"
+%note
+標準命令
+
+%index
+exec
+Windowsのファイルを実行する
+%prm
+"filename", p1, "command"
+%note
+標準命令
+
+%index
+assert
+デバッグウィンドウ表示
+%prm
+p1
+%note
+標準命令
+
+%index
+logmes
+デバッグメッセージ送信
+%prm
+"message"
+%note
+標準命令
+
+%index
+randomize
+乱数発生の初期化
+%prm
+p1
+%note
+標準命令
+
+%index
+mref
+特殊なメモリを変数に割り当てる
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+dim
+配列変数を作成
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+sdim
+文字列型配列変数を作成
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+ddim
+実数型配列変数を作成
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+ldim
+ラベル型配列変数を作成
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+dimtype
+指定型の配列変数を作成
+%prm
+p1, p2, p3, p4, p5, p6
+%note
+標準命令
+
+%index
+newmod
+モジュール型変数の作成
+%prm
+p1, module, p2, ...
+%note
+標準命令
+
+%index
+delmod
+モジュール型変数の要素削除
+%prm
+p1
+%note
+標準命令
+
+%index
+dup
+クローン変数を作成
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+dupptr
+ポインタからクローン変数を作成
+%prm
+p1, p2, p3, p4
+%note
+標準命令
+
+%index
+mcall
+メソッドの呼び出し
+%prm
+p1, p2, p3, ...
+%note
+標準命令
+
+%index
+newcom
+COMオブジェクト型変数の新規作成
+%prm
+p1, "interface", p2, p3
+%note
+標準命令
+
+%index
+delcom
+COMオブジェクト型変数の破棄
+%prm
+p1
+%note
+標準命令
+
+%index
+querycom
+COMオブジェクト型変数の作成
+%prm
+p1, p2, "interface"
+%note
+標準命令
+
+%index
+comres
+メソッド返値代入変数を設定
+%prm
+p1
+%note
+標準命令
+
+%index
+comevent
+COMイベントの取得開始
+%prm
+p1, p2, "IID", *label
+%note
+標準命令
+
+%index
+comevarg
+COMイベントのパラメーターを取得
+%prm
+p1, p2, p3, p4
+%note
+標準命令
+
+%index
+sarrayconv
+Variant型との一括変換を行なう
+%prm
+p1, p2, p3, p4
+%note
+標準命令
+
+%index
+poke
+バッファに1byte書き込む
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+wpoke
+バッファに2byte書き込む
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+lpoke
+バッファに4byte書き込む
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+memset
+メモリブロックのクリア
+%prm
+p1, p2, p3, p4
+%note
+標準命令
+
+%index
+memcpy
+メモリブロックのコピー
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+memexpand
+メモリブロックの再確保
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+alloc
+バッファを確保
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+getstr
+バッファから文字列読み出し
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+strrep
+文字列の置換
+%prm
+p1, "search", "replace"
+%note
+標準命令
+
+%index
+split
+文字列から分割された要素を代入
+%prm
+p1, "string", p2, ...
+%note
+標準命令
+
+%index
+cnvstow
+通常文字列をunicodeに変換
+%prm
+p1, "string"
+%note
+標準命令
+
+%index
+cnvstoa
+通常文字列をANSI文字列に変換
+%prm
+p1, "string"
+%note
+標準命令
+
+%index
+noteadd
+指定行の追加・変更
+%prm
+"string", p1, p2
+%note
+標準命令
+
+%index
+notedel
+行の削除
+%prm
+p1
+%note
+標準命令
+
+%index
+noteget
+指定行を読み込み
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+notesel
+対象バッファ指定
+%prm
+p1
+%note
+標準命令
+
+%index
+noteunsel
+対象バッファの復帰
+%note
+標準命令
+
+%index
+noteload
+対象バッファ読み込み
+%prm
+"filename", p1
+%note
+標準命令
+
+%index
+notesave
+対象バッファ保存
+%prm
+"filename"
+%note
+標準命令
+
+%index
+setease
+イージング関数の計算式を設定
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+sortval
+配列変数を数値でソート
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+sortstr
+配列変数を文字列でソート
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+sortnote
+メモリノートパッドをソート
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+sortget
+ソート元のインデックスを取得
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+exist
+ファイルのサイズ取得
+%prm
+"filename"
+%note
+標準命令
+
+%index
+delete
+ファイル削除
+%prm
+"filename"
+%note
+標準命令
+
+%index
+mkdir
+ディレクトリ作成
+%prm
+"dirname"
+%note
+標準命令
+
+%index
+chdir
+ディレクトリ移動
+%prm
+"dirname"
+%note
+標準命令
+
+%index
+dirlist
+ディレクトリ一覧を取得
+%prm
+p1, "filemask", p2
+%note
+標準命令
+
+%index
+bload
+バッファにファイルをロード
+%prm
+"filename", p1, p2, p3
+%note
+標準命令
+
+%index
+bsave
+バッファをファイルにセーブ
+%prm
+"filename", p1, p2, p3
+%note
+標準命令
+
+%index
+bcopy
+ファイルのコピー
+%prm
+"filename1", "filename2"
+%note
+標準命令
+
+%index
+memfile
+メモリストリーム設定
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+chdpm
+DPMファイル設定
+%prm
+"dpmfile", p1
+%note
+標準命令
+
+%index
+mes
+メッセージ表示
+%prm
+"message", sw
+%note
+標準命令
+
+%index
+print
+メッセージ表示
+%prm
+"message", sw
+%note
+標準命令
+
+%index
+pos
+カレントポジション設定
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+color
+カラー設定
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+palcolor
+描画パレット設定
+%prm
+p1
+%note
+標準命令
+
+%index
+hsvcolor
+HSV形式でカラーを設定する
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+rgbcolor
+RGB形式でカラーを設定する
+%prm
+p1
+%note
+標準命令
+
+%index
+syscolor
+システムカラーを設定する
+%prm
+p1
+%note
+標準命令
+
+%index
+sysfont
+システムフォント選択
+%prm
+p1
+%note
+標準命令
+
+%index
+font
+フォント設定
+%prm
+"fontname", p1, p2, p3
+%note
+標準命令
+
+%index
+cls
+画面クリア
+%prm
+p1
+%note
+標準命令
+
+%index
+boxf
+矩形を塗りつぶす
+%prm
+p1, p2, p3, p4
+%note
+標準命令
+
+%index
+line
+直線を描画
+%prm
+p1, p2, p3, p4
+%note
+標準命令
+
+%index
+circle
+円を描画
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+pset
+1dotの点を表示
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+pget
+1dotの色を取得
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+screen
+ウィンドウを初期化
+%prm
+p1, p2, p3, p4, p5, p6, p7, p8
+%note
+標準命令
+
+%index
+bgscr
+枠のないウィンドウを初期化
+%prm
+p1, p2, p3, p4, p5, p6, p7, p8
+%note
+標準命令
+
+%index
+buffer
+仮想画面を初期化
+%prm
+p1, p2, p3, p4
+%note
+標準命令
+
+%index
+gsel
+描画先指定、ウィンドウ最前面、非表示設定
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+gmode
+画面コピーモード設定
+%prm
+p1, p2, p3, p4
+%note
+標準命令
+
+%index
+gcopy
+画面コピー
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+gzoom
+変倍して画面コピー
+%prm
+p1, p2, p3, p4, p5, p6, p7, p8
+%note
+標準命令
+
+%index
+grotate
+矩形画像を回転してコピー
+%prm
+p1, p2, p3, p4, p5, p6
+%note
+標準命令
+
+%index
+grect
+回転する矩形で塗りつぶす
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+gsquare
+任意の四角形を描画
+%prm
+p1, p2, p3, p4
+%note
+標準命令
+
+%index
+gradf
+矩形をグラデーションで塗りつぶす
+%prm
+p1, p2, p3, p4, p5, p6, p7
+%note
+標準命令
+
+%index
+groll
+ウィンドウの描画基点を設定
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+picload
+画像ファイルをロード
+%prm
+"filename", p1
+%note
+標準命令
+
+%index
+bmpsave
+画面イメージセーブ
+%prm
+"filename"
+%note
+標準命令
+
+%index
+celload
+画像ファイルをバッファにロード
+%prm
+"filename", p1, p2
+%note
+標準命令
+
+%index
+celdiv
+画像素材の分割サイズを設定
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+celput
+画像素材を描画
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+celbitmap
+ビットマップ情報を画像バッファに適用する
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+palette
+パレット設定
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+redraw
+再描画の設定
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+title
+タイトルバー設定
+%prm
+"string"
+%note
+標準命令
+
+%index
+width
+ウィンドウサイズ設定
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+chgdisp
+画面解像度を変更する
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+mouse
+マウスカーソル座標設定
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+stick
+キー入力情報取得
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+getkey
+キー入力チェック
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+dialog
+ダイアログを開く
+%prm
+"message", p1, "option"
+%note
+標準命令
+
+%index
+mmload
+サウンドファイル読み込み
+%prm
+"filename", p1, p2
+%note
+標準命令
+
+%index
+mmplay
+サウンド再生
+%prm
+p1
+%note
+標準命令
+
+%index
+mmstop
+サウンド停止
+%prm
+p1
+%note
+標準命令
+
+%index
+mmvol
+音量の設定
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+mmpan
+パンニングの設定
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+mmstat
+メディアの状態取得
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+mci
+MCIにコマンドを送る
+%prm
+"command"
+%note
+標準命令
+
+%index
+objsize
+オブジェクトサイズ設定
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+objsel
+オブジェクトに入力フォーカスを設定
+%prm
+p1
+%note
+標準命令
+
+%index
+objenable
+オブジェクトの有効・無効を設定
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+objskip
+オブジェクトのフォーカス移動モードを設定
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+objprm
+オブジェクトの内容を変更
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+objmode
+オブジェクトモード設定
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+objcolor
+オブジェクトのカラー設定
+%prm
+p1, p2, p3
+%note
+標準命令
+
+%index
+objimage
+カスタムボタンの設定
+%prm
+p1, p2, p3, p4, p5, p6, p7, p8
+%note
+標準命令
+
+%index
+clrobj
+オブジェクトをクリア
+%prm
+p1, p2
+%note
+標準命令
+
+%index
+button
+ボタン表示
+%prm
+goto/gosub "name", *label
+%note
+標準命令
+
+%index
+input
+入力ボックス表示
+%prm
+p1, p2, p3, p4
+%note
+標準命令
+
+%index
+mesbox
+メッセージボックス表示
+%prm
+p1, p2, p3, p4, p5
+%note
+標準命令
+
+%index
+chkbox
+チェックボックス表示
+%prm
+"strings", p1
+%note
+標準命令
+
+%index
+combox
+コンボボックス表示
+%prm
+p1, p2, "strings"
+%note
+標準命令
+
+%index
+listbox
+リストボックス表示
+%prm
+p1, p2, "strings"
+%note
+標準命令
+
+%index
+axobj
+ActiveXコントロールの配置
+%prm
+p1, "IID", p2, p3
+%note
+標準命令
+
+%index
+winobj
+ウィンドウオブジェクトの配置
+%prm
+"class", "title", p1, p2, p3, p4, p5, p6
+%note
+標準命令
+
+%index
+sendmsg
+ウィンドウメッセージの送信
+%prm
+p1, p2, p3, p4
+%note
+標準命令
+
+%index
+int
+整数値に変換
+%prm
+(p1)
+%note
+標準関数
+
+%index
+double
+実数値に変換
+%prm
+(p1)
+%note
+標準関数
+
+%index
+str
+文字列に変換
+%prm
+(p1)
+%note
+標準関数
+
+%index
+strlen
+文字列の長さを調べる
+%prm
+(p1)
+%note
+標準関数
+
+%index
+strmid
+文字列の一部を取り出す
+%prm
+(p1, p2, p3)
+%note
+標準関数
+
+%index
+instr
+文字列の検索をする
+%prm
+(p1, p2, "search")
+%note
+標準関数
+
+%index
+strf
+書式付き文字列を変換
+%prm
+("format", p1, ...)
+%note
+標準関数
+
+%index
+strtrim
+指定した文字だけを取り除く
+%prm
+(p1, p2, p3)
+%note
+標準関数
+
+%index
+getpath
+パスの一部を取得
+%prm
+("filename", p1)
+%note
+標準関数
+
+%index
+cnvwtos
+unicodeを通常文字列に変換
+%prm
+(p1)
+%note
+標準関数
+
+%index
+cnvatos
+ANSI文字列を通常文字列に変換
+%prm
+(p1)
+%note
+標準関数
+
+%index
+peek
+バッファから1byte読み出し
+%prm
+(p1, p2)
+%note
+標準関数
+
+%index
+wpeek
+バッファから2byte読み出し
+%prm
+(p1, p2)
+%note
+標準関数
+
+%index
+lpeek
+バッファから4byte読み出し
+%prm
+(p1, p2)
+%note
+標準関数
+
+%index
+varptr
+変数データのポインタを返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+varuse
+変数の使用状況を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+vartype
+変数の型を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+varsize
+変数データのバッファサイズを返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+length
+配列の1次元要素数を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+length2
+配列の2次元要素数を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+length3
+配列の3次元要素数を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+length4
+配列の4次元要素数を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+libptr
+外部呼び出しコマンドの情報アドレスを得る
+%prm
+(p1)
+%note
+標準関数
+
+%index
+callfunc
+外部関数の呼び出し
+%prm
+(p1, p2, p3)
+%note
+標準関数
+
+%index
+comevdisp
+COMイベントの内容を確認
+%prm
+(p1)
+%note
+標準関数
+
+%index
+abs
+整数の絶対値を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+absf
+実数の絶対値を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+sin
+サイン値を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+cos
+コサイン値を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+tan
+タンジェント値を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+atan
+アークタンジェント値を返す
+%prm
+(p1, p2)
+%note
+標準関数
+
+%index
+sqrt
+ルート値を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+expf
+指数を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+logf
+対数を返す
+%prm
+(p1)
+%note
+標準関数
+
+%index
+powf
+累乗(べき乗)を求める
+%prm
+(p1, p2)
+%note
+標準関数
+
+%index
+limit
+一定範囲内の整数を返す
+%prm
+(p1, p2, p3)
+%note
+標準関数
+
+%index
+limitf
+一定範囲内の実数を返す
+%prm
+(p1, p2, p3)
+%note
+標準関数
+
+%index
+rnd
+乱数を発生
+%prm
+(p1)
+%note
+標準関数
+
+%index
+getease
+イージング値を整数で取得
+%prm
+(p1, p2)
+%note
+標準関数
+
+%index
+geteasef
+イージング値を実数で取得
+%prm
+(p1, p2)
+%note
+標準関数
+
+%index
+gettime
+時間・日付を取得する
+%prm
+(p1)
+%note
+標準関数
+
+%index
+ginfo
+ウィンドウ情報の取得
+%prm
+(p1)
+%note
+標準関数
+
+%index
+objinfo
+ウィンドウオブジェクト情報の取得
+%prm
+(p1, p2)
+%note
+標準関数
+
+%index
+sysinfo
+システム情報の取得
+%prm
+(p1)
+%note
+標準関数
+
+%index
+dirinfo
+ディレクトリ情報の取得
+%prm
+(p1)
+%note
+標準関数
+
+%index
+noteinfo
+メモリノートパッド情報取得
+%prm
+(p1)
+%note
+標準関数
+
+%index
+notefind
+メモリノートパッド検索
+%prm
+("search", p1)
+%note
+標準関数
+
+%index
+#include
+別ファイルを結合
+%prm
+"filename"
+%note
+プリプロセッサ命令
+
+%index
+#addition
+別ファイルを結合 (ファイルがなければ無視)
+%prm
+"filename"
+%note
+プリプロセッサ命令
+
+%index
+#define
+新規マクロを登録する
+%prm
+マクロ名 マクロ定義
+%note
+プリプロセッサ命令
+
+%index
+#undef
+マクロ名の取り消し
+%prm
+マクロ名
+%note
+プリプロセッサ命令
+
+%index
+#const
+マクロ名の定数定義
+%prm
+マクロ名 定数式
+%note
+プリプロセッサ命令
+
+%index
+#enum
+マクロ名の列挙定義
+%prm
+マクロ名 = p1
+%note
+プリプロセッサ命令
+
+%index
+#if
+数値からコンパイル制御
+%prm
+p1
+%note
+プリプロセッサ命令
+
+%index
+#ifdef
+マクロ定義からコンパイル制御
+%prm
+マクロ名
+%note
+プリプロセッサ命令
+
+%index
+#ifndef
+マクロ定義からコンパイル制御 (否定)
+%prm
+マクロ名
+%note
+プリプロセッサ命令
+
+%index
+#else
+コンパイル制御を反転
+%note
+プリプロセッサ命令
+
+%index
+#endif
+コンパイル制御ブロック終了
+%note
+プリプロセッサ命令
+
+%index
+#module
+モジュールの開始
+%prm
+モジュール名 変数名1, …
+%note
+プリプロセッサ命令
+
+%index
+#global
+モジュールの終了
+%note
+プリプロセッサ命令
+
+%index
+#deffunc
+新規命令を登録する
+%prm
+命令名 p1, …
+%note
+プリプロセッサ命令
+
+%index
+#defcfunc
+新規関数を登録する
+%prm
+関数名 p1, …
+%note
+プリプロセッサ命令
+
+%index
+#modfunc
+モジュール変数を処理する命令を登録
+%prm
+命令名 p1, …
+%note
+プリプロセッサ命令
+
+%index
+#modcfunc
+モジュール変数を処理する関数を登録
+%prm
+関数名 p1, …
+%note
+プリプロセッサ命令
+
+%index
+#modinit
+モジュール初期化処理の登録
+%prm
+p1, …
+%note
+プリプロセッサ命令
+
+%index
+#modterm
+モジュール解放処理の登録
+%note
+プリプロセッサ命令
+
+%index
+#uselib
+外部DLLの指定
+%prm
+"ファイル名"
+%note
+プリプロセッサ命令
+
+%index
+#func
+外部DLL呼び出し命令登録
+%prm
+新規名 "関数名" p1, …
+%note
+プリプロセッサ命令
+
+%index
+#cfunc
+外部DLL呼び出し関数登録
+%prm
+新規名 "関数名" p1, …
+%note
+プリプロセッサ命令
+
+%index
+#usecom
+外部COMインターフェースの指定
+%prm
+インターフェース名 "インターフェースIID" "クラスIID"
+%note
+プリプロセッサ命令
+
+%index
+#comfunc
+外部COM呼び出し命令登録
+%prm
+新規名 インデックス p1, …
+%note
+プリプロセッサ命令
+
+%index
+#regcmd
+拡張プラグインの登録
+%prm
+"初期化関数名", "DLLファイル名", 変数型登録数
+%note
+プリプロセッサ命令
+
+%index
+#cmd
+拡張キーワードの登録
+%prm
+新規名 サブID
+%note
+プリプロセッサ命令
+
+%index
+#runtime
+ランタイムファイルの設定
+%prm
+"ランタイム名"
+%note
+プリプロセッサ命令
+
+%index
+#packopt
+自動作成オプション指定
+%prm
+キーワード名 パラメーター
+%note
+プリプロセッサ命令
+
+%index
+#pack
+PACKFILE追加ファイル指定
+%prm
+"filename"
+%note
+プリプロセッサ命令
+
+%index
+#epack
+PACKFILE暗号化ファイル指定
+%prm
+"filename"
+%note
+プリプロセッサ命令
+
+%index
+#cmpopt
+コンパイル時の設定
+%prm
+オプション名 パラメーター
+%note
+プリプロセッサ命令
+
+%index
+#bootopt
+ランタイム起動設定
+%prm
+オプション名 パラメーター
+%note
+プリプロセッサ命令
+
+%index
+hspstat
+HSPランタイムの情報
+%note
+システム変数
+
+%index
+hspver
+HSPのバージョン番号
+%note
+システム変数
+
+%index
+cnt
+repeat～loopのカウンター
+%note
+システム変数
+
+%index
+err
+HSPのエラーコード
+%note
+システム変数
+
+%index
+stat
+命令の実行結果
+%note
+システム変数
+
+%index
+mousex
+マウスカーソルのX座標
+%note
+システム変数
+
+%index
+mousey
+マウスカーソルのY座標
+%note
+システム変数
+
+%index
+mousew
+マウスカーソルのホイール値
+%note
+システム変数
+
+%index
+strsize
+読み込み・取得した文字列のサイズ
+%note
+システム変数
+
+%index
+refstr
+命令の実行結果 (文字列)
+%note
+システム変数
+
+%index
+refdval
+命令の実行結果 (実数)
+%note
+システム変数
+
+%index
+looplev
+repeat～loopのネストレベル
+%note
+システム変数
+
+%index
+sublev
+サブルーチンのネストレベル
+%note
+システム変数
+
+%index
+iparam
+割り込み要因を示す値
+%note
+システム変数
+
+%index
+wparam
+割り込み時に保存されるWindowsのパラメーター (wParam)
+%note
+システム変数
+
+%index
+lparam
+割り込み時に保存されるWindowsのパラメーター (lParam)
+%note
+システム変数
+
+%index
+hwnd
+現在のウィンドウハンドル
+%note
+システム変数
+
+%index
+hinstance
+現在のインスタンスハンドル
+%note
+システム変数
+
+%index
+hdc
+現在のデバイスコンテキスト
+%note
+システム変数
+
+%index
+thismod
+現在有効なモジュール変数
+%note
+システム変数
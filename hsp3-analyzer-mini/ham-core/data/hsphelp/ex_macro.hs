@@ -0,0 +1,332 @@
+;
+;	W`}N (hspdef.as) ̃wv\[Xt@C (̃f[^x[X̌f[^)
+;	hsphelp ƓŁA%index, %prm ƍڂ̎ (%note) Ă܂B
+;	`ham --hsp data gen-builtin > data/builtin.hs`  data/builtin.hs 𐶐܂B
+;
+
+%note
+W`}N
+
+%index
+M_PI
+~
+
+%index
+dir_cur
+JgfBNg
+
+%index
+dir_exe
+st@CfBNg
+
+%index
+dir_win
+WindowsfBNg
+
+%index
+dir_sys
+Windows̃VXefBNg
+
+%index
+dir_cmdline
+R}hC
+
+%index
+dir_desktop
+fXNgbvfBNg
+
+%index
+dir_mydoc
+}ChLgfBNg
+
+%index
+dir_tv
+HSPTVfBNg
+
+%index
+ginfo_mx
+XN[̃}EXJ[\XW
+
+%index
+ginfo_my
+XN[̃}EXJ[\YW
+
+%index
+ginfo_act
+ANeBuȃEBhEID
+
+%index
+ginfo_sel
+EBhEID
+
+%index
+ginfo_wx1
+EBhE̍XW
+
+%index
+ginfo_wy1
+EBhE̍YW
+
+%index
+ginfo_wx2
+EBhẺEXW
+
+%index
+ginfo_wy2
+EBhẺEYW
+
+%index
+ginfo_vx
+EBhẼXN[XW
+
+%index
+ginfo_vy
+EBhẼXN[YW
+
+%index
+ginfo_sizex
+EBhEŜXTCY
+
+%index
+ginfo_sizey
+EBhEŜYTCY
+
+%index
+ginfo_winx
+ʂ̕`GAXTCY
+
+%index
+ginfo_winy
+ʂ̕`GAYTCY
+
+%index
+ginfo_mesx
+bZ[W̏oXTCY
+
+%index
+ginfo_mesy
+bZ[W̏oYTCY
+
+%index
+ginfo_r
+ݐݒ肳ĂJ[R[h (R)
+
+%index
+ginfo_g
+ݐݒ肳ĂJ[R[h (G)
+
+%index
+ginfo_b
+ݐݒ肳ĂJ[R[h (B)
+
+%index
+ginfo_paluse
+fXNgbṽJ[[h
+
+%index
+ginfo_dispx
+fXNgbvŜXTCY
+
+%index
+ginfo_dispy
+fXNgbvŜYTCY
+
+%index
+ginfo_cx
+Jg|WVXW
+
+%index
+ginfo_cy
+Jg|WVYW
+
+%index
+ginfo_intid
+bZ[W荞ݎ̃EBhEID
+
+%index
+ginfo_newid
+gpEBhEID
+
+%index
+ginfo_sx
+ʂ̏XTCY
+
+%index
+ginfo_sy
+ʂ̏YTCY
+
+%index
+objinfo_mode
+[h
+%prm
+(p1)
+
+%index
+objinfo_bmscr
+IuWFNgzuĂBMSCR\̂̃|C^
+%prm
+(p1)
+
+%index
+objinfo_hwnd
+EBhEIuWFNg̃nh
+%prm
+(p1)
+
+%index
+notemax
+m[gpbh̍s
+
+%index
+notesize
+m[gpbh̕
+
+%index
+msgothic
+lr SVbN
+
+%index
+msmincho
+lr 
+
+%index
+font_normal
+ʏ̃X^C
+
+%index
+font_bold
+
+
+%index
+font_italic
+C^bN
+
+%index
+font_underline
+
+
+%index
+font_strikeout
+ł
+
+%index
+font_antialias
+A`GCAX
+
+%index
+screen_normal
+ʏ̃EBhE
+
+%index
+screen_palette
+pbg[h
+
+%index
+screen_hide
+\̃EBhE
+
+%index
+screen_fixedsize
+TCYŒEBhE
+
+%index
+screen_tool
+c[EBhE
+
+%index
+screen_frame
+[̂EBhE
+
+%index
+gmode_gdi
+ʏ̃Rs[
+
+%index
+gmode_mem
+ԃRs[
+
+%index
+gmode_rgb0
+FtRs[
+
+%index
+gmode_alpha
+Rs[
+
+%index
+gmode_rgb0alpha
+FtRs[
+
+%index
+gmode_add
+FZRs[
+
+%index
+gmode_sub
+FZRs[
+
+%index
+gmode_pixela
+sNZAt@uhRs[
+
+%index
+switch
+rubNJn
+%prm
+p1
+
+%index
+case
+rlw
+%prm
+p1
+
+%index
+default
+ftHgrw
+
+%index
+swbreak
+rsEow
+
+%index
+swend
+rubNI
+
+%index
+while
+while`wenďJԂ
+%prm
+p1
+
+%index
+wend
+while`wenďJԂ
+
+%index
+do
+do`untiľJԂ
+
+%index
+until
+do`untiľJԂ
+%prm
+p1
+
+%index
+for
+for`nexťJԂ
+%prm
+p1, p2, p3, p4
+
+%index
+next
+for`nexťJԂ
+
+%index
+_break
+[v甲
+
+%index
+_continue
+[v蒼
@@ -0,0 +1,874 @@
+;
+;	W ̃wv\[Xt@C (̃f[^x[X̌f[^)
+;	hsphelp ƓŁA%index, %prm ƍڂ̎ (%note) Ă܂B
+;	`ham --hsp data gen-builtin > data/builtin.hs`  data/builtin.hs 𐶐܂B
+;
+
+%note
+W
+
+%index
+goto
+w胉xɃWv
+%prm
+*label
+
+%index
+gosub
+w胉xɃTu[`Wv
+%prm
+*label
+
+%index
+return
+Tu[`畜A
+%prm
+p1
+
+%index
+end
+vOI
+%prm
+p1
+
+%index
+stop
+vOf
+
+%index
+wait
+s莞Ԓf
+%prm
+p1
+
+%index
+await
+̎Ԃő҂
+%prm
+p1
+
+%index
+repeat
+[v̎n܂̏ꏊ
+%prm
+p1, p2
+
+%index
+loop
+[v̎n܂ɖ߂
+
+%index
+break
+[v甲
+
+%index
+continue
+[v蒼
+%prm
+p1
+
+%index
+foreach
+zϐ̗vfJԂ
+%prm
+p1
+
+%index
+if
+𖞂Ă΂̍s̖߂s
+%prm
+p1
+
+%index
+else
+𖞂ĂȂ΂̍s̖߂s
+
+%index
+on
+lɂ镪
+%prm
+p1 goto/gosub *label1, *label2, ...
+
+%index
+onexit
+IɃWv
+%prm
+goto/gosub *label
+
+%index
+onerror
+G[ɃWv
+%prm
+goto/gosub *label
+
+%index
+onkey
+L[荞ݎsw
+%prm
+goto/gosub *label
+
+%index
+onclick
+NbN荞ݎsw
+%prm
+goto/gosub *label
+
+%index
+oncmd
+WindowsbZ[W荞ݎsw
+%prm
+goto/gosub *label, p1
+
+%index
+exgoto
+wɂWv
+%prm
+p1, p2, p3, *label
+
+%index
+run
+w肵t@Cɐڂ
+%prm
+"filename", "cmdline"
+
+%index
+exec
+Windows̃t@Cs
+%prm
+"filename", p1, "command"
+
+%index
+assert
+fobOEBhE\
+%prm
+p1
+
+%index
+logmes
+fobObZ[WM
+%prm
+"message"
+
+%index
+randomize
+̏
+%prm
+p1
+
+%index
+mref
+ȃϐɊ蓖Ă
+%prm
+p1, p2
+
+%index
+dim
+zϐ쐬
+%prm
+p1, p2, p3, p4, p5
+
+%index
+sdim
+^zϐ쐬
+%prm
+p1, p2, p3, p4, p5
+
+%index
+ddim
+^zϐ쐬
+%prm
+p1, p2, p3, p4, p5
+
+%index
+ldim
+x^zϐ쐬
+%prm
+p1, p2, p3, p4, p5
+
+%index
+dimtype
+w^̔zϐ쐬
+%prm
+p1, p2, p3, p4, p5, p6
+
+%index
+newmod
+W[^ϐ̍쐬
+%prm
+p1, module, p2, ...
+
+%index
+delmod
+W[^ϐ̗vf폜
+%prm
+p1
+
+%index
+dup
+N[ϐ쐬
+%prm
+p1, p2
+
+%index
+dupptr
+|C^N[ϐ쐬
+%prm
+p1, p2, p3, p4
+
+%index
+mcall
+\bȟĂяo
+%prm
+p1, p2, p3, ...
+
+%index
+newcom
+COMIuWFNg^ϐ̐VK쐬
+%prm
+p1, "interface", p2, p3
+
+%index
+delcom
+COMIuWFNg^ϐ̔j
+%prm
+p1
+
+%index
+querycom
+COMIuWFNg^ϐ̍쐬
+%prm
+p1, p2, "interface"
+
+%index
+comres
+\bhԒlϐݒ
+%prm
+p1
+
+%index
+comevent
+COMCxg̎擾Jn
+%prm
+p1, p2, "IID", *label
+
+%index
+comevarg
+COMCxg̃p[^[擾
+%prm
+p1, p2, p3, p4
+
+%index
+sarrayconv
+Variant^Ƃ̈ꊇϊsȂ
+%prm
+p1, p2, p3, p4
+
+%index
+poke
+obt@1byte
+%prm
+p1, p2, p3
+
+%index
+wpoke
+obt@2byte
+%prm
+p1, p2, p3
+
+%index
+lpoke
+obt@4byte
+%prm
+p1, p2, p3
+
+%index
+memset
+ubÑNA
+%prm
+p1, p2, p3, p4
+
+%index
+memcpy
+ubÑRs[
+%prm
+p1, p2, p3, p4, p5
+
+%index
+memexpand
+ubN̍Ċm
+%prm
+p1, p2
+
+%index
+alloc
+obt@m
+%prm
+p1, p2
+
+%index
+getstr
+obt@當ǂݏo
+%prm
+p1, p2, p3, p4, p5
+
+%index
+strrep
+̒u
+%prm
+p1, "search", "replace"
+
+%index
+split
+񂩂番ꂽvf
+%prm
+p1, "string", p2, ...
+
+%index
+cnvstow
+ʏ핶unicodeɕϊ
+%prm
+p1, "string"
+
+%index
+cnvstoa
+ʏ핶ANSIɕϊ
+%prm
+p1, "string"
+
+%index
+noteadd
+ws̒ǉEύX
+%prm
+"string", p1, p2
+
+%index
+notedel
+s̍폜
+%prm
+p1
+
+%index
+noteget
+wsǂݍ
+%prm
+p1, p2
+
+%index
+notesel
+Ώۃobt@w
+%prm
+p1
+
+%index
+noteunsel
+Ώۃobt@̕A
+
+%index
+noteload
+Ώۃobt@ǂݍ
+%prm
+"filename", p1
+
+%index
+notesave
+Ώۃobt@ۑ
+%prm
+"filename"
+
+%index
+setease
+C[WO֐̌vZݒ
+%prm
+p1, p2, p3
+
+%index
+sortval
+zϐ𐔒lŃ\[g
+%prm
+p1, p2
+
+%index
+sortstr
+zϐ𕶎Ń\[g
+%prm
+p1, p2
+
+%index
+sortnote
+m[gpbh\[g
+%prm
+p1, p2
+
+%index
+sortget
+\[g̃CfbNX擾
+%prm
+p1, p2
+
+%index
+exist
+t@C̃TCY擾
+%prm
+"filename"
+
+%index
+delete
+t@C폜
+%prm
+"filename"
+
+%index
+mkdir
+fBNg쐬
+%prm
+"dirname"
+
+%index
+chdir
+fBNgړ
+%prm
+"dirname"
+
+%index
+dirlist
+fBNgꗗ擾
+%prm
+p1, "filemask", p2
+
+%index
+bload
+obt@Ƀt@C[h
+%prm
+"filename", p1, p2, p3
+
+%index
+bsave
+obt@t@CɃZ[u
+%prm
+"filename", p1, p2, p3
+
+%index
+bcopy
+t@C̃Rs[
+%prm
+"filename1", "filename2"
+
+%index
+memfile
+Xg[ݒ
+%prm
+p1, p2, p3
+
+%index
+chdpm
+DPMt@Cݒ
+%prm
+"dpmfile", p1
+
+%index
+mes
+bZ[W\
+%prm
+"message", sw
+
+%index
+print
+bZ[W\
+%prm
+"message", sw
+
+%index
+pos
+Jg|WVݒ
+%prm
+p1, p2
+
+%index
+color
+J[ݒ
+%prm
+p1, p2, p3
+
+%index
+palcolor
+`pbgݒ
+%prm
+p1
+
+%index
+hsvcolor
+HSV`ŃJ[ݒ肷
+%prm
+p1, p2, p3
+
+%index
+rgbcolor
+RGB`ŃJ[ݒ肷
+%prm
+p1
+
+%index
+syscolor
+VXeJ[ݒ肷
+%prm
+p1
+
+%index
+sysfont
+VXetHgI
+%prm
+p1
+
+%index
+font
+tHgݒ
+%prm
+"fontname", p1, p2, p3
+
+%index
+cls
+ʃNA
+%prm
+p1
+
+%index
+boxf
+`hԂ
+%prm
+p1, p2, p3, p4
+
+%index
+line
+`
+%prm
+p1, p2, p3, p4
+
+%index
+circle
+~`
+%prm
+p1, p2, p3, p4, p5
+
+%index
+pset
+1dot̓_\
+%prm
+p1, p2
+
+%index
+pget
+1dot̐F擾
+%prm
+p1, p2
+
+%index
+screen
+EBhE
+%prm
+p1, p2, p3, p4, p5, p6, p7, p8
+
+%index
+bgscr
+ĝȂEBhE
+%prm
+p1, p2, p3, p4, p5, p6, p7, p8
+
+%index
+buffer
+zʂ
+%prm
+p1, p2, p3, p4
+
+%index
+gsel
+`wAEBhEőOʁA\ݒ
+%prm
+p1, p2
+
+%index
+gmode
+ʃRs[[hݒ
+%prm
+p1, p2, p3, p4
+
+%index
+gcopy
+ʃRs[
+%prm
+p1, p2, p3, p4, p5
+
+%index
+gzoom
+ϔ{ĉʃRs[
+%prm
+p1, p2, p3, p4, p5, p6, p7, p8
+
+%index
+grotate
+`摜]ăRs[
+%prm
+p1, p2, p3, p4, p5, p6
+
+%index
+grect
+]`œhԂ
+%prm
+p1, p2, p3, p4, p5
+
+%index
+gsquare
+Cӂ̎lp``
+%prm
+p1, p2, p3, p4
+
+%index
+gradf
+`Of[VœhԂ
+%prm
+p1, p2, p3, p4, p5, p6, p7
+
+%index
+groll
+EBhE̕`_ݒ
+%prm
+p1, p2
+
+%index
+picload
+摜t@C[h
+%prm
+"filename", p1
+
+%index
+bmpsave
+ʃC[WZ[u
+%prm
+"filename"
+
+%index
+celload
+摜t@Cobt@Ƀ[h
+%prm
+"filename", p1, p2
+
+%index
+celdiv
+摜fނ̕TCYݒ
+%prm
+p1, p2, p3, p4, p5
+
+%index
+celput
+摜fނ`
+%prm
+p1, p2, p3, p4, p5
+
+%index
+celbitmap
+rbg}bv摜obt@ɓKp
+%prm
+p1, p2, p3
+
+%index
+palette
+pbgݒ
+%prm
+p1, p2, p3, p4, p5
+
+%index
+redraw
+ĕ`̐ݒ
+%prm
+p1, p2, p3, p4, p5
+
+%index
+title
+^Cgo[ݒ
+%prm
+"string"
+
+%index
+width
+EBhETCYݒ
+%prm
+p1, p2, p3, p4, p5
+
+%index
+chgdisp
+ʉ𑜓xύX
+%prm
+p1, p2, p3
+
+%index
+mouse
+}EXJ[\Wݒ
+%prm
+p1, p2, p3
+
+%index
+stick
+L[͏擾
+%prm
+p1, p2, p3
+
+%index
+getkey
+L[̓`FbN
+%prm
+p1, p2
+
+%index
+dialog
+_CAOJ
+%prm
+"message", p1, "option"
+
+%index
+mmload
+TEht@Cǂݍ
+%prm
+"filename", p1, p2
+
+%index
+mmplay
+TEhĐ
+%prm
+p1
+
+%index
+mmstop
+TEh~
+%prm
+p1
+
+%index
+mmvol
+ʂ̐ݒ
+%prm
+p1, p2
+
+%index
+mmpan
+pjO̐ݒ
+%prm
+p1, p2
+
+%index
+mmstat
+fBȀԎ擾
+%prm
+p1, p2, p3
+
+%index
+mci
+MCIɃR}h𑗂
+%prm
+"command"
+
+%index
+objsize
+IuWFNgTCYݒ
+%prm
+p1, p2, p3
+
+%index
+objsel
+IuWFNgɓ̓tH[JXݒ
+%prm
+p1
+
+%index
+objenable
+IuWFNg̗LEݒ
+%prm
+p1, p2
+
+%index
+objskip
+IuWFNg̃tH[JXړ[hݒ
+%prm
+p1, p2
+
+%index
+objprm
+IuWFNg̓eύX
+%prm
+p1, p2
+
+%index
+objmode
+IuWFNg[hݒ
+%prm
+p1, p2
+
+%index
+objcolor
+IuWFNg̃J[ݒ
+%prm
+p1, p2, p3
+
+%index
+objimage
+JX^{^̐ݒ
+%prm
+p1, p2, p3, p4, p5, p6, p7, p8
+
+%index
+clrobj
+IuWFNgNA
+%prm
+p1, p2
+
+%index
+button
+{^\
+%prm
+goto/gosub "name", *label
+
+%index
+input
+̓{bNX\
+%prm
+p1, p2, p3, p4
+
+%index
+mesbox
+bZ[W{bNX\
+%prm
+p1, p2, p3, p4, p5
+
+%index
+chkbox
+`FbN{bNX\
+%prm
+"strings", p1
+
+%index
+combox
+R{{bNX\
+%prm
+p1, p2, "strings"
+
+%index
+listbox
+Xg{bNX\
+%prm
+p1, p2, "strings"
+
+%index
+axobj
+ActiveXRg[̔zu
+%prm
+p1, "IID", p2, p3
+
+%index
+winobj
+EBhEIuWFNg̔zu
+%prm
+"class", "title", p1, p2, p3, p4, p5, p6
+
+%index
+sendmsg
+EBhEbZ[W̑M
+%prm
+p1, p2, p3, p4
@@ -0,0 +1,290 @@
+;
+;	W֐ ̃wv\[Xt@C (̃f[^x[X̌f[^)
+;	hsphelp ƓŁA%index, %prm ƍڂ̎ (%note) Ă܂B
+;	`ham --hsp data gen-builtin > data/builtin.hs`  data/builtin.hs 𐶐܂B
+;
+
+%note
+W֐
+
+%index
+int
+lɕϊ
+%prm
+(p1)
+
+%index
+double
+lɕϊ
+%prm
+(p1)
+
+%index
+str
+ɕϊ
+%prm
+(p1)
+
+%index
+strlen
+̒𒲂ׂ
+%prm
+(p1)
+
+%index
+strmid
+̈ꕔo
+%prm
+(p1, p2, p3)
+
+%index
+instr
+̌
+%prm
+(p1, p2, "search")
+
+%index
+strf
+tϊ
+%prm
+("format", p1, ...)
+
+%index
+strtrim
+w肵菜
+%prm
+(p1, p2, p3)
+
+%index
+getpath
+pẌꕔ擾
+%prm
+("filename", p1)
+
+%index
+cnvwtos
+unicodeʏ핶ɕϊ
+%prm
+(p1)
+
+%index
+cnvatos
+ANSIʏ핶ɕϊ
+%prm
+(p1)
+
+%index
+peek
+obt@1byteǂݏo
+%prm
+(p1, p2)
+
+%index
+wpeek
+obt@2byteǂݏo
+%prm
+(p1, p2)
+
+%index
+lpeek
+obt@4byteǂݏo
+%prm
+(p1, p2)
+
+%index
+varptr
+ϐf[^̃|C^Ԃ
+%prm
+(p1)
+
+%index
+varuse
+ϐ̎gp󋵂Ԃ
+%prm
+(p1)
+
+%index
+vartype
+ϐ̌^Ԃ
+%prm
+(p1)
+
+%index
+varsize
+ϐf[^̃obt@TCYԂ
+%prm
+(p1)
+
+%index
+length
+z1vfԂ
+%prm
+(p1)
+
+%index
+length2
+z2vfԂ
+%prm
+(p1)
+
+%index
+length3
+z3vfԂ
+%prm
+(p1)
+
+%index
+length4
+z4vfԂ
+%prm
+(p1)
+
+%index
+libptr
+OĂяoR}h̏AhX𓾂
+%prm
+(p1)
+
+%index
+callfunc
+O֐̌Ăяo
+%prm
+(p1, p2, p3)
+
+%index
+comevdisp
+COMCxg̓emF
+%prm
+(p1)
+
+%index
+abs
+̐ΒlԂ
+%prm
+(p1)
+
+%index
+absf
+̐ΒlԂ
+%prm
+(p1)
+
+%index
+sin
+TClԂ
+%prm
+(p1)
+
+%index
+cos
+RTClԂ
+%prm
+(p1)
+
+%index
+tan
+^WFglԂ
+%prm
+(p1)
+
+%index
+atan
+A[N^WFglԂ
+%prm
+(p1, p2)
+
+%index
+sqrt
+[glԂ
+%prm
+(p1)
+
+%index
+expf
+wԂ
+%prm
+(p1)
+
+%index
+logf
+ΐԂ
+%prm
+(p1)
+
+%index
+powf
+ݏ(ׂ)߂
+%prm
+(p1, p2)
+
+%index
+limit
+͈͓̐Ԃ
+%prm
+(p1, p2, p3)
+
+%index
+limitf
+͈͓̎Ԃ
+%prm
+(p1, p2, p3)
+
+%index
+rnd
+𔭐
+%prm
+(p1)
+
+%index
+getease
+C[WOl𐮐Ŏ擾
+%prm
+(p1, p2)
+
+%index
+geteasef
+C[WOlŎ擾
+%prm
+(p1, p2)
+
+%index
+gettime
+ԁEt擾
+%prm
+(p1)
+
+%index
+ginfo
+EBhE̎擾
+%prm
+(p1)
+
+%index
+objinfo
+EBhEIuWFNg̎擾
+%prm
+(p1, p2)
+
+%index
+sysinfo
+VXe̎擾
+%prm
+(p1)
+
+%index
+dirinfo
+fBNg̎擾
+%prm
+(p1)
+
+%index
+noteinfo
+m[gpbh擾
+%prm
+(p1)
+
+%index
+notefind
+m[gpbh
+%prm
+("search", p1)
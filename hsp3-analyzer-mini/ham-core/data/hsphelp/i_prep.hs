@@ -0,0 +1,192 @@
+;
+;	vvZbT ̃wv\[Xt@C (̃f[^x[X̌f[^)
+;	hsphelp ƓŁA%index, %prm ƍڂ̎ (%note) Ă܂B
+;	`ham --hsp data gen-builtin > data/builtin.hs`  data/builtin.hs 𐶐܂B
+;
+
+%note
+vvZbT
+
+%index
+#include
+ʃt@C
+%prm
+"filename"
+
+%index
+#addition
+ʃt@C (t@CȂΖ)
+%prm
+"filename"
+
+%index
+#define
+VK}No^
+%prm
+}N }N`
+
+%index
+#undef
+}N̎
+%prm
+}N
+
+%index
+#const
+}N̒萔`
+%prm
+}N 萔
+
+%index
+#enum
+}N̗񋓒`
+%prm
+}N = p1
+
+%index
+#if
+lRpC
+%prm
+p1
+
+%index
+#ifdef
+}N`RpC
+%prm
+}N
+
+%index
+#ifndef
+}N`RpC (ے)
+%prm
+}N
+
+%index
+#else
+RpC𔽓]
+
+%index
+#endif
+RpCubNI
+
+%index
+#module
+W[̊Jn
+%prm
+W[ ϐ1, c
+
+%index
+#global
+W[̏I
+
+%index
+#deffunc
+VK߂o^
+%prm
+ߖ p1, c
+
+%index
+#defcfunc
+VK֐o^
+%prm
+֐ p1, c
+
+%index
+#modfunc
+W[ϐ閽߂o^
+%prm
+ߖ p1, c
+
+%index
+#modcfunc
+W[ϐ֐o^
+%prm
+֐ p1, c
+
+%index
+#modinit
+W[̓o^
+%prm
+p1, c
+
+%index
+#modterm
+W[̓o^
+
+%index
+#uselib
+ODLL̎w
+%prm
+"t@C"
+
+%index
+#func
+ODLLĂяoߓo^
+%prm
+VK "֐" p1, c
+
+%index
+#cfunc
+ODLLĂяo֐o^
+%prm
+VK "֐" p1, c
+
+%index
+#usecom
+OCOMC^[tF[X̎w
+%prm
+C^[tF[X "C^[tF[XIID" "NXIID"
+
+%index
+#comfunc
+OCOMĂяoߓo^
+%prm
+VK CfbNX p1, c
+
+%index
+#regcmd
+gvOC̓o^
+%prm
+"֐", "DLLt@C", ϐ^o^
+
+%index
+#cmd
+gL[[h̓o^
+%prm
+VK TuID
+
+%index
+#runtime
+^Ct@C̐ݒ
+%prm
+"^C"
+
+%index
+#packopt
+쐬IvVw
+%prm
+L[[h p[^[
+
+%index
+#pack
+PACKFILEǉt@Cw
+%prm
+"filename"
+
+%index
+#epack
+PACKFILEÍt@Cw
+%prm
+"filename"
+
+%index
+#cmpopt
+RpC̐ݒ
+%prm
+IvV p[^[
+
+%index
+#bootopt
+^CNݒ
+%prm
+IvV p[^[
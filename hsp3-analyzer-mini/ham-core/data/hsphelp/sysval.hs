@@ -0,0 +1,88 @@
+;
+;	VXeϐ ̃wv\[Xt@C (̃f[^x[X̌f[^)
+;	hsphelp ƓŁA%index, %prm ƍڂ̎ (%note) Ă܂B
+;	`ham --hsp data gen-builtin > data/builtin.hs`  data/builtin.hs 𐶐܂B
+;
+
+%note
+VXeϐ
+
+%index
+hspstat
+HSP^C̏
+
+%index
+hspver
+HSP̃o[Wԍ
+
+%index
+cnt
+repeat`loop̃JE^[
+
+%index
+err
+HSP̃G[R[h
+
+%index
+stat
+߂̎s
+
+%index
+mousex
+}EXJ[\XW
+
+%index
+mousey
+}EXJ[\YW
+
+%index
+mousew
+}EXJ[\̃zC[l
+
+%index
+strsize
+ǂݍ݁E擾̃TCY
+
+%index
+refstr
+߂̎s ()
+
+%index
+refdval
+߂̎s ()
+
+%index
+looplev
+repeat`loop̃lXgx
+
+%index
+sublev
+Tu[`̃lXgx
+
+%index
+iparam
+荞ݗvl
+
+%index
+wparam
+荞ݎɕۑWindows̃p[^[ (wParam)
+
+%index
+lparam
+荞ݎɕۑWindows̃p[^[ (lParam)
+
+%index
+hwnd
+݂̃EBhEnh
+
+%index
+hinstance
+݂̃CX^Xnh
+
+%index
+hdc
+݂̃foCXReLXg
+
+%index
+thismod
+ݗLȃW[ϐ
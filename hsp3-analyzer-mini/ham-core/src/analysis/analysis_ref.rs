@@ -43,6 +43,7 @@ impl SignatureHelpDb {
 /// プリプロセッサ命令やプリプロセッサ関連のキーワードを入力補完候補として列挙する
 pub(crate) fn collect_preproc_completion_items(
     an: &AnalyzerRef<'_>,
+    doc: DocId,
     completion_items: &mut Vec<lsp_types::CompletionItem>,
 ) {
     for (keyword, detail) in &[
@@ -67,11 +68,9 @@ pub(crate) fn collect_preproc_completion_items(
     }
 
     completion_items.extend(
-        an.hsphelp_info()
-            .doc_symbols
-            .iter()
-            .filter(|(&doc, _)| an.is_active_help_doc(doc))
-            .flat_map(|(_, symbols)| symbols.iter().filter(|s| s.label.starts_with("#")))
+        an.hsphelp_info_of(doc)
+            .completion_items(|doc| an.is_active_help_doc(doc))
+            .filter(|s| s.label.starts_with("#"))
             .cloned(),
     );
 }
@@ -199,20 +198,6 @@ pub(crate) fn collect_highlights(
     }
 }
 
-/// 指定したドキュメント内のすべてのシンボルの出現箇所 (定義・使用両方) を列挙する
-/// (セマンティックトークン用)
-pub(crate) fn collect_symbol_occurrences_in_doc<'a>(
-    an: &AnalyzerRef<'a>,
-    doc: DocId,
-    symbols: &mut Vec<(&'a SymbolRc, Loc)>,
-) {
-    for (symbol, loc) in an.def_sites.iter().chain(an.use_sites) {
-        if loc.doc == doc {
-            symbols.push((symbol, *loc));
-        }
-    }
-}
-
 pub(crate) struct CollectSymbolOptions {
     pub(crate) include_def: bool,
     pub(crate) include_use: bool,
@@ -0,0 +1,82 @@
+//! 参照渡しの引数 (`var`, `array` パラメータに渡される変数) の検出
+
+use super::*;
+use crate::{analyzer::AnalyzerRef, parse::*};
+
+struct V<'a> {
+    use_site_map: &'a HashMap<Pos, SymbolRc>,
+    sites: &'a mut Vec<Loc>,
+}
+
+impl V<'_> {
+    /// 命令・関数の呼び出しの引数のうち、参照渡しのパラメータに対応するものを探す
+    fn on_call(&mut self, callee: &PToken, args: &[PArg]) {
+        let signature_data = match self
+            .use_site_map
+            .get(&callee.body.loc.start())
+            .and_then(|symbol| symbol.signature_opt())
+        {
+            Some(it) => it,
+            None => return,
+        };
+
+        for (arg, (param, _, _)) in args.iter().zip(&signature_data.params) {
+            if !matches!(param, Some(PParamTy::Var | PParamTy::Array)) {
+                continue;
+            }
+
+            // カッコを外す。
+            let mut expr_opt = arg.expr_opt.as_ref();
+            while let Some(PExpr::Paren(expr)) = expr_opt {
+                expr_opt = expr.body_opt.as_deref();
+            }
+
+            if let Some(PExpr::Compound(compound)) = expr_opt {
+                self.sites.push(compound.name().body.loc);
+            }
+        }
+    }
+}
+
+impl PVisitor for V<'_> {
+    fn on_compound(&mut self, compound: &PCompound) {
+        if let PCompound::Paren(np) = compound {
+            self.on_call(&np.name, &np.args);
+        }
+        self.on_compound_default(compound);
+    }
+
+    fn on_stmt(&mut self, stmt: &PStmt) {
+        if let PStmt::Command(stmt) = stmt {
+            self.on_call(&stmt.command, &stmt.args);
+        }
+        self.on_stmt_default(stmt);
+    }
+}
+
+/// 指定したドキュメントで、参照渡しの引数として変数が渡されている箇所を列挙する
+///
+/// (渡された変数は呼び出し先で書き換えられる可能性があるので、書き込みとみなす。)
+pub(crate) fn collect_by_ref_arg_sites(an: &AnalyzerRef<'_>, doc: DocId, sites: &mut Vec<Loc>) {
+    let root = match an
+        .doc_analysis_map
+        .get(&doc)
+        .and_then(|da| da.tree_opt.as_ref())
+    {
+        Some(it) => it,
+        None => return,
+    };
+
+    let use_site_map = an
+        .use_sites
+        .iter()
+        .filter(|(_, loc)| loc.doc == doc)
+        .map(|(symbol, loc)| (loc.start(), symbol.clone()))
+        .collect::<HashMap<_, _>>();
+
+    V {
+        use_site_map: &use_site_map,
+        sites,
+    }
+    .on_root(root);
+}
@@ -10,7 +10,7 @@ pub(crate) fn str_is_ornament_comment(s: &str) -> bool {
 }
 
 fn trim_comment_leader(s: RcStr) -> RcStr {
-    for prefix in &["/// ", "///", "// ", "//", "; ", ";"] {
+    for prefix in &["/// ", "///", "// ", "//", ";; ", ";;", "; ", ";"] {
         if s.starts_with(prefix) {
             return s.slice(prefix.len(), s.len());
         }
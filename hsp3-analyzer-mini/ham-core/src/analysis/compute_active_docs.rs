@@ -8,10 +8,12 @@ use super::*;
 /// ヘルプファイルは、それとリンクしているモジュールがアクティブである場合にアクティブとみなす。
 /// (`hsphelp` と `common` にある同じ名前のファイルをリンクしているとみなす。
 ///  `hsphelp/foo.hs` は `common/foo.as` がアクティブである場合にアクティブとみなされる。)
+///
+/// `include` されるcommonのファイルは、インクルードする側のドキュメントが対象とするHSPのものを使う。
 pub(crate) fn compute_active_docs(
     doc_analysis_map: &HashMap<DocId, DocAnalysis>,
-    common_docs: &HashMap<String, DocId>,
-    hsphelp_info: &HspHelpInfo,
+    hsp3_roots: &[Hsp3Root],
+    doc_targets: &HashMap<DocId, HspTarget>,
     active_docs: &mut HashSet<DocId>,
     active_help_docs: &mut HashSet<DocId>,
     help_docs: &mut HashMap<DocId, DocId>,
@@ -20,13 +22,19 @@ pub(crate) fn compute_active_docs(
     // それらのファイルからincludeされているcommonのファイルはアクティブとする
     {
         let mut included_docs = HashSet::new();
-        let in_common = common_docs.values().cloned().collect::<HashSet<_>>();
+        let in_common = hsp3_roots
+            .iter()
+            .flat_map(|root| root.common_docs.values().cloned())
+            .collect::<HashSet<_>>();
 
         for (&doc, da) in doc_analysis_map.iter() {
             if in_common.contains(&doc) {
                 continue;
             }
 
+            let root = doc_targets.get(&doc).map_or(0, |target| target.root);
+            let common_docs = &hsp3_roots[root].common_docs;
+
             for (include, _) in &da.includes {
                 let doc_opt = common_docs.get(include.as_str()).cloned();
                 included_docs.extend(doc_opt);
@@ -53,12 +61,15 @@ pub(crate) fn compute_active_docs(
     // hsphelp
     {
         debug!("active_help_docs.len={}", active_help_docs.len());
-        active_help_docs.extend(hsphelp_info.builtin_docs.iter().cloned());
+        for root in hsp3_roots {
+            let hsphelp_info = &root.hsphelp_info;
+            active_help_docs.extend(hsphelp_info.builtin_docs.iter().cloned());
 
-        for (&common_doc, &hs_doc) in &hsphelp_info.linked_docs {
-            if active_docs.contains(&common_doc) {
-                active_help_docs.insert(hs_doc);
-                help_docs.insert(common_doc, hs_doc);
+            for (&common_doc, &hs_doc) in &hsphelp_info.linked_docs {
+                if active_docs.contains(&common_doc) {
+                    active_help_docs.insert(hs_doc);
+                    help_docs.insert(common_doc, hs_doc);
+                }
             }
         }
     }
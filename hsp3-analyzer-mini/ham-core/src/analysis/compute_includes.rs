@@ -4,7 +4,8 @@ use super::*;
 pub(crate) fn compute_includes(
     doc_interner: &DocInterner,
     doc_analysis_map: &HashMap<DocId, DocAnalysis>,
-    common_docs: &HashMap<String, DocId>,
+    hsp3_roots: &[Hsp3Root],
+    doc_targets: &HashMap<DocId, HspTarget>,
     include_resolution: &mut Vec<(Loc, DocId)>,
 ) {
     let get_name = |doc: DocId| match doc_interner
@@ -17,6 +18,10 @@ pub(crate) fn compute_includes(
     };
 
     for (&src_doc, da) in doc_analysis_map {
+        // (インクルードする側のドキュメントが対象とするHSPのcommonを使う)
+        let root = doc_targets.get(&src_doc).map_or(0, |target| target.root);
+        let common_docs = &hsp3_roots[root].common_docs;
+
         for (included_name, loc) in &da.includes {
             let included_doc_opt =
                 docs::resolve_included_name(doc_interner, included_name, src_doc)
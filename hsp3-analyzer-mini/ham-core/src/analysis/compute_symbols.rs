@@ -1,7 +1,7 @@
 use super::*;
 
 pub(crate) fn compute_symbols(
-    hsphelp_info: &HspHelpInfo,
+    hsp3_roots: &[Hsp3Root],
     active_docs: &HashSet<DocId>,
     help_docs: &HashMap<DocId, DocId>,
     doc_analysis_map: &DocAnalysisMap,
@@ -41,20 +41,26 @@ pub(crate) fn compute_symbols(
             Some((symbol.clone(), loc))
         }));
 
-        crate::analysis::var::analyze_var_def(
-            doc,
-            da.tree_opt.as_ref().unwrap(),
-            &module_map,
-            symbols,
-            public_env,
-            ns_env,
-            def_sites,
-            use_sites,
-        );
+        // (キャッシュから復元したドキュメントは構文木を持たないので、変数の解析を省く)
+        if let Some(tree) = &da.tree_opt {
+            crate::analysis::var::analyze_var_def(
+                doc,
+                tree,
+                &module_map,
+                symbols,
+                public_env,
+                ns_env,
+                def_sites,
+                use_sites,
+            );
+        }
 
         // ヘルプファイルの情報をシンボルに統合する。
         if let Some(hs_doc) = help_docs.get(&doc) {
-            if let Some(hs_symbols) = hsphelp_info.doc_symbols.get(&hs_doc) {
+            let hs_symbols_opt = hsp3_roots
+                .iter()
+                .find_map(|root| root.hsphelp_info.doc_symbols.get(hs_doc));
+            if let Some(hs_symbols) = hs_symbols_opt {
                 let mut hs_symbols_map = hs_symbols
                     .iter()
                     .map(|s| (s.label.as_str(), s.clone()))
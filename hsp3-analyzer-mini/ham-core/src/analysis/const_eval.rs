@@ -0,0 +1,602 @@
+//! 定数畳み込み (`#const`, `#enum` の値の計算)
+
+use super::*;
+use crate::{
+    analyzer::AnalyzerRef,
+    i18n::{tr, Locale},
+    parse::*,
+};
+
+/// 定数の値
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ConstValue {
+    Int(i32),
+    Double(f64),
+    Str(RcStr),
+}
+
+impl ConstValue {
+    fn to_int(&self) -> Result<i32, ConstEvalError> {
+        match *self {
+            ConstValue::Int(value) => Ok(value),
+            ConstValue::Double(value) => double_to_int(value),
+            ConstValue::Str(_) => Err(ConstEvalError::NotConstant),
+        }
+    }
+
+    fn to_double(&self) -> Result<f64, ConstEvalError> {
+        match *self {
+            ConstValue::Int(value) => Ok(value as f64),
+            ConstValue::Double(value) => Ok(value),
+            ConstValue::Str(_) => Err(ConstEvalError::NotConstant),
+        }
+    }
+}
+
+impl Display for ConstValue {
+    /// 整数は10進数と16進数で表示する。
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstValue::Int(value) => write!(f, "{} (0x{:X})", value, *value as u32),
+            ConstValue::Double(value) => write!(f, "{:?}", value),
+            ConstValue::Str(value) => write!(f, "\"{}\"", value),
+        }
+    }
+}
+
+/// 定数の計算の誤り
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ConstEvalError {
+    DivisionByZero,
+    Overflow,
+    NotConstant,
+    /// 数値の書きかたが正しくない (`0x` の後に数字がないなど)
+    MalformedNumber,
+}
+
+impl ConstEvalError {
+    pub(crate) fn as_str(self, locale: Locale) -> &'static str {
+        match self {
+            ConstEvalError::DivisionByZero => tr(locale, "lint.division_by_zero"),
+            ConstEvalError::Overflow => tr(locale, "lint.overflow"),
+            ConstEvalError::NotConstant => tr(locale, "lint.not_constant"),
+            ConstEvalError::MalformedNumber => tr(locale, "lint.malformed_number"),
+        }
+    }
+}
+
+fn double_to_int(value: f64) -> Result<i32, ConstEvalError> {
+    let value = value.trunc();
+    if (i32::MIN as f64) <= value && value <= (i32::MAX as f64) {
+        Ok(value as i32)
+    } else {
+        Err(ConstEvalError::Overflow)
+    }
+}
+
+fn parse_number(text: &str) -> Result<ConstValue, ConstEvalError> {
+    let radix_digits = text
+        .strip_prefix('$')
+        .or_else(|| text.strip_prefix("0x"))
+        .or_else(|| text.strip_prefix("0X"))
+        .map(|s| (16, s))
+        .or_else(|| {
+            text.strip_prefix('%')
+                .or_else(|| text.strip_prefix("0b"))
+                .or_else(|| text.strip_prefix("0B"))
+                .map(|s| (2, s))
+        });
+
+    // 16進数・2進数は32ビットの範囲で符号なしとして解釈する。(`$ffffffff` は -1)
+    if let Some((radix, digits)) = radix_digits {
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return Err(ConstEvalError::MalformedNumber);
+        }
+        return match u32::from_str_radix(digits, radix) {
+            Ok(value) => Ok(ConstValue::Int(value as i32)),
+            Err(_) => Err(ConstEvalError::Overflow),
+        };
+    }
+
+    if text.contains(['.', 'e', 'E']) {
+        return match text.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(ConstValue::Double(value)),
+            _ => Err(ConstEvalError::Overflow),
+        };
+    }
+
+    match text.parse::<i32>() {
+        Ok(value) => Ok(ConstValue::Int(value)),
+        Err(_) => Err(ConstEvalError::Overflow),
+    }
+}
+
+/// 引用符の中身のエスケープシーケンスを解除する。
+fn unescape(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            s.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => s.push('\n'),
+            Some('r') => s.push('\r'),
+            Some('t') => s.push('\t'),
+            Some('0') => s.push('\0'),
+            Some(c) => s.push(c),
+            None => {}
+        }
+    }
+    s
+}
+
+/// 文字定数の値を計算する。ASCIIでない文字は (文字コードに依存するので) 計算しない。
+fn parse_char(text: &str) -> Option<ConstValue> {
+    let body = text.strip_prefix('\'')?;
+    let body = body.strip_suffix('\'').unwrap_or(body);
+    let s = unescape(body);
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii() => Some(ConstValue::Int(c as i32)),
+        _ => None,
+    }
+}
+
+fn parse_str(text: &str) -> ConstValue {
+    let body = if let Some(s) = text.strip_prefix("{\"") {
+        s.strip_suffix("\"}").unwrap_or(s)
+    } else {
+        let s = text.strip_prefix('"').unwrap_or(text);
+        s.strip_suffix('"').unwrap_or(s)
+    };
+    ConstValue::Str(unescape(body).into())
+}
+
+fn eval_int_infix(op: TokenKind, l: i32, r: i32) -> Result<i32, ConstEvalError> {
+    let value = match op {
+        TokenKind::Plus => l.checked_add(r),
+        TokenKind::Minus => l.checked_sub(r),
+        TokenKind::Star => l.checked_mul(r),
+        TokenKind::Slash | TokenKind::Backslash if r == 0 => {
+            return Err(ConstEvalError::DivisionByZero)
+        }
+        TokenKind::Slash => l.checked_div(r),
+        TokenKind::Backslash => l.checked_rem(r),
+        TokenKind::And | TokenKind::AndAnd => Some(l & r),
+        TokenKind::Pipe | TokenKind::PipePipe => Some(l | r),
+        TokenKind::Hat => Some(l ^ r),
+        TokenKind::LeftShift => Some(l.wrapping_shl(r as u32)),
+        TokenKind::RightShift => Some(l.wrapping_shr(r as u32)),
+        TokenKind::Equal | TokenKind::EqualEqual => Some((l == r) as i32),
+        TokenKind::Bang | TokenKind::BangEqual => Some((l != r) as i32),
+        TokenKind::LeftAngle => Some((l < r) as i32),
+        TokenKind::RightAngle => Some((l > r) as i32),
+        TokenKind::LeftEqual => Some((l <= r) as i32),
+        TokenKind::RightEqual => Some((l >= r) as i32),
+        _ => return Err(ConstEvalError::NotConstant),
+    };
+    value.ok_or(ConstEvalError::Overflow)
+}
+
+fn eval_double_infix(op: TokenKind, l: f64, r: f64) -> Result<ConstValue, ConstEvalError> {
+    let value = match op {
+        TokenKind::Plus => l + r,
+        TokenKind::Minus => l - r,
+        TokenKind::Star => l * r,
+        TokenKind::Slash | TokenKind::Backslash if r == 0.0 => {
+            return Err(ConstEvalError::DivisionByZero)
+        }
+        TokenKind::Slash => l / r,
+        TokenKind::Backslash => l % r,
+        TokenKind::Equal | TokenKind::EqualEqual => return Ok(ConstValue::Int((l == r) as i32)),
+        TokenKind::Bang | TokenKind::BangEqual => return Ok(ConstValue::Int((l != r) as i32)),
+        TokenKind::LeftAngle => return Ok(ConstValue::Int((l < r) as i32)),
+        TokenKind::RightAngle => return Ok(ConstValue::Int((l > r) as i32)),
+        TokenKind::LeftEqual => return Ok(ConstValue::Int((l <= r) as i32)),
+        TokenKind::RightEqual => return Ok(ConstValue::Int((l >= r) as i32)),
+        // ビット演算は整数に変換して行う。
+        _ => {
+            let value = eval_int_infix(op, double_to_int(l)?, double_to_int(r)?)?;
+            return Ok(ConstValue::Int(value));
+        }
+    };
+
+    if value.is_finite() {
+        Ok(ConstValue::Double(value))
+    } else {
+        Err(ConstEvalError::Overflow)
+    }
+}
+
+fn eval_str_infix(op: TokenKind, l: &str, r: &ConstValue) -> Result<ConstValue, ConstEvalError> {
+    let r = match r {
+        ConstValue::Str(s) => s.to_string(),
+        ConstValue::Int(value) => value.to_string(),
+        ConstValue::Double(value) => format!("{:.6}", value),
+    };
+
+    let value = match op {
+        TokenKind::Plus => return Ok(ConstValue::Str(format!("{}{}", l, r).into())),
+        TokenKind::Equal | TokenKind::EqualEqual => l == r,
+        TokenKind::Bang | TokenKind::BangEqual => l != r,
+        _ => return Err(ConstEvalError::NotConstant),
+    };
+    Ok(ConstValue::Int(value as i32))
+}
+
+/// 二項演算の値を計算する。
+///
+/// (HSPの二項演算の結果の型は左辺の型に合わせられる。)
+fn eval_infix(op: TokenKind, l: &ConstValue, r: &ConstValue) -> Result<ConstValue, ConstEvalError> {
+    match l {
+        ConstValue::Int(l) => Ok(ConstValue::Int(eval_int_infix(op, *l, r.to_int()?)?)),
+        ConstValue::Double(l) => eval_double_infix(op, *l, r.to_double()?),
+        ConstValue::Str(l) => eval_str_infix(op, l, r),
+    }
+}
+
+/// 定数の定義
+enum ConstDef<'a> {
+    Const {
+        ty_opt: Option<PConstTy>,
+        init_opt: Option<&'a PExpr>,
+    },
+    Enum {
+        /// 直前の `#enum` の名前の位置 (自動で番号を振るときに使う)
+        prev_opt: Option<Loc>,
+        init_opt: Option<&'a PExpr>,
+    },
+}
+
+/// 定数の定義を集めるもの
+struct DefCollector<'a> {
+    defs: HashMap<Loc, ConstDef<'a>>,
+    prev_enum_opt: Option<Loc>,
+}
+
+impl<'a> DefCollector<'a> {
+    fn on_stmt(&mut self, stmt: &'a PStmt) {
+        match stmt {
+            PStmt::Const(PConstStmt {
+                ty_opt,
+                name_opt: Some(name),
+                init_opt,
+                ..
+            }) => {
+                let def = ConstDef::Const {
+                    ty_opt: ty_opt.as_ref().map(|&(ty, _)| ty),
+                    init_opt: init_opt.as_ref(),
+                };
+                self.defs.insert(name.body.loc, def);
+            }
+            PStmt::Enum(PEnumStmt {
+                name_opt: Some(name),
+                init_opt,
+                ..
+            }) => {
+                let def = ConstDef::Enum {
+                    prev_opt: if init_opt.is_some() {
+                        None
+                    } else {
+                        self.prev_enum_opt
+                    },
+                    init_opt: init_opt.as_ref(),
+                };
+                self.defs.insert(name.body.loc, def);
+                self.prev_enum_opt = Some(name.body.loc);
+            }
+            PStmt::If(stmt) => {
+                self.on_block(&stmt.body);
+                self.on_block(&stmt.alt);
+            }
+            PStmt::DefFunc(stmt) => self.on_stmts(&stmt.stmts),
+            PStmt::Module(stmt) => self.on_stmts(&stmt.stmts),
+            _ => {}
+        }
+    }
+
+    fn on_stmts(&mut self, stmts: &'a [PStmt]) {
+        for stmt in stmts {
+            self.on_stmt(stmt);
+        }
+    }
+
+    fn on_block(&mut self, block: &'a PBlock) {
+        self.on_stmts(&block.outer_stmts);
+        self.on_stmts(&block.inner_stmts);
+    }
+}
+
+struct Evaluator<'a> {
+    defs: HashMap<Loc, ConstDef<'a>>,
+    use_site_map: HashMap<(DocId, Pos), SymbolRc>,
+
+    /// 計算済みの定数の値 (計算できなかったものは None)
+    values: HashMap<Loc, Option<ConstValue>>,
+
+    /// 計算中の定数 (循環参照の検出に使う)
+    visiting: HashSet<Loc>,
+
+    errors: Vec<(ConstEvalError, Loc)>,
+}
+
+impl Evaluator<'_> {
+    /// 定数の値を計算する。
+    fn eval_def(&mut self, loc: Loc) -> Option<ConstValue> {
+        if let Some(value_opt) = self.values.get(&loc) {
+            return value_opt.clone();
+        }
+        if !self.visiting.insert(loc) {
+            return None;
+        }
+
+        let value_opt = match self.defs.get(&loc) {
+            Some(&ConstDef::Const { ty_opt, init_opt }) => (|| {
+                let value = self.eval_expr(init_opt?)?;
+                let converted = match (ty_opt, &value) {
+                    (Some(PConstTy::Int), _) => value.to_int().map(ConstValue::Int),
+                    (Some(PConstTy::Double), _) => value.to_double().map(ConstValue::Double),
+                    (None, _) => Ok(value),
+                };
+                self.ok_or_report(converted, init_opt?)
+            })(),
+            Some(&ConstDef::Enum {
+                init_opt: Some(init),
+                ..
+            }) => {
+                let value = self.eval_expr(init)?;
+                let converted = value.to_int().map(ConstValue::Int);
+                self.ok_or_report(converted, init)
+            }
+            Some(&ConstDef::Enum {
+                prev_opt,
+                init_opt: None,
+            }) => match prev_opt {
+                // 直前の値の次の番号
+                Some(prev) => match self.eval_def(prev)? {
+                    ConstValue::Int(value) => match value.checked_add(1) {
+                        Some(value) => Some(ConstValue::Int(value)),
+                        None => {
+                            self.errors.push((ConstEvalError::Overflow, loc));
+                            None
+                        }
+                    },
+                    _ => None,
+                },
+                None => Some(ConstValue::Int(0)),
+            },
+            None => None,
+        };
+
+        self.visiting.remove(&loc);
+        self.values.insert(loc, value_opt.clone());
+        value_opt
+    }
+
+    fn ok_or_report(
+        &mut self,
+        result: Result<ConstValue, ConstEvalError>,
+        expr: &PExpr,
+    ) -> Option<ConstValue> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                let loc = expr.compute_range();
+                let doc = expr_doc(expr);
+                self.errors.push((err, Loc { doc, range: loc }));
+                None
+            }
+        }
+    }
+
+    fn report(&mut self, err: ConstEvalError, loc: Loc) -> Option<ConstValue> {
+        self.errors.push((err, loc));
+        None
+    }
+
+    /// 名前が指すシンボルの値を計算する。
+    ///
+    /// マクロや定義が見つからないものは (展開後の内容が分からないので) 計算せず、エラーにもしない。
+    fn eval_name(&mut self, name: &PToken) -> Option<ConstValue> {
+        let loc = name.body.loc;
+        let symbol = self.use_site_map.get(&(loc.doc, loc.start()))?.clone();
+        match symbol.kind {
+            HspSymbolKind::Const | HspSymbolKind::Enum => {
+                self.eval_def(symbol.preproc_def_site_opt?)
+            }
+            HspSymbolKind::Unresolved
+            | HspSymbolKind::Unknown
+            | HspSymbolKind::Macro { .. }
+            | HspSymbolKind::PluginCmd => None,
+            _ => self.report(ConstEvalError::NotConstant, loc),
+        }
+    }
+
+    fn eval_expr(&mut self, expr: &PExpr) -> Option<ConstValue> {
+        match expr {
+            PExpr::Literal(token) => match token.body.kind {
+                TokenKind::Number => match parse_number(token.body_text()) {
+                    Ok(value) => Some(value),
+                    Err(err) => self.report(err, token.body.loc),
+                },
+                TokenKind::Char => parse_char(token.body_text()),
+                TokenKind::Str => Some(parse_str(token.body_text())),
+                _ => None,
+            },
+            PExpr::Label(_) => {
+                let loc = Loc {
+                    doc: expr_doc(expr),
+                    range: expr.compute_range(),
+                };
+                self.report(ConstEvalError::NotConstant, loc)
+            }
+            PExpr::Compound(PCompound::Name(name)) => self.eval_name(name),
+            PExpr::Compound(compound) => {
+                // 関数の呼び出しなど。マクロの可能性があるので名前を調べる。
+                let name = compound.name();
+                let is_non_const_symbol = self
+                    .use_site_map
+                    .get(&(name.body.loc.doc, name.body.loc.start()))
+                    .is_some_and(|symbol| {
+                        !matches!(
+                            symbol.kind,
+                            HspSymbolKind::Unresolved
+                                | HspSymbolKind::Unknown
+                                | HspSymbolKind::Macro { .. }
+                                | HspSymbolKind::PluginCmd
+                        )
+                    });
+                if is_non_const_symbol {
+                    self.report(ConstEvalError::NotConstant, name.body.loc)
+                } else {
+                    None
+                }
+            }
+            PExpr::Paren(PParenExpr { body_opt, .. }) => self.eval_expr(body_opt.as_deref()?),
+            // 2進数 (`%0101`)
+            PExpr::Prefix(PPrefixExpr {
+                prefix,
+                arg_opt: Some(arg),
+            }) if prefix.body.kind == TokenKind::Percent => match arg.as_ref() {
+                PExpr::Literal(token) if token.body.kind == TokenKind::Number => {
+                    match parse_number(&format!("%{}", token.body_text())) {
+                        Ok(value) => Some(value),
+                        Err(err) => {
+                            let loc = prefix.body.loc.unite(&token.body.loc);
+                            self.report(err, loc)
+                        }
+                    }
+                }
+                _ => None,
+            },
+            PExpr::Prefix(PPrefixExpr { prefix, arg_opt }) => {
+                let value = self.eval_expr(arg_opt.as_deref()?)?;
+                let result = match value {
+                    ConstValue::Int(value) => value
+                        .checked_neg()
+                        .map(ConstValue::Int)
+                        .ok_or(ConstEvalError::Overflow),
+                    ConstValue::Double(value) => Ok(ConstValue::Double(-value)),
+                    ConstValue::Str(_) => Err(ConstEvalError::NotConstant),
+                };
+                match result {
+                    Ok(value) => Some(value),
+                    Err(err) => self.report(err, prefix.body.loc),
+                }
+            }
+            PExpr::Infix(PInfixExpr {
+                left,
+                infix,
+                right_opt,
+            }) => {
+                let left = self.eval_expr(left)?;
+                let right = self.eval_expr(right_opt.as_deref()?)?;
+                match eval_infix(infix.body.kind, &left, &right) {
+                    Ok(value) => Some(value),
+                    Err(err) => self.report(err, infix.body.loc),
+                }
+            }
+        }
+    }
+}
+
+fn expr_doc(expr: &PExpr) -> DocId {
+    match expr {
+        PExpr::Literal(token) => token.body.loc.doc,
+        PExpr::Label(label) => label.star.body.loc.doc,
+        PExpr::Compound(compound) => compound.name().body.loc.doc,
+        PExpr::Paren(expr) => expr.left_paren.body.loc.doc,
+        PExpr::Prefix(expr) => expr.prefix.body.loc.doc,
+        PExpr::Infix(expr) => expr_doc(&expr.left),
+    }
+}
+
+/// 有効なドキュメントにある定数の定義を集めて、値を計算する準備をする。
+fn new_evaluator<'a>(an: &'a AnalyzerRef<'_>) -> Evaluator<'a> {
+    let mut collector = DefCollector {
+        defs: HashMap::new(),
+        prev_enum_opt: None,
+    };
+
+    for (&doc, da) in an.doc_analysis_map.iter() {
+        if !an.is_active_doc(doc) {
+            continue;
+        }
+
+        if let Some(root) = &da.tree_opt {
+            // `#enum` の番号はドキュメントごとに0から振る。
+            collector.prev_enum_opt = None;
+            collector.on_stmts(&root.stmts);
+        }
+    }
+
+    let use_site_map = an
+        .use_sites
+        .iter()
+        .map(|(symbol, loc)| ((loc.doc, loc.start()), symbol.clone()))
+        .collect::<HashMap<_, _>>();
+
+    Evaluator {
+        defs: collector.defs,
+        use_site_map,
+        values: HashMap::new(),
+        visiting: HashSet::new(),
+        errors: vec![],
+    }
+}
+
+/// 定数 (`#const`, `#enum`) の値を計算する。
+pub(crate) fn eval_const_symbol(an: &AnalyzerRef<'_>, symbol: &SymbolRc) -> Option<ConstValue> {
+    if !matches!(symbol.kind, HspSymbolKind::Const | HspSymbolKind::Enum) {
+        return None;
+    }
+
+    new_evaluator(an).eval_def(symbol.preproc_def_site_opt?)
+}
+
+/// すべての定数の値を計算して、計算の誤りを列挙する。
+pub(crate) fn collect_const_eval_errors(
+    an: &AnalyzerRef<'_>,
+    errors: &mut Vec<(ConstEvalError, Loc)>,
+) {
+    let mut evaluator = new_evaluator(an);
+
+    let mut locs = evaluator.defs.keys().copied().collect::<Vec<_>>();
+    locs.sort();
+    for loc in locs {
+        evaluator.eval_def(loc);
+    }
+
+    errors.extend(evaluator.errors);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_number() {
+        let int = |text: &str| match parse_number(text) {
+            Ok(ConstValue::Int(value)) => Ok(value),
+            Ok(_) => panic!("not int"),
+            Err(err) => Err(err),
+        };
+
+        assert_eq!(int("42"), Ok(42));
+        assert_eq!(int("$ff"), Ok(255));
+        assert_eq!(int("0xffffffff"), Ok(-1));
+        assert_eq!(int("0b101"), Ok(5));
+        assert_eq!(int("%1010"), Ok(10));
+        assert_eq!(int("3000000000"), Err(ConstEvalError::Overflow));
+        assert_eq!(int("0x100000000"), Err(ConstEvalError::Overflow));
+
+        // 数字がない、または基数に合わない数字がある
+        assert_eq!(int("0x"), Err(ConstEvalError::MalformedNumber));
+        assert_eq!(int("$"), Err(ConstEvalError::MalformedNumber));
+        assert_eq!(int("%"), Err(ConstEvalError::MalformedNumber));
+        assert_eq!(int("%102"), Err(ConstEvalError::MalformedNumber));
+    }
+}
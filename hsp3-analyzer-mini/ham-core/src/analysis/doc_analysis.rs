@@ -1,4 +1,5 @@
 use super::*;
+use crate::utils::read_file::FileEncoding;
 
 /// ドキュメント単位の解析結果
 #[derive(Default)]
@@ -6,6 +7,9 @@ pub(crate) struct DocAnalysis {
     pub(crate) doc_opt: Option<DocId>,
     pub(crate) text: RcStr,
 
+    /// 保存されているファイルの文字コード
+    pub(crate) encoding_opt: Option<FileEncoding>,
+
     // 構文:
     pub(crate) tokens: RcSlice<PToken>,
     pub(crate) tree_opt: Option<PRoot>,
@@ -24,9 +28,15 @@ pub(crate) struct DocAnalysis {
 
 impl DocAnalysis {
     pub(crate) fn compute(&mut self, doc: DocId, text: RcStr) {
-        let tokens = crate::token::tokenize(doc, text.clone());
-        let p_tokens: RcSlice<_> = PToken::from_tokens(tokens.into()).into();
-        let root = crate::parse::parse_root(p_tokens.to_owned());
+        let (p_tokens, root) = match self.reparse(doc, &text) {
+            Some(it) => it,
+            None => {
+                let tokens = crate::token::tokenize(doc, text.clone());
+                let p_tokens: RcSlice<_> = PToken::from_tokens(tokens.into()).into();
+                let root = crate::parse::parse_root(p_tokens.to_owned());
+                (p_tokens, root)
+            }
+        };
         let preproc = crate::analysis::preproc::analyze_preproc(doc, &root);
 
         self.doc_opt = Some(doc);
@@ -34,6 +44,17 @@ impl DocAnalysis {
         self.set_preproc(preproc);
     }
 
+    /// 前回の構文木を再利用して、変更された文だけを解析しなおす。
+    fn reparse(&mut self, doc: DocId, text: &RcStr) -> Option<(RcSlice<PToken>, PRoot)> {
+        if self.doc_opt != Some(doc) {
+            return None;
+        }
+
+        let root = self.tree_opt.take()?;
+        let (tokens, root) = crate::parse::reparse(doc, &self.text, &self.tokens, root, text)?;
+        Some((tokens.into(), root))
+    }
+
     fn set_syntax(&mut self, text: RcStr, tokens: RcSlice<PToken>, tree: PRoot) {
         self.text = text;
         self.tokens = tokens;
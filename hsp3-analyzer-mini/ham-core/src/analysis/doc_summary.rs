@@ -0,0 +1,363 @@
+//! ドキュメントの解析結果の要約
+//!
+//! プリプロセッサ命令の解析結果 (シンボル、シグネチャ、モジュールなど) を、
+//! ディスクに保存できる形で表したもの。(`analyzer::index_cache` を参照)
+//!
+//! DocIdは起動するたびに変わるため、要約には含めない。復元するときに指定する。
+
+use super::*;
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+
+/// 位置 (index, row, column, column16)
+type SummaryPos = (u32, u32, u32, u32);
+
+/// 範囲 (始点と終点)
+type SummaryRange = (SummaryPos, SummaryPos);
+
+fn to_summary_range(range: Range) -> SummaryRange {
+    let pos = |p: Pos| (p.index, p.row, p.column, p.column16);
+    (pos(range.start()), pos(range.end()))
+}
+
+fn from_summary_range(doc: DocId, (start, end): SummaryRange) -> Loc {
+    let pos = |(index, row, column, column16): SummaryPos| Pos::new(index, row, column, column16);
+    Loc::new3(doc, pos(start), pos(end))
+}
+
+/// スコープ (モジュールと `#deffunc` はドキュメント内の番号で表す)
+#[derive(Clone, Serialize, Deserialize)]
+enum SummaryScope {
+    Global,
+    Local {
+        module_opt: Option<usize>,
+        deffunc_opt: Option<usize>,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SummarySignature {
+    name: String,
+    params: Vec<(Option<PParamTy>, Option<String>, Option<String>)>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct SummarySymbol {
+    kind: HspSymbolKind,
+    name: String,
+    scope_opt: Option<SummaryScope>,
+    ns_opt: Option<String>,
+    desc_opt: Option<String>,
+    docs: Vec<String>,
+    signature_opt: Option<SummarySignature>,
+    def_site: SummaryRange,
+}
+
+/// ドキュメントの解析結果の要約
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct DocSummary {
+    symbols: Vec<SummarySymbol>,
+    include_guard_opt: Option<SummaryRange>,
+    includes: Vec<(String, SummaryRange)>,
+    uses: Vec<(String, SummaryRange)>,
+    /// (番号, 名前, 範囲)
+    modules: Vec<(usize, Option<String>, SummaryRange)>,
+    /// (番号, 範囲)
+    deffuncs: Vec<(usize, SummaryRange)>,
+}
+
+impl DocSummary {
+    /// 解析結果を要約する。(要約できないシンボルがあればNone)
+    pub(crate) fn new(da: &DocAnalysis) -> Option<Self> {
+        let symbols = da
+            .preproc_symbols
+            .iter()
+            .map(|symbol| {
+                let scope_opt = symbol.scope_opt.as_ref().map(|scope| match scope {
+                    Scope::Global => SummaryScope::Global,
+                    Scope::Local(local) => SummaryScope::Local {
+                        module_opt: local.module_opt.map(|m| m.index),
+                        deffunc_opt: local.deffunc_opt.map(|d| d.index),
+                    },
+                });
+                let details = symbol.leader_details();
+                let signature_opt = symbol.signature_opt().map(|s| SummarySignature {
+                    name: s.name.to_string(),
+                    params: s
+                        .params
+                        .iter()
+                        .map(|(ty_opt, name_opt, details_opt)| {
+                            (
+                                *ty_opt,
+                                name_opt.as_ref().map(|s| s.to_string()),
+                                details_opt.clone(),
+                            )
+                        })
+                        .collect(),
+                });
+
+                Some(SummarySymbol {
+                    kind: symbol.kind,
+                    name: symbol.name.to_string(),
+                    scope_opt,
+                    ns_opt: symbol.ns_opt.as_ref().map(|s| s.to_string()),
+                    desc_opt: details.desc.map(|s| s.to_string()),
+                    docs: details.docs,
+                    signature_opt,
+                    def_site: to_summary_range(symbol.preproc_def_site_opt?.range),
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut modules = da
+            .module_map
+            .iter()
+            .map(|(m, data)| {
+                (
+                    m.index,
+                    data.name_opt.as_ref().map(|s| s.to_string()),
+                    to_summary_range(data.content_loc.range),
+                )
+            })
+            .collect::<Vec<_>>();
+        modules.sort_by_key(|&(index, _, _)| index);
+
+        let mut deffuncs = da
+            .deffunc_map
+            .iter()
+            .map(|(d, data)| (d.index, to_summary_range(data.content_loc.range)))
+            .collect::<Vec<_>>();
+        deffuncs.sort_by_key(|&(index, _)| index);
+
+        Some(DocSummary {
+            symbols,
+            include_guard_opt: da
+                .include_guard
+                .as_ref()
+                .map(|g| to_summary_range(g.loc.range)),
+            includes: da
+                .includes
+                .iter()
+                .map(|(name, loc)| (name.to_string(), to_summary_range(loc.range)))
+                .collect(),
+            uses: da
+                .uses
+                .iter()
+                .map(|(name, loc)| (name.clone(), to_summary_range(loc.range)))
+                .collect(),
+            modules,
+            deffuncs,
+        })
+    }
+
+    /// 要約から解析結果を復元する。
+    ///
+    /// (構文木は復元されない。`tree_opt` はNoneになる。)
+    pub(crate) fn restore(self, doc: DocId) -> DocAnalysis {
+        let preproc_symbols = self
+            .symbols
+            .into_iter()
+            .map(|symbol| {
+                let scope_opt = symbol.scope_opt.map(|scope| match scope {
+                    SummaryScope::Global => Scope::Global,
+                    SummaryScope::Local {
+                        module_opt,
+                        deffunc_opt,
+                    } => Scope::Local(LocalScope {
+                        module_opt: module_opt.map(|index| ModuleKey::new(doc, index)),
+                        deffunc_opt: deffunc_opt.map(|index| DefFuncKey::new(doc, index)),
+                    }),
+                });
+                let signature_opt = symbol.signature_opt.map(|s| {
+                    Rc::new(SignatureData {
+                        name: s.name.into(),
+                        params: s
+                            .params
+                            .into_iter()
+                            .map(|(ty_opt, name_opt, details_opt)| {
+                                (ty_opt, name_opt.map(RcStr::from), details_opt)
+                            })
+                            .collect(),
+                    })
+                });
+
+                DefInfo::Cached {
+                    kind: symbol.kind,
+                    basename: symbol.name.into(),
+                    scope_opt,
+                    ns_opt: symbol.ns_opt.map(RcStr::from),
+                    details: SymbolDetails {
+                        desc: symbol.desc_opt.map(RcStr::from),
+                        docs: symbol.docs,
+                    },
+                    signature_opt,
+                    loc: from_summary_range(doc, symbol.def_site),
+                }
+                .into_symbol()
+            })
+            .collect();
+
+        DocAnalysis {
+            doc_opt: Some(doc),
+            include_guard: self.include_guard_opt.map(|range| IncludeGuard {
+                loc: from_summary_range(doc, range),
+            }),
+            includes: self
+                .includes
+                .into_iter()
+                .map(|(name, range)| (name.into(), from_summary_range(doc, range)))
+                .collect(),
+            uses: self
+                .uses
+                .into_iter()
+                .map(|(name, range)| (name, from_summary_range(doc, range)))
+                .collect(),
+            module_map: self
+                .modules
+                .into_iter()
+                .map(|(index, name_opt, range)| {
+                    (
+                        ModuleKey::new(doc, index),
+                        ModuleRc::new(ModuleData {
+                            name_opt: name_opt.map(RcStr::from),
+                            content_loc: from_summary_range(doc, range),
+                        }),
+                    )
+                })
+                .collect(),
+            deffunc_map: self
+                .deffuncs
+                .into_iter()
+                .map(|(index, range)| {
+                    (
+                        DefFuncKey::new(doc, index),
+                        DefFuncData {
+                            content_loc: from_summary_range(doc, range),
+                        },
+                    )
+                })
+                .collect(),
+            preproc_symbols,
+            ..DocAnalysis::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore() {
+        let text = r#"#ifndef included
+#define included
+#include "hspext.as"
+#use mod_b
+
+#module m_a x
+;; 挨拶する
+;;
+;; s: 名前
+#deffunc a_hello str s
+    return
+
+#defcfunc local a_len
+    return 0
+#global
+
+#const global A_MAX 10
+#endif
+"#;
+        let mut da = DocAnalysis::default();
+        da.compute(1, text.into());
+
+        let summary = DocSummary::new(&da).unwrap();
+        let json = serde_json::to_string(&summary).unwrap();
+        let restored = serde_json::from_str::<DocSummary>(&json)
+            .unwrap()
+            .restore(2);
+
+        let format = |da: &DocAnalysis| {
+            let mut symbols = da
+                .preproc_symbols
+                .iter()
+                .map(|s| {
+                    let details = s.compute_details();
+                    format!(
+                        "{:?} {:?} scope={:?} ns={:?} def={:?} desc={:?} docs={:?} sig={:?}",
+                        s.kind,
+                        s.name,
+                        s.scope_opt.as_ref().map(|scope| match scope {
+                            Scope::Global => None,
+                            Scope::Local(local) => Some((
+                                local.module_opt.map(|m| m.index),
+                                local.deffunc_opt.map(|d| d.index),
+                            )),
+                        }),
+                        s.ns_opt,
+                        s.preproc_def_site_opt.map(|loc| loc.range),
+                        details.desc,
+                        details.docs,
+                        s.signature_opt().map(|s| {
+                            (
+                                s.name.to_string(),
+                                s.params
+                                    .iter()
+                                    .map(|(ty, name, details)| {
+                                        (*ty, name.as_ref().map(|s| s.to_string()), details.clone())
+                                    })
+                                    .collect::<Vec<_>>(),
+                            )
+                        }),
+                    )
+                })
+                .collect::<Vec<_>>();
+            symbols.sort();
+
+            let mut modules = da
+                .module_map
+                .iter()
+                .map(|(m, data)| {
+                    format!(
+                        "{} {:?} {:?}",
+                        m.index, data.name_opt, data.content_loc.range
+                    )
+                })
+                .collect::<Vec<_>>();
+            modules.sort();
+
+            let mut deffuncs = da
+                .deffunc_map
+                .iter()
+                .map(|(d, data)| format!("{} {:?}", d.index, data.content_loc.range))
+                .collect::<Vec<_>>();
+            deffuncs.sort();
+
+            format!(
+                "{:#?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}",
+                symbols,
+                modules,
+                deffuncs,
+                da.include_guard.as_ref().map(|g| g.loc.range),
+                da.includes
+                    .iter()
+                    .map(|(name, loc)| (name, loc.range))
+                    .collect::<Vec<_>>(),
+                da.uses
+                    .iter()
+                    .map(|(name, loc)| (name, loc.range))
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        // DocIdを除いて、元の解析結果と一致する。
+        assert_eq!(format(&restored), format(&da));
+        assert_eq!(restored.doc_opt, Some(2));
+        assert!(restored.tree_opt.is_none());
+        assert!(restored
+            .preproc_symbols
+            .iter()
+            .all(|s| s.preproc_def_site_opt.unwrap().doc == 2));
+        assert!(restored.preproc_symbols.len() >= 5);
+    }
+}
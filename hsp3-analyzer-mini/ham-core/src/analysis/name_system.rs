@@ -43,7 +43,20 @@ impl NamePath {
     }
 }
 
+/// 名前を比較するためのキー
+///
+/// HSPの識別子は大文字・小文字を区別しない。(hspcmpと同様に、ASCIIの範囲だけ小文字に揃える。)
+pub(crate) fn name_key(name: &RcStr) -> RcStr {
+    if name.bytes().any(|b| b.is_ascii_uppercase()) {
+        name.to_ascii_lowercase().into()
+    } else {
+        name.clone()
+    }
+}
+
 /// 環境。名前からシンボルへのマップ。
+///
+/// (キーは `name_key` で正規化したもの。)
 #[derive(Clone, Debug, Default)]
 pub(crate) struct SymbolEnv {
     map: HashMap<RcStr, SymbolRc>,
@@ -51,11 +64,14 @@ pub(crate) struct SymbolEnv {
 
 impl SymbolEnv {
     pub(crate) fn get(&self, name: &str) -> Option<SymbolRc> {
+        if name.bytes().any(|b| b.is_ascii_uppercase()) {
+            return self.map.get(name.to_ascii_lowercase().as_str()).cloned();
+        }
         self.map.get(name).cloned()
     }
 
     pub(crate) fn insert(&mut self, name: RcStr, symbol: SymbolRc) {
-        self.map.insert(name, symbol);
+        self.map.insert(name_key(&name), symbol);
     }
 
     pub(crate) fn clear(&mut self) {
@@ -64,6 +80,8 @@ impl SymbolEnv {
 }
 
 /// 名前空間
+///
+/// (キーは `name_key` で正規化した名前空間の名前。)
 pub(crate) type NsEnv = HashMap<RcStr, SymbolEnv>;
 
 #[derive(Clone, Default)]
@@ -153,7 +171,7 @@ pub(crate) struct NameScopeNsTriple {
 }
 
 fn module_name(m: ModuleKey, module_map: &ModuleMap) -> Option<RcStr> {
-    module_map.get(&m)?.name_opt.clone()
+    module_map.get(&m)?.name_opt.as_ref().map(name_key)
 }
 
 /// 定義箇所の名前に関連付けられるスコープと名前空間を決定する。
@@ -185,7 +203,7 @@ pub(crate) fn resolve_name_scope_ns_for_def(
 
     let ns_opt: Option<RcStr> = match (qual, mode, &local.module_opt) {
         (_, ImportMode::Param, _) => None,
-        (Qual::Module(ns), _, _) => Some(ns),
+        (Qual::Module(ns), _, _) => Some(name_key(&ns)),
         (Qual::Toplevel, _, _)
         | (Qual::Unqualified, ImportMode::Global, _)
         | (Qual::Unqualified, ImportMode::Local, None) => Some("".into()),
@@ -214,7 +232,7 @@ pub(crate) fn resolve_name_scope_ns_for_use(
     };
 
     let ns_opt: Option<RcStr> = match (qual, &local.module_opt) {
-        (Qual::Module(ns), _) => Some(ns),
+        (Qual::Module(ns), _) => Some(name_key(&ns)),
         (Qual::Toplevel, _) | (Qual::Unqualified, None) => Some("".into()),
         (Qual::Unqualified, Some(m)) => module_name(*m, module_map),
     };
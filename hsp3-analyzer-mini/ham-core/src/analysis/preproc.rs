@@ -399,7 +399,7 @@ fn new_signature_data_for_lib_func(stmt: &PLibFuncStmt) -> Option<SignatureData>
     Some(SignatureData { name, params })
 }
 
-fn new_signature_data_for_deffunc(stmt: &PDefFuncStmt) -> Option<SignatureData> {
+pub(crate) fn new_signature_data_for_deffunc(stmt: &PDefFuncStmt) -> Option<SignatureData> {
     let take_modvar = match stmt.kind {
         PDefFuncKind::DefFunc | PDefFuncKind::DefCFunc => false,
         PDefFuncKind::ModFunc | PDefFuncKind::ModCFunc => true,
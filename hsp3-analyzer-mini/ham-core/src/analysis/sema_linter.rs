@@ -9,6 +9,12 @@ pub(crate) enum Diagnostic {
     Undefined,
     /// 変数か配列要素が必要です
     VarRequired,
+    /// 書式指定の数と引数の数が一致しません (書式指定の数, 引数の数)
+    FormatArgCount(usize, usize),
+    /// 数値の書式指定に文字列が渡されています (書式指定)
+    FormatArgType(String),
+    /// 引数が多すぎます (パラメータの数)
+    TooManyArgs(usize),
 }
 
 type UseSiteMap = HashMap<(DocId, Pos), SymbolRc>;
@@ -23,6 +29,8 @@ impl SemaLinter {
         for stmt in &root.stmts {
             on_stmt(stmt, self)
         }
+
+        CallLinter { ctx: self }.on_root(root);
     }
 
     fn symbol(&self, loc: Loc) -> Option<SymbolRc> {
@@ -116,3 +124,219 @@ fn arg_is_definitely_rval(arg: &PArg, ctx: &SemaLinter) -> bool {
     }
     false
 }
+
+// -----------------------------------------------
+// 呼び出しの検査
+// -----------------------------------------------
+
+/// 命令・関数の呼び出しの引数を検査するもの
+struct CallLinter<'a> {
+    ctx: &'a mut SemaLinter,
+}
+
+impl PVisitor for CallLinter<'_> {
+    fn on_compound(&mut self, compound: &PCompound) {
+        if let PCompound::Paren(np) = compound {
+            on_call(&np.name, &np.args, self.ctx);
+        }
+        self.on_compound_default(compound);
+    }
+
+    fn on_stmt(&mut self, stmt: &PStmt) {
+        if let PStmt::Command(stmt) = stmt {
+            on_call(&stmt.command, &stmt.args, self.ctx);
+        }
+        self.on_stmt_default(stmt);
+    }
+}
+
+/// 標準命令・関数の名前とみなせるか？
+///
+/// (スクリプト内で `#deffunc` や `#define` などにより定義されたものでなければ、標準のものとみなす。)
+fn is_builtin_name(symbol_opt: Option<&SymbolRc>) -> bool {
+    match symbol_opt {
+        Some(symbol) => symbol.preproc_def_site_opt.is_none(),
+        None => true,
+    }
+}
+
+fn on_call(callee: &PToken, args: &[PArg], ctx: &mut SemaLinter) {
+    let symbol_opt = ctx.symbol(callee.body.loc);
+
+    if !is_builtin_name(symbol_opt.as_ref()) {
+        return;
+    }
+
+    if name_key(&callee.body.text).as_str() == "strf" {
+        lint_format_args(args, ctx);
+    }
+
+    if let Some(symbol) = symbol_opt.filter(|symbol| symbol.builtin) {
+        lint_builtin_arity(callee, args, &symbol, ctx);
+    }
+}
+
+/// 標準命令・関数の引数の個数を `%prm` のパラメータの個数と比較する。
+fn lint_builtin_arity(callee: &PToken, args: &[PArg], symbol: &SymbolRc, ctx: &mut SemaLinter) {
+    let signature_data = match symbol.signature_opt() {
+        Some(it) => it,
+        None => return,
+    };
+
+    // `%prm` の先頭行が空のときはパラメータなし。
+    let params = signature_data
+        .params
+        .iter()
+        .filter_map(|(_, name_opt, _)| name_opt.as_ref())
+        .filter(|name| !name.trim().is_empty())
+        .collect::<Vec<_>>();
+
+    // 可変長の引数をとるものは検査しない。
+    if params
+        .iter()
+        .any(|name| name.contains("...") || name.contains('…'))
+    {
+        return;
+    }
+
+    if args.len() <= params.len() {
+        return;
+    }
+
+    let range = match &args[params.len()].expr_opt {
+        Some(expr) => expr.compute_range(),
+        None => callee.body.loc.range,
+    };
+    let loc = callee.body.loc.with_range(range);
+    ctx.diagnostics
+        .push((Diagnostic::TooManyArgs(params.len()), loc));
+}
+
+/// 書式指定が受け取る値の種類
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ArgTy {
+    Int,
+    Double,
+    Str,
+}
+
+/// 書式指定文字列 (`strf` の第1引数) に含まれる書式指定を列挙する。
+fn parse_format(text: &str, specs: &mut Vec<(String, ArgTy)>) {
+    let mut chars = text.char_indices();
+    while let Some((start, c)) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+
+        // フラグ、幅、精度、長さ修飾子を読み飛ばす。
+        let mut end = start + 1;
+        let mut conversion = None;
+        for (i, c) in chars.by_ref() {
+            end = i + c.len_utf8();
+            match c {
+                '-' | '+' | ' ' | '#' | '.' | '0'..='9' | 'l' | 'h' | 'I' => continue,
+                _ => {
+                    conversion = Some(c);
+                    break;
+                }
+            }
+        }
+
+        let ty = match conversion {
+            Some('d' | 'i' | 'u' | 'o' | 'x' | 'X' | 'c' | 'p') => ArgTy::Int,
+            Some('e' | 'E' | 'f' | 'g' | 'G' | 'a' | 'A') => ArgTy::Double,
+            Some('s') => ArgTy::Str,
+            // `%%` など
+            _ => continue,
+        };
+        specs.push((text[start..end].to_string(), ty));
+    }
+}
+
+/// 式の値の種類を推測する。
+fn infer_arg_ty(expr: &PExpr, ctx: &SemaLinter) -> Option<ArgTy> {
+    match expr {
+        PExpr::Literal(token) => match token.body.kind {
+            TokenKind::Str => Some(ArgTy::Str),
+            TokenKind::Char => Some(ArgTy::Int),
+            TokenKind::Number => {
+                let text = token.body.text.as_str();
+                let is_double = !text.starts_with('$')
+                    && !text.starts_with("0x")
+                    && !text.starts_with("0X")
+                    && text.contains(['.', 'e', 'E']);
+                Some(if is_double { ArgTy::Double } else { ArgTy::Int })
+            }
+            _ => None,
+        },
+        PExpr::Compound(compound) => {
+            let name = &compound.name().body;
+            let symbol = ctx.symbol(name.loc);
+            match symbol.as_ref().map(|symbol| symbol.kind) {
+                Some(HspSymbolKind::Param(Some(param))) => match param {
+                    PParamTy::Str => Some(ArgTy::Str),
+                    PParamTy::Int => Some(ArgTy::Int),
+                    PParamTy::Double => Some(ArgTy::Double),
+                    _ => None,
+                },
+                _ if is_builtin_name(symbol.as_ref()) => match compound {
+                    // 文字列を返す標準関数
+                    PCompound::Paren(_) => match name_key(&name.text).as_str() {
+                        "str" | "strf" | "strmid" | "strtrim" | "getpath" | "cnvwtos"
+                        | "dirinfo" => Some(ArgTy::Str),
+                        "int" | "strlen" | "instr" | "length" => Some(ArgTy::Int),
+                        "double" | "sqrt" | "sin" | "cos" | "tan" | "atan" | "expf" | "logf"
+                        | "powf" | "absf" => Some(ArgTy::Double),
+                        _ => None,
+                    },
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        PExpr::Paren(expr) => infer_arg_ty(expr.body_opt.as_deref()?, ctx),
+        PExpr::Prefix(expr) => match infer_arg_ty(expr.arg_opt.as_deref()?, ctx)? {
+            ArgTy::Str => None,
+            ty => Some(ty),
+        },
+        // 二項演算の値の種類は左辺の種類になる。
+        PExpr::Infix(expr) => infer_arg_ty(&expr.left, ctx),
+        PExpr::Label(_) => None,
+    }
+}
+
+/// `strf` の書式指定と引数を比較する。
+fn lint_format_args(args: &[PArg], ctx: &mut SemaLinter) {
+    let (format, rest) = match args.split_first() {
+        Some(it) => it,
+        None => return,
+    };
+
+    // 書式指定が文字列リテラルのときだけ検査する。
+    let format_token = match &format.expr_opt {
+        Some(PExpr::Literal(token)) if token.body.kind == TokenKind::Str => token,
+        _ => return,
+    };
+
+    let mut specs = vec![];
+    parse_format(format_token.body_text(), &mut specs);
+
+    if specs.len() != rest.len() {
+        ctx.diagnostics.push((
+            Diagnostic::FormatArgCount(specs.len(), rest.len()),
+            format_token.body.loc,
+        ));
+    }
+
+    for ((spec, ty), arg) in specs.into_iter().zip(rest) {
+        let expr = match &arg.expr_opt {
+            Some(it) => it,
+            None => continue,
+        };
+
+        if ty != ArgTy::Str && infer_arg_ty(expr, ctx) == Some(ArgTy::Str) {
+            let loc = format_token.body.loc.with_range(expr.compute_range());
+            ctx.diagnostics.push((Diagnostic::FormatArgType(spec), loc));
+        }
+    }
+}
@@ -1,8 +1,13 @@
 use super::comment::*;
 use super::*;
-use crate::parse::PParamTy;
-
-#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash)]
+use crate::{
+    hsp_version::HspVersion,
+    i18n::{tr, Locale},
+    parse::PParamTy,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub(crate) enum HspSymbolKind {
     /// 定義箇所がみつからない。
     Unresolved,
@@ -42,28 +47,28 @@ pub(crate) enum HspSymbolKind {
 }
 
 impl HspSymbolKind {
-    pub(crate) fn as_str(self) -> &'static str {
+    pub(crate) fn as_str(self, locale: Locale) -> &'static str {
         match self {
-            HspSymbolKind::Unresolved => "未解決",
-            HspSymbolKind::Unknown => "不明",
-            HspSymbolKind::Const => "定数",
-            HspSymbolKind::Enum => "列挙子",
-            HspSymbolKind::Macro { ctype: false } => "マクロ",
-            HspSymbolKind::Macro { ctype: true } => "関数形式マクロ",
-            HspSymbolKind::DefFunc => "命令",
-            HspSymbolKind::DefCFunc => "関数",
-            HspSymbolKind::ModFunc => "命令(モジュール変数)",
-            HspSymbolKind::ModCFunc => "関数(モジュール変数)",
-            HspSymbolKind::Param(None) => "パラメータ",
+            HspSymbolKind::Unresolved => tr(locale, "symbol.unresolved"),
+            HspSymbolKind::Unknown => tr(locale, "symbol.unknown"),
+            HspSymbolKind::Const => tr(locale, "symbol.const"),
+            HspSymbolKind::Enum => tr(locale, "symbol.enum"),
+            HspSymbolKind::Macro { ctype: false } => tr(locale, "symbol.macro"),
+            HspSymbolKind::Macro { ctype: true } => tr(locale, "symbol.ctype_macro"),
+            HspSymbolKind::DefFunc => tr(locale, "symbol.deffunc"),
+            HspSymbolKind::DefCFunc => tr(locale, "symbol.defcfunc"),
+            HspSymbolKind::ModFunc => tr(locale, "symbol.modfunc"),
+            HspSymbolKind::ModCFunc => tr(locale, "symbol.modcfunc"),
+            HspSymbolKind::Param(None) => tr(locale, "symbol.param"),
             HspSymbolKind::Param(Some(param)) => param.to_str(),
-            HspSymbolKind::LibFunc => "ライブラリ関数",
-            HspSymbolKind::PluginCmd => "プラグインコマンド",
-            HspSymbolKind::Module => "モジュール",
-            HspSymbolKind::Field => "モジュール変数",
-            HspSymbolKind::Label => "ラベル",
-            HspSymbolKind::StaticVar => "変数",
-            HspSymbolKind::ComInterface => "COMインターフェイス",
-            HspSymbolKind::ComFunc => "COMメソッド",
+            HspSymbolKind::LibFunc => tr(locale, "symbol.libfunc"),
+            HspSymbolKind::PluginCmd => tr(locale, "symbol.plugin_cmd"),
+            HspSymbolKind::Module => tr(locale, "symbol.module"),
+            HspSymbolKind::Field => tr(locale, "symbol.field"),
+            HspSymbolKind::Label => tr(locale, "symbol.label"),
+            HspSymbolKind::StaticVar => tr(locale, "symbol.static_var"),
+            HspSymbolKind::ComInterface => tr(locale, "symbol.com_interface"),
+            HspSymbolKind::ComFunc => tr(locale, "symbol.com_func"),
         }
     }
 }
@@ -111,6 +116,15 @@ impl SymbolRc {
             };
         }
 
+        self.leader_details()
+    }
+
+    /// 定義の直前にあるコメントから得られる説明
+    pub(crate) fn leader_details(&self) -> SymbolDetails {
+        if let Some(details) = self.leader_details_opt.as_ref() {
+            return details.clone();
+        }
+
         match &self.leader_opt {
             Some(leader) => calculate_details(&collect_comments(leader)),
             None => SymbolDetails::default(),
@@ -159,6 +173,7 @@ pub(crate) enum DefInfo {
         details: SymbolDetails,
         builtin: bool,
         signature_opt: Option<Rc<SignatureData>>,
+        since_opt: Option<HspVersion>,
     },
     Preproc {
         kind: HspSymbolKind,
@@ -175,6 +190,16 @@ pub(crate) enum DefInfo {
         scope_opt: Option<Scope>,
         ns_opt: Option<RcStr>,
     },
+    /// 解析結果のキャッシュから復元したもの (`DocSummary` を参照)
+    Cached {
+        kind: HspSymbolKind,
+        basename: RcStr,
+        scope_opt: Option<Scope>,
+        ns_opt: Option<RcStr>,
+        details: SymbolDetails,
+        signature_opt: Option<Rc<SignatureData>>,
+        loc: Loc,
+    },
 }
 
 impl DefInfo {
@@ -185,14 +210,17 @@ impl DefInfo {
                 details,
                 builtin,
                 signature_opt,
+                since_opt,
             } => SymbolData {
                 kind: HspSymbolKind::Unknown,
                 name,
                 scope_opt: None,
                 ns_opt: None,
                 leader_opt: None,
+                leader_details_opt: None,
                 details_opt: Some(details),
                 builtin,
+                since_opt,
 
                 preproc_def_site_opt: None,
                 signature_opt: RefCell::new(signature_opt),
@@ -211,7 +239,9 @@ impl DefInfo {
                 scope_opt,
                 ns_opt,
                 leader_opt: Some(leader),
+                leader_details_opt: None,
                 builtin: false,
+                since_opt: None,
 
                 details_opt: None,
                 preproc_def_site_opt: Some(loc),
@@ -230,13 +260,38 @@ impl DefInfo {
                 scope_opt,
                 ns_opt,
                 leader_opt: Some(name),
+                leader_details_opt: None,
                 builtin: false,
+                since_opt: None,
 
                 details_opt: None,
                 preproc_def_site_opt: None,
                 signature_opt: Default::default(),
                 linked_symbol_opt: Default::default(),
             },
+            DefInfo::Cached {
+                kind,
+                basename,
+                scope_opt,
+                ns_opt,
+                details,
+                signature_opt,
+                loc,
+            } => SymbolData {
+                kind,
+                name: basename,
+                scope_opt,
+                ns_opt,
+                leader_opt: None,
+                leader_details_opt: Some(details),
+                builtin: false,
+                since_opt: None,
+
+                details_opt: None,
+                preproc_def_site_opt: Some(loc),
+                signature_opt: RefCell::new(signature_opt),
+                linked_symbol_opt: Default::default(),
+            },
         };
         SymbolRc::from(symbol_data)
     }
@@ -248,9 +303,12 @@ pub(crate) struct SymbolData {
     pub(crate) scope_opt: Option<Scope>,
     pub(crate) ns_opt: Option<RcStr>,
     leader_opt: Option<PToken>,
+    /// 定義の直前にあるコメントから得られる説明 (キャッシュから復元したシンボルは、`leader_opt` の代わりにこれを持つ)
+    leader_details_opt: Option<SymbolDetails>,
     /// 標準命令か
-    #[allow(unused)]
     pub(crate) builtin: bool,
+    /// このシンボルが使えるようになったHSPの版 (ヘルプファイルの `%ver` から)
+    pub(crate) since_opt: Option<HspVersion>,
 
     details_opt: Option<SymbolDetails>,
     pub(crate) preproc_def_site_opt: Option<Loc>,
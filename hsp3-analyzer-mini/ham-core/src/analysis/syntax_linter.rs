@@ -1,7 +1,10 @@
 //! 構文リント
 
 use super::*;
-use crate::parse::*;
+use crate::{
+    i18n::{tr, Locale},
+    parse::*,
+};
 
 #[derive(Clone)]
 pub(crate) enum SyntaxLint {
@@ -9,9 +12,9 @@ pub(crate) enum SyntaxLint {
 }
 
 impl SyntaxLint {
-    pub(crate) fn as_str(self) -> &'static str {
+    pub(crate) fn as_str(self, locale: Locale) -> &'static str {
         match self {
-            SyntaxLint::ReturnInLoop => "repeatループの中ではreturnできません。",
+            SyntaxLint::ReturnInLoop => tr(locale, "lint.return_in_loop"),
         }
     }
 }
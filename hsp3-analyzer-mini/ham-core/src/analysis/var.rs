@@ -23,6 +23,11 @@ struct Ctx<'a> {
     deffunc_len: usize,
     module_len: usize,
     scope: LocalScope,
+
+    /// 定数式 (`#const` などの値) の中か？
+    ///
+    /// (定数式の中の名前は参照として解決するだけで、未定義でも暗黙の変数にしない。)
+    in_const_expr: bool,
 }
 
 const DEF_SITE: bool = true;
@@ -108,6 +113,7 @@ fn on_symbol_use(name: &PToken, is_var: bool, ctx: &mut Ctx) {
         Some(symbol) => {
             ctx.public_use_sites.push((symbol, name.body.loc));
         }
+        None if ctx.in_const_expr => {}
         None => {
             let kind = if is_var {
                 HspSymbolKind::StaticVar
@@ -321,9 +327,12 @@ fn on_stmt(stmt: &PStmt, ctx: &mut Ctx) {
 
             ctx.scope = parent_scope;
         }
-        PStmt::Const(_)
-        | PStmt::Define(_)
-        | PStmt::Enum(_)
+        PStmt::Const(PConstStmt { init_opt, .. }) | PStmt::Enum(PEnumStmt { init_opt, .. }) => {
+            ctx.in_const_expr = true;
+            on_expr_opt(init_opt.as_ref(), ctx);
+            ctx.in_const_expr = false;
+        }
+        PStmt::Define(_)
         | PStmt::UseLib(_)
         | PStmt::LibFunc(_)
         | PStmt::UseCom(_)
@@ -362,6 +371,7 @@ pub(crate) fn analyze_var_def(
         deffunc_len: 0,
         module_len: 0,
         scope: LocalScope::default(),
+        in_const_expr: false,
     };
 
     for stmt in &root.stmts {
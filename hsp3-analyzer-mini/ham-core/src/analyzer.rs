@@ -1,8 +1,12 @@
 pub(crate) mod doc_change;
 pub(crate) mod doc_interner;
 pub(crate) mod docs;
-mod file_scan;
+mod file_poll;
+pub(crate) mod file_scan;
+pub(crate) mod hsp3_root;
+pub(crate) mod index_cache;
 pub(crate) mod search_hsphelp;
+pub(crate) mod vfs;
 
 use super::*;
 use crate::{
@@ -11,13 +15,19 @@ use crate::{
         doc_change::{DocChange, DocChangeOrigin},
         doc_interner::DocInterner,
         docs::Docs,
-        search_hsphelp::{search_hsphelp, HspHelpInfo},
+        file_poll::{FileChange, FilePoller},
+        file_scan::FileExtensions,
+        hsp3_root::{resolve_target, Hsp3Root, HspTarget, WorkspaceConfig},
+        search_hsphelp::HspHelpInfo,
+        vfs::{Vfs, VfsRc},
     },
     help_source::HsSymbol,
-    ide,
+    hsp_version::HspVersion,
+    i18n::Locale,
+    ide::{self, lsp::convert_positions::PositionConverter},
     lang::Lang,
-    source::{DocId, Loc, Pos16},
-    utils::read_file::read_file,
+    source::{DocId, Loc, Pos16, PositionEncoding},
+    utils::read_file::{decode_contents, decode_sjis_contents, FileEncoding},
 };
 use lsp_types::*;
 
@@ -25,12 +35,26 @@ use lsp_types::*;
 #[derive(Default)]
 pub(super) struct Analyzer {
     // 入力 (起動時):
-    hsp3_root: PathBuf,
+    /// ファイルの読み込みなどに使うファイルシステム
+    vfs: VfsRc,
     workspace_folders: Vec<CanonicalUri>,
+    /// ワークスペースで探索するファイルの拡張子
+    file_extensions: FileExtensions,
+    /// クライアントが使う列番号の単位
+    position_encoding: PositionEncoding,
+    /// メッセージの表示言語
+    locale: Locale,
 
     // 状態 (ファイルスキャンの結果):
-    pub(crate) common_docs: HashMap<String, DocId>,
-    pub(crate) hsphelp_info: HspHelpInfo,
+    /// HSPのインストールディレクトリごとの情報
+    ///
+    /// (先頭は起動時に指定されたもので、ワークスペースの設定に書かれたものが後ろに追加される。
+    ///  インストールディレクトリの指定がなければ、先頭は同梱のデータベースを使う。)
+    pub(crate) hsp3_roots: Vec<Hsp3Root>,
+    /// ワークスペースのディレクトリと、そこにあるファイルが対象とするHSP
+    folder_targets: Vec<(PathBuf, HspTarget)>,
+    /// ファイルの変更をポーリングで検出するときに使う (`enable_file_polling` を参照)
+    file_poller_opt: Option<FilePoller>,
 
     // 状態 (ドキュメント):
     doc_interner: DocInterner,
@@ -40,6 +64,8 @@ pub(super) struct Analyzer {
     doc_analysis_map: DocAnalysisMap,
 
     // 状態 (ドキュメント全体の解析処理の計算結果):
+    /// ドキュメントが対象とするHSP (`hsp3_root::resolve_target` を参照)
+    doc_targets: HashMap<DocId, HspTarget>,
     active_docs: HashSet<DocId>,
     active_help_docs: HashSet<DocId>,
     help_docs: HashMap<DocId, DocId>,
@@ -55,6 +81,13 @@ pub(super) struct Analyzer {
     module_map: ModuleMap,
 }
 
+/// エディタで開かれたドキュメントの言語を決める (拡張子が不明ならHSP3とみなす)
+fn lang_of_uri(uri: &CanonicalUri) -> Lang {
+    uri.to_file_path()
+        .and_then(|path| Lang::from_path(&path))
+        .unwrap_or(Lang::Hsp3)
+}
+
 /// `Analyzer` の解析処理を完了した状態への参照
 pub(super) struct AnalyzerRef<'a> {
     pub(crate) owner: &'a Analyzer,
@@ -66,6 +99,7 @@ pub(super) struct AnalyzerRef<'a> {
     // computed:
     pub(crate) doc_analysis_map: &'a DocAnalysisMap,
 
+    doc_targets: &'a HashMap<DocId, HspTarget>,
     pub(crate) active_docs: &'a HashSet<DocId>,
     pub(crate) active_help_docs: &'a HashSet<DocId>,
     pub(crate) include_resolution: &'a [(Loc, DocId)],
@@ -75,9 +109,18 @@ pub(super) struct AnalyzerRef<'a> {
 }
 
 impl Analyzer {
-    pub(super) fn new(hsp3_root: PathBuf) -> Self {
+    pub(super) fn new(hsp3_root_opt: Option<PathBuf>) -> Self {
+        Self {
+            hsp3_roots: vec![Hsp3Root::new(hsp3_root_opt)],
+            ..Default::default()
+        }
+    }
+
+    /// ディスクの代わりに指定したファイルシステムを使うアナライザーを作る。
+    pub(crate) fn with_vfs(hsp3_root_opt: Option<PathBuf>, vfs: Rc<dyn Vfs>) -> Self {
         Self {
-            hsp3_root,
+            vfs: VfsRc::new(vfs),
+            hsp3_roots: vec![Hsp3Root::new(hsp3_root_opt)],
             ..Default::default()
         }
     }
@@ -87,59 +130,312 @@ impl Analyzer {
         let root = crate::test_utils::dummy_path();
         let an = Self {
             // no_exist/hsp3
-            hsp3_root: root.clone().join("hsp3"),
+            hsp3_roots: vec![Hsp3Root::new(Some(root.clone().join("hsp3")))],
             // no_exist/ws
             workspace_folders: vec![CanonicalUri::from_abs_path(&root.join("ws")).unwrap()],
             ..Default::default()
         };
 
         // 既定値を使う:
-        // self.common_docs = common_docs;
-        // self.hsphelp_info = hsphelp_info;
+        // self.hsp3_roots[0].common_docs = common_docs;
+        // self.hsp3_roots[0].hsphelp_info = hsphelp_info;
         // self.public_env.builtin = builtin_env;
 
         an
     }
 
+    /// メモリ上に置いたHSPのインストールディレクトリを使うアナライザーを作る。
+    ///
+    /// (`files` はインストールディレクトリからの相対パスとファイルの内容の組。
+    ///  ヘルプファイルは shift_jis に変換して置く。)
+    #[cfg(test)]
+    pub(crate) fn new_with_hsp3_files(files: &[(&str, &str)]) -> Self {
+        use encoding::{EncoderTrap, Encoding};
+
+        let root = crate::test_utils::dummy_path();
+        let hsp3_root = root.join("hsp3");
+
+        let vfs = vfs::MemoryFs::default();
+        for &(path, contents) in files {
+            let contents = if path.ends_with(".hs") {
+                encoding::all::WINDOWS_31J
+                    .encode(contents, EncoderTrap::Strict)
+                    .unwrap()
+            } else {
+                contents.as_bytes().to_vec()
+            };
+            vfs.write(hsp3_root.join(path), contents);
+        }
+
+        let mut an = Self::with_vfs(Some(hsp3_root), Rc::new(vfs));
+        an.workspace_folders = vec![CanonicalUri::from_abs_path(&root.join("ws")).unwrap()];
+        an
+    }
+
     pub(super) fn add_workspace_folder(&mut self, folder: lsp_types::WorkspaceFolder) {
         self.workspace_folders
             .push(CanonicalUri::from_url(&folder.uri));
     }
 
+    pub(super) fn set_file_extensions(&mut self, extensions: FileExtensions) {
+        self.file_extensions = extensions;
+    }
+
+    /// ワークスペースのファイルの変更をポーリングで検出するようにする。(`did_initialize` より前に呼ぶ)
+    ///
+    /// (クライアントがファイルの監視に対応していないときに使う。`poll_files` を参照)
+    pub(super) fn enable_file_polling(&mut self) {
+        self.file_poller_opt = Some(FilePoller::default());
+    }
+
+    pub(super) fn set_position_encoding(&mut self, encoding: PositionEncoding) {
+        self.position_encoding = encoding;
+    }
+
+    pub(super) fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+
     pub(super) fn did_initialize(&mut self) {
-        let mut builtin_env = SymbolEnv::default();
-        let mut common_docs = HashMap::new();
+        if self.hsp3_roots.is_empty() {
+            self.hsp3_roots.push(Hsp3Root::default());
+        }
+        self.load_hsp3_root(0);
+
+        debug!("scan_workspace_files");
+        for w_uri in self.workspace_folders.clone() {
+            self.scan_workspace_folder(&w_uri);
+        }
+    }
 
-        file_scan::scan_common(
-            &self.hsp3_root,
+    /// HSPのインストールディレクトリを探索して、標準命令などのシンボルを登録しなおす。
+    fn load_hsp3_root(&mut self, index: usize) {
+        let root = &mut self.hsp3_roots[index];
+        root.load(
+            &*self.vfs,
             &mut self.doc_interner,
             &mut self.docs,
-            &mut common_docs,
+            index == 0,
         );
 
-        let hsphelp_info = search_hsphelp(
-            &self.hsp3_root,
-            &common_docs,
-            &mut self.doc_interner,
-            &mut self.docs,
-            &mut builtin_env,
-        )
-        .unwrap_or_default();
+        // commonのファイルは、キャッシュに解析結果があればそれを使い、なければ読み込む。
+        // (キャッシュから復元したものは構文木を持たない。ファイルが変更されたら読み込む)
+        for &doc in root.common_docs.values() {
+            if self.doc_analysis_map.contains_key(&doc) {
+                continue;
+            }
+
+            let path = match self
+                .doc_interner
+                .get_uri(doc)
+                .and_then(|uri| uri.to_file_path())
+            {
+                Some(it) => it,
+                None => continue,
+            };
+            match root.index_cache.get_doc(&*self.vfs, &path) {
+                Some(summary) => {
+                    self.doc_analysis_map
+                        .insert(doc, summary.clone().restore(doc));
+                    self.docs.add_unloaded_file(doc);
+                }
+                None => {
+                    self.docs.change_file(doc, &path);
+                }
+            }
+        }
 
-        self.public_env.builtin = builtin_env;
-        self.common_docs = common_docs;
-        self.hsphelp_info = hsphelp_info;
+        self.collect_builtin_env();
+    }
 
-        debug!("scan_script_files");
-        for w_uri in &self.workspace_folders {
-            let workspace_dir = w_uri.to_file_path().unwrap();
-            file_scan::scan_script_files(&workspace_dir, |script_path| {
-                if let Some(uri) = CanonicalUri::from_abs_path(&script_path) {
-                    let (_, doc) = self.doc_interner.intern(&uri);
-                    self.docs.change_file(doc, &script_path);
+    /// すべてのHSPのインストールディレクトリから、標準命令などのシンボルを環境に登録する。
+    ///
+    /// (名前が重複するときは、既定のHSPのものを優先する。)
+    fn collect_builtin_env(&mut self) {
+        self.public_env.builtin.clear();
+        for root in self.hsp3_roots.iter().rev() {
+            root.hsphelp_info
+                .collect_builtin_env(&mut self.public_env.builtin);
+        }
+    }
+
+    /// ワークスペースの設定ファイルを読んで、ワークスペースにあるファイルが対象とするHSPを決める。
+    ///
+    /// (設定されたインストールディレクトリが未知のものなら、探索して追加する。)
+    fn configure_workspace_folder(&mut self, workspace_dir: &Path) {
+        let config = match WorkspaceConfig::load(&*self.vfs, workspace_dir) {
+            Some(it) => it,
+            None => return,
+        };
+        debug!("workspace config {:?} {:?}", workspace_dir, config);
+
+        let root = match config.hsp3_root_in(workspace_dir) {
+            Some(dir) => {
+                let found = self
+                    .hsp3_roots
+                    .iter()
+                    .position(|root| root.dir_opt.as_deref() == Some(dir.as_path()));
+                match found {
+                    Some(index) => index,
+                    None => {
+                        self.hsp3_roots.push(Hsp3Root::new(Some(dir)));
+                        let index = self.hsp3_roots.len() - 1;
+                        self.load_hsp3_root(index);
+                        index
+                    }
                 }
-            });
+            }
+            None => 0,
+        };
+        let version_opt = config
+            .hsp_version
+            .as_deref()
+            .and_then(HspVersion::parse)
+            .or(self.hsp3_roots[root].version_opt);
+
+        self.folder_targets
+            .retain(|(dir, _)| dir.as_path() != workspace_dir);
+        self.folder_targets
+            .push((workspace_dir.to_path_buf(), HspTarget { root, version_opt }));
+    }
+
+    /// ワークスペースのファイルを探索して、読み込む。
+    fn scan_workspace_folder(&mut self, w_uri: &CanonicalUri) {
+        let workspace_dir = match w_uri.to_file_path() {
+            Some(it) => it,
+            None => return,
+        };
+
+        self.configure_workspace_folder(&workspace_dir);
+
+        let vfs = self.vfs.clone();
+        file_scan::scan_workspace_files(&*vfs, &workspace_dir, &self.file_extensions, |path| {
+            if let Some(uri) = CanonicalUri::from_abs_path(&path) {
+                let (_, doc) = self.doc_interner.intern(&uri);
+                self.docs.change_file(doc, &path);
+            }
+            if let Some(poller) = &mut self.file_poller_opt {
+                poller.insert(&*vfs, path);
+            }
+        });
+    }
+
+    /// ワークスペースの追加・削除を反映する。
+    ///
+    /// 追加されたワークスペースのファイルを読み込み、
+    /// 削除されたワークスペースにあるファイルのドキュメントを削除する。
+    /// (エディタで開かれているドキュメントや、ほかのワークスペースにあるファイルは削除しない。)
+    pub(super) fn change_workspace_folders(
+        &mut self,
+        added: Vec<lsp_types::WorkspaceFolder>,
+        removed: Vec<lsp_types::WorkspaceFolder>,
+    ) {
+        for folder in removed {
+            let w_uri = CanonicalUri::from_url(&folder.uri);
+            let len = self.workspace_folders.len();
+            self.workspace_folders.retain(|uri| *uri != w_uri);
+            if self.workspace_folders.len() == len {
+                continue;
+            }
+
+            let workspace_dir = match w_uri.to_file_path() {
+                Some(it) => it,
+                None => continue,
+            };
+            self.folder_targets.retain(|(dir, _)| *dir != workspace_dir);
+
+            // 残すファイルがあるディレクトリ
+            let kept_dirs = self
+                .workspace_folders
+                .iter()
+                .filter_map(|uri| uri.to_file_path())
+                .chain(
+                    self.hsp3_roots
+                        .iter()
+                        .filter_map(|root| root.dir_opt.clone()),
+                )
+                .collect::<Vec<_>>();
+            let is_dropped = |path: &Path| {
+                path.starts_with(&workspace_dir)
+                    && !kept_dirs.iter().any(|dir| path.starts_with(dir))
+            };
+
+            let dropped_docs = self
+                .doc_interner
+                .iter()
+                .filter(|(_, uri)| uri.to_file_path().is_some_and(|path| is_dropped(&path)))
+                .map(|(doc, uri)| (doc, uri.clone()))
+                .collect::<Vec<_>>();
+            if let Some(poller) = &mut self.file_poller_opt {
+                poller.retain(|path| !is_dropped(path));
+            }
+
+            for (doc, uri) in dropped_docs {
+                debug!("drop doc:{} {:?}", doc, uri);
+                if self.docs.close_file(doc) {
+                    self.doc_interner.remove(doc, &uri);
+                }
+            }
+        }
+
+        for folder in added {
+            let w_uri = CanonicalUri::from_url(&folder.uri);
+            if self.workspace_folders.contains(&w_uri) {
+                continue;
+            }
+            self.workspace_folders.push(w_uri.clone());
+            self.scan_workspace_folder(&w_uri);
+        }
+    }
+
+    /// ワークスペースを探索して、前回の探索からのファイルの変更を反映する。変更があれば true を返す。
+    ///
+    /// (`enable_file_polling` が呼ばれていなければ何もしない。)
+    pub(super) fn poll_files(&mut self) -> bool {
+        if self.file_poller_opt.is_none() {
+            return false;
+        }
+
+        let mut paths = vec![];
+        for w_uri in &self.workspace_folders {
+            if let Some(workspace_dir) = w_uri.to_file_path() {
+                file_scan::scan_workspace_files(
+                    &*self.vfs,
+                    &workspace_dir,
+                    &self.file_extensions,
+                    |path| paths.push(path),
+                );
+            }
+        }
+
+        let changes = self
+            .file_poller_opt
+            .as_mut()
+            .unwrap()
+            .poll(&*self.vfs, paths);
+
+        for (path, change) in &changes {
+            let uri = match Url::from_file_path(path) {
+                Ok(it) => it,
+                Err(()) => continue,
+            };
+            debug!("poll {:?} {:?}", change, path);
+            match change {
+                FileChange::Created => self.on_file_created(uri),
+                FileChange::Changed => self.on_file_changed(uri),
+                FileChange::Deleted => self.on_file_deleted(uri),
+            }
         }
+        !changes.is_empty()
+    }
+
+    /// ドキュメントが対象とするHSPを決める
+    fn target_of(&self, doc: DocId) -> HspTarget {
+        let path_opt = self
+            .doc_interner
+            .get_uri(doc)
+            .and_then(|uri| uri.to_file_path());
+        resolve_target(&self.hsp3_roots, &self.folder_targets, path_opt.as_deref())
     }
 
     fn is_computed(&self) -> bool {
@@ -167,39 +463,92 @@ impl Analyzer {
         // ドキュメントごとの変更を適用する
         // (開かれた・変更されたドキュメントに対して再解析を行い、
         //  閉じられたドキュメントに関するデータを削除する)
+        let mut help_changed = false;
         for (_, change) in change_map.drain() {
             match change {
                 DocChange::Opened { doc, lang, origin }
                 | DocChange::Changed { doc, lang, origin } => {
+                    // 保存されているファイルの文字コード (分からなければ None)
+                    let mut encoding_opt = None;
+                    let mut path_opt = None;
+
                     let text = match origin {
                         DocChangeOrigin::Editor(text) => text,
                         DocChangeOrigin::Path(path) => {
                             let mut text = String::new();
-                            if !read_file(&path, &mut text) {
+                            let ok = match (lang, self.vfs.read(&path)) {
+                                (Lang::Hsp3, Ok(contents)) => {
+                                    encoding_opt = decode_contents(&contents, &mut text);
+                                    encoding_opt.is_some()
+                                }
+                                (Lang::HelpSource, Ok(contents)) => {
+                                    decode_sjis_contents(&contents, &mut text)
+                                }
+                                (_, Err(_)) => false,
+                            };
+                            if !ok {
                                 warn!("ファイルを開けません。{:?}", path);
+                                self.doc_analysis_map.remove(&doc);
                                 continue;
                             }
+                            path_opt = Some(path);
                             text.into()
                         }
                     };
 
                     match lang {
-                        // .hs ファイルの変更追跡は未実装
-                        Lang::HelpSource => continue,
+                        Lang::HelpSource => {
+                            let target = self.target_of(doc);
+                            let root = &mut self.hsp3_roots[target.root];
+                            let hs_symbols = root.hsphelp_info.update_doc(doc, text);
+                            if let Some(path) = &path_opt {
+                                root.index_cache.put_help(&*self.vfs, path, hs_symbols);
+                            }
+                            help_changed = true;
+                            continue;
+                        }
                         Lang::Hsp3 => {}
                     }
 
+                    let target = self.target_of(doc);
                     let da = self.doc_analysis_map.entry(doc).or_default();
                     da.compute(doc, text);
+
+                    if let Some(path) = &path_opt {
+                        let root = &mut self.hsp3_roots[target.root];
+                        if root.contains(path) {
+                            if let Some(summary) = DocSummary::new(da) {
+                                root.index_cache.put_doc(&*self.vfs, path, summary);
+                            }
+                        }
+                    }
+
+                    // エディタで開かれているドキュメントは、保存されているファイルの文字コードを調べる。
+                    // (調べた結果は、ドキュメントやファイルが変更されるまで使いまわす。)
+                    if encoding_opt.is_none() {
+                        encoding_opt = da
+                            .encoding_opt
+                            .or_else(|| detect_file_encoding(&*self.vfs, &self.doc_interner, doc));
+                    }
+                    da.encoding_opt = encoding_opt;
                 }
                 DocChange::Closed { doc } => {
                     self.doc_analysis_map.remove(&doc);
+                    for root in &mut self.hsp3_roots {
+                        help_changed |= root.hsphelp_info.remove_doc(doc);
+                    }
                 }
             }
         }
 
+        // ヘルプファイルが変更されたら、標準命令などのシンボルを登録しなおす
+        if help_changed {
+            self.collect_builtin_env();
+        }
+
         // ドキュメント全体に対する解析処理を再実行する
         {
+            self.doc_targets.clear();
             self.active_docs.clear();
             self.active_help_docs.clear();
             self.help_docs.clear();
@@ -216,10 +565,17 @@ impl Analyzer {
                     .extend(da.module_map.iter().map(|(&m, rc)| (m, rc.clone())));
             }
 
+            let doc_targets = self
+                .doc_analysis_map
+                .keys()
+                .map(|&doc| (doc, self.target_of(doc)))
+                .collect();
+            self.doc_targets = doc_targets;
+
             compute_active_docs::compute_active_docs(
                 &self.doc_analysis_map,
-                &self.common_docs,
-                &self.hsphelp_info,
+                &self.hsp3_roots,
+                &self.doc_targets,
                 &mut self.active_docs,
                 &mut self.active_help_docs,
                 &mut self.help_docs,
@@ -228,12 +584,13 @@ impl Analyzer {
             compute_includes::compute_includes(
                 &self.doc_interner,
                 &self.doc_analysis_map,
-                &self.common_docs,
+                &self.hsp3_roots,
+                &self.doc_targets,
                 &mut self.include_resolution,
             );
 
             compute_symbols::compute_symbols(
-                &self.hsphelp_info,
+                &self.hsp3_roots,
                 &self.active_docs,
                 &self.help_docs,
                 &self.doc_analysis_map,
@@ -262,6 +619,10 @@ impl Analyzer {
             }
         }
 
+        for root in &mut self.hsp3_roots {
+            root.index_cache.save();
+        }
+
         debug_assert!(self.is_computed());
     }
 
@@ -272,6 +633,7 @@ impl Analyzer {
             doc_interner: &self.doc_interner,
             docs: &self.docs,
             doc_analysis_map: &self.doc_analysis_map,
+            doc_targets: &self.doc_targets,
             active_docs: &self.active_docs,
             active_help_docs: &self.active_help_docs,
             include_resolution: &self.include_resolution,
@@ -294,13 +656,18 @@ impl Analyzer {
     pub(super) fn open_doc(&mut self, uri: Url, version: i32, text: String) {
         let c_uri = CanonicalUri::from_url(&uri);
         let (_, doc) = self.doc_interner.intern(&c_uri);
-        self.docs.open_doc_in_editor(doc, version, text.into());
+        let lang = lang_of_uri(&c_uri);
+        self.docs
+            .open_doc_in_editor(doc, version, lang, text.into());
     }
 
     pub(super) fn change_doc(&mut self, uri: Url, version: i32, text: String) {
         let c_uri = CanonicalUri::from_url(&uri);
         let (_, doc) = self.doc_interner.intern(&c_uri);
-        self.docs.change_doc_in_editor(doc, version, text.into());
+        let lang = lang_of_uri(&c_uri);
+        self.refresh_encoding(doc);
+        self.docs
+            .change_doc_in_editor(doc, version, lang, text.into());
     }
 
     pub(super) fn close_doc(&mut self, uri: Url) {
@@ -315,6 +682,7 @@ impl Analyzer {
     pub(super) fn on_file_created(&mut self, url: Url) {
         let c_uri = CanonicalUri::from_url(&url);
         let (_, doc) = self.doc_interner.intern(&c_uri);
+        self.refresh_encoding(doc);
         if let Some(path) = c_uri.to_file_path() {
             self.docs.change_file(doc, &path);
         }
@@ -323,11 +691,27 @@ impl Analyzer {
     pub(super) fn on_file_changed(&mut self, uri: Url) {
         let c_uri = CanonicalUri::from_url(&uri);
         let (_, doc) = self.doc_interner.intern(&c_uri);
+        self.refresh_encoding(doc);
         if let Some(path) = c_uri.to_file_path() {
             self.docs.change_file(doc, &path);
         }
     }
 
+    /// 保存されているファイルの文字コードを調べなおす。
+    ///
+    /// (エディタで開かれているドキュメントは、ファイルが変更されても再解析されないので、ここで調べる。)
+    fn refresh_encoding(&mut self, doc: DocId) {
+        let da = match self.doc_analysis_map.get_mut(&doc) {
+            Some(it) => it,
+            None => return,
+        };
+        da.encoding_opt = None;
+
+        if self.docs.is_open_in_editor(doc) {
+            da.encoding_opt = detect_file_encoding(&*self.vfs, &self.doc_interner, doc);
+        }
+    }
+
     pub(super) fn on_file_deleted(&mut self, uri: Url) {
         let c_uri = CanonicalUri::from_url(&uri);
         if let Some(doc) = self.doc_interner.get_doc(&c_uri) {
@@ -338,20 +722,73 @@ impl Analyzer {
     }
 }
 
+/// ドキュメントに対応するファイルを読んで、文字コードを調べる。(ファイルがなければNone)
+fn detect_file_encoding(
+    vfs: &dyn Vfs,
+    doc_interner: &DocInterner,
+    doc: DocId,
+) -> Option<FileEncoding> {
+    let path = doc_interner.get_uri(doc)?.to_file_path()?;
+    let contents = vfs.read(&path).ok()?;
+    decode_contents(&contents, &mut String::new())
+}
+
 impl<'a> AnalyzerRef<'a> {
-    #[cfg(test)]
-    #[allow(unused)]
+    /// メッセージの表示言語
+    pub(crate) fn locale(&self) -> Locale {
+        self.owner.locale
+    }
+
     pub(crate) fn get_doc_interner(&self) -> &DocInterner {
         self.doc_interner
     }
 
-    #[allow(unused)]
-    pub(crate) fn common_docs(&self) -> &HashMap<String, DocId> {
-        &self.owner.common_docs
+    pub(crate) fn hsp3_roots(&self) -> &'a [Hsp3Root] {
+        &self.owner.hsp3_roots
+    }
+
+    /// ドキュメントが対象とするHSP
+    pub(crate) fn target_of(&self, doc: DocId) -> HspTarget {
+        match self.doc_targets.get(&doc) {
+            Some(&it) => it,
+            None => self.owner.target_of(doc),
+        }
+    }
+
+    /// ドキュメントが対象とするHSPの情報
+    pub(crate) fn hsp3_root_of(&self, doc: DocId) -> &'a Hsp3Root {
+        &self.owner.hsp3_roots[self.target_of(doc).root]
+    }
+
+    /// ドキュメントが対象とするHSPのヘルプファイルの情報
+    pub(crate) fn hsphelp_info_of(&self, doc: DocId) -> &'a HspHelpInfo {
+        &self.hsp3_root_of(doc).hsphelp_info
+    }
+
+    /// いずれかのHSPのインストールディレクトリにあるパスか？
+    pub(crate) fn is_in_hsp3_root(&self, path: &Path) -> bool {
+        self.hsp3_roots().iter().any(|root| root.contains(path))
     }
 
-    pub(crate) fn hsphelp_info(&self) -> &HspHelpInfo {
-        &self.owner.hsphelp_info
+    /// いずれかのHSPの common にあるドキュメントか？
+    pub(crate) fn is_common_doc(&self, doc: DocId) -> bool {
+        self.hsp3_roots()
+            .iter()
+            .any(|root| root.common_docs.values().any(|&d| d == doc))
+    }
+
+    /// ヘルプファイルとして解析されているドキュメントか？
+    pub(crate) fn is_help_doc(&self, doc: DocId) -> bool {
+        self.hsp3_roots()
+            .iter()
+            .any(|root| root.hsphelp_info.is_help_doc(doc))
+    }
+
+    /// ヘルプファイルの内容
+    pub(crate) fn help_text(&self, doc: DocId) -> Option<&'a RcStr> {
+        self.hsp3_roots()
+            .iter()
+            .find_map(|root| root.hsphelp_info.get_text(doc))
     }
 
     pub(crate) fn is_active_doc(&self, doc: DocId) -> bool {
@@ -407,12 +844,25 @@ impl<'a> AnalyzerRef<'a> {
             .cloned()
     }
 
+    /// クライアントの列番号の単位と UTF-16 を変換するものを作る
+    pub(crate) fn position_converter(&self) -> PositionConverter<'a> {
+        PositionConverter::new(
+            self.owner.position_encoding,
+            self.doc_interner,
+            self.doc_analysis_map,
+        )
+    }
+
     pub(super) fn code_action(
         &self,
         uri: Url,
-        range: Range,
-        _context: CodeActionContext,
+        mut range: Range,
+        mut context: CodeActionContext,
     ) -> Vec<CodeAction> {
+        let mut cv = self.position_converter();
+        cv.map_from_client(&uri, &mut range);
+        cv.map_from_client(&uri, &mut context);
+
         let mut actions = vec![];
         actions.extend(
             ide::code_actions::flip_comma::flip_comma(
@@ -434,12 +884,64 @@ impl<'a> AnalyzerRef<'a> {
             )
             .unwrap_or_default(),
         );
+        actions.extend(
+            ide::code_actions::replace_bad_chars::replace_bad_chars(
+                self,
+                self.doc_interner,
+                self.docs,
+                &uri,
+                range,
+            )
+            .unwrap_or_default(),
+        );
+        actions.extend(
+            ide::code_actions::unify_name_casing::unify_name_casing(
+                self,
+                self.doc_interner,
+                self.docs,
+                &uri,
+                range,
+                &context,
+            )
+            .unwrap_or_default(),
+        );
+        cv.map_to_client(&uri, &mut actions);
         actions
     }
 
-    pub(super) fn completion(&self, uri: Url, position: Position) -> CompletionList {
-        ide::completion::completion(self, self.doc_interner, uri, position)
-            .unwrap_or_else(ide::completion::incomplete_completion_list)
+    pub(super) fn code_lens(&self, uri: Url) -> Vec<CodeLens> {
+        let mut lenses =
+            ide::code_lens::code_lens(self, self.doc_interner, uri.clone()).unwrap_or_default();
+        self.position_converter().map_to_client(&uri, &mut lenses);
+        lenses
+    }
+
+    pub(super) fn code_lens_resolve(&self, mut lens: CodeLens) -> CodeLens {
+        // コードレンズの範囲はドキュメントのURIを持たないため、`data` に含まれるURIを使う。
+        let uri_opt = lens
+            .data
+            .as_ref()
+            .and_then(|data| data.get("uri"))
+            .and_then(|uri| serde_json::from_value::<Url>(uri.clone()).ok());
+        let uri = match uri_opt {
+            Some(it) => it,
+            None => return ide::code_lens::code_lens_resolve(self, self.doc_interner, lens),
+        };
+
+        let mut cv = self.position_converter();
+        cv.map_from_client(&uri, &mut lens.range);
+        let mut lens = ide::code_lens::code_lens_resolve(self, self.doc_interner, lens);
+        cv.map_to_client(&uri, &mut lens);
+        lens
+    }
+
+    pub(super) fn completion(&self, uri: Url, mut position: Position) -> CompletionList {
+        let mut cv = self.position_converter();
+        cv.map_from_client(&uri, &mut position);
+        let mut list = ide::completion::completion(self, self.doc_interner, uri.clone(), position)
+            .unwrap_or_else(ide::completion::incomplete_completion_list);
+        cv.map_to_client(&uri, &mut list);
+        list
     }
 
     pub(super) fn completion_resolve(
@@ -450,28 +952,79 @@ impl<'a> AnalyzerRef<'a> {
     }
 
     pub(crate) fn formatting(&self, uri: Url) -> Option<Vec<TextEdit>> {
-        ide::formatting::formatting(self, self.doc_interner, uri)
+        let mut edits = ide::formatting::formatting(self, self.doc_interner, uri.clone());
+        self.position_converter().map_to_client(&uri, &mut edits);
+        edits
+    }
+
+    /// 位置を受け取って位置のリストを返すIDE機能を、列番号を変換しながら呼ぶ
+    fn with_locations(
+        &self,
+        uri: Url,
+        mut position: Position,
+        f: impl FnOnce(Url, Position) -> Option<Vec<Location>>,
+    ) -> Vec<Location> {
+        let mut cv = self.position_converter();
+        cv.map_from_client(&uri, &mut position);
+        let mut locations = f(uri.clone(), position).unwrap_or(vec![]);
+        cv.map_to_client(&uri, &mut locations);
+        locations
+    }
+
+    pub(super) fn declarations(&self, uri: Url, position: Position) -> Vec<Location> {
+        self.with_locations(uri, position, |uri, position| {
+            ide::definitions::declarations(self, self.doc_interner, uri, position)
+        })
     }
 
     pub(super) fn definitions(&self, uri: Url, position: Position) -> Vec<Location> {
-        ide::definitions::definitions(self, self.doc_interner, uri, position).unwrap_or(vec![])
+        self.with_locations(uri, position, |uri, position| {
+            ide::definitions::definitions(self, self.doc_interner, uri, position)
+        })
+    }
+
+    pub(super) fn type_definitions(&self, uri: Url, position: Position) -> Vec<Location> {
+        self.with_locations(uri, position, |uri, position| {
+            ide::definitions::type_definitions(self, self.doc_interner, uri, position)
+        })
+    }
+
+    pub(super) fn implementations(&self, uri: Url, position: Position) -> Vec<Location> {
+        self.with_locations(uri, position, |uri, position| {
+            ide::definitions::implementations(self, self.doc_interner, uri, position)
+        })
     }
 
     pub(super) fn document_highlight(
         &self,
         uri: Url,
-        position: Position,
+        mut position: Position,
     ) -> Vec<DocumentHighlight> {
-        ide::document_highlight::document_highlight(self, self.doc_interner, uri, position)
-            .unwrap_or(vec![])
+        let mut cv = self.position_converter();
+        cv.map_from_client(&uri, &mut position);
+        let mut highlights = ide::document_highlight::document_highlight(
+            self,
+            self.doc_interner,
+            uri.clone(),
+            position,
+        )
+        .unwrap_or(vec![]);
+        cv.map_to_client(&uri, &mut highlights);
+        highlights
     }
 
     pub(super) fn document_symbol(&self, uri: Url) -> Option<DocumentSymbolResponse> {
-        ide::document_symbol::symbol(self, self.doc_interner, uri)
+        let mut symbols = ide::document_symbol::symbol(self, self.doc_interner, uri.clone());
+        self.position_converter().map_to_client(&uri, &mut symbols);
+        symbols
     }
 
-    pub(super) fn hover(&self, uri: Url, position: Position) -> Option<Hover> {
-        ide::hover::hover(self, self.doc_interner, uri, position)
+    pub(super) fn hover(&self, uri: Url, mut position: Position) -> Option<Hover> {
+        let mut cv = self.position_converter();
+        cv.map_from_client(&uri, &mut position);
+        let mut hover = ide::hover::hover(self, self.doc_interner, uri.clone(), position);
+        cv.map_to_client(&uri, &mut hover);
+        hover
     }
 
     pub(super) fn references(
@@ -480,45 +1033,109 @@ impl<'a> AnalyzerRef<'a> {
         position: Position,
         include_definition: bool,
     ) -> Vec<Location> {
-        ide::references::references(self, self.doc_interner, uri, position, include_definition)
-            .unwrap_or(vec![])
+        self.with_locations(uri, position, |uri, position| {
+            ide::references::references(self, self.doc_interner, uri, position, include_definition)
+        })
+    }
+
+    pub(super) fn write_references(&self, uri: Url, position: Position) -> Vec<Location> {
+        self.with_locations(uri, position, |uri, position| {
+            ide::references::write_references(self, self.doc_interner, uri, position)
+        })
     }
 
     pub(super) fn prepare_rename(
         &self,
         uri: Url,
-        position: Position,
+        mut position: Position,
     ) -> Option<PrepareRenameResponse> {
-        ide::rename::prepare_rename(self, self.doc_interner, uri, position)
+        let mut cv = self.position_converter();
+        cv.map_from_client(&uri, &mut position);
+        let mut response =
+            ide::rename::prepare_rename(self, self.doc_interner, uri.clone(), position);
+        cv.map_to_client(&uri, &mut response);
+        response
     }
 
     pub(super) fn rename(
         &self,
         uri: Url,
-        position: Position,
+        mut position: Position,
         new_name: String,
-    ) -> Option<WorkspaceEdit> {
-        ide::rename::rename(self, self.doc_interner, self.docs, uri, position, new_name)
+    ) -> Result<Option<WorkspaceEdit>, String> {
+        let mut cv = self.position_converter();
+        cv.map_from_client(&uri, &mut position);
+        let mut edit = ide::rename::rename(
+            self,
+            self.doc_interner,
+            self.docs,
+            uri.clone(),
+            position,
+            new_name,
+        )?;
+        cv.map_to_client(&uri, &mut edit);
+        Ok(edit)
+    }
+
+    pub(super) fn will_rename_files(&self, files: Vec<FileRename>) -> Option<WorkspaceEdit> {
+        let mut edit =
+            ide::rename_files::will_rename_files(self, self.doc_interner, self.docs, files);
+        // (編集はそれぞれのドキュメントのURIを持っている。)
+        if let Some(edit) = &mut edit {
+            let uri = Url::parse("file:///").unwrap();
+            self.position_converter().map_to_client(&uri, edit);
+        }
+        edit
     }
 
     pub(super) fn semantic_tokens(&self, uri: Url) -> lsp_types::SemanticTokens {
-        let tokens = ide::semantic_tokens::full(self, self.doc_interner, uri).unwrap_or(vec![]);
+        let mut tokens =
+            ide::semantic_tokens::full(self, self.doc_interner, uri.clone()).unwrap_or(vec![]);
+        self.position_converter()
+            .semantic_tokens_to_client(&uri, &mut tokens);
         SemanticTokens {
             data: tokens,
             result_id: None,
         }
     }
 
-    pub(super) fn signature_help(&self, uri: Url, position: Position) -> Option<SignatureHelp> {
+    pub(super) fn semantic_tokens_range(
+        &self,
+        uri: Url,
+        mut range: lsp_types::Range,
+    ) -> lsp_types::SemanticTokens {
+        let mut cv = self.position_converter();
+        cv.map_from_client(&uri, &mut range);
+        let mut tokens = ide::semantic_tokens::range(self, self.doc_interner, uri.clone(), range)
+            .unwrap_or(vec![]);
+        cv.semantic_tokens_to_client(&uri, &mut tokens);
+        SemanticTokens {
+            data: tokens,
+            result_id: None,
+        }
+    }
+
+    pub(super) fn signature_help(&self, uri: Url, mut position: Position) -> Option<SignatureHelp> {
+        self.position_converter()
+            .map_from_client(&uri, &mut position);
         ide::signature_help::signature_help(self, self.doc_interner, uri, position)
     }
 
     pub(super) fn workspace_symbol(&self, query: String) -> Vec<SymbolInformation> {
-        ide::workspace_symbol::symbol(self, self.doc_interner, &query)
+        let mut symbols = ide::workspace_symbol::symbol(self, self.doc_interner, &query);
+        // (シンボルはそれぞれのドキュメントのURIを持っている。)
+        let uri = Url::parse("file:///").unwrap();
+        self.position_converter().map_to_client(&uri, &mut symbols);
+        symbols
     }
 
     pub(super) fn diagnose(&self) -> Vec<(Url, Option<i32>, Vec<lsp_types::Diagnostic>)> {
-        ide::diagnose::diagnose(self, &self.owner.hsp3_root, &self.doc_interner, &self.docs)
+        let mut diagnostics = ide::diagnose::diagnose(self, &self.doc_interner, &self.docs);
+        let mut cv = self.position_converter();
+        for (uri, _, d) in &mut diagnostics {
+            cv.map_to_client(uri, d);
+        }
+        diagnostics
     }
 }
 
@@ -540,3 +1157,129 @@ impl DocDb for Analyzer {
         self.doc_interner.get_doc(uri)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::vfs::MemoryFs;
+
+    fn folder(path: &Path) -> WorkspaceFolder {
+        WorkspaceFolder {
+            uri: Url::from_directory_path(path).unwrap(),
+            name: "".to_string(),
+        }
+    }
+
+    /// 記録されているドキュメントのパス (`root` からの相対パス)
+    fn doc_paths(an: &Analyzer, root: &Path) -> Vec<String> {
+        let mut paths = an
+            .doc_interner
+            .iter()
+            .filter_map(|(_, uri)| {
+                let path = uri.to_file_path()?;
+                let relative = path.strip_prefix(root).ok()?;
+                Some(relative.to_string_lossy().replace('\\', "/"))
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_workspace_folders() {
+        let root = crate::test_utils::dummy_path();
+        let fs = Rc::new(MemoryFs::default());
+        fs.write(root.join("ws1/main.hsp"), "#include \"mod_a.as\"");
+        fs.write(root.join("ws1/mod_a.as"), "#module m_a\n#global");
+        fs.write(root.join("ws1/mod_a.hs"), "");
+        fs.write(root.join("ws1/readme.txt"), "");
+        fs.write(root.join("ws2/sub/b.as"), "");
+
+        let mut an = Analyzer::with_vfs(None, fs.clone());
+        an.add_workspace_folder(folder(&root.join("ws1")));
+        an.enable_file_polling();
+        an.did_initialize();
+        an.compute_ref();
+        assert_eq!(
+            doc_paths(&an, &root),
+            ["ws1/main.hsp", "ws1/mod_a.as", "ws1/mod_a.hs"]
+        );
+
+        // ワークスペースを入れ替える。
+        an.change_workspace_folders(
+            vec![folder(&root.join("ws2"))],
+            vec![folder(&root.join("ws1"))],
+        );
+        an.compute_ref();
+        assert_eq!(doc_paths(&an, &root), ["ws2/sub/b.as"]);
+
+        // ファイルの作成・削除をポーリングで検出する。
+        assert!(!an.poll_files());
+        fs.write(root.join("ws2/c.hsp"), "");
+        fs.remove(&root.join("ws2/sub/b.as"));
+        assert!(an.poll_files());
+        an.compute_ref();
+        assert_eq!(doc_paths(&an, &root), ["ws2/c.hsp"]);
+    }
+
+    #[test]
+    fn test_hsp3_roots() {
+        let root = crate::test_utils::dummy_path();
+        let fs = Rc::new(MemoryFs::default());
+        fs.write(
+            root.join("hsp37/hsphelp/i_hsp3func.hs"),
+            "%ver\n3.7\n%index\nmes\n",
+        );
+        fs.write(root.join("hsp37/common/mod_new.as"), "");
+        fs.write(
+            root.join("hsp36/hsphelp/i_hsp3func.hs"),
+            "%ver\n3.6\n%index\nmes\n",
+        );
+        fs.write(root.join("hsp36/common/mod_old.as"), "");
+        let main = "#include \"mod_new.as\"\n#include \"mod_old.as\"\n";
+        fs.write(root.join("ws1/main.hsp"), main);
+        fs.write(root.join("ws2/main.hsp"), main);
+        fs.write(
+            root.join("ws2/hsp3-analyzer-mini.json"),
+            r#"{ "hsp3Root": "../hsp36" }"#,
+        );
+
+        let mut an = Analyzer::with_vfs(Some(root.join("hsp37")), fs);
+        an.add_workspace_folder(folder(&root.join("ws1")));
+        an.add_workspace_folder(folder(&root.join("ws2")));
+        an.did_initialize();
+        let an = an.compute_ref();
+
+        let path_of = |doc: DocId| {
+            let path = an.get_doc_interner().get_uri(doc)?.to_file_path()?;
+            let relative = path.strip_prefix(&root).ok()?;
+            Some(relative.to_string_lossy().replace('\\', "/"))
+        };
+
+        // ワークスペースごとに、対象とするHSPの common が使われる。
+        let mut resolved = an
+            .include_resolution
+            .iter()
+            .map(|&(loc, doc)| {
+                format!("{} -> {}", path_of(loc.doc).unwrap(), path_of(doc).unwrap())
+            })
+            .collect::<Vec<_>>();
+        resolved.sort();
+        assert_eq!(
+            resolved,
+            [
+                "ws1/main.hsp -> hsp37/common/mod_new.as",
+                "ws2/main.hsp -> hsp36/common/mod_old.as"
+            ]
+        );
+
+        // 版はヘルプファイルから推定される。
+        let version_of = |path: &str| {
+            let uri = CanonicalUri::from_abs_path(&root.join(path)).unwrap();
+            let doc = an.get_doc_interner().get_doc(&uri).unwrap();
+            an.target_of(doc).version_opt.map(|v| v.to_string())
+        };
+        assert_eq!(version_of("ws1/main.hsp").as_deref(), Some("3.7"));
+        assert_eq!(version_of("ws2/main.hsp").as_deref(), Some("3.6"));
+    }
+}
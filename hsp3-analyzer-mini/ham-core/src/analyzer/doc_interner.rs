@@ -45,6 +45,11 @@ impl DocInterner {
         self.uri_to_doc.get(uri).cloned()
     }
 
+    /// 記録されているドキュメントとURIの組を列挙する (順不同)
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (DocId, &CanonicalUri)> {
+        self.doc_to_uri.iter().map(|(&doc, uri)| (doc, uri))
+    }
+
     /// 使用されなくなったDocIdに関するデータを削除する
     pub(crate) fn remove(&mut self, doc: DocId, uri: &CanonicalUri) {
         // デバッグ時のみ: 正しいペアが渡されたことを確認する
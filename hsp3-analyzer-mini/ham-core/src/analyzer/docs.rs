@@ -65,7 +65,7 @@ impl Docs {
         self.doc_changes.push(DocChange::Closed { doc });
     }
 
-    pub(crate) fn open_doc_in_editor(&mut self, doc: DocId, version: i32, text: RcStr) {
+    pub(crate) fn open_doc_in_editor(&mut self, doc: DocId, version: i32, lang: Lang, text: RcStr) {
         #[cfg(feature = "trace_docs")]
         trace!(
             "クライアントでファイルが開かれました ({:?} version={}, len={})",
@@ -75,11 +75,17 @@ impl Docs {
         );
 
         assert!(!self.editor_docs.contains(&doc));
-        self.do_open_doc(doc, version, Lang::Hsp3, DocChangeOrigin::Editor(text));
+        self.do_open_doc(doc, version, lang, DocChangeOrigin::Editor(text));
         self.editor_docs.insert(doc);
     }
 
-    pub(crate) fn change_doc_in_editor(&mut self, doc: DocId, version: i32, text: RcStr) {
+    pub(crate) fn change_doc_in_editor(
+        &mut self,
+        doc: DocId,
+        version: i32,
+        lang: Lang,
+        text: RcStr,
+    ) {
         #[cfg(feature = "trace_docs")]
         trace!(
             "クライアントでファイルが変更されました ({:?} version={}, len={})",
@@ -89,10 +95,14 @@ impl Docs {
         );
 
         assert!(self.editor_docs.contains(&doc));
-        self.do_change_doc(doc, version, Lang::Hsp3, DocChangeOrigin::Editor(text));
+        self.do_change_doc(doc, version, lang, DocChangeOrigin::Editor(text));
         self.editor_docs.insert(doc);
     }
 
+    pub(crate) fn is_open_in_editor(&self, doc: DocId) -> bool {
+        self.editor_docs.contains(&doc)
+    }
+
     pub(crate) fn close_doc_in_editor(&mut self, doc: DocId) -> bool {
         #[cfg(feature = "trace_docs")]
         trace!("クライアントでファイルが閉じられました ({:?})", uri);
@@ -139,6 +149,13 @@ impl Docs {
         }
     }
 
+    /// ファイルとDocIdの対応付けを行う。ただしファイルの内容は読み込まない。
+    ///
+    /// (内容が必要になったら `change_file` で読み込む。)
+    pub(crate) fn add_unloaded_file(&mut self, doc: DocId) {
+        self.file_docs.insert(doc);
+    }
+
     /// ファイルとDocIdの対応付けを行う。
     pub(crate) fn ensure_file_opened(&mut self, doc: DocId, abs_path: &Path) -> Option<DocId> {
         self.change_file(doc, abs_path)
@@ -0,0 +1,113 @@
+//! ファイルの変更の検出 (ポーリング)
+//!
+//! クライアントがファイルの監視 (`workspace/didChangeWatchedFiles` の動的な登録) に対応していないとき、
+//! サーバーがワークスペースを定期的に探索して、ファイルの作成・変更・削除を検出する。
+
+use super::*;
+use std::time::SystemTime;
+
+/// ファイルの変更の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum FileChange {
+    Created,
+    Changed,
+    Deleted,
+}
+
+/// 前回の探索の結果を覚えておいて、ファイルの変更を検出するもの
+#[derive(Default)]
+pub(crate) struct FilePoller {
+    /// 前回の探索で見つかったファイルと、その最終更新日時
+    files: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl FilePoller {
+    /// 探索とは別に見つかったファイルを記録する。(次の探索で作成されたとみなさないように)
+    pub(crate) fn insert(&mut self, vfs: &dyn Vfs, path: PathBuf) {
+        let mtime = vfs.mtime(&path);
+        self.files.insert(path, mtime);
+    }
+
+    /// 条件を満たさないファイルの記録を削除する。(次の探索で削除されたとみなさないように)
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(&Path) -> bool) {
+        self.files.retain(|path, _| f(path));
+    }
+
+    /// 探索で見つかったファイルのパスを受け取って、前回からの変更を列挙する。
+    ///
+    /// (結果はパスの順に並ぶ。)
+    pub(crate) fn poll(
+        &mut self,
+        vfs: &dyn Vfs,
+        paths: Vec<PathBuf>,
+    ) -> Vec<(PathBuf, FileChange)> {
+        let files = paths
+            .into_iter()
+            .map(|path| {
+                let mtime = vfs.mtime(&path);
+                (path, mtime)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut changes = vec![];
+        for (path, mtime) in &files {
+            match self.files.get(path) {
+                None => changes.push((path.clone(), FileChange::Created)),
+                Some(old_mtime) if old_mtime != mtime => {
+                    changes.push((path.clone(), FileChange::Changed))
+                }
+                Some(_) => {}
+            }
+        }
+        for path in self.files.keys() {
+            if !files.contains_key(path) {
+                changes.push((path.clone(), FileChange::Deleted));
+            }
+        }
+        changes.sort();
+
+        self.files = files;
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::vfs::MemoryFs;
+    use std::time::Duration;
+
+    #[test]
+    fn test_poll() {
+        let fs = MemoryFs::default();
+        let mut poller = FilePoller::default();
+        let mut poll = |fs: &MemoryFs| {
+            let paths = fs.glob("/ws/**/*.hsp");
+            poller
+                .poll(fs, paths)
+                .into_iter()
+                .map(|(path, change)| format!("{:?} {}", change, path.display()))
+                .collect::<Vec<_>>()
+        };
+
+        fs.write("/ws/a.hsp", "");
+        fs.write("/ws/b.hsp", "");
+        assert_eq!(poll(&fs), vec!["Created /ws/a.hsp", "Created /ws/b.hsp"]);
+        assert_eq!(poll(&fs), Vec::<String>::new());
+
+        fs.set_mtime(
+            Path::new("/ws/a.hsp"),
+            SystemTime::now() + Duration::from_secs(1),
+        );
+        fs.remove(Path::new("/ws/b.hsp"));
+        fs.write("/ws/sub/c.hsp", "");
+        assert_eq!(
+            poll(&fs),
+            vec![
+                "Changed /ws/a.hsp",
+                "Deleted /ws/b.hsp",
+                "Created /ws/sub/c.hsp"
+            ]
+        );
+    }
+}
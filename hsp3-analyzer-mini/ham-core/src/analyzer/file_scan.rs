@@ -3,10 +3,12 @@
 use super::*;
 
 /// `common` ディレクトリを探索してファイルを収集する
+///
+/// (ファイルの内容は読み込まない。`Analyzer::load_hsp3_root` を参照)
 pub(super) fn scan_common(
+    vfs: &dyn Vfs,
     hsp3_root: &Path,
     doc_interner: &mut DocInterner,
-    docs: &mut Docs,
     common_docs: &mut HashMap<String, DocId>,
 ) {
     debug!("scan_common");
@@ -27,20 +29,20 @@ pub(super) fn scan_common(
 
     for path in patterns
         .into_iter()
-        .flat_map(|pattern| glob::glob(&pattern).unwrap())
-        .flat_map(|result| result.ok())
+        .flat_map(|pattern| vfs.glob(&pattern))
         .filter(|path| !is_excluded(&path))
     {
         (|| -> Option<()> {
             // commonに対する相対パス
+            // (`#include` や `#use` の名前と照合するため、小文字に揃える)
             let relative = path
                 .strip_prefix(&common_dir)
                 .ok()?
                 .to_string_lossy()
-                .replace("\\", "/");
+                .replace("\\", "/")
+                .to_ascii_lowercase();
 
             let (_, doc) = doc_interner.intern(&CanonicalUri::from_abs_path(&path)?);
-            docs.ensure_file_opened(doc, &path)?;
             // debug!("common/{} => doc={}", relative, doc);
             common_docs.insert(relative, doc);
             None
@@ -48,19 +50,45 @@ pub(super) fn scan_common(
     }
 }
 
-/// スクリプトファイルを探索する
+/// ワークスペースで探索・監視するファイルの拡張子
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FileExtensions(Vec<String>);
+
+impl FileExtensions {
+    /// カンマ区切りの拡張子の並び (`hsp,as,hs` など) から作る。(先頭の `.` はあってもなくてもいい)
+    pub(crate) fn parse(s: &str) -> Self {
+        FileExtensions(
+            s.split(',')
+                .map(|ext| ext.trim().trim_start_matches('.'))
+                .filter(|ext| !ext.is_empty())
+                .map(|ext| ext.to_string())
+                .collect(),
+        )
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|ext| ext.as_str())
+    }
+}
+
+impl Default for FileExtensions {
+    fn default() -> Self {
+        FileExtensions::parse("hsp,as,hs")
+    }
+}
+
+/// ワークスペースのファイルを探索する
 ///
-/// - ルートディレクトリから再帰的にディレクトリをたどり、`.hsp` 拡張子のファイルを見つけるたび、 `on_script_path` 関数が呼ばれる
-pub(crate) fn scan_script_files(root_dir: &Path, mut on_script_path: impl FnMut(PathBuf)) {
-    let glob_results = match glob::glob(&format!("{}/**/*.hsp", root_dir.to_string_lossy())) {
-        Ok(it) => it,
-        Err(err) => {
-            warn!("glob: {:?}", err);
-            return;
+/// - ルートディレクトリから再帰的にディレクトリをたどり、指定された拡張子のファイルを見つけるたび、 `on_path` 関数が呼ばれる
+pub(crate) fn scan_workspace_files(
+    vfs: &dyn Vfs,
+    root_dir: &Path,
+    extensions: &FileExtensions,
+    mut on_path: impl FnMut(PathBuf),
+) {
+    for ext in extensions.iter() {
+        for path in vfs.glob(&format!("{}/**/*.{}", root_dir.to_string_lossy(), ext)) {
+            on_path(path);
         }
-    };
-
-    for path in glob_results.flatten() {
-        on_script_path(path);
     }
 }
@@ -0,0 +1,330 @@
+//! HSPのインストールディレクトリと、ワークスペースごとの対象のHSP
+//!
+//! 複数の版のHSPを使い分けられるように、ワークスペースごとにHSPのインストールディレクトリと版を設定できる。
+//! (ワークスペースの直下にある `hsp3-analyzer-mini.json` で設定する。)
+
+use super::*;
+use crate::{
+    analyzer::{
+        index_cache::IndexCache,
+        search_hsphelp::{is_builtin, search_hsphelp, HspHelpInfo},
+        vfs::Vfs,
+    },
+    hsp_version::HspVersion,
+    utils::read_file::decode_sjis_contents,
+};
+use normalize_path::NormalizePath;
+use serde::Deserialize;
+
+/// ワークスペースの設定ファイルの名前
+pub(crate) const WORKSPACE_CONFIG_FILE: &str = "hsp3-analyzer-mini.json";
+
+/// HSPのインストールディレクトリから得られる情報
+#[derive(Default)]
+pub(crate) struct Hsp3Root {
+    /// インストールディレクトリ (なければ同梱のデータベースを使う)
+    pub(crate) dir_opt: Option<PathBuf>,
+    /// インストールされているHSPの版 (分からなければNone)
+    pub(crate) version_opt: Option<HspVersion>,
+
+    pub(crate) index_cache: IndexCache,
+    pub(crate) common_docs: HashMap<String, DocId>,
+    pub(crate) hsphelp_info: HspHelpInfo,
+}
+
+impl Hsp3Root {
+    pub(crate) fn new(dir_opt: Option<PathBuf>) -> Self {
+        Self {
+            dir_opt,
+            ..Default::default()
+        }
+    }
+
+    /// インストールディレクトリにある common とヘルプファイルを探索する。
+    ///
+    /// (`use_bundled` なら、標準命令などのヘルプファイルがないとき同梱のデータベースを使う。)
+    pub(crate) fn load(
+        &mut self,
+        vfs: &dyn Vfs,
+        doc_interner: &mut DocInterner,
+        docs: &mut Docs,
+        use_bundled: bool,
+    ) {
+        if let Some(hsp3_root) = &self.dir_opt {
+            if vfs.is_disk() && hsp3_root.is_dir() {
+                self.index_cache = IndexCache::load(hsp3_root);
+            }
+
+            file_scan::scan_common(vfs, hsp3_root, doc_interner, &mut self.common_docs);
+
+            self.hsphelp_info = search_hsphelp(
+                vfs,
+                hsp3_root,
+                &self.common_docs,
+                &self.index_cache,
+                doc_interner,
+                docs,
+            )
+            .unwrap_or_default();
+
+            if self.version_opt.is_none() {
+                self.version_opt = detect_hsp_version(vfs, hsp3_root);
+                debug!("hsp3_root={:?} version={:?}", hsp3_root, self.version_opt);
+            }
+        }
+
+        if use_bundled && self.hsphelp_info.builtin_docs.is_empty() {
+            debug!("use bundled builtin symbols");
+            self.hsphelp_info.load_bundled();
+        }
+    }
+
+    pub(crate) fn contains(&self, path: &Path) -> bool {
+        match &self.dir_opt {
+            Some(dir) => path.starts_with(dir),
+            None => false,
+        }
+    }
+}
+
+/// HSPのコンパイラ (hspcmp) のファイル名
+const HSPCMP_FILES: &[&str] = &["hspcmp.dll", "hspcmp.exe"];
+
+/// インストールされているHSPの版を推定する。
+///
+/// hspcmp のバージョン情報から得る。分からなければ、ヘルプファイルから推定する。
+pub(crate) fn detect_hsp_version(vfs: &dyn Vfs, hsp3_root: &Path) -> Option<HspVersion> {
+    hspcmp_version(vfs, hsp3_root).or_else(|| hsphelp_version(vfs, hsp3_root))
+}
+
+/// hspcmp のバージョン情報にある版
+fn hspcmp_version(vfs: &dyn Vfs, hsp3_root: &Path) -> Option<HspVersion> {
+    HSPCMP_FILES.iter().find_map(|name| {
+        let contents = vfs.read(&hsp3_root.join(name)).ok()?;
+        let version = file_version(&contents)?;
+        parse_file_version(&version)
+    })
+}
+
+/// 実行ファイルのバージョン情報リソースから `FileVersion` の値を探す。
+///
+/// (バージョン情報の文字列は UTF-16LE で、キーの後ろに0で埋められた部分を挟んで値が続く。)
+fn file_version(contents: &[u8]) -> Option<String> {
+    let key = "FileVersion\0"
+        .encode_utf16()
+        .flat_map(|c| c.to_le_bytes())
+        .collect::<Vec<_>>();
+    let start = contents.windows(key.len()).position(|w| w == key)? + key.len();
+
+    let value = contents[start..]
+        .chunks_exact(2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .skip_while(|&c| c == 0)
+        .take_while(|&c| c != 0)
+        .collect::<Vec<_>>();
+    String::from_utf16(&value).ok()
+}
+
+/// `3.6.0.1` や `3, 5, 1, 0` のような形式の版を解釈する。(3つ目の数は `3.51` の `1` にあたる。)
+fn parse_file_version(s: &str) -> Option<HspVersion> {
+    let numbers = s
+        .split(['.', ','])
+        .map(|n| n.trim().parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>();
+    match numbers.as_deref() {
+        Some(&[major, minor, patch, ..]) => Some(HspVersion::new(major, minor, patch)),
+        _ => HspVersion::parse(s),
+    }
+}
+
+/// 標準命令などのヘルプファイルの先頭に書かれている `%ver` のうち、もっとも新しいもの
+fn hsphelp_version(vfs: &dyn Vfs, hsp3_root: &Path) -> Option<HspVersion> {
+    let hsphelp_dir = hsp3_root.join("hsphelp");
+
+    vfs.glob(&format!("{}/*.hs", hsphelp_dir.to_string_lossy()))
+        .into_iter()
+        .filter(|path| {
+            path.file_stem()
+                .is_some_and(|stem| is_builtin(&stem.to_string_lossy()))
+        })
+        .filter_map(|path| {
+            let contents = vfs.read(&path).ok()?;
+            let mut text = String::new();
+            if !decode_sjis_contents(&contents, &mut text) {
+                return None;
+            }
+            header_version(&text)
+        })
+        .max()
+}
+
+/// ヘルプファイルの先頭 (最初の `%index` より前) にある `%ver` の値
+fn header_version(text: &str) -> Option<HspVersion> {
+    let mut lines = text
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.starts_with(';'));
+
+    while let Some(line) = lines.next() {
+        let key = line.to_ascii_lowercase();
+        if key.starts_with("%index") {
+            break;
+        }
+        if key.starts_with("%ver") {
+            return lines
+                .find(|line| !line.is_empty())
+                .and_then(HspVersion::parse);
+        }
+    }
+    None
+}
+
+/// ワークスペースの設定 (`hsp3-analyzer-mini.json`)
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WorkspaceConfig {
+    /// HSPのインストールディレクトリ (ワークスペースからの相対パスでもいい)
+    pub(crate) hsp3_root: Option<String>,
+    /// 対象とするHSPの版 (`"3.6"` など。なければインストールされているものの版)
+    pub(crate) hsp_version: Option<String>,
+}
+
+impl WorkspaceConfig {
+    /// ワークスペースの設定ファイルを読む。(なければNone)
+    pub(crate) fn load(vfs: &dyn Vfs, workspace_dir: &Path) -> Option<WorkspaceConfig> {
+        let path = workspace_dir.join(WORKSPACE_CONFIG_FILE);
+        let contents = vfs.read(&path).ok()?;
+        match serde_json::from_slice(&contents) {
+            Ok(it) => Some(it),
+            Err(err) => {
+                warn!("設定ファイルを読めません。{:?} {}", path, err);
+                None
+            }
+        }
+    }
+
+    /// インストールディレクトリの絶対パス
+    ///
+    /// (ほかのワークスペースと同じディレクトリを指していると分かるように、`..` などを取り除く。)
+    pub(crate) fn hsp3_root_in(&self, workspace_dir: &Path) -> Option<PathBuf> {
+        let dir = self.hsp3_root.as_deref()?;
+        Some(workspace_dir.join(dir).normalize())
+    }
+}
+
+/// ドキュメントが対象とするHSP
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct HspTarget {
+    /// `Analyzer::hsp3_roots` のインデックス
+    pub(crate) root: usize,
+    /// 対象とするHSPの版 (分からなければNone)
+    pub(crate) version_opt: Option<HspVersion>,
+}
+
+/// パスにあるファイルが対象とするHSPを決める。
+///
+/// HSPのインストールディレクトリにあるファイルはそのHSPを対象とし、
+/// ワークスペースにあるファイルはそのワークスペースの設定に従う。
+/// (どちらでもなければ既定のHSP (`hsp3_roots[0]`) を対象とする。)
+pub(crate) fn resolve_target(
+    hsp3_roots: &[Hsp3Root],
+    folder_targets: &[(PathBuf, HspTarget)],
+    path_opt: Option<&Path>,
+) -> HspTarget {
+    let default_target = || HspTarget {
+        root: 0,
+        version_opt: hsp3_roots.first().and_then(|root| root.version_opt),
+    };
+    let path = match path_opt {
+        Some(it) => it,
+        None => return default_target(),
+    };
+
+    // 入れ子になっているときは、より深いほうを優先する。
+    let root_opt = hsp3_roots
+        .iter()
+        .enumerate()
+        .filter(|(_, root)| root.contains(path))
+        .max_by_key(|(_, root)| root.dir_opt.as_ref().map(|dir| dir.as_os_str().len()));
+    if let Some((index, root)) = root_opt {
+        return HspTarget {
+            root: index,
+            version_opt: root.version_opt,
+        };
+    }
+
+    folder_targets
+        .iter()
+        .filter(|(dir, _)| path.starts_with(dir))
+        .max_by_key(|(dir, _)| dir.as_os_str().len())
+        .map(|&(_, target)| target)
+        .unwrap_or_else(default_target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::vfs::MemoryFs;
+
+    #[test]
+    fn test_header_version() {
+        let text = "; comment\n%dll\n標準命令\n%ver\n3.6\n%index\nfoo\n%ver\n3.7\n";
+        assert_eq!(header_version(text), HspVersion::parse("3.6"));
+
+        // `%index` の後ろにあるものは使わない。
+        let text = "%index\nfoo\n%ver\n3.7\n";
+        assert_eq!(header_version(text), None);
+    }
+
+    /// バージョン情報リソースの `FileVersion` を含むファイルの内容 (の一部) を作る。
+    fn fake_hspcmp(version: &str) -> Vec<u8> {
+        let utf16 = |s: &str| {
+            s.encode_utf16()
+                .flat_map(|c| c.to_le_bytes())
+                .collect::<Vec<_>>()
+        };
+
+        let mut contents = b"MZ\x90\0 dummy".to_vec();
+        contents.extend(utf16("CompanyName\0\0onion software\0"));
+        contents.extend(utf16("FileVersion\0\0"));
+        contents.extend(utf16(version));
+        contents.extend(utf16("\0\0ProductName\0"));
+        contents
+    }
+
+    #[test]
+    fn test_parse_file_version() {
+        let parse = |s: &str| parse_file_version(s).map(|v| v.to_string());
+        assert_eq!(parse("3.7.0.0").as_deref(), Some("3.7"));
+        assert_eq!(parse("3, 5, 1, 0").as_deref(), Some("3.51"));
+        assert_eq!(parse("3.7beta6").as_deref(), Some("3.7"));
+        assert_eq!(parse("unknown"), None);
+    }
+
+    #[test]
+    fn test_detect_hsp_version() {
+        let root = crate::test_utils::dummy_path().join("hsp3");
+        let fs = MemoryFs::default();
+        fs.write(
+            root.join("hsphelp/i_hsp3func.hs"),
+            "%ver\n3.6\n%index\nmes\n",
+        );
+
+        // hspcmp がなければ、ヘルプファイルの `%ver` を使う。
+        let detect = |fs: &MemoryFs| detect_hsp_version(fs, &root).map(|v| v.to_string());
+        assert_eq!(detect(&fs).as_deref(), Some("3.6"));
+
+        // バージョン情報のない hspcmp は無視する。
+        fs.write(root.join("hspcmp.dll"), b"MZ\x90\0 no version".to_vec());
+        assert_eq!(detect(&fs).as_deref(), Some("3.6"));
+
+        // hspcmp のバージョン情報を優先する。
+        fs.write(root.join("hspcmp.dll"), fake_hspcmp("3.7.0.0"));
+        assert_eq!(detect(&fs).as_deref(), Some("3.7"));
+
+        // hspcmp.exe しかなくてもいい。
+        fs.remove(&root.join("hspcmp.dll"));
+        fs.write(root.join("hspcmp.exe"), fake_hspcmp("3, 5, 1, 0"));
+        assert_eq!(detect(&fs).as_deref(), Some("3.51"));
+    }
+}
@@ -0,0 +1,295 @@
+//! 解析結果のキャッシュ
+//!
+//! HSPのインストールディレクトリにあるファイル (common のスクリプトと hsphelp のヘルプファイル) の
+//! 解析結果をディスクに保存して、次回の起動時にファイルの読み込みと解析を省く。
+//!
+//! 解析結果はファイルごとに、ファイルの最終更新日時とともに保存する。
+//! ファイルが更新されていたら、そのファイルの解析結果だけを捨てる。
+//! (ham のバージョンが変わったら、すべて捨てる。)
+
+use super::*;
+use crate::{analysis::DocSummary, analyzer::vfs::Vfs, help_source::HsSymbol};
+use serde::{Deserialize, Serialize};
+use std::{env, time::UNIX_EPOCH};
+
+/// キャッシュの形式が変わったら増やす
+const FORMAT_VERSION: u32 = 3;
+
+#[derive(Default, Serialize, Deserialize)]
+struct CacheData {
+    /// キャッシュを書き込んだ ham のバージョン (形式のバージョンを含む)
+    version: String,
+
+    /// ファイルパスごとのスクリプトファイルの解析結果
+    doc_entries: HashMap<String, DocEntry>,
+
+    /// ファイルパスごとのヘルプファイルの解析結果
+    help_entries: HashMap<String, HelpEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DocEntry {
+    /// 解析したときのファイルの最終更新日時 (UNIXエポックからのミリ秒)
+    mtime: u64,
+    summary: DocSummary,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HelpEntry {
+    /// 解析したときのファイルの最終更新日時 (UNIXエポックからのミリ秒)
+    mtime: u64,
+    symbols: Vec<HsSymbol>,
+}
+
+/// ディスク上に保存される解析結果のキャッシュ
+#[derive(Default)]
+pub(crate) struct IndexCache {
+    /// キャッシュの対象になるディレクトリ (HSPのインストールディレクトリ)
+    root: PathBuf,
+
+    /// キャッシュファイルのパス (Noneならキャッシュは無効)
+    file_path_opt: Option<PathBuf>,
+
+    data: CacheData,
+
+    /// 保存されていない変更があるか
+    dirty: bool,
+}
+
+fn current_version() -> String {
+    format!("{}+{}", env!("CARGO_PKG_VERSION"), FORMAT_VERSION)
+}
+
+/// キャッシュを置くディレクトリを決める
+///
+/// (環境変数 `HAM_CACHE_DIR` で指定できる。空ならキャッシュを使わない。)
+fn cache_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("HAM_CACHE_DIR") {
+        return if dir.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(dir))
+        };
+    }
+
+    let base = if cfg!(target_os = "windows") {
+        PathBuf::from(env::var_os("LOCALAPPDATA")?)
+    } else if let Some(dir) = env::var_os("XDG_CACHE_HOME") {
+        PathBuf::from(dir)
+    } else {
+        PathBuf::from(env::var_os("HOME")?).join(".cache")
+    };
+    Some(base.join("hsp3-analyzer-mini"))
+}
+
+/// キャッシュファイルの名前
+///
+/// (インストールディレクトリのパスのハッシュ値を含む。
+///  Rustのバージョンなどによって名前が変わらないように、FNV-1a でハッシュ値を計算する。)
+fn cache_file_name(hsp3_root: &Path) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in hsp3_root.to_string_lossy().as_bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("index-{:016x}.json", hash)
+}
+
+/// ファイルの最終更新日時を得る
+fn mtime_of(vfs: &dyn Vfs, path: &Path) -> Option<u64> {
+    let duration = vfs.mtime(path)?.duration_since(UNIX_EPOCH).ok()?;
+    Some(duration.as_millis() as u64)
+}
+
+impl IndexCache {
+    /// HSPのインストールディレクトリに対応するキャッシュを読み込む。
+    pub(crate) fn load(hsp3_root: &Path) -> Self {
+        let file_path_opt = cache_dir().map(|dir| dir.join(cache_file_name(hsp3_root)));
+        Self::load_from(hsp3_root, file_path_opt)
+    }
+
+    pub(crate) fn load_from(hsp3_root: &Path, file_path_opt: Option<PathBuf>) -> Self {
+        let mut data = file_path_opt
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(
+                |contents| match serde_json::from_str::<CacheData>(&contents) {
+                    Ok(it) => Some(it),
+                    Err(err) => {
+                        warn!("キャッシュを読み込めません。{:?}", err);
+                        None
+                    }
+                },
+            )
+            .unwrap_or_default();
+
+        // 異なるバージョンで作られたキャッシュは使わない。
+        if data.version != current_version() {
+            data = CacheData {
+                version: current_version(),
+                ..Default::default()
+            };
+        }
+
+        debug!(
+            "index_cache: {:?} doc_entries={} help_entries={}",
+            file_path_opt,
+            data.doc_entries.len(),
+            data.help_entries.len()
+        );
+
+        Self {
+            root: hsp3_root.to_path_buf(),
+            file_path_opt,
+            data,
+            dirty: false,
+        }
+    }
+
+    /// スクリプトファイルの解析結果の要約を得る。ファイルが更新されていたらNone
+    pub(crate) fn get_doc(&self, vfs: &dyn Vfs, path: &Path) -> Option<&DocSummary> {
+        let entry = self.data.doc_entries.get(path.to_str()?)?;
+        if mtime_of(vfs, path)? != entry.mtime {
+            return None;
+        }
+        Some(&entry.summary)
+    }
+
+    /// スクリプトファイルの解析結果の要約を記録する。(キャッシュの対象外のファイルなら何もしない。)
+    pub(crate) fn put_doc(&mut self, vfs: &dyn Vfs, path: &Path, summary: DocSummary) {
+        if let Some((key, mtime)) = self.entry_key(vfs, path) {
+            self.data
+                .doc_entries
+                .insert(key, DocEntry { mtime, summary });
+            self.dirty = true;
+        }
+    }
+
+    /// ヘルプファイルの解析結果を得る。ファイルが更新されていたらNone
+    pub(crate) fn get_help(&self, vfs: &dyn Vfs, path: &Path) -> Option<&[HsSymbol]> {
+        let entry = self.data.help_entries.get(path.to_str()?)?;
+        if mtime_of(vfs, path)? != entry.mtime {
+            return None;
+        }
+        Some(&entry.symbols)
+    }
+
+    /// ヘルプファイルの解析結果を記録する。(キャッシュの対象外のファイルなら何もしない。)
+    pub(crate) fn put_help(&mut self, vfs: &dyn Vfs, path: &Path, symbols: Vec<HsSymbol>) {
+        if let Some((key, mtime)) = self.entry_key(vfs, path) {
+            self.data
+                .help_entries
+                .insert(key, HelpEntry { mtime, symbols });
+            self.dirty = true;
+        }
+    }
+
+    /// 解析結果を記録するときのキーと、ファイルの最終更新日時 (キャッシュの対象外のファイルならNone)
+    fn entry_key(&self, vfs: &dyn Vfs, path: &Path) -> Option<(String, u64)> {
+        if self.file_path_opt.is_none() || !path.starts_with(&self.root) {
+            return None;
+        }
+        Some((path.to_str()?.to_string(), mtime_of(vfs, path)?))
+    }
+
+    /// 変更があればキャッシュファイルに書き込む。
+    pub(crate) fn save(&mut self) {
+        let file_path = match &self.file_path_opt {
+            Some(it) if self.dirty => it,
+            _ => return,
+        };
+        self.dirty = false;
+
+        let result = (|| -> std::io::Result<()> {
+            if let Some(dir) = file_path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+
+            // 書き込み中に中断されても壊れたファイルが残らないように、別のファイルに書いてから置き換える。
+            let temp_path = file_path.with_extension("json.tmp");
+            fs::write(&temp_path, serde_json::to_string(&self.data)?)?;
+            fs::rename(&temp_path, file_path)
+        })();
+        if let Err(err) = result {
+            warn!("キャッシュを保存できません。{:?} {:?}", file_path, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::vfs::MemoryFs;
+    use std::time::Duration;
+
+    #[test]
+    fn test_help_entry_invalidation() {
+        let root = env::temp_dir().join(format!("ham-index-cache-test-{}", std::process::id()));
+        let hs_path = root.join("hsphelp").join("a.hs");
+        let cache_path = root.join("cache").join("index.json");
+        let vfs = MemoryFs::default();
+        vfs.write(hs_path.clone(), "%index\nfoo\n");
+
+        let symbols = vec![HsSymbol {
+            name: "foo".into(),
+            ..Default::default()
+        }];
+
+        let mut cache = IndexCache::load_from(&root, Some(cache_path.clone()));
+        cache.put_help(&vfs, &hs_path, symbols);
+        cache.save();
+
+        // 読み込みなおしたキャッシュから解析結果を得られる。
+        let cache = IndexCache::load_from(&root, Some(cache_path.clone()));
+        let names = cache
+            .get_help(&vfs, &hs_path)
+            .map(|symbols| symbols.iter().map(|s| s.name.as_str()).collect::<Vec<_>>());
+        assert_eq!(names, Some(vec!["foo"]));
+
+        // ファイルが更新されたら無効になる。
+        vfs.set_mtime(&hs_path, UNIX_EPOCH + Duration::from_secs(1));
+        assert!(cache.get_help(&vfs, &hs_path).is_none());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_doc_entry_invalidation() {
+        let root = env::temp_dir().join(format!("ham-index-cache-doc-test-{}", std::process::id()));
+        let as_path = root.join("common").join("a.as");
+        let cache_path = root.join("cache").join("index.json");
+        let vfs = MemoryFs::default();
+        vfs.write(as_path.clone(), "#deffunc foo\n");
+
+        let mut da = DocAnalysis::default();
+        da.compute(1, "#deffunc foo\n".into());
+
+        let mut cache = IndexCache::load_from(&root, Some(cache_path.clone()));
+        cache.put_doc(&vfs, &as_path, DocSummary::new(&da).unwrap());
+        cache.save();
+
+        // 読み込みなおしたキャッシュから解析結果を得られる。
+        let cache = IndexCache::load_from(&root, Some(cache_path.clone()));
+        let summary = cache.get_doc(&vfs, &as_path);
+        assert!(summary.is_some());
+
+        // ファイルが更新されたら無効になる。
+        vfs.set_mtime(&as_path, UNIX_EPOCH + Duration::from_secs(1));
+        assert!(cache.get_doc(&vfs, &as_path).is_none());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_cache_file_name() {
+        // 名前は実行環境によらず、パスだけで決まる。
+        assert_eq!(
+            cache_file_name(Path::new("")),
+            "index-cbf29ce484222325.json"
+        );
+        assert_eq!(
+            cache_file_name(Path::new("a")),
+            "index-af63dc4c8601ec8c.json"
+        );
+    }
+}
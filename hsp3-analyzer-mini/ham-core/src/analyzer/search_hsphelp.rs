@@ -1,7 +1,17 @@
 //! hsphelp ディレクトリ内のファイル探索
 
 use super::*;
-use crate::{help_source::parse_for_symbols, source::DocId, utils::read_file::read_sjis_file};
+use crate::{
+    analyzer::{index_cache::IndexCache, vfs::Vfs},
+    help_source::{lint_help_source, parse_for_symbols, HsLint},
+    hsp_version::HspVersion,
+    source::{DocId, Range},
+};
+
+/// 同梱している標準命令などのデータベース
+///
+/// (`data/hsphelp` から `ham gen-builtin` で生成したもの。HSPのインストールがないときに使う。)
+const BUNDLED_HELP_SOURCE: &str = include_str!("../../data/builtin.hs");
 
 #[derive(Default)]
 pub(crate) struct HspHelpInfo {
@@ -13,9 +23,130 @@ pub(crate) struct HspHelpInfo {
 
     /// ヘルプファイルに含まれる情報
     pub(crate) doc_symbols: HashMap<DocId, Vec<CompletionItem>>,
+
+    /// 標準命令や関数などのヘルプファイルに含まれるシンボル
+    builtin_symbols: HashMap<DocId, Vec<SymbolRc>>,
+
+    /// ヘルプファイルの内容
+    doc_texts: HashMap<DocId, RcStr>,
+
+    /// ヘルプファイルの書きかたの誤り
+    pub(crate) doc_lints: HashMap<DocId, Vec<(HsLint, Range)>>,
+
+    /// 同梱のデータベースに含まれる情報 (`load_bundled` で読み込んだときだけ)
+    pub(crate) bundled_items: Vec<CompletionItem>,
+
+    /// 同梱のデータベースに含まれるシンボル
+    bundled_symbols: Vec<SymbolRc>,
+}
+
+impl HspHelpInfo {
+    /// ヘルプファイルとして解析されているドキュメントか？
+    pub(crate) fn is_help_doc(&self, doc: DocId) -> bool {
+        self.doc_symbols.contains_key(&doc)
+    }
+
+    pub(crate) fn get_text(&self, doc: DocId) -> Option<&RcStr> {
+        self.doc_texts.get(&doc)
+    }
+
+    /// ヘルプファイルを解析して、そのファイルに関する情報を更新する。
+    ///
+    /// (キャッシュに保存できるように、解析したシンボル情報を返す。)
+    pub(crate) fn update_doc(&mut self, doc: DocId, text: RcStr) -> Vec<HsSymbol> {
+        let mut hs_symbols = vec![];
+        let mut warnings = vec![];
+        parse_for_symbols(&text, &mut hs_symbols, &mut warnings);
+        for w in warnings {
+            warn!("hsphelp(doc:{}): {}", doc, w);
+        }
+
+        self.set_symbols(doc, &hs_symbols);
+
+        let mut lints = vec![];
+        lint_help_source(&text, &mut lints);
+
+        self.doc_texts.insert(doc, text);
+        self.doc_lints.insert(doc, lints);
+        hs_symbols
+    }
+
+    /// キャッシュから復元したシンボル情報を登録する。(ファイルの内容は読み込まない。)
+    pub(crate) fn restore_doc(&mut self, doc: DocId, hs_symbols: &[HsSymbol]) {
+        self.set_symbols(doc, hs_symbols);
+    }
+
+    fn set_symbols(&mut self, doc: DocId, hs_symbols: &[HsSymbol]) {
+        let builtin = self.builtin_docs.contains(&doc);
+        let mut symbols = vec![];
+        let mut completion_items = vec![];
+        for hs_symbol in hs_symbols {
+            let (symbol, completion_item) = convert_symbol(hs_symbol.clone());
+            completion_items.push(completion_item);
+
+            if builtin {
+                symbols.push(symbol);
+            }
+        }
+
+        self.doc_symbols.insert(doc, completion_items);
+        self.builtin_symbols.insert(doc, symbols);
+    }
+
+    /// ヘルプファイルに関する情報を削除する。ヘルプファイルでなければfalseを返す
+    pub(crate) fn remove_doc(&mut self, doc: DocId) -> bool {
+        self.builtin_symbols.remove(&doc);
+        self.doc_lints.remove(&doc);
+        self.doc_texts.remove(&doc);
+        self.doc_symbols.remove(&doc).is_some()
+    }
+
+    /// 同梱のデータベースから標準命令や関数などの情報を読み込む。
+    pub(crate) fn load_bundled(&mut self) {
+        let mut hs_symbols = vec![];
+        let mut warnings = vec![];
+        parse_for_symbols(BUNDLED_HELP_SOURCE, &mut hs_symbols, &mut warnings);
+        for w in warnings {
+            warn!("hsphelp(bundled): {}", w);
+        }
+
+        self.bundled_items.clear();
+        self.bundled_symbols.clear();
+        for hs_symbol in hs_symbols {
+            let (symbol, completion_item) = convert_symbol(hs_symbol);
+            self.bundled_items.push(completion_item);
+            self.bundled_symbols.push(symbol);
+        }
+    }
+
+    /// 補完候補として使うヘルプファイルの情報を列挙する
+    pub(crate) fn completion_items<'a>(
+        &'a self,
+        is_active: impl Fn(DocId) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a CompletionItem> + 'a {
+        self.doc_symbols
+            .iter()
+            .filter(move |(&doc, _)| is_active(doc))
+            .flat_map(|(_, items)| items.iter())
+            .chain(self.bundled_items.iter())
+    }
+
+    /// 標準命令や関数などのシンボルを環境に登録する
+    pub(crate) fn collect_builtin_env(&self, builtin_env: &mut SymbolEnv) {
+        for symbol in &self.bundled_symbols {
+            builtin_env.insert(symbol.name.clone(), symbol.clone());
+        }
+
+        for &doc in &self.builtin_docs {
+            for symbol in self.builtin_symbols.get(&doc).into_iter().flatten() {
+                builtin_env.insert(symbol.name.clone(), symbol.clone());
+            }
+        }
+    }
 }
 
-fn is_builtin(stem: &str) -> bool {
+/// 標準命令などのヘルプファイルか？ (ファイル名の拡張子を除いた部分で判定する)
+pub(crate) fn is_builtin(stem: &str) -> bool {
     match stem {
         "ex_macro" | "sysval" => true,
         "i_hsp3util" => false,
@@ -31,7 +162,9 @@ fn convert_symbol(hs_symbol: HsSymbol) -> (SymbolRc, CompletionItem) {
         description,
         documentation,
         params_opt,
+        inst_opt: _,
         builtin,
+        version_opt,
     } = hs_symbol;
 
     let name_rc = RcStr::from(name.clone());
@@ -56,6 +189,7 @@ fn convert_symbol(hs_symbol: HsSymbol) -> (SymbolRc, CompletionItem) {
         },
         builtin,
         signature_opt,
+        since_opt: version_opt.as_deref().and_then(HspVersion::parse),
     }
     .into_symbol();
 
@@ -93,58 +227,34 @@ fn convert_symbol(hs_symbol: HsSymbol) -> (SymbolRc, CompletionItem) {
     (symbol, completion_item)
 }
 
+/// hsphelp ディレクトリにあるヘルプファイルを列挙する
+///
+/// (ファイルの内容は、ドキュメントとして開かれた後に `HspHelpInfo::update_doc` で解析される。
+///  ただしキャッシュに有効な解析結果があるファイルは読み込まず、その結果を使う。)
 pub(crate) fn search_hsphelp(
+    vfs: &dyn Vfs,
     hsp3_root: &Path,
     common_docs: &HashMap<String, DocId>,
+    index_cache: &IndexCache,
     doc_interner: &mut DocInterner,
     docs: &mut Docs,
-    builtin_env: &mut SymbolEnv,
 ) -> Option<HspHelpInfo> {
     debug!("search_hsphelp");
 
     let hsphelp_dir = hsp3_root.join("hsphelp");
 
-    let entries = match fs::read_dir(&hsphelp_dir) {
-        Ok(it) => it,
-        Err(err) => {
-            warn!(
-                "hsphelpを開けません。hsphelp={:?}, err={:?}",
-                hsphelp_dir, err
-            );
-            return None;
-        }
-    };
+    let paths = vfs.glob(&format!("{}/*.hs", hsphelp_dir.to_string_lossy()));
+    if paths.is_empty() {
+        warn!("hsphelpにファイルがありません。hsphelp={:?}", hsphelp_dir);
+        return None;
+    }
 
     let mut info = HspHelpInfo::default();
-    let mut contents = String::new();
-    let mut hs_symbols = vec![];
-    let mut symbols = vec![];
-    let mut warnings = vec![];
-
-    for entry_result in entries {
-        debug_assert_eq!(hs_symbols.len(), 0);
-        debug_assert_eq!(warnings.len(), 0);
-        debug_assert_eq!(contents.len(), 0);
 
+    for full_path in paths {
         (|| -> Option<()> {
-            let relative_path = entry_result.ok()?.path();
-            let stem = relative_path.file_stem()?.to_string_lossy();
-            let ext = relative_path.extension()?;
-            if ext != "hs" {
-                return None;
-            }
-
-            let full_path = hsphelp_dir.join(&relative_path);
-            if !read_sjis_file(&full_path, &mut contents) {
-                warn!(
-                    "ファイルをshift_jisとして解釈できません。path={:?}",
-                    full_path
-                );
-                return None;
-            }
-
+            let stem = full_path.file_stem()?.to_string_lossy();
             let (_, hs_doc) = doc_interner.intern(&CanonicalUri::from_abs_path(&full_path)?);
-            docs.ensure_file_opened(hs_doc, &full_path)?;
 
             let builtin = is_builtin(&stem);
             debug!("{}.hs builtin={:?}", stem, builtin);
@@ -152,25 +262,20 @@ pub(crate) fn search_hsphelp(
                 info.builtin_docs.push(hs_doc);
             }
 
-            parse_for_symbols(&contents, &mut hs_symbols, &mut warnings);
-            contents.clear();
-            for w in warnings.drain(..) {
-                warn!("hsphelp({:?}): {}", full_path, w);
-            }
-
-            for hs_symbol in hs_symbols.drain(..) {
-                let (symbol, completion_item) = convert_symbol(hs_symbol);
-                symbols.push(completion_item);
-
-                if builtin {
-                    builtin_env.insert(symbol.name.clone(), symbol);
+            match index_cache.get_help(vfs, &full_path) {
+                Some(hs_symbols) => {
+                    docs.add_unloaded_file(hs_doc);
+                    info.restore_doc(hs_doc, hs_symbols);
+                }
+                None => {
+                    docs.ensure_file_opened(hs_doc, &full_path)?;
                 }
             }
 
-            info.doc_symbols.insert(hs_doc, symbols.split_off(0));
-
             // 同名のcommonのファイルとリンクする。
-            for name in [format!("{}.as", stem), format!("{}.hsp", stem)].iter() {
+            // (commonのファイル名は小文字に揃えてある)
+            let lower = stem.to_ascii_lowercase();
+            for name in [format!("{}.as", lower), format!("{}.hsp", lower)].iter() {
                 if let Some(&common_doc) = common_docs.get(name.as_str()) {
                     debug!("link {}.hs => {}", stem, name);
                     info.linked_docs.insert(common_doc, hs_doc);
@@ -0,0 +1,170 @@
+//! 仮想ファイルシステム
+//!
+//! アナライザーはファイルの読み込みやディレクトリの探索をこのトレイトを介して行う。
+//! (ディスク上のファイルを使わずに、メモリ上のファイルを与えて解析できるようにするため。)
+
+use super::*;
+use std::time::SystemTime;
+
+/// ファイルシステムに対する操作
+pub(crate) trait Vfs {
+    /// ファイルの内容を読む。
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// パターンに一致するファイルのパスを列挙する。(パターンの書き方は `glob` クレートと同じ)
+    fn glob(&self, pattern: &str) -> Vec<PathBuf>;
+
+    /// ファイルの最終更新日時を得る。
+    fn mtime(&self, path: &Path) -> Option<SystemTime>;
+
+    /// ディスク上のファイルシステムか？
+    ///
+    /// (解析結果のキャッシュはディスク上のファイルに対してだけ使う。)
+    fn is_disk(&self) -> bool {
+        false
+    }
+}
+
+/// ディスク上のファイルシステム
+pub(crate) struct StdFs;
+
+impl Vfs for StdFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn glob(&self, pattern: &str) -> Vec<PathBuf> {
+        match glob::glob(pattern) {
+            Ok(paths) => paths.flatten().collect(),
+            Err(err) => {
+                warn!("glob: {:?}", err);
+                vec![]
+            }
+        }
+    }
+
+    fn mtime(&self, path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).ok()?.modified().ok()
+    }
+
+    fn is_disk(&self) -> bool {
+        true
+    }
+}
+
+/// ファイルの内容と最終更新日時
+type FileEntry = (Rc<[u8]>, SystemTime);
+
+/// メモリ上のファイルシステム
+///
+/// (埋め込み先やテストがディスクを使わずにファイルを与えるためのもの。)
+#[derive(Default)]
+pub(crate) struct MemoryFs {
+    files: RefCell<HashMap<PathBuf, FileEntry>>,
+}
+
+impl MemoryFs {
+    /// ファイルを追加または変更する。最終更新日時は現在時刻になる。
+    pub(crate) fn write(&self, path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) {
+        self.files
+            .borrow_mut()
+            .insert(path.into(), (contents.into().into(), SystemTime::now()));
+    }
+
+    /// ファイルの最終更新日時を変更する。
+    #[cfg(test)]
+    pub(crate) fn set_mtime(&self, path: &Path, mtime: SystemTime) {
+        if let Some(entry) = self.files.borrow_mut().get_mut(path) {
+            entry.1 = mtime;
+        }
+    }
+
+    /// ファイルを削除する。ファイルがなければfalseを返す
+    pub(crate) fn remove(&self, path: &Path) -> bool {
+        self.files.borrow_mut().remove(path).is_some()
+    }
+}
+
+impl Vfs for MemoryFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.files.borrow().get(path) {
+            Some((contents, _)) => Ok(contents.to_vec()),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn glob(&self, pattern: &str) -> Vec<PathBuf> {
+        let pattern = match glob::Pattern::new(pattern) {
+            Ok(it) => it,
+            Err(err) => {
+                warn!("glob: {:?}", err);
+                return vec![];
+            }
+        };
+
+        // `*` がディレクトリの区切りにマッチしないようにする。(`glob::glob` と同じ)
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+
+        let mut paths = self
+            .files
+            .borrow()
+            .keys()
+            .filter(|path| pattern.matches_path_with(path, options))
+            .cloned()
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+
+    fn mtime(&self, path: &Path) -> Option<SystemTime> {
+        Some(self.files.borrow().get(path)?.1)
+    }
+}
+
+/// アナライザーが使うファイルシステムへの参照 (既定ではディスク上のファイルシステム)
+#[derive(Clone)]
+pub(crate) struct VfsRc(Rc<dyn Vfs>);
+
+impl VfsRc {
+    pub(crate) fn new(vfs: Rc<dyn Vfs>) -> Self {
+        Self(vfs)
+    }
+}
+
+impl Default for VfsRc {
+    fn default() -> Self {
+        Self(Rc::new(StdFs))
+    }
+}
+
+impl Deref for VfsRc {
+    type Target = dyn Vfs;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn test_memory_fs_glob() {
+        let fs = MemoryFs::default();
+        fs.write("/hsp3/common/a.as", "");
+        fs.write("/hsp3/common/sub/b.as", "");
+        fs.write("/hsp3/common/c.hsp", "");
+        fs.write("/hsp3/hsphelp/i_prog.hs", "");
+
+        let format = |pattern: &str| format!("{:?}", fs.glob(pattern));
+        expect![[r#"["/hsp3/common/a.as", "/hsp3/common/sub/b.as"]"#]]
+            .assert_eq(&format("/hsp3/common/**/*.as"));
+        expect![[r#"["/hsp3/common/a.as"]"#]].assert_eq(&format("/hsp3/common/*.as"));
+        expect![[r#"["/hsp3/hsphelp/i_prog.hs"]"#]].assert_eq(&format("/hsp3/hsphelp/*.hs"));
+    }
+}
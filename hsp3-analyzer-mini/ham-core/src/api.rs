@@ -0,0 +1,575 @@
+//! Rust から利用するための API
+//!
+//! パーサーやアナライザーを Rust のツール (ドキュメント生成、独自のリント、レビューボットなど) から使うためのもの。
+//! ここにある型と関数は、内部の実装を変更しても互換性を保つようにする。
+//!
+//! ## 使いかた
+//!
+//! `AnalysisHost` にファイルの内容を設定して、`snapshot` で得た `Analysis` に問い合わせる。
+//!
+//! ```ignore
+//! let mut host = AnalysisHost::new(AnalysisHostOptions::default());
+//! host.set_file_text(path, "#deffunc f\n\treturn\n".to_string());
+//! let analysis = host.snapshot();
+//! let diagnostics = analysis.diagnostics(path);
+//! ```
+//!
+//! ## 位置情報
+//!
+//! ファイルはパス (絶対パス) で指定する。
+//! 位置情報の行番号・列番号は 0 から数える。列番号は行の先頭からの UTF-8 のバイト数とする。
+
+mod syntax;
+
+pub use self::syntax::{Expr, ExprKind, Param, Stmt, StmtKind, SyntaxToken, SyntaxTree};
+
+use super::*;
+use crate::{
+    analysis::{collect_doc_symbols, HspSymbolKind},
+    analyzer::{Analyzer, AnalyzerRef},
+    source::{DocId, Pos, PositionEncoding, Range},
+};
+use lsp_types::{
+    CompletionTextEdit, Documentation, HoverContents, Location, MarkedString, Position, Url,
+    WorkspaceFolder,
+};
+
+/// 位置 (行番号と列番号)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextPosition {
+    /// 行番号 (0 から)
+    pub line: u32,
+    /// 列番号 (0 から。UTF-8 のバイト数)
+    pub column: u32,
+}
+
+impl TextPosition {
+    pub fn new(line: u32, column: u32) -> Self {
+        Self { line, column }
+    }
+
+    fn from_pos(pos: Pos) -> Self {
+        Self::new(pos.row, pos.column)
+    }
+
+    fn from_lsp(position: Position) -> Self {
+        Self::new(position.line, position.character)
+    }
+
+    fn to_lsp(self) -> Position {
+        Position::new(self.line, self.column)
+    }
+}
+
+/// 範囲 (終端を含まない)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TextRange {
+    pub start: TextPosition,
+    pub end: TextPosition,
+}
+
+impl TextRange {
+    fn from_range(range: Range) -> Self {
+        Self {
+            start: TextPosition::from_pos(range.start()),
+            end: TextPosition::from_pos(range.end()),
+        }
+    }
+
+    fn from_lsp(range: lsp_types::Range) -> Self {
+        Self {
+            start: TextPosition::from_lsp(range.start),
+            end: TextPosition::from_lsp(range.end),
+        }
+    }
+}
+
+/// ファイル内の範囲
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FileRange {
+    pub path: PathBuf,
+    pub range: TextRange,
+}
+
+impl FileRange {
+    fn from_location(location: Location) -> Option<Self> {
+        Some(Self {
+            path: location.uri.to_file_path().ok()?,
+            range: TextRange::from_lsp(location.range),
+        })
+    }
+}
+
+/// 診断の深刻度
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+/// 診断 (エラーや警告)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub range: TextRange,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// シンボルの種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SymbolKind {
+    Label,
+    /// 静的変数
+    Variable,
+    /// `#const`
+    Const,
+    /// `#enum`
+    Enum,
+    /// `#define`
+    Macro,
+    /// `#deffunc`, `#defcfunc`
+    Function,
+    /// `#modfunc`, `#modcfunc`
+    ModFunction,
+    /// `#deffunc` などのパラメータ
+    Param,
+    /// `#module`
+    Module,
+    /// モジュール変数
+    Field,
+    /// `#func`
+    LibFunc,
+    /// `#cmd`
+    PluginCmd,
+    /// `#usecom`
+    ComInterface,
+    /// `#comfunc`
+    ComFunc,
+    /// 不明 (定義箇所がみつからないものなど)
+    Unknown,
+}
+
+impl SymbolKind {
+    fn from_hsp(kind: HspSymbolKind) -> Self {
+        match kind {
+            HspSymbolKind::Unresolved | HspSymbolKind::Unknown => SymbolKind::Unknown,
+            HspSymbolKind::Label => SymbolKind::Label,
+            HspSymbolKind::StaticVar => SymbolKind::Variable,
+            HspSymbolKind::Const => SymbolKind::Const,
+            HspSymbolKind::Enum => SymbolKind::Enum,
+            HspSymbolKind::Macro { .. } => SymbolKind::Macro,
+            HspSymbolKind::DefFunc | HspSymbolKind::DefCFunc => SymbolKind::Function,
+            HspSymbolKind::ModFunc | HspSymbolKind::ModCFunc => SymbolKind::ModFunction,
+            HspSymbolKind::Param(_) => SymbolKind::Param,
+            HspSymbolKind::Module => SymbolKind::Module,
+            HspSymbolKind::Field => SymbolKind::Field,
+            HspSymbolKind::LibFunc => SymbolKind::LibFunc,
+            HspSymbolKind::PluginCmd => SymbolKind::PluginCmd,
+            HspSymbolKind::ComInterface => SymbolKind::ComInterface,
+            HspSymbolKind::ComFunc => SymbolKind::ComFunc,
+        }
+    }
+}
+
+/// ファイル内で定義されているシンボル
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// 定義箇所
+    pub range: TextRange,
+}
+
+/// 入力補完の候補
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompletionEntry {
+    pub label: String,
+    /// 挿入されるテキスト
+    pub insert_text: String,
+    /// 概要 (シグネチャなど)
+    pub detail: Option<String>,
+    /// 説明
+    pub documentation: Option<String>,
+}
+
+/// テキストの編集 (範囲を置き換える)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: TextRange,
+    pub new_text: String,
+}
+
+/// `AnalysisHost` の設定
+#[derive(Clone, Debug, Default)]
+pub struct AnalysisHostOptions {
+    /// HSPのインストールディレクトリ
+    ///
+    /// (指定されていなければ、同梱のデータベースにある標準命令だけを使う。)
+    pub hsp3_root: Option<PathBuf>,
+    /// ワークスペースのディレクトリ
+    ///
+    /// (ここにあるスクリプトファイルはすべて解析の対象になる。)
+    pub workspace_folders: Vec<PathBuf>,
+}
+
+/// 解析器の状態を持つもの
+///
+/// ファイルの内容を設定・変更して、`snapshot` で解析結果に問い合わせる。
+pub struct AnalysisHost {
+    analyzer: Analyzer,
+    /// `set_file_text` で設定されたファイルとそのバージョン
+    versions: HashMap<PathBuf, i32>,
+}
+
+impl AnalysisHost {
+    pub fn new(options: AnalysisHostOptions) -> Self {
+        let mut analyzer = Analyzer::new(options.hsp3_root);
+        analyzer.set_position_encoding(PositionEncoding::Utf8);
+
+        for folder in &options.workspace_folders {
+            match Url::from_directory_path(folder) {
+                Ok(uri) => analyzer.add_workspace_folder(WorkspaceFolder {
+                    uri,
+                    name: "".to_string(),
+                }),
+                Err(()) => warn!("workspace folder must be absolute {:?}", folder),
+            }
+        }
+
+        analyzer.did_initialize();
+
+        Self {
+            analyzer,
+            versions: HashMap::new(),
+        }
+    }
+
+    /// ファイルの内容を設定する。
+    ///
+    /// (ディスク上のファイルの内容より優先される。`path` は絶対パスでなければいけない。)
+    pub fn set_file_text(&mut self, path: &Path, text: String) {
+        let uri = match Url::from_file_path(path) {
+            Ok(it) => it,
+            Err(()) => {
+                warn!("path must be absolute {:?}", path);
+                return;
+            }
+        };
+
+        match self.versions.get_mut(path) {
+            Some(version) => {
+                *version += 1;
+                self.analyzer.change_doc(uri, *version, text);
+            }
+            None => {
+                self.versions.insert(path.to_path_buf(), 1);
+                self.analyzer.open_doc(uri, 1, text);
+            }
+        }
+    }
+
+    /// `set_file_text` で設定した内容を取り除く。
+    pub fn remove_file(&mut self, path: &Path) {
+        if self.versions.remove(path).is_none() {
+            return;
+        }
+        if let Ok(uri) = Url::from_file_path(path) {
+            self.analyzer.close_doc(uri);
+        }
+    }
+
+    /// 解析を行い、解析結果に問い合わせるためのスナップショットを得る。
+    pub fn snapshot(&mut self) -> Analysis<'_> {
+        Analysis {
+            an: self.analyzer.compute_ref(),
+        }
+    }
+}
+
+/// 解析結果のスナップショット
+///
+/// (`AnalysisHost` を変更するまで使える。)
+pub struct Analysis<'a> {
+    an: AnalyzerRef<'a>,
+}
+
+fn url_from_path(path: &Path) -> Option<Url> {
+    Url::from_file_path(path).ok()
+}
+
+impl<'a> Analysis<'a> {
+    /// 指定した位置にあるシンボルの定義箇所を列挙する。
+    pub fn definitions(&self, path: &Path, position: TextPosition) -> Vec<FileRange> {
+        let uri = match url_from_path(path) {
+            Some(it) => it,
+            None => return vec![],
+        };
+
+        self.an
+            .definitions(uri, position.to_lsp())
+            .into_iter()
+            .filter_map(FileRange::from_location)
+            .collect()
+    }
+
+    /// 指定した位置にあるシンボルの出現箇所を列挙する。
+    pub fn references(
+        &self,
+        path: &Path,
+        position: TextPosition,
+        include_definition: bool,
+    ) -> Vec<FileRange> {
+        let uri = match url_from_path(path) {
+            Some(it) => it,
+            None => return vec![],
+        };
+
+        self.an
+            .references(uri, position.to_lsp(), include_definition)
+            .into_iter()
+            .filter_map(FileRange::from_location)
+            .collect()
+    }
+
+    /// 指定した位置にあるシンボルの説明 (Markdown) を得る。
+    pub fn hover(&self, path: &Path, position: TextPosition) -> Option<String> {
+        let hover = self.an.hover(url_from_path(path)?, position.to_lsp())?;
+
+        fn marked_string_to_string(it: MarkedString) -> String {
+            match it {
+                MarkedString::String(text) => text,
+                MarkedString::LanguageString(s) => s.value,
+            }
+        }
+
+        let text = match hover.contents {
+            HoverContents::Scalar(scalar) => marked_string_to_string(scalar),
+            HoverContents::Array(contents) => contents
+                .into_iter()
+                .map(marked_string_to_string)
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            HoverContents::Markup(markup) => markup.value,
+        };
+        Some(text)
+    }
+
+    /// 指定した位置における入力補完の候補を列挙する。
+    pub fn completion(&self, path: &Path, position: TextPosition) -> Vec<CompletionEntry> {
+        let uri = match url_from_path(path) {
+            Some(it) => it,
+            None => return vec![],
+        };
+
+        self.an
+            .completion(uri, position.to_lsp())
+            .items
+            .into_iter()
+            .map(|item| {
+                let insert_text = match item.text_edit {
+                    Some(CompletionTextEdit::Edit(edit)) => edit.new_text,
+                    Some(CompletionTextEdit::InsertAndReplace(edit)) => edit.new_text,
+                    None => item.insert_text.unwrap_or_else(|| item.label.clone()),
+                };
+                let documentation = item.documentation.map(|it| match it {
+                    Documentation::String(text) => text,
+                    Documentation::MarkupContent(markup) => markup.value,
+                });
+
+                CompletionEntry {
+                    label: item.label,
+                    insert_text,
+                    detail: item.detail,
+                    documentation,
+                }
+            })
+            .collect()
+    }
+
+    /// ファイルの診断 (エラーや警告) を列挙する。
+    pub fn diagnostics(&self, path: &Path) -> Vec<Diagnostic> {
+        let c_uri = match CanonicalUri::from_abs_path(path) {
+            Some(it) => it,
+            None => return vec![],
+        };
+
+        let diagnostics = self
+            .an
+            .diagnose()
+            .into_iter()
+            .find(|(uri, _, _)| CanonicalUri::from_url(uri) == c_uri)
+            .map(|(_, _, d)| d)
+            .unwrap_or_default();
+
+        diagnostics
+            .into_iter()
+            .map(|d| {
+                use lsp_types::DiagnosticSeverity as S;
+
+                let severity = match d.severity {
+                    Some(S::WARNING) => Severity::Warning,
+                    Some(S::INFORMATION) => Severity::Information,
+                    Some(S::HINT) => Severity::Hint,
+                    _ => Severity::Error,
+                };
+
+                Diagnostic {
+                    range: TextRange::from_lsp(d.range),
+                    severity,
+                    message: d.message,
+                }
+            })
+            .collect()
+    }
+
+    /// ファイル内で定義されているシンボルを、定義箇所の順に列挙する。
+    pub fn document_symbols(&self, path: &Path) -> Vec<Symbol> {
+        let doc = match self.doc_of_path(path) {
+            Some(it) => it,
+            None => return vec![],
+        };
+
+        let mut symbols = vec![];
+        collect_doc_symbols(&self.an, doc, &mut symbols);
+        symbols.retain(|(s, _)| !s.name().is_empty());
+        symbols.sort_by_key(|(_, loc)| loc.start());
+
+        symbols
+            .into_iter()
+            .map(|(symbol, loc)| Symbol {
+                name: symbol.name().to_string(),
+                kind: SymbolKind::from_hsp(symbol.kind),
+                range: TextRange::from_range(loc.range),
+            })
+            .collect()
+    }
+
+    /// ファイルを整形するための編集を得る。
+    pub fn formatting(&self, path: &Path) -> Vec<TextEdit> {
+        let uri = match url_from_path(path) {
+            Some(it) => it,
+            None => return vec![],
+        };
+
+        self.an
+            .formatting(uri)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|edit| TextEdit {
+                range: TextRange::from_lsp(edit.range),
+                new_text: edit.new_text,
+            })
+            .collect()
+    }
+
+    /// ファイルの構文木を得る。
+    pub fn syntax_tree(&self, path: &Path) -> Option<SyntaxTree> {
+        let syntax = self.an.get_syntax(self.doc_of_path(path)?)?;
+        Some(SyntaxTree::new(syntax))
+    }
+
+    fn doc_of_path(&self, path: &Path) -> Option<DocId> {
+        let c_uri = CanonicalUri::from_abs_path(path)?;
+        self.an.get_doc_interner().get_doc(&c_uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    #[test]
+    fn test_analysis_host() {
+        let ws = crate::test_utils::dummy_path().join("ws");
+        let path = ws.join("main.hsp");
+        let text = "#deffunc foo int a\n\treturn\n\n\tfoo 1\n\tmes \"あ\", 2, 3\n";
+
+        let mut host = AnalysisHost::new(AnalysisHostOptions {
+            hsp3_root: None,
+            workspace_folders: vec![ws],
+        });
+        host.set_file_text(&path, text.to_string());
+
+        let analysis = host.snapshot();
+        let position = TextPosition::new(3, 2);
+
+        let definitions = analysis.definitions(&path, position);
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].path, path);
+        expect![[r#"
+            TextRange {
+                start: TextPosition {
+                    line: 0,
+                    column: 9,
+                },
+                end: TextPosition {
+                    line: 0,
+                    column: 12,
+                },
+            }
+        "#]]
+        .assert_debug_eq(&definitions[0].range);
+
+        let references = analysis.references(&path, position, true);
+        assert_eq!(references.len(), 2);
+
+        // 列番号は UTF-8 のバイト数で数える。
+        expect![[r#"
+            [
+                Diagnostic {
+                    range: TextRange {
+                        start: TextPosition {
+                            line: 4,
+                            column: 15,
+                        },
+                        end: TextPosition {
+                            line: 4,
+                            column: 16,
+                        },
+                    },
+                    severity: Error,
+                    message: "引数が多すぎます。(パラメータは 2 個です)",
+                },
+            ]
+        "#]]
+        .assert_debug_eq(&analysis.diagnostics(&path));
+
+        expect![[r#"
+            [
+                (
+                    "foo",
+                    Function,
+                    0,
+                ),
+                (
+                    "a",
+                    Param,
+                    0,
+                ),
+            ]
+        "#]]
+        .assert_debug_eq(
+            &analysis
+                .document_symbols(&path)
+                .into_iter()
+                .map(|s| (s.name, s.kind, s.range.start.line))
+                .collect::<Vec<_>>(),
+        );
+
+        assert!(analysis.hover(&path, position).is_some());
+
+        // 内容を変更すると解析結果に反映される。
+        host.set_file_text(&path, "\tfoo 1\n".to_string());
+        assert_eq!(
+            host.snapshot().definitions(&path, TextPosition::new(0, 2)),
+            vec![]
+        );
+
+        host.remove_file(&path);
+        assert!(host.snapshot().syntax_tree(&path).is_none());
+    }
+}
@@ -0,0 +1,471 @@
+//! 構文木の読み取り専用のビュー
+//!
+//! 内部の具象構文木の構造を隠して、文・式・トークンの種類と名前、子要素だけを見せる。
+
+use super::*;
+use crate::{
+    analysis::DocSyntax,
+    cst::{
+        self,
+        ast::{self, AstNode, HasArgs, HasName, HasParams, HasStmts},
+        build_cst, LineIndex,
+    },
+};
+
+/// ファイルの構文木
+pub struct SyntaxTree {
+    text: RcStr,
+    root: ast::Root,
+    line_index: Rc<LineIndex>,
+}
+
+impl SyntaxTree {
+    /// 解析結果の構文木から具象構文木を作る。
+    ///
+    /// (具象構文木は解析結果として保持していないので、構文木が要求されるたびに作る。)
+    pub(super) fn new(syntax: DocSyntax<'_>) -> Self {
+        Self {
+            text: syntax.text.clone(),
+            root: ast::Root::new(build_cst(&syntax.tokens, syntax.root)),
+            line_index: Rc::new(LineIndex::new(syntax.text)),
+        }
+    }
+
+    /// ファイルの内容
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+
+    /// トップレベルの文
+    pub fn stmts(&self) -> Vec<Stmt> {
+        self.wrap_stmts(self.root.stmts())
+    }
+
+    fn wrap_stmts(&self, stmts: Vec<ast::Stmt>) -> Vec<Stmt> {
+        wrap_stmts(&self.line_index, stmts)
+    }
+}
+
+fn wrap_stmts(line_index: &Rc<LineIndex>, stmts: Vec<ast::Stmt>) -> Vec<Stmt> {
+    stmts
+        .into_iter()
+        .map(|node| Stmt {
+            node,
+            line_index: line_index.clone(),
+        })
+        .collect()
+}
+
+/// 構文上のトークン (識別子、リテラル、キーワードなど)
+#[derive(Clone)]
+pub struct SyntaxToken {
+    token: cst::SyntaxToken,
+    line_index: Rc<LineIndex>,
+}
+
+impl SyntaxToken {
+    pub fn text(&self) -> &str {
+        self.token.text()
+    }
+
+    /// 範囲 (前後の空白やコメントを含まない)
+    pub fn range(&self) -> TextRange {
+        TextRange::from_range(self.line_index.range(self.token.text_range()))
+    }
+}
+
+impl Debug for SyntaxToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.text())
+    }
+}
+
+fn wrap_token(
+    line_index: &Rc<LineIndex>,
+    token_opt: Option<cst::SyntaxToken>,
+) -> Option<SyntaxToken> {
+    Some(SyntaxToken {
+        token: token_opt?,
+        line_index: line_index.clone(),
+    })
+}
+
+fn wrap_expr(line_index: &Rc<LineIndex>, node_opt: Option<ast::Expr>) -> Option<Expr> {
+    Some(Expr {
+        node: node_opt?,
+        line_index: line_index.clone(),
+    })
+}
+
+fn name_of(name_opt: Option<ast::Name>) -> Option<cst::SyntaxToken> {
+    name_opt?.ident()
+}
+
+/// 文の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum StmtKind {
+    /// `*name`
+    Label,
+    /// `x = 1` など
+    Assign,
+    /// `mes "hello"` など
+    Command,
+    /// `x->"method"` など
+    Invoke,
+    If,
+    Const,
+    Define,
+    Enum,
+    /// `#var`, `#varint` など
+    Var,
+    /// `#deffunc`, `#defcfunc`, `#modfunc`, `#modcfunc`, `#modinit`, `#modterm`
+    DefFunc,
+    UseLib,
+    /// `#func`, `#cfunc`
+    LibFunc,
+    UseCom,
+    ComFunc,
+    RegCmd,
+    Cmd,
+    Module,
+    Global,
+    /// `#include`, `#addition`
+    Include,
+    Use,
+    /// 解釈できなかったプリプロセッサ命令
+    UnknownPreProc,
+}
+
+/// 文
+#[derive(Clone)]
+pub struct Stmt {
+    node: ast::Stmt,
+    line_index: Rc<LineIndex>,
+}
+
+impl Stmt {
+    pub fn kind(&self) -> StmtKind {
+        match self.node {
+            ast::Stmt::Label(_) => StmtKind::Label,
+            ast::Stmt::Assign(_) => StmtKind::Assign,
+            ast::Stmt::Command(_) => StmtKind::Command,
+            ast::Stmt::Invoke(_) => StmtKind::Invoke,
+            ast::Stmt::If(_) => StmtKind::If,
+            ast::Stmt::Const(_) => StmtKind::Const,
+            ast::Stmt::Define(_) => StmtKind::Define,
+            ast::Stmt::Enum(_) => StmtKind::Enum,
+            ast::Stmt::Var(_) => StmtKind::Var,
+            ast::Stmt::DefFunc(_) => StmtKind::DefFunc,
+            ast::Stmt::UseLib(_) => StmtKind::UseLib,
+            ast::Stmt::LibFunc(_) => StmtKind::LibFunc,
+            ast::Stmt::UseCom(_) => StmtKind::UseCom,
+            ast::Stmt::ComFunc(_) => StmtKind::ComFunc,
+            ast::Stmt::RegCmd(_) => StmtKind::RegCmd,
+            ast::Stmt::Cmd(_) => StmtKind::Cmd,
+            ast::Stmt::Module(_) => StmtKind::Module,
+            ast::Stmt::Global(_) => StmtKind::Global,
+            ast::Stmt::Include(_) => StmtKind::Include,
+            ast::Stmt::Use(_) => StmtKind::Use,
+            ast::Stmt::UnknownPreProc(_) => StmtKind::UnknownPreProc,
+        }
+    }
+
+    /// 文の範囲 (前後の空白やコメントを含まない)
+    pub fn range(&self) -> TextRange {
+        TextRange::from_range(self.line_index.range(self.node.range()))
+    }
+
+    /// 文の種類を表すトークン
+    ///
+    /// (命令文なら命令の名前、`if` 文なら `if`、プリプロセッサ命令なら `#` の後のキーワード。)
+    pub fn keyword(&self) -> Option<SyntaxToken> {
+        wrap_token(&self.line_index, self.node.keyword())
+    }
+
+    /// 文が定義・使用する名前
+    ///
+    /// (ラベルならラベルの名前 (`*` を除く)、代入文なら左辺の変数、定義なら定義される名前。)
+    pub fn name(&self) -> Option<SyntaxToken> {
+        let token_opt = match &self.node {
+            ast::Stmt::Label(stmt) => name_of(stmt.name()),
+            ast::Stmt::Assign(stmt) => stmt.left().and_then(|e| e.token()),
+            ast::Stmt::Command(stmt) => stmt.command(),
+            ast::Stmt::Invoke(stmt) => stmt.left().and_then(|e| e.token()),
+            ast::Stmt::Const(stmt) => name_of(stmt.name()),
+            ast::Stmt::Define(stmt) => name_of(stmt.name()),
+            ast::Stmt::Enum(stmt) => name_of(stmt.name()),
+            ast::Stmt::DefFunc(stmt) => name_of(stmt.name()),
+            ast::Stmt::LibFunc(stmt) => name_of(stmt.name()),
+            ast::Stmt::UseCom(stmt) => name_of(stmt.name()),
+            ast::Stmt::ComFunc(stmt) => name_of(stmt.name()),
+            ast::Stmt::Cmd(stmt) => name_of(stmt.name()),
+            ast::Stmt::Module(stmt) => name_of(stmt.name()),
+            ast::Stmt::If(_)
+            | ast::Stmt::Var(_)
+            | ast::Stmt::UseLib(_)
+            | ast::Stmt::RegCmd(_)
+            | ast::Stmt::Global(_)
+            | ast::Stmt::Include(_)
+            | ast::Stmt::Use(_)
+            | ast::Stmt::UnknownPreProc(_) => None,
+        };
+        wrap_token(&self.line_index, token_opt)
+    }
+
+    /// `#include` や `#uselib` のファイル名 (文字列リテラル)
+    pub fn file_path(&self) -> Option<SyntaxToken> {
+        let token_opt = match &self.node {
+            ast::Stmt::Include(stmt) => stmt.file_path(),
+            ast::Stmt::UseLib(stmt) => stmt.file_path(),
+            _ => None,
+        };
+        wrap_token(&self.line_index, token_opt)
+    }
+
+    /// `#deffunc` などのパラメータ、または `#module` のモジュール変数
+    pub fn params(&self) -> Vec<Param> {
+        let params = match &self.node {
+            ast::Stmt::DefFunc(stmt) => stmt.params(),
+            ast::Stmt::LibFunc(stmt) => stmt.params(),
+            ast::Stmt::ComFunc(stmt) => stmt.params(),
+            ast::Stmt::Module(stmt) => stmt.params(),
+            _ => return vec![],
+        };
+        params
+            .into_iter()
+            .map(|node| Param {
+                node,
+                line_index: self.line_index.clone(),
+            })
+            .collect()
+    }
+
+    /// 引数の並び
+    ///
+    /// (`mes , 1` のように省略された引数は `None` になる。)
+    pub fn args(&self) -> Vec<Option<Expr>> {
+        let args = match &self.node {
+            ast::Stmt::Assign(stmt) => stmt.args(),
+            ast::Stmt::Command(stmt) => stmt.args(),
+            ast::Stmt::Invoke(stmt) => stmt.args(),
+            ast::Stmt::UseCom(stmt) => stmt.args(),
+            ast::Stmt::RegCmd(stmt) => stmt.args(),
+            _ => return vec![],
+        };
+        args.into_iter()
+            .map(|arg| wrap_expr(&self.line_index, arg.expr()))
+            .collect()
+    }
+
+    /// `if` 文の条件式、`#const` や `#enum` の値
+    pub fn expr(&self) -> Option<Expr> {
+        let expr_opt = match &self.node {
+            ast::Stmt::If(stmt) => stmt.cond(),
+            ast::Stmt::Const(stmt) => stmt.init(),
+            ast::Stmt::Enum(stmt) => stmt.init(),
+            _ => None,
+        };
+        wrap_expr(&self.line_index, expr_opt)
+    }
+
+    /// 内側にある文
+    ///
+    /// (`if` 文なら本体と `else` 節の文、`#deffunc` や `#module` ならその中にある文。`#module` を閉じる `#global` は含まない。)
+    pub fn children(&self) -> Vec<Stmt> {
+        let stmts = match &self.node {
+            ast::Stmt::If(stmt) => stmt
+                .body()
+                .into_iter()
+                .chain(stmt.alt())
+                .flat_map(|block| block.stmts())
+                .collect(),
+            ast::Stmt::DefFunc(stmt) => stmt.stmts(),
+            ast::Stmt::Module(stmt) => stmt
+                .stmts()
+                .into_iter()
+                .filter(|stmt| !matches!(stmt, ast::Stmt::Global(_)))
+                .collect(),
+            _ => return vec![],
+        };
+        wrap_stmts(&self.line_index, stmts)
+    }
+}
+
+impl Debug for Stmt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.kind())?;
+        if let Some(name) = self.name() {
+            write!(f, " {:?}", name)?;
+        }
+        Ok(())
+    }
+}
+
+/// パラメータ (`int a` など)
+#[derive(Clone)]
+pub struct Param {
+    node: ast::Param,
+    line_index: Rc<LineIndex>,
+}
+
+impl Param {
+    /// パラメータの型 (`int`, `var` など)
+    pub fn ty(&self) -> Option<SyntaxToken> {
+        wrap_token(&self.line_index, self.node.ty())
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        wrap_token(&self.line_index, name_of(self.node.name()))
+    }
+}
+
+/// 式の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ExprKind {
+    /// 数値や文字列のリテラル
+    Literal,
+    /// `*name`
+    Label,
+    /// 変数や定数の名前
+    Name,
+    /// `f(x, y)` など (配列要素の参照と関数の呼び出しは区別しない)
+    Call,
+    /// `a.i` など
+    Dots,
+    /// `(x)`
+    Paren,
+    /// `-x` など
+    Prefix,
+    /// `x + y` など
+    Infix,
+}
+
+/// 式
+#[derive(Clone)]
+pub struct Expr {
+    node: ast::Expr,
+    line_index: Rc<LineIndex>,
+}
+
+impl Expr {
+    pub fn kind(&self) -> ExprKind {
+        match self.node {
+            ast::Expr::Literal(_) => ExprKind::Literal,
+            ast::Expr::Label(_) => ExprKind::Label,
+            ast::Expr::Name(_) => ExprKind::Name,
+            ast::Expr::Call(_) => ExprKind::Call,
+            ast::Expr::Dots(_) => ExprKind::Dots,
+            ast::Expr::Paren(_) => ExprKind::Paren,
+            ast::Expr::Prefix(_) => ExprKind::Prefix,
+            ast::Expr::Infix(_) => ExprKind::Infix,
+        }
+    }
+
+    pub fn range(&self) -> TextRange {
+        TextRange::from_range(self.line_index.range(self.node.range()))
+    }
+
+    /// 式の主なトークン
+    ///
+    /// (リテラルならそのトークン、ラベルならラベルの名前、名前や呼び出しなら名前、演算なら演算子。)
+    pub fn token(&self) -> Option<SyntaxToken> {
+        wrap_token(&self.line_index, self.node.token())
+    }
+
+    /// 内側にある式 (引数や被演算子)
+    pub fn children(&self) -> Vec<Expr> {
+        self.node
+            .children()
+            .into_iter()
+            .filter_map(|node| wrap_expr(&self.line_index, Some(node)))
+            .collect()
+    }
+}
+
+impl Debug for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.kind())?;
+        if let Some(token) = self.token() {
+            write!(f, " {:?}", token)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+    use std::fmt::Write as _;
+
+    fn format_stmts(w: &mut String, stmts: &[Stmt], depth: usize) {
+        for stmt in stmts {
+            let _ = write!(w, "{}{:?}", "  ".repeat(depth), stmt);
+            for param in stmt.params() {
+                let _ = write!(w, " ({:?} {:?})", param.ty(), param.name());
+            }
+            for arg in stmt.args() {
+                let _ = write!(w, " [{:?}]", arg);
+            }
+            let _ = writeln!(w);
+            format_stmts(w, &stmt.children(), depth + 1);
+        }
+    }
+
+    #[test]
+    fn test_syntax_tree() {
+        let ws = crate::test_utils::dummy_path().join("ws");
+        let path = ws.join("main.hsp");
+        let text = r#"#module m x
+#deffunc f int a, var b
+    if a : b = a + 1 : else : mes "a", , f(a)
+    return
+#global
+*l
+    goto *l
+"#;
+
+        let mut host = AnalysisHost::new(AnalysisHostOptions::default());
+        host.set_file_text(&path, text.to_string());
+        let analysis = host.snapshot();
+        let tree = analysis.syntax_tree(&path).unwrap();
+        assert_eq!(tree.text(), text);
+
+        let mut w = String::new();
+        format_stmts(&mut w, &tree.stmts(), 0);
+        expect![[r#"
+            Module "m" (None Some("x"))
+              DefFunc "f" (Some("int") Some("a")) (Some("var") Some("b"))
+                If
+                  Assign "b" [Some(Infix "+")]
+                  Command "mes" [Some(Literal "\"a\"")] [None] [Some(Call "f")]
+                Command "return"
+            Label "l"
+            Command "goto" [Some(Label "l")]
+        "#]]
+        .assert_eq(&w);
+
+        let stmts = tree.stmts();
+        let def_func = &stmts[0].children()[0];
+        expect![[r#"
+            (
+                Some(
+                    "deffunc",
+                ),
+                TextRange {
+                    start: TextPosition {
+                        line: 1,
+                        column: 0,
+                    },
+                    end: TextPosition {
+                        line: 3,
+                        column: 10,
+                    },
+                },
+            )
+        "#]]
+        .assert_debug_eq(&(def_func.keyword(), def_func.range()));
+    }
+}
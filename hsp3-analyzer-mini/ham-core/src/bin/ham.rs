@@ -9,6 +9,7 @@
 use ham_core::{
     run_lsp_server,
     subcommands::{self, format_comments::format_comments},
+    tr, Locale,
 };
 use std::{
     fs,
@@ -16,48 +17,16 @@ use std::{
     path::PathBuf,
 };
 
-fn get_help() -> String {
-    format!(
-        r#"ham {version}
-
-    USAGE: ham [OPTIONS] [SUBCOMMAND]
-
-    EXAMPLE: ham --hsp "C:/hsp37" profile-parse
-
-    SUBCOMMANDS:
-        lsp
-            LSPサーバーとして起動する (標準入出力でメッセージを送受信する)
-            (HSPインストールディレクトリの指定が必須)
-            環境変数はtopics.mdを参照
-
-        parse [FILES...]
-
-        profile-parse
-            (HSPインストールディレクトリの指定が必須)
-
-        format-comments [FILES]
-            (**注意**: ファイルは上書きされます。必ずバックアップしてください)
-            HSPのスクリプトのコメントを // 形式から ; 形式に変更し、
-            入力スクリプトファイルを上書きします
-
-    OPTIONS:
-        -h, --help      Print help
-        -V, --version   Print Version
-            --hsp       HSPインストールディレクトリを指定
-
-    ENV:
-        HSP3_ROOT       HSPインストールディレクトリを指定 (--hsp より優先度低)
-"#,
-        version = get_version()
-    )
+fn get_help(locale: Locale) -> String {
+    tr(locale, "cli.help").replace("{version}", get_version())
 }
 
 fn get_version() -> &'static str {
     env!("CARGO_PKG_VERSION")
 }
 
-fn exit_with_help() -> ! {
-    eprintln!("{}", get_help());
+fn exit_with_help(locale: Locale) -> ! {
+    eprintln!("{}", get_help(locale));
     std::process::exit(0)
 }
 
@@ -68,6 +37,8 @@ fn exit_with_version() -> ! {
 
 static SUBCOMMANDS: &'static [&'static str] = &[
     "format-comments",
+    "gen-builtin",
+    "gen-help",
     "lsp",
     "parse",
     "profile-parse",
@@ -75,8 +46,6 @@ static SUBCOMMANDS: &'static [&'static str] = &[
     "version",
 ];
 
-static ERROR_HSP3_ROOT_MISSING: &'static str = r#"HSPのインストールディレクトリを指定してください。(例: コマンドライン引数に --hsp "C:/hsp37" のように指定する、あるいは環境変数 HSP3_ROOT にパスを指定する)"#;
-
 fn main() {
     let mut args = std::env::args();
 
@@ -85,16 +54,21 @@ fn main() {
 
     let mut subcommand_opt = None;
     let mut hsp3_root_opt = None;
+    let mut locale_opt = None;
 
     while let Some(arg) = args.next() {
         if arg.starts_with("-") && arg != "-" {
             match arg.as_str() {
-                "-h" | "--help" => exit_with_help(),
+                "-h" | "--help" => exit_with_help(locale_opt.unwrap_or_default()),
                 "-V" | "--version" => exit_with_version(),
                 "--hsp" => {
                     let value = args.next().expect("--hsp value");
                     hsp3_root_opt = Some(value.to_string());
                 }
+                "--lang" => {
+                    let value = args.next().expect("--lang value");
+                    locale_opt = Some(Locale::from_tag(&value));
+                }
                 _ => {
                     eprintln!("ERROR: Unrecognized option: {arg:?}");
                     std::process::exit(1)
@@ -114,8 +88,10 @@ fn main() {
         }
     }
 
+    let locale = locale_opt.unwrap_or_default();
+
     match subcommand_opt.unwrap_or_default().as_str() {
-        "" | "help" => exit_with_help(),
+        "" | "help" => exit_with_help(locale),
         "version" => exit_with_version(),
         "format-comments" => {
             let mut count = 0;
@@ -137,27 +113,71 @@ fn main() {
                 count += 1;
             }
             if count == 0 {
-                eprintln!("ERROR: 入力ファイルが出力されていません");
+                eprintln!("ERROR: {}", tr(locale, "cli.no_input_files"));
             }
             return;
         }
-        "lsp" => {
+        "gen-builtin" => {
             // require root
             let hsp3_root = PathBuf::from(
                 hsp3_root_opt
                     .or_else(|| std::env::var("HSP3_ROOT").ok())
-                    .expect(ERROR_HSP3_ROOT_MISSING),
+                    .unwrap_or_else(|| panic!("{}", tr(locale, "cli.hsp3_root_missing"))),
             );
             if !hsp3_root.is_dir() {
-                panic!("HSP3_ROOTディレクトリがみつかりません: {hsp3_root:?}");
+                panic!("{}: {hsp3_root:?}", tr(locale, "cli.hsp3_root_not_found"));
+            }
+
+            // halt args
+            if let Some(arg) = args.next() {
+                panic!("ERROR: Unrecognized argument: {arg:?}");
             }
 
+            subcommands::gen_builtin::gen_builtin_subcommand(&hsp3_root);
+        }
+        "gen-help" => {
+            let mut check = false;
+            let mut files = vec![];
+            for arg in args {
+                if arg == "--check" {
+                    check = true;
+                    continue;
+                }
+                if arg.starts_with("-") && arg != "-" {
+                    panic!("ERROR: Unknown argument: {arg:?}");
+                }
+                files.push(arg);
+            }
+            if files.is_empty() {
+                panic!("ERROR: {}", tr(locale, "cli.no_input_files"));
+            }
+
+            if !subcommands::gen_help::gen_help_subcommand(files, check, locale) {
+                std::process::exit(1)
+            }
+        }
+        "lsp" => {
+            // root is optional (標準命令などは同梱のデータベースで補う)
+            let hsp3_root_opt = hsp3_root_opt
+                .or_else(|| std::env::var("HSP3_ROOT").ok())
+                .map(PathBuf::from)
+                .filter(|hsp3_root| {
+                    let ok = hsp3_root.is_dir();
+                    if !ok {
+                        eprintln!(
+                            "WARN: {}: {hsp3_root:?}",
+                            tr(locale, "cli.hsp3_root_not_found")
+                        );
+                    }
+                    ok
+                });
+
             // halt args
             if let Some(arg) = args.next() {
                 panic!("ERROR: Unrecognized argument: {arg:?}");
             }
 
-            run_lsp_server(hsp3_root);
+            run_lsp_server(hsp3_root_opt, locale_opt);
             return;
         }
         "parse" => {
@@ -169,7 +189,7 @@ fn main() {
                 files.push(arg);
             }
             if files.is_empty() {
-                panic!("ERROR: 入力ファイルが指定されていません");
+                panic!("ERROR: {}", tr(locale, "cli.no_input_files"));
             }
 
             subcommands::parse::parse_subcommand(files);
@@ -179,10 +199,10 @@ fn main() {
             let hsp3_root = PathBuf::from(
                 hsp3_root_opt
                     .or_else(|| std::env::var("HSP3_ROOT").ok())
-                    .expect(ERROR_HSP3_ROOT_MISSING),
+                    .unwrap_or_else(|| panic!("{}", tr(locale, "cli.hsp3_root_missing"))),
             );
             if !hsp3_root.is_dir() {
-                panic!("HSP3_ROOTディレクトリがみつかりません: {hsp3_root:?}");
+                panic!("{}: {hsp3_root:?}", tr(locale, "cli.hsp3_root_not_found"));
             }
 
             // halt args
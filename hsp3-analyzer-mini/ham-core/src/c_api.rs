@@ -1,8 +1,32 @@
 //! C言語や HSP3 などから利用するための関数群
+//!
+//! ## 文字列の受け渡し
+//!
+//! 文字列はポインタと長さ (バイト数) の組で渡す。エンコーディングは UTF-8 とする。
+//!
+//! 結果を JSON で返す関数 (`ham_completion` など) は、引数 `output`, `output_len` に結果を書き込む。
+//! 呼び出し側は `*output_len` にバッファの大きさを入れておく。
+//! 関数は `*output_len` に結果の長さを書き込み、結果がバッファに収まるときだけ `output` に結果を書き込む。
+//! (結果の長さを調べるには、`output` をヌルにして呼び出せばよい。
+//!  `*output_len` がバッファの大きさより大きくなったら、バッファを広げて呼び出しなおすこと。)
+//!
+//! JSON の形式は LSP の対応するメッセージの結果と同じ。
+//!
+//! ## ヘッダファイル
+//!
+//! `ham-sdk/ham_sdk.h` はこのファイルから生成される。
+//! (関数を変更したら `UPDATE_EXPECT=1 cargo test c_api` で更新する。)
 
 use super::*;
-use crate::analyzer::Analyzer;
-use lsp_types::{HoverContents, MarkedString, Position, Url};
+use crate::{
+    analyzer::{
+        vfs::{MemoryFs, Vfs},
+        Analyzer,
+    },
+    source::PositionEncoding,
+};
+use lsp_types::{HoverContents, MarkedString, Position, Url, WorkspaceFolder};
+use serde::{Deserialize, Serialize};
 use std::{os::raw::c_char, ptr::null_mut, slice, str};
 
 const TRUE: i32 = 1;
@@ -10,6 +34,33 @@ const FALSE: i32 = 0;
 
 pub struct HamInstance {
     analyzer: Analyzer,
+    options: HamOptions,
+
+    /// メモリ上のファイルシステム (`ham_create_in_memory` で作ったときだけ)
+    fs_opt: Option<Rc<MemoryFs>>,
+    initialized: bool,
+}
+
+/// `ham_create` に渡すオプション (JSON)
+#[derive(Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct HamOptions {
+    /// 診断 (エラーや警告) を報告するか
+    lint_enabled: bool,
+    /// ワークスペースのフォルダのURI
+    workspace_folder: Option<Url>,
+    /// 列番号の単位 ("utf-8", "utf-16" または "shift_jis")
+    position_encoding: String,
+}
+
+impl Default for HamOptions {
+    fn default() -> Self {
+        Self {
+            lint_enabled: true,
+            workspace_folder: None,
+            position_encoding: "utf-8".to_string(),
+        }
+    }
 }
 
 unsafe fn str_from_raw_parts(data: *const c_char, len: i32) -> Option<&'static str> {
@@ -45,6 +96,29 @@ unsafe fn str_assign(dest: *mut c_char, dest_len: *mut i32, src: &str) {
     *dest_len = len as i32;
 }
 
+/// 結果を JSON にしてバッファに書き込む。(モジュールのドキュメントを参照)
+unsafe fn json_assign<T: Serialize>(output: *mut c_char, output_len: *mut i32, value: &T) -> i32 {
+    if output_len.is_null() {
+        error!("output_len must be non-null");
+        return FALSE;
+    }
+
+    let json = match serde_json::to_string(value) {
+        Ok(it) => it,
+        Err(err) => {
+            error!("can't serialize {:?}", err);
+            return FALSE;
+        }
+    };
+
+    let capacity = (*output_len).max(0) as usize;
+    if !output.is_null() && json.len() <= capacity {
+        slice::from_raw_parts_mut(output as *mut u8, json.len()).copy_from_slice(json.as_bytes());
+    }
+    *output_len = json.len() as i32;
+    TRUE
+}
+
 unsafe fn url_from_raw_parts(uri: *const c_char, uri_len: i32) -> Option<Url> {
     let uri = str_from_raw_parts(uri, uri_len)?;
     match Url::parse(uri) {
@@ -56,6 +130,22 @@ unsafe fn url_from_raw_parts(uri: *const c_char, uri_len: i32) -> Option<Url> {
     }
 }
 
+unsafe fn options_from_raw_parts(options: *const c_char, options_len: i32) -> Option<HamOptions> {
+    // ヌルまたは空文字列なら既定値を使う。
+    if options.is_null() || options_len == 0 {
+        return Some(HamOptions::default());
+    }
+
+    let options = str_from_raw_parts(options, options_len)?;
+    match serde_json::from_str(options) {
+        Ok(it) => Some(it),
+        Err(err) => {
+            error!("invalid options {:?}", err);
+            None
+        }
+    }
+}
+
 fn position_from_raw(line: i32, character: i32) -> Option<Position> {
     if line < 0 {
         error!("line can't be negative {:?}", line);
@@ -67,7 +157,7 @@ fn position_from_raw(line: i32, character: i32) -> Option<Position> {
         return None;
     }
 
-    // FIXME: 列番号をエンコーディングに基づいて変換する？
+    // (列番号の単位の変換はアナライザーが行う。)
     Some(Position::new(line as u32, character as u32))
 }
 
@@ -78,33 +168,205 @@ fn marked_string_to_string(it: MarkedString) -> String {
     }
 }
 
+fn new_instance(
+    hsp3_root_opt: Option<PathBuf>,
+    options: HamOptions,
+    fs_opt: Option<Rc<MemoryFs>>,
+) -> Option<HamInstance> {
+    let position_encoding = match PositionEncoding::parse(&options.position_encoding) {
+        Some(it) => it,
+        None => {
+            error!("unknown position encoding {:?}", options.position_encoding);
+            return None;
+        }
+    };
+
+    let mut analyzer = match &fs_opt {
+        Some(fs) => Analyzer::with_vfs(hsp3_root_opt, fs.clone()),
+        None => Analyzer::new(hsp3_root_opt),
+    };
+    analyzer.set_position_encoding(position_encoding);
+
+    if let Some(uri) = &options.workspace_folder {
+        analyzer.add_workspace_folder(WorkspaceFolder {
+            uri: uri.clone(),
+            name: "".to_string(),
+        });
+    }
+
+    Some(HamInstance {
+        analyzer,
+        options,
+        fs_opt,
+        initialized: false,
+    })
+}
+
+/// ログの出力などを初期化する。
 #[no_mangle]
 pub extern "C" fn ham_init() {
     // FIXME: ログレベルなどを設定可能にする。(logmes に吐きたい。)
     crate::lsp_server::lsp_log::init_log();
 }
 
-// FIXME: オプションを設定できるようにする。
+/// インスタンスを作る。
+///
+/// `hsp3_root` はHSPのインストールディレクトリ。(空文字列ならなし)
+/// `options` はオプションを表す JSON。(空文字列なら既定値)
+///
+/// ```json
+/// { "lintEnabled": true, "workspaceFolder": "file:///C:/work", "positionEncoding": "utf-8" }
+/// ```
+///
+/// `positionEncoding` は位置情報の列番号の単位で、
+/// `"utf-8"` (既定値), `"utf-16"` または `"shift_jis"` (バイト数) を指定できる。
+///
+/// 失敗したらヌルを返す。
 #[no_mangle]
 pub unsafe extern "C" fn ham_create(
     hsp3_root: *const c_char,
     hsp3_root_len: i32,
+    options: *const c_char,
+    options_len: i32,
 ) -> *mut HamInstance {
-    let hsp3_root = match str_from_raw_parts(hsp3_root, hsp3_root_len) {
-        Some(x) => PathBuf::from(x),
+    // 空文字列ならHSPのインストールディレクトリなしとみなす。
+    let hsp3_root_opt = match str_from_raw_parts(hsp3_root, hsp3_root_len) {
+        Some("") => None,
+        Some(x) => Some(PathBuf::from(x)),
         None => return null_mut(),
     };
 
-    let mut instance = HamInstance {
-        analyzer: Analyzer::new(hsp3_root),
+    let options = match options_from_raw_parts(options, options_len) {
+        Some(it) => it,
+        None => return null_mut(),
     };
 
+    let mut instance = match new_instance(hsp3_root_opt, options, None) {
+        Some(it) => it,
+        None => return null_mut(),
+    };
     instance.analyzer.did_initialize();
+    instance.initialized = true;
 
     // Rust の所有権ルールから外して、ネイティブポインタに変換する。ham_destroy で破棄してもらう。
     Box::into_raw(Box::new(instance))
 }
 
+/// ディスクの代わりにメモリ上のファイルシステムを使うインスタンスを作る。
+///
+/// 引数は `ham_create` と同じ。
+/// `ham_fs_write` でファイルを置いてから `ham_initialize` を呼ぶこと。
+#[no_mangle]
+pub unsafe extern "C" fn ham_create_in_memory(
+    hsp3_root: *const c_char,
+    hsp3_root_len: i32,
+    options: *const c_char,
+    options_len: i32,
+) -> *mut HamInstance {
+    let hsp3_root_opt = match str_from_raw_parts(hsp3_root, hsp3_root_len) {
+        Some("") => None,
+        Some(x) => Some(PathBuf::from(x)),
+        None => return null_mut(),
+    };
+
+    let options = match options_from_raw_parts(options, options_len) {
+        Some(it) => it,
+        None => return null_mut(),
+    };
+
+    let fs = Rc::new(MemoryFs::default());
+    let instance = match new_instance(hsp3_root_opt, options, Some(fs)) {
+        Some(it) => it,
+        None => return null_mut(),
+    };
+    Box::into_raw(Box::new(instance))
+}
+
+/// `ham_create_in_memory` で作ったインスタンスの初期化を行う。
+#[no_mangle]
+pub unsafe extern "C" fn ham_initialize(instance: *mut HamInstance) -> i32 {
+    if instance.is_null() || (*instance).initialized {
+        return FALSE;
+    }
+
+    (*instance).analyzer.did_initialize();
+    (*instance).initialized = true;
+    TRUE
+}
+
+/// メモリ上のファイルシステムにファイルを置く。(すでにあれば内容を置き換える。)
+#[no_mangle]
+pub unsafe extern "C" fn ham_fs_write(
+    instance: *mut HamInstance,
+    path: *const c_char,
+    path_len: i32,
+    data: *const c_char,
+    data_len: i32,
+) -> i32 {
+    if instance.is_null() || data.is_null() || data_len < 0 {
+        return FALSE;
+    }
+
+    let fs = match &(*instance).fs_opt {
+        Some(fs) => fs.clone(),
+        None => return FALSE,
+    };
+
+    let path = match str_from_raw_parts(path, path_len) {
+        Some(x) => PathBuf::from(x),
+        None => return FALSE,
+    };
+
+    let exists = fs.mtime(&path).is_some();
+    let data = slice::from_raw_parts(data as *const u8, data_len as usize);
+    fs.write(path.clone(), data);
+
+    if (*instance).initialized {
+        if let Ok(uri) = Url::from_file_path(&path) {
+            if exists {
+                (*instance).analyzer.on_file_changed(uri);
+            } else {
+                (*instance).analyzer.on_file_created(uri);
+            }
+        }
+    }
+    TRUE
+}
+
+/// メモリ上のファイルシステムからファイルを削除する。
+#[no_mangle]
+pub unsafe extern "C" fn ham_fs_remove(
+    instance: *mut HamInstance,
+    path: *const c_char,
+    path_len: i32,
+) -> i32 {
+    if instance.is_null() {
+        return FALSE;
+    }
+
+    let fs = match &(*instance).fs_opt {
+        Some(fs) => fs.clone(),
+        None => return FALSE,
+    };
+
+    let path = match str_from_raw_parts(path, path_len) {
+        Some(x) => PathBuf::from(x),
+        None => return FALSE,
+    };
+
+    if !fs.remove(&path) {
+        return FALSE;
+    }
+
+    if (*instance).initialized {
+        if let Ok(uri) = Url::from_file_path(&path) {
+            (*instance).analyzer.on_file_deleted(uri);
+        }
+    }
+    TRUE
+}
+
+/// インスタンスを破棄する。
 #[no_mangle]
 pub unsafe extern "C" fn ham_destroy(instance: *mut HamInstance) -> i32 {
     if instance.is_null() {
@@ -118,6 +380,7 @@ pub unsafe extern "C" fn ham_destroy(instance: *mut HamInstance) -> i32 {
     TRUE
 }
 
+/// テキストドキュメントが開かれたことを通知する。
 #[no_mangle]
 pub unsafe extern "C" fn ham_doc_did_open(
     instance: *mut HamInstance,
@@ -145,6 +408,7 @@ pub unsafe extern "C" fn ham_doc_did_open(
     TRUE
 }
 
+/// テキストドキュメントが変更されたことを通知する。
 #[no_mangle]
 pub unsafe extern "C" fn ham_doc_did_change(
     instance: *mut HamInstance,
@@ -172,6 +436,7 @@ pub unsafe extern "C" fn ham_doc_did_change(
     TRUE
 }
 
+/// テキストドキュメントが閉じられたことを通知する。
 #[no_mangle]
 pub unsafe extern "C" fn ham_doc_did_close(
     instance: *mut HamInstance,
@@ -191,6 +456,9 @@ pub unsafe extern "C" fn ham_doc_did_close(
     TRUE
 }
 
+/// カーソル付近のシンボルの情報をテキストで得る。
+///
+/// (結果がバッファに収まらないときは切り詰められる。)
 #[no_mangle]
 pub unsafe extern "C" fn ham_hover(
     instance: *mut HamInstance,
@@ -231,3 +499,449 @@ pub unsafe extern "C" fn ham_hover(
     str_assign(output, output_len, &contents);
     TRUE
 }
+
+/// 入力補完の候補を得る。(結果は `CompletionList` の JSON)
+#[no_mangle]
+pub unsafe extern "C" fn ham_completion(
+    instance: *mut HamInstance,
+    uri: *const c_char,
+    uri_len: i32,
+    position_line: i32,
+    position_character: i32,
+    output: *mut c_char,
+    output_len: *mut i32,
+) -> i32 {
+    if instance.is_null() {
+        return FALSE;
+    }
+
+    let (uri, position) = match (
+        url_from_raw_parts(uri, uri_len),
+        position_from_raw(position_line, position_character),
+    ) {
+        (Some(uri), Some(position)) => (uri, position),
+        _ => return FALSE,
+    };
+
+    let list = (*instance).analyzer.compute_ref().completion(uri, position);
+    json_assign(output, output_len, &list)
+}
+
+/// 関数呼び出しのシグネチャの情報を得る。(結果は `SignatureHelp` または null の JSON)
+#[no_mangle]
+pub unsafe extern "C" fn ham_signature_help(
+    instance: *mut HamInstance,
+    uri: *const c_char,
+    uri_len: i32,
+    position_line: i32,
+    position_character: i32,
+    output: *mut c_char,
+    output_len: *mut i32,
+) -> i32 {
+    if instance.is_null() {
+        return FALSE;
+    }
+
+    let (uri, position) = match (
+        url_from_raw_parts(uri, uri_len),
+        position_from_raw(position_line, position_character),
+    ) {
+        (Some(uri), Some(position)) => (uri, position),
+        _ => return FALSE,
+    };
+
+    let help = (*instance)
+        .analyzer
+        .compute_ref()
+        .signature_help(uri, position);
+    json_assign(output, output_len, &help)
+}
+
+/// シンボルの定義箇所を得る。(結果は `Location` の配列の JSON)
+#[no_mangle]
+pub unsafe extern "C" fn ham_definitions(
+    instance: *mut HamInstance,
+    uri: *const c_char,
+    uri_len: i32,
+    position_line: i32,
+    position_character: i32,
+    output: *mut c_char,
+    output_len: *mut i32,
+) -> i32 {
+    if instance.is_null() {
+        return FALSE;
+    }
+
+    let (uri, position) = match (
+        url_from_raw_parts(uri, uri_len),
+        position_from_raw(position_line, position_character),
+    ) {
+        (Some(uri), Some(position)) => (uri, position),
+        _ => return FALSE,
+    };
+
+    let locations = (*instance)
+        .analyzer
+        .compute_ref()
+        .definitions(uri, position);
+    json_assign(output, output_len, &locations)
+}
+
+/// シンボルの出現箇所を得る。(結果は `Location` の配列の JSON)
+///
+/// `include_definition` が真なら定義箇所も含める。
+#[no_mangle]
+pub unsafe extern "C" fn ham_references(
+    instance: *mut HamInstance,
+    uri: *const c_char,
+    uri_len: i32,
+    position_line: i32,
+    position_character: i32,
+    include_definition: i32,
+    output: *mut c_char,
+    output_len: *mut i32,
+) -> i32 {
+    if instance.is_null() {
+        return FALSE;
+    }
+
+    let (uri, position) = match (
+        url_from_raw_parts(uri, uri_len),
+        position_from_raw(position_line, position_character),
+    ) {
+        (Some(uri), Some(position)) => (uri, position),
+        _ => return FALSE,
+    };
+
+    let locations =
+        (*instance)
+            .analyzer
+            .compute_ref()
+            .references(uri, position, include_definition != FALSE);
+    json_assign(output, output_len, &locations)
+}
+
+/// ドキュメントに含まれるシンボルの一覧を得る。(結果は `DocumentSymbol` の配列または null の JSON)
+#[no_mangle]
+pub unsafe extern "C" fn ham_document_symbols(
+    instance: *mut HamInstance,
+    uri: *const c_char,
+    uri_len: i32,
+    output: *mut c_char,
+    output_len: *mut i32,
+) -> i32 {
+    if instance.is_null() {
+        return FALSE;
+    }
+
+    let uri = match url_from_raw_parts(uri, uri_len) {
+        Some(uri) => uri,
+        None => return FALSE,
+    };
+
+    let symbols = (*instance).analyzer.compute_ref().document_symbol(uri);
+    json_assign(output, output_len, &symbols)
+}
+
+/// ドキュメントの診断 (エラーや警告) を得る。(結果は `Diagnostic` の配列の JSON)
+///
+/// オプションで診断が無効になっているときは空の配列になる。
+#[no_mangle]
+pub unsafe extern "C" fn ham_diagnostics(
+    instance: *mut HamInstance,
+    uri: *const c_char,
+    uri_len: i32,
+    output: *mut c_char,
+    output_len: *mut i32,
+) -> i32 {
+    if instance.is_null() {
+        return FALSE;
+    }
+
+    let uri = match url_from_raw_parts(uri, uri_len) {
+        Some(uri) => CanonicalUri::from_url(&uri),
+        None => return FALSE,
+    };
+
+    let mut diagnostics = vec![];
+    if (*instance).options.lint_enabled {
+        for (d_uri, _, d) in (*instance).analyzer.compute_ref().diagnose() {
+            if CanonicalUri::from_url(&d_uri) == uri {
+                diagnostics = d;
+                break;
+            }
+        }
+    }
+    json_assign(output, output_len, &diagnostics)
+}
+
+/// ドキュメントを整形するための編集を得る。(結果は `TextEdit` の配列または null の JSON)
+#[no_mangle]
+pub unsafe extern "C" fn ham_formatting(
+    instance: *mut HamInstance,
+    uri: *const c_char,
+    uri_len: i32,
+    output: *mut c_char,
+    output_len: *mut i32,
+) -> i32 {
+    if instance.is_null() {
+        return FALSE;
+    }
+
+    let uri = match url_from_raw_parts(uri, uri_len) {
+        Some(uri) => uri,
+        None => return FALSE,
+    };
+
+    let edits = (*instance).analyzer.compute_ref().formatting(uri);
+    json_assign(output, output_len, &edits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::{expect, expect_file};
+
+    /// 関数を呼んで、結果の JSON を得る。(長さを調べてから結果を受け取る。)
+    fn call_json(f: impl Fn(*mut c_char, *mut i32) -> i32) -> String {
+        let mut len = 0;
+        assert_eq!(f(null_mut(), &mut len), TRUE);
+
+        let mut buf = vec![0_u8; len as usize];
+        let mut len2 = len;
+        assert_eq!(f(buf.as_mut_ptr() as *mut c_char, &mut len2), TRUE);
+        assert_eq!(len, len2);
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_json_functions() {
+        let uri = "file:///ws/main.hsp";
+        let text = "#deffunc foo int a\n\treturn\n\n\tfoo 1\n\tmes 1, 2, 3\n";
+        let options = r#"{"workspaceFolder":"file:///ws"}"#;
+
+        unsafe {
+            let instance = ham_create_in_memory(
+                "".as_ptr() as *const c_char,
+                0,
+                options.as_ptr() as *const c_char,
+                options.len() as i32,
+            );
+            assert!(!instance.is_null());
+            assert_eq!(ham_initialize(instance), TRUE);
+
+            let uri_ptr = uri.as_ptr() as *const c_char;
+            let uri_len = uri.len() as i32;
+            ham_doc_did_open(
+                instance,
+                uri_ptr,
+                uri_len,
+                1,
+                text.as_ptr() as *const c_char,
+                text.len() as i32,
+            );
+
+            let definitions =
+                call_json(|out, len| ham_definitions(instance, uri_ptr, uri_len, 3, 2, out, len));
+            expect![[r#"[{"uri":"file:///ws/main.hsp","range":{"start":{"line":0,"character":9},"end":{"line":0,"character":12}}}]"#]]
+                .assert_eq(&definitions);
+
+            let references = call_json(|out, len| {
+                ham_references(instance, uri_ptr, uri_len, 3, 2, FALSE, out, len)
+            });
+            expect![[r#"[{"uri":"file:///ws/main.hsp","range":{"start":{"line":3,"character":1},"end":{"line":3,"character":4}}}]"#]]
+                .assert_eq(&references);
+
+            let diagnostics =
+                call_json(|out, len| ham_diagnostics(instance, uri_ptr, uri_len, out, len));
+            expect![[r#"[{"range":{"start":{"line":4,"character":11},"end":{"line":4,"character":12}},"severity":1,"source":"ham-core","message":"引数が多すぎます。(パラメータは 2 個です)"}]"#]].assert_eq(&diagnostics);
+
+            // バッファが足りないときは書き込まず、必要な長さを返す。
+            let mut buf = [0_u8; 4];
+            let mut len = buf.len() as i32;
+            ham_definitions(
+                instance,
+                uri_ptr,
+                uri_len,
+                3,
+                2,
+                buf.as_mut_ptr() as *mut c_char,
+                &mut len,
+            );
+            assert_eq!(len as usize, definitions.len());
+            assert_eq!(buf, [0; 4]);
+
+            ham_destroy(instance);
+        }
+    }
+
+    #[test]
+    fn test_lint_disabled() {
+        let uri = "file:///ws/main.hsp";
+        let text = "\tmes 1, 2, 3\n";
+        let options = r#"{"lintEnabled":false}"#;
+
+        unsafe {
+            let instance = ham_create_in_memory(
+                "".as_ptr() as *const c_char,
+                0,
+                options.as_ptr() as *const c_char,
+                options.len() as i32,
+            );
+            ham_initialize(instance);
+
+            let uri_ptr = uri.as_ptr() as *const c_char;
+            let uri_len = uri.len() as i32;
+            ham_doc_did_open(
+                instance,
+                uri_ptr,
+                uri_len,
+                1,
+                text.as_ptr() as *const c_char,
+                text.len() as i32,
+            );
+
+            let diagnostics =
+                call_json(|out, len| ham_diagnostics(instance, uri_ptr, uri_len, out, len));
+            expect![[r#"[]"#]].assert_eq(&diagnostics);
+
+            ham_destroy(instance);
+        }
+    }
+
+    #[test]
+    fn test_shift_jis_columns() {
+        let uri = "file:///ws/main.hsp";
+        let text = "\tmes \"あ\": foo\n#deffunc foo\n\treturn\n";
+        let options = r#"{"positionEncoding":"shift_jis"}"#;
+
+        unsafe {
+            let instance = ham_create_in_memory(
+                "".as_ptr() as *const c_char,
+                0,
+                options.as_ptr() as *const c_char,
+                options.len() as i32,
+            );
+            ham_initialize(instance);
+
+            let uri_ptr = uri.as_ptr() as *const c_char;
+            let uri_len = uri.len() as i32;
+            ham_doc_did_open(
+                instance,
+                uri_ptr,
+                uri_len,
+                1,
+                text.as_ptr() as *const c_char,
+                text.len() as i32,
+            );
+
+            // `foo` はバイト数で数えて11の位置から始まる。(あ は2バイト)
+            let references = call_json(|out, len| {
+                ham_references(instance, uri_ptr, uri_len, 0, 12, FALSE, out, len)
+            });
+            expect![[r#"[{"uri":"file:///ws/main.hsp","range":{"start":{"line":0,"character":11},"end":{"line":0,"character":14}}}]"#]].assert_eq(&references);
+
+            ham_destroy(instance);
+        }
+    }
+
+    /// Rust の型を C の型に変換する。
+    fn c_type(ty: &str) -> String {
+        match ty.trim() {
+            "i32" => "int32_t".to_string(),
+            "*const c_char" => "const char *".to_string(),
+            "*mut c_char" => "char *".to_string(),
+            "*mut i32" => "int32_t *".to_string(),
+            "*mut HamInstance" => "HamInstance *".to_string(),
+            ty => panic!("unknown type {ty:?}"),
+        }
+    }
+
+    /// このファイルにある関数の定義から、C言語のヘッダファイルを生成する。
+    fn generate_c_header(source: &str) -> String {
+        let mut out = String::new();
+        out.push_str("// このファイルは ham-core/src/c_api.rs から生成されたものです。(手動で編集しないこと)\n");
+        out.push_str("// LICENSE: CC0-1.0\n\n");
+        out.push_str("#ifndef HAM_SDK_H\n#define HAM_SDK_H\n\n");
+        out.push_str("#include <stdint.h>\n\n");
+        out.push_str("#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n");
+        out.push_str("typedef struct HamInstance HamInstance;\n");
+
+        let mut docs: Vec<&str> = vec![];
+        let mut lines = source.lines();
+        while let Some(line) = lines.next() {
+            if line.starts_with("#[cfg(test)]") {
+                break;
+            }
+
+            if let Some(doc) = line.strip_prefix("///") {
+                docs.push(doc);
+                continue;
+            }
+            if line.starts_with("#[no_mangle]") {
+                continue;
+            }
+
+            let rest = match line.find("extern \"C\" fn ") {
+                Some(i) if line.starts_with("pub ") => &line[i + "extern \"C\" fn ".len()..],
+                _ => {
+                    docs.clear();
+                    continue;
+                }
+            };
+
+            // シグネチャを `{` まで連結する。
+            let mut signature = rest.to_string();
+            while !signature.ends_with('{') {
+                signature.push_str(lines.next().unwrap().trim());
+            }
+
+            let (name, rest) = signature.split_once('(').unwrap();
+            let (params, rest) = rest.rsplit_once(')').unwrap();
+            let ret = match rest.trim_end_matches('{').trim().strip_prefix("->") {
+                Some(ty) => c_type(ty),
+                None => "void".to_string(),
+            };
+            let params = params
+                .split(',')
+                .filter(|p| !p.trim().is_empty())
+                .map(|p| {
+                    let (name, ty) = p.split_once(':').unwrap();
+                    let ty = c_type(ty);
+                    if ty.ends_with('*') {
+                        format!("{}{}", ty, name.trim())
+                    } else {
+                        format!("{} {}", ty, name.trim())
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            out.push('\n');
+            for doc in docs.drain(..) {
+                out.push_str("//");
+                out.push_str(doc);
+                out.push('\n');
+            }
+            let sep = if ret.ends_with('*') { "" } else { " " };
+            if params.is_empty() {
+                out.push_str(&format!("{ret}{sep}{name}(void);\n"));
+            } else {
+                out.push_str(&format!("{ret}{sep}{name}(\n"));
+                for (i, p) in params.iter().enumerate() {
+                    let comma = if i + 1 < params.len() { "," } else { "" };
+                    out.push_str(&format!("    {p}{comma}\n"));
+                }
+                out.push_str(");\n");
+            }
+        }
+
+        out.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n#endif // HAM_SDK_H\n");
+        out
+    }
+
+    #[test]
+    fn test_c_header_is_up_to_date() {
+        let header = generate_c_header(include_str!("c_api.rs"));
+        expect_file!["../../ham-sdk/ham_sdk.h"].assert_eq(&header);
+    }
+}
@@ -0,0 +1,409 @@
+//! 具象構文木の型付きのビュー
+//!
+//! 具象構文木のノードを、ノードの種類ごとの型で包んだもの。
+//! 子要素は具象構文木から都度探すので、構文木の一部が欠けていても (構文エラーがあっても) 使える。
+
+use super::*;
+
+pub(crate) trait AstNode: Sized {
+    fn can_cast(kind: NodeKind) -> bool;
+
+    fn cast(node: SyntaxNode) -> Option<Self>;
+
+    fn syntax(&self) -> &SyntaxNode;
+
+    /// ノードの範囲 (前後のトリビアを含まない)
+    fn range(&self) -> rowan::TextRange {
+        node_range(self.syntax())
+    }
+}
+
+fn node_kind(node: &SyntaxNode) -> Option<NodeKind> {
+    match node.kind() {
+        SyntaxKind::Node(kind) => Some(kind),
+        SyntaxKind::Token(_) => None,
+    }
+}
+
+fn is_trivia(token: &SyntaxToken) -> bool {
+    match token.kind() {
+        SyntaxKind::Token(kind) => kind.is_leading_trivia(),
+        SyntaxKind::Node(_) => false,
+    }
+}
+
+/// ノードの範囲から前後のトリビアを除いたもの
+pub(crate) fn node_range(node: &SyntaxNode) -> rowan::TextRange {
+    let mut tokens = node
+        .descendants_with_tokens()
+        .filter_map(|e| e.into_token())
+        .filter(|t| !is_trivia(t));
+    let first = match tokens.next() {
+        Some(it) => it,
+        None => return rowan::TextRange::empty(node.text_range().start()),
+    };
+    let last = tokens.last().unwrap_or_else(|| first.clone());
+    rowan::TextRange::new(first.text_range().start(), last.text_range().end())
+}
+
+fn child<N: AstNode>(parent: &SyntaxNode) -> Option<N> {
+    parent.children().find_map(N::cast)
+}
+
+fn children<N: AstNode>(parent: &SyntaxNode) -> impl Iterator<Item = N> {
+    parent.children().filter_map(N::cast)
+}
+
+/// 直接の子要素であるトークンのうち、指定した種類の最初のもの
+fn token(parent: &SyntaxNode, kind: TokenKind) -> Option<SyntaxToken> {
+    parent
+        .children_with_tokens()
+        .filter_map(|e| e.into_token())
+        .find(|t| t.kind() == SyntaxKind::Token(kind))
+}
+
+/// 直接の子要素であるトークンのうち、トリビアでない最初のもの
+fn first_token(parent: &SyntaxNode) -> Option<SyntaxToken> {
+    parent
+        .children_with_tokens()
+        .filter_map(|e| e.into_token())
+        .find(|t| !is_trivia(t))
+}
+
+macro_rules! ast_node {
+    ($($(#[$meta:meta])* $name:ident;)*) => {
+        $(
+            $(#[$meta])*
+            #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+            pub(crate) struct $name(SyntaxNode);
+
+            impl AstNode for $name {
+                fn can_cast(kind: NodeKind) -> bool {
+                    kind == NodeKind::$name
+                }
+
+                fn cast(node: SyntaxNode) -> Option<Self> {
+                    if Self::can_cast(node_kind(&node)?) {
+                        Some(Self(node))
+                    } else {
+                        None
+                    }
+                }
+
+                fn syntax(&self) -> &SyntaxNode {
+                    &self.0
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! ast_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident($node:ident),)* }) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        pub(crate) enum $name {
+            $($variant($node),)*
+        }
+
+        impl AstNode for $name {
+            fn can_cast(kind: NodeKind) -> bool {
+                $($node::can_cast(kind))||*
+            }
+
+            fn cast(node: SyntaxNode) -> Option<Self> {
+                let it = match node_kind(&node)? {
+                    $(NodeKind::$node => $name::$variant($node(node)),)*
+                    _ => return None,
+                };
+                Some(it)
+            }
+
+            fn syntax(&self) -> &SyntaxNode {
+                match self {
+                    $($name::$variant(it) => it.syntax(),)*
+                }
+            }
+        }
+    };
+}
+
+ast_node! {
+    /// ファイル全体
+    Root;
+    /// 定義される名前
+    Name;
+    Block;
+    Arg;
+    Param;
+
+    LabelStmt;
+    AssignStmt;
+    CommandStmt;
+    InvokeStmt;
+    IfStmt;
+    ConstStmt;
+    DefineStmt;
+    EnumStmt;
+    VarStmt;
+    DefFuncStmt;
+    UseLibStmt;
+    LibFuncStmt;
+    UseComStmt;
+    ComFuncStmt;
+    RegCmdStmt;
+    CmdStmt;
+    ModuleStmt;
+    GlobalStmt;
+    IncludeStmt;
+    UseStmt;
+    UnknownPreProcStmt;
+
+    LiteralExpr;
+    LabelExpr;
+    NameExpr;
+    CallExpr;
+    DotsExpr;
+    ParenExpr;
+    PrefixExpr;
+    InfixExpr;
+}
+
+ast_enum! {
+    /// 文
+    Stmt {
+        Label(LabelStmt),
+        Assign(AssignStmt),
+        Command(CommandStmt),
+        Invoke(InvokeStmt),
+        If(IfStmt),
+        Const(ConstStmt),
+        Define(DefineStmt),
+        Enum(EnumStmt),
+        Var(VarStmt),
+        DefFunc(DefFuncStmt),
+        UseLib(UseLibStmt),
+        LibFunc(LibFuncStmt),
+        UseCom(UseComStmt),
+        ComFunc(ComFuncStmt),
+        RegCmd(RegCmdStmt),
+        Cmd(CmdStmt),
+        Module(ModuleStmt),
+        Global(GlobalStmt),
+        Include(IncludeStmt),
+        Use(UseStmt),
+        UnknownPreProc(UnknownPreProcStmt),
+    }
+}
+
+ast_enum! {
+    /// 式
+    Expr {
+        Literal(LiteralExpr),
+        Label(LabelExpr),
+        Name(NameExpr),
+        Call(CallExpr),
+        Dots(DotsExpr),
+        Paren(ParenExpr),
+        Prefix(PrefixExpr),
+        Infix(InfixExpr),
+    }
+}
+
+/// 名前を定義するもの
+pub(crate) trait HasName: AstNode {
+    fn name(&self) -> Option<Name> {
+        child(self.syntax())
+    }
+}
+
+/// 引数の並びを持つもの
+pub(crate) trait HasArgs: AstNode {
+    fn args(&self) -> Vec<Arg> {
+        children(self.syntax()).collect()
+    }
+}
+
+/// パラメータの並びを持つもの
+pub(crate) trait HasParams: AstNode {
+    fn params(&self) -> Vec<Param> {
+        children(self.syntax()).collect()
+    }
+}
+
+/// 内側に文を持つもの
+pub(crate) trait HasStmts: AstNode {
+    fn stmts(&self) -> Vec<Stmt> {
+        children(self.syntax()).collect()
+    }
+}
+
+impl HasName for LabelStmt {}
+impl HasName for ConstStmt {}
+impl HasName for DefineStmt {}
+impl HasName for EnumStmt {}
+impl HasName for DefFuncStmt {}
+impl HasName for LibFuncStmt {}
+impl HasName for UseComStmt {}
+impl HasName for ComFuncStmt {}
+impl HasName for CmdStmt {}
+impl HasName for ModuleStmt {}
+impl HasName for Param {}
+impl HasName for LabelExpr {}
+
+impl HasArgs for AssignStmt {}
+impl HasArgs for CommandStmt {}
+impl HasArgs for InvokeStmt {}
+impl HasArgs for UseComStmt {}
+impl HasArgs for RegCmdStmt {}
+impl HasArgs for CallExpr {}
+
+impl HasParams for DefFuncStmt {}
+impl HasParams for LibFuncStmt {}
+impl HasParams for ComFuncStmt {}
+impl HasParams for ModuleStmt {}
+
+impl HasStmts for Root {}
+impl HasStmts for Block {}
+impl HasStmts for DefFuncStmt {}
+impl HasStmts for ModuleStmt {}
+
+impl Root {
+    /// 具象構文木の根から作る。
+    pub(crate) fn new(green: rowan::GreenNode) -> Self {
+        Root(SyntaxNode::new_root(green))
+    }
+}
+
+impl Name {
+    pub(crate) fn ident(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenKind::Ident)
+    }
+}
+
+impl Arg {
+    pub(crate) fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl Param {
+    /// パラメータの型 (`int`, `var` など)
+    pub(crate) fn ty(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenKind::Ident)
+    }
+}
+
+impl Stmt {
+    /// 文の種類を表すトークン
+    ///
+    /// (命令文なら命令の名前、`if` 文なら `if`、プリプロセッサ命令なら `#` の後のキーワード。)
+    pub(crate) fn keyword(&self) -> Option<SyntaxToken> {
+        match self {
+            Stmt::Label(_) | Stmt::Assign(_) | Stmt::Invoke(_) => None,
+            Stmt::Command(stmt) => stmt.command(),
+            Stmt::If(stmt) => token(&stmt.0, TokenKind::If),
+            _ => token(self.syntax(), TokenKind::Ident),
+        }
+    }
+}
+
+impl AssignStmt {
+    /// 左辺
+    pub(crate) fn left(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl CommandStmt {
+    pub(crate) fn command(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenKind::Ident)
+    }
+}
+
+impl InvokeStmt {
+    /// `->` の左辺
+    pub(crate) fn left(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl IfStmt {
+    pub(crate) fn cond(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    /// 本体
+    pub(crate) fn body(&self) -> Option<Block> {
+        self.blocks().find(|(is_alt, _)| !is_alt).map(|(_, b)| b)
+    }
+
+    /// `else` 節
+    pub(crate) fn alt(&self) -> Option<Block> {
+        self.blocks().find(|(is_alt, _)| *is_alt).map(|(_, b)| b)
+    }
+
+    /// ブロックと、それが `else` の後ろにあるか
+    fn blocks(&self) -> impl Iterator<Item = (bool, Block)> {
+        let mut is_alt = false;
+        self.0.children_with_tokens().filter_map(move |e| match e {
+            rowan::NodeOrToken::Token(t) => {
+                if t.kind() == SyntaxKind::Token(TokenKind::Else) {
+                    is_alt = true;
+                }
+                None
+            }
+            rowan::NodeOrToken::Node(node) => Some((is_alt, Block::cast(node)?)),
+        })
+    }
+}
+
+impl ConstStmt {
+    pub(crate) fn init(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl EnumStmt {
+    pub(crate) fn init(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl UseLibStmt {
+    pub(crate) fn file_path(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenKind::Str)
+    }
+}
+
+impl IncludeStmt {
+    pub(crate) fn file_path(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenKind::Str)
+    }
+}
+
+impl Expr {
+    /// 式の主なトークン
+    ///
+    /// (リテラルならそのトークン、ラベルならラベルの名前、名前や呼び出しなら名前、演算なら演算子。)
+    pub(crate) fn token(&self) -> Option<SyntaxToken> {
+        match self {
+            Expr::Literal(expr) => first_token(&expr.0),
+            Expr::Label(expr) => expr.name()?.ident(),
+            Expr::Name(expr) => token(&expr.0, TokenKind::Ident),
+            Expr::Call(expr) => token(&expr.0, TokenKind::Ident),
+            Expr::Dots(expr) => token(&expr.0, TokenKind::Ident),
+            Expr::Paren(_) => None,
+            Expr::Prefix(expr) => first_token(&expr.0),
+            Expr::Infix(expr) => first_token(&expr.0),
+        }
+    }
+
+    /// 内側にある式 (引数や被演算子)
+    pub(crate) fn children(&self) -> Vec<Expr> {
+        match self {
+            Expr::Call(expr) => expr.args().iter().filter_map(|arg| arg.expr()).collect(),
+            _ => children(self.syntax()).collect(),
+        }
+    }
+}
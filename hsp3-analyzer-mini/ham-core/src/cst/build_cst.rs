@@ -0,0 +1,392 @@
+//! 構文木 (`PRoot`) から具象構文木を構築する
+
+use super::*;
+use rowan::{GreenNode, GreenNodeBuilder, Language};
+
+/// トークン列と構文木から具象構文木を構築する
+///
+/// `tokens` は構文解析に渡したトークン列。
+/// 構文木から参照されていないトークン (読み飛ばされたトークンなど) も、元の順番で具象構文木に含める。
+/// そのため、具象構文木のテキストは元のテキストに一致する。
+pub(crate) fn build_cst(tokens: &[PToken], root: &PRoot) -> GreenNode {
+    let mut b = CstBuilder {
+        builder: GreenNodeBuilder::new(),
+        tokens,
+        next: 0,
+        pending: vec![],
+    };
+
+    b.builder.start_node(raw_node_kind(NodeKind::Root));
+    b.on_root(root);
+    b.skip_to(tokens.len());
+    b.builder.finish_node();
+    b.builder.finish()
+}
+
+fn raw_node_kind(kind: NodeKind) -> rowan::SyntaxKind {
+    HspLang::kind_to_raw(SyntaxKind::Node(kind))
+}
+
+struct CstBuilder<'a> {
+    builder: GreenNodeBuilder<'static>,
+    tokens: &'a [PToken],
+    /// 次に出力するトークンの `tokens` における位置
+    next: usize,
+    /// 開始したが、まだトークンを出力していないノード
+    ///
+    /// (ノードは最初のトークンの先行トリビアより後ろから始める。)
+    pending: Vec<NodeKind>,
+}
+
+impl CstBuilder<'_> {
+    fn start(&mut self, kind: NodeKind) {
+        self.pending.push(kind);
+    }
+
+    fn finish(&mut self) {
+        // トークンを含まないノードは作らない。
+        if self.pending.pop().is_none() {
+            self.builder.finish_node();
+        }
+    }
+
+    fn node(&mut self, kind: NodeKind, f: impl FnOnce(&mut Self)) {
+        self.start(kind);
+        f(self);
+        self.finish();
+    }
+
+    fn token_data(&mut self, token: &TokenData) {
+        let kind = HspLang::kind_to_raw(SyntaxKind::Token(token.kind));
+        self.builder.token(kind, token.text.as_str());
+    }
+
+    fn on_name_opt(&mut self, name_opt: Option<&PToken>) {
+        if let Some(name) = name_opt {
+            self.node(NodeKind::Name, |b| b.on_token(name));
+        }
+    }
+
+    /// 構文木に含まれていないトークンを出力する
+    fn skipped_token(&mut self, token: &PToken) {
+        // 文の区切りは構文木に含まれないので、そのまま出力する。
+        let is_separator = matches!(
+            token.kind(),
+            TokenKind::Eos | TokenKind::Colon | TokenKind::Eof
+        );
+
+        if !is_separator {
+            self.builder.start_node(raw_node_kind(NodeKind::Error));
+        }
+        for t in token.iter() {
+            self.token_data(t);
+        }
+        if !is_separator {
+            self.builder.finish_node();
+        }
+    }
+
+    /// 指定した位置より前にあるトークンをすべて出力する
+    fn skip_to(&mut self, index: usize) {
+        while self.next < index {
+            let token = &self.tokens[self.next];
+            self.next += 1;
+            self.skipped_token(token);
+        }
+    }
+}
+
+impl PVisitor for CstBuilder<'_> {
+    fn on_token(&mut self, token: &PToken) {
+        let index = match self.tokens[self.next..]
+            .iter()
+            .position(|t| t.body.ptr_eq(&token.body))
+        {
+            Some(i) => self.next + i,
+            // (出力済み)
+            None => return,
+        };
+        self.skip_to(index);
+        self.next += 1;
+
+        for t in token.leading.iter() {
+            self.token_data(t);
+        }
+        for kind in take(&mut self.pending) {
+            self.builder.start_node(raw_node_kind(kind));
+        }
+        self.token_data(&token.body);
+        for t in token.trailing.iter() {
+            self.token_data(t);
+        }
+    }
+
+    fn on_label(&mut self, label: &PLabel) {
+        self.on_token(&label.star);
+        self.node(NodeKind::Name, |b| b.on_token_opt(label.name_opt.as_ref()));
+    }
+
+    fn on_compound(&mut self, compound: &PCompound) {
+        let kind = match compound {
+            PCompound::Name(_) => NodeKind::NameExpr,
+            PCompound::Paren(_) => NodeKind::CallExpr,
+            PCompound::Dots(_) => NodeKind::DotsExpr,
+        };
+        self.node(kind, |b| b.on_compound_default(compound));
+    }
+
+    fn on_arg(&mut self, arg: &PArg) {
+        self.node(NodeKind::Arg, |b| {
+            b.on_expr_opt(arg.expr_opt.as_ref());
+            b.on_token_opt(arg.comma_opt.as_ref());
+        });
+    }
+
+    fn on_expr(&mut self, expr: &PExpr) {
+        let kind = match expr {
+            PExpr::Literal(_) => NodeKind::LiteralExpr,
+            PExpr::Label(_) => NodeKind::LabelExpr,
+            // (`on_compound` がノードを作る。)
+            PExpr::Compound(_) => return self.on_expr_default(expr),
+            PExpr::Paren(_) => NodeKind::ParenExpr,
+            PExpr::Prefix(_) => NodeKind::PrefixExpr,
+            PExpr::Infix(_) => NodeKind::InfixExpr,
+        };
+        self.node(kind, |b| b.on_expr_default(expr));
+    }
+
+    fn on_param(&mut self, param: &PParam) {
+        self.node(NodeKind::Param, |b| {
+            b.on_token_opt(param.param_ty_opt.as_ref().map(|(_, t)| t));
+            b.on_name_opt(param.name_opt.as_ref());
+            b.on_token_opt(param.comma_opt.as_ref());
+        });
+    }
+
+    fn on_block(&mut self, block: &PBlock) {
+        self.node(NodeKind::Block, |b| {
+            b.on_stmts(&block.outer_stmts);
+            b.on_token_opt(block.left_opt.as_ref());
+            b.on_stmts(&block.inner_stmts);
+            b.on_token_opt(block.right_opt.as_ref());
+        });
+    }
+
+    fn on_stmt(&mut self, stmt: &PStmt) {
+        // 既定の実装はいくつかの文の中身を訪問しないので、ここですべて訪問する。
+        let kind = match stmt {
+            PStmt::Label(_) => NodeKind::LabelStmt,
+            PStmt::Assign(_) => NodeKind::AssignStmt,
+            PStmt::Command(_) => NodeKind::CommandStmt,
+            PStmt::Invoke(_) => NodeKind::InvokeStmt,
+            PStmt::If(_) => NodeKind::IfStmt,
+            PStmt::Const(_) => NodeKind::ConstStmt,
+            PStmt::Define(_) => NodeKind::DefineStmt,
+            PStmt::Enum(_) => NodeKind::EnumStmt,
+            PStmt::Var(_) => NodeKind::VarStmt,
+            PStmt::DefFunc(_) => NodeKind::DefFuncStmt,
+            PStmt::UseLib(_) => NodeKind::UseLibStmt,
+            PStmt::LibFunc(_) => NodeKind::LibFuncStmt,
+            PStmt::UseCom(_) => NodeKind::UseComStmt,
+            PStmt::ComFunc(_) => NodeKind::ComFuncStmt,
+            PStmt::RegCmd(_) => NodeKind::RegCmdStmt,
+            PStmt::Cmd(_) => NodeKind::CmdStmt,
+            PStmt::Module(_) => NodeKind::ModuleStmt,
+            PStmt::Global(_) => NodeKind::GlobalStmt,
+            PStmt::Include(_) => NodeKind::IncludeStmt,
+            PStmt::Use(_) => NodeKind::UseStmt,
+            PStmt::UnknownPreProc(_) => NodeKind::UnknownPreProcStmt,
+        };
+
+        self.start(kind);
+        match stmt {
+            PStmt::Label(label) => self.on_label(label),
+            PStmt::If(stmt) => {
+                self.on_token(&stmt.command);
+                self.on_expr_opt(stmt.cond_opt.as_ref());
+                self.on_block(&stmt.body);
+                if let Some(e) = &stmt.else_opt {
+                    self.on_token(e);
+                    self.on_block(&stmt.alt);
+                }
+            }
+            PStmt::Const(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                self.on_token_opt(stmt.privacy_opt.as_ref().map(|(_, t)| t));
+                self.on_token_opt(stmt.ty_opt.as_ref().map(|(_, t)| t));
+                self.on_name_opt(stmt.name_opt.as_ref());
+                self.on_expr_opt(stmt.init_opt.as_ref());
+            }
+            PStmt::Define(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                self.on_token_opt(stmt.privacy_opt.as_ref().map(|(_, t)| t));
+                self.on_token_opt(stmt.ctype_opt.as_ref());
+                self.on_name_opt(stmt.name_opt.as_ref());
+                self.on_token_opt(stmt.left_paren_opt.as_ref());
+                for param in &stmt.params {
+                    self.node(NodeKind::MacroParam, |b| {
+                        b.on_token_opt(param.percent_opt.as_ref());
+                        b.on_token_opt(param.number_opt.as_ref());
+                        b.on_token_opt(param.equal_opt.as_ref());
+                        b.on_tokens(&param.init);
+                        b.on_token_opt(param.comma_opt.as_ref());
+                    });
+                }
+                self.on_token_opt(stmt.right_paren_opt.as_ref());
+                self.on_tokens(&stmt.tokens);
+            }
+            PStmt::Enum(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                self.on_token_opt(stmt.privacy_opt.as_ref().map(|(_, t)| t));
+                self.on_name_opt(stmt.name_opt.as_ref());
+                self.on_token_opt(stmt.equal_opt.as_ref());
+                self.on_expr_opt(stmt.init_opt.as_ref());
+            }
+            PStmt::DefFunc(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                self.on_token_opt(stmt.privacy_opt.as_ref().map(|(_, t)| t));
+                self.on_name_opt(stmt.name_opt.as_ref());
+                self.on_params(&stmt.params);
+                self.on_token_opt(stmt.onexit_opt.as_ref());
+                self.on_stmts(&stmt.stmts);
+            }
+            PStmt::UseLib(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                self.on_token_opt(stmt.file_path_opt.as_ref());
+            }
+            PStmt::LibFunc(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                self.on_token_opt(stmt.privacy_opt.as_ref().map(|(_, t)| t));
+                self.on_name_opt(stmt.name_opt.as_ref());
+                self.on_token_opt(stmt.onexit_opt.as_ref());
+                self.on_token_opt(stmt.func_name_opt.as_ref());
+                self.on_token_opt(stmt.type_id_opt.as_ref());
+                self.on_params(&stmt.params);
+            }
+            PStmt::UseCom(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                self.on_token_opt(stmt.privacy_opt.as_ref().map(|(_, t)| t));
+                self.on_name_opt(stmt.name_opt.as_ref());
+                self.on_args(&stmt.args);
+            }
+            PStmt::ComFunc(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                self.on_token_opt(stmt.privacy_opt.as_ref().map(|(_, t)| t));
+                self.on_name_opt(stmt.name_opt.as_ref());
+                self.on_token_opt(stmt.index_opt.as_ref());
+                self.on_params(&stmt.params);
+            }
+            PStmt::RegCmd(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                self.on_args(&stmt.args);
+            }
+            PStmt::Cmd(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                self.on_token_opt(stmt.privacy_opt.as_ref().map(|(_, t)| t));
+                self.on_name_opt(stmt.name_opt.as_ref());
+                self.on_token_opt(stmt.command_id_opt.as_ref());
+            }
+            PStmt::Module(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                self.on_name_opt(stmt.name_opt.as_ref());
+                self.on_params(&stmt.fields);
+                self.on_stmts(&stmt.stmts);
+                if let Some(global) = &stmt.global_opt {
+                    self.node(NodeKind::GlobalStmt, |b| {
+                        b.on_token(&global.hash);
+                        b.on_token(&global.keyword);
+                    });
+                }
+            }
+            PStmt::Global(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+            }
+            PStmt::Include(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                self.on_token_opt(stmt.file_path_opt.as_ref());
+            }
+            PStmt::Use(stmt) => {
+                self.on_token(&stmt.hash);
+                self.on_token(&stmt.keyword);
+                for (name, comma_opt) in &stmt.names {
+                    self.on_token(name);
+                    self.on_token_opt(comma_opt.as_ref());
+                }
+            }
+            PStmt::Assign(_)
+            | PStmt::Command(_)
+            | PStmt::Invoke(_)
+            | PStmt::Var(_)
+            | PStmt::UnknownPreProc(_) => self.on_stmt_default(stmt),
+        }
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse::parse_root, token::tokenize};
+    use expect_test::expect;
+
+    fn parse_to_cst(text: &str) -> SyntaxNode {
+        let tokens = tokenize(1, RcStr::from(text));
+        let tokens: RcSlice<_> = PToken::from_tokens(tokens.into()).into();
+        let root = parse_root(tokens.to_owned());
+        SyntaxNode::new_root(build_cst(&tokens, &root))
+    }
+
+    #[test]
+    fn test_lossless() {
+        let text = r#")) mes 1 +
+#deffunc
+if a { ; comment
+    */ x = , (
+}
+#module m
+"#;
+        let node = parse_to_cst(text);
+        assert_eq!(node.text(), text);
+    }
+
+    #[test]
+    fn test_tree() {
+        let node = parse_to_cst("*l\n    goto *l ; loop\n");
+        expect![[r#"
+            Node(Root)@0..22
+              Node(LabelStmt)@0..2
+                Token(Star)@0..1 "*"
+                Node(Name)@1..2
+                  Token(Ident)@1..2 "l"
+              Token(Eos)@2..2 ""
+              Token(Newlines)@2..7 "\n    "
+              Node(CommandStmt)@7..21
+                Token(Ident)@7..11 "goto"
+                Token(Blank)@11..12 " "
+                Node(Arg)@12..21
+                  Node(LabelExpr)@12..21
+                    Token(Star)@12..13 "*"
+                    Node(Name)@13..21
+                      Token(Ident)@13..14 "l"
+                      Token(Blank)@14..15 " "
+                      Token(Comment)@15..21 "; loop"
+              Token(Eos)@21..21 ""
+              Token(Newlines)@21..22 "\n"
+              Token(Eof)@22..22 ""
+        "#]]
+        .assert_eq(&format!("{:#?}", node));
+    }
+}
@@ -0,0 +1,36 @@
+use super::*;
+
+/// 具象構文木の位置 (テキストの先頭からのバイト数) を行・列の位置に変換するもの
+pub(crate) struct LineIndex {
+    text: RcStr,
+    /// 各行の開始位置
+    line_starts: Vec<u32>,
+}
+
+impl LineIndex {
+    pub(crate) fn new(text: RcStr) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| i as u32 + 1));
+        Self { text, line_starts }
+    }
+
+    pub(crate) fn pos(&self, offset: rowan::TextSize) -> Pos {
+        let index = u32::from(offset);
+        let row = match self.line_starts.binary_search(&index) {
+            Ok(row) => row,
+            Err(row) => row - 1,
+        };
+        let start = self.line_starts[row];
+        let line = &self.text[start as usize..index as usize];
+        Pos::new(
+            index,
+            row as u32,
+            line.len() as u32,
+            line.encode_utf16().count() as u32,
+        )
+    }
+
+    pub(crate) fn range(&self, range: rowan::TextRange) -> Range {
+        Range::from(self.pos(range.start())..self.pos(range.end()))
+    }
+}
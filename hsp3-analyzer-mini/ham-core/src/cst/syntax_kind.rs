@@ -0,0 +1,106 @@
+//! 具象構文木の要素の種類
+
+use super::*;
+
+/// ノードの種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub(crate) enum NodeKind {
+    /// ファイル全体
+    Root,
+    /// 構文解析で読み飛ばされたトークン
+    Error,
+    /// 定義される名前 (`#deffunc` の関数名、ラベル名など)
+    Name,
+
+    // 文:
+    LabelStmt,
+    AssignStmt,
+    CommandStmt,
+    InvokeStmt,
+    IfStmt,
+    ConstStmt,
+    DefineStmt,
+    EnumStmt,
+    VarStmt,
+    DefFuncStmt,
+    UseLibStmt,
+    LibFuncStmt,
+    UseComStmt,
+    ComFuncStmt,
+    RegCmdStmt,
+    CmdStmt,
+    ModuleStmt,
+    GlobalStmt,
+    IncludeStmt,
+    UseStmt,
+    UnknownPreProcStmt,
+
+    // 文の部品:
+    /// `if` 文の本体または `else` 節
+    Block,
+    /// 引数 (式と後続するカンマ)
+    Arg,
+    /// `#deffunc` などのパラメータ、または `#module` のモジュール変数
+    Param,
+    /// `#define` のパラメータ
+    MacroParam,
+
+    // 式:
+    LiteralExpr,
+    LabelExpr,
+    NameExpr,
+    /// `f(x, y)` など
+    CallExpr,
+    /// `a.i` など
+    DotsExpr,
+    ParenExpr,
+    PrefixExpr,
+    InfixExpr,
+}
+
+impl NodeKind {
+    /// 最後の列挙子 (列挙子を追加したら更新すること)
+    const LAST: NodeKind = NodeKind::InfixExpr;
+}
+
+/// 具象構文木の要素の種類 (トークンまたはノード)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum SyntaxKind {
+    Token(TokenKind),
+    Node(NodeKind),
+}
+
+/// HSP3 の言語 (rowan の構文木の種類を表す)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum HspLang {}
+
+/// ノードの種類の番号の開始位置 (これより小さい番号はトークンの種類を表す)
+const NODE_BASE: u16 = 0x100;
+
+impl rowan::Language for HspLang {
+    type Kind = SyntaxKind;
+
+    fn kind_from_raw(raw: rowan::SyntaxKind) -> SyntaxKind {
+        // 番号は `kind_to_raw` で作られたものなので、範囲内にあれば対応する値がある。
+        if raw.0 < NODE_BASE {
+            assert!(raw.0 <= TokenKind::LAST as u16);
+            SyntaxKind::Token(unsafe { mem::transmute::<u8, TokenKind>(raw.0 as u8) })
+        } else {
+            let n = raw.0 - NODE_BASE;
+            assert!(n <= NodeKind::LAST as u16);
+            SyntaxKind::Node(unsafe { mem::transmute::<u8, NodeKind>(n as u8) })
+        }
+    }
+
+    fn kind_to_raw(kind: SyntaxKind) -> rowan::SyntaxKind {
+        let n = match kind {
+            SyntaxKind::Token(kind) => kind as u16,
+            SyntaxKind::Node(kind) => NODE_BASE + kind as u16,
+        };
+        rowan::SyntaxKind(n)
+    }
+}
+
+pub(crate) type SyntaxNode = rowan::SyntaxNode<HspLang>;
+pub(crate) type SyntaxToken = rowan::SyntaxToken<HspLang>;
@@ -1,19 +1,32 @@
 //! HSP Help Source (.hs) ファイルの解析
 
 use super::*;
+use crate::{
+    i18n::{tr, Locale},
+    source::{Pos, Range},
+};
+use serde::{Deserialize, Serialize};
 
 const EOL: &str = "\r\n";
 
 /// ヘルプソースファイルから抽出したシンボル情報
-#[derive(Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub(crate) struct HsSymbol {
     pub(crate) name: String,
     pub(crate) description: Option<String>,
     pub(crate) documentation: Vec<String>,
     pub(crate) params_opt: Option<Vec<HsParamInfo>>,
 
+    /// 解説文 (`%inst`)
+    #[serde(default)]
+    pub(crate) inst_opt: Option<String>,
+
     /// 標準命令か関数？
     pub(crate) builtin: bool,
+
+    /// このシンボルが追加されたHSPの版 (セクションに書かれた `%ver`)
+    #[serde(default)]
+    pub(crate) version_opt: Option<String>,
 }
 
 fn str_is_whitespace(s: &str) -> bool {
@@ -130,10 +143,18 @@ pub(crate) fn parse_for_symbols(
     }
 
     // セクションの既定値を合成する。
+    // (`%ver` は合成する前の、セクション自身に書かれたものを覚えておく。)
 
     let default_map = maps.drain(..1).next().unwrap();
+    let mut own_versions = vec![];
 
     for map in maps.iter_mut() {
+        own_versions.push(
+            map.get("ver")
+                .and_then(|v| v.first())
+                .map(|s| s.trim().to_string()),
+        );
+
         for (k, v) in &default_map {
             if !map.contains_key(k) {
                 map.insert(k.clone(), v.clone());
@@ -143,7 +164,7 @@ pub(crate) fn parse_for_symbols(
 
     // シンボル情報を構築する。
 
-    for mut map in maps {
+    for (mut map, version_opt) in maps.into_iter().zip(own_versions) {
         let index_lines = match map.get_mut("index") {
             None => {
                 // unreachable?
@@ -173,8 +194,9 @@ pub(crate) fn parse_for_symbols(
             documentation.push(prm.join(EOL));
         }
 
-        if let Some(inst) = map.get("inst") {
-            documentation.push(inst.join(EOL));
+        let inst_opt = map.get("inst").map(|inst| inst.join(EOL));
+        if let Some(inst) = &inst_opt {
+            documentation.push(inst.clone());
         }
 
         if let Some(note) = map.get("note") {
@@ -195,11 +217,14 @@ pub(crate) fn parse_for_symbols(
             description,
             documentation,
             params_opt,
+            inst_opt,
             builtin,
+            version_opt,
         });
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub(crate) struct HsParamInfo {
     pub(crate) name: String,
     pub(crate) details_opt: Option<String>,
@@ -284,6 +309,114 @@ fn parse_prm_section(prm: &[&str]) -> Vec<HsParamInfo> {
     params
 }
 
+/// ヘルプソースファイルで使えるキーと、その説明の翻訳キー
+pub(crate) const HS_KEYS: &[(&str, &str)] = &[
+    ("dll", "hs_key.dll"),
+    ("ver", "hs_key.ver"),
+    ("date", "hs_key.date"),
+    ("author", "hs_key.author"),
+    ("url", "hs_key.url"),
+    ("note", "hs_key.note"),
+    ("type", "hs_key.type"),
+    ("group", "hs_key.group"),
+    ("port", "hs_key.port"),
+    ("portinfo", "hs_key.portinfo"),
+    ("index", "hs_key.index"),
+    ("prm", "hs_key.prm"),
+    ("inst", "hs_key.inst"),
+    ("sample", "hs_key.sample"),
+    ("href", "hs_key.href"),
+];
+
+/// ヘルプソースファイルの書きかたの誤り
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HsLint {
+    UnclosedHtml,
+    MissingIndexName,
+    UnknownKey,
+    DuplicateIndex,
+}
+
+impl HsLint {
+    pub(crate) fn as_str(self, locale: Locale) -> &'static str {
+        match self {
+            HsLint::UnclosedHtml => tr(locale, "lint.hs_unclosed_html"),
+            HsLint::MissingIndexName => tr(locale, "lint.hs_missing_index_name"),
+            HsLint::UnknownKey => tr(locale, "lint.hs_unknown_key"),
+            HsLint::DuplicateIndex => tr(locale, "lint.hs_duplicate_index"),
+        }
+    }
+}
+
+/// ヘルプソースファイルの書きかたの誤りを検出する。
+///
+/// (解析の方法は `parse_for_symbols` に合わせている。)
+pub(crate) fn lint_help_source(content: &str, lints: &mut Vec<(HsLint, Range)>) {
+    let mut pos = Pos::default();
+    let mut html_opt: Option<Range> = None;
+
+    // `%index` の範囲。次のキーワードの行を待っている状態なら Some
+    let mut index_opt: Option<Range> = None;
+    let mut names = HashSet::new();
+
+    for raw_line in content.split_inclusive('\n') {
+        let start = pos;
+        pos += Pos::from(raw_line);
+
+        let line = raw_line.trim_end_matches(&['\r', '\n'][..]);
+        let line_range = Range::from(start..start + Pos::from(line));
+
+        if line.starts_with(";") {
+            continue;
+        }
+
+        if html_opt.is_some() {
+            if line.starts_with("}html") {
+                html_opt = None;
+            }
+            continue;
+        } else if line.starts_with("html{") {
+            html_opt = Some(Range::from(start..start + Pos::from("html{")));
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix('%') {
+            let name = rest
+                .chars()
+                .take_while(|c| c.is_ascii_alphabetic())
+                .collect::<String>()
+                .to_ascii_lowercase();
+            let key_range = Range::from(start..start + Pos::from(&line[..1 + name.len()]));
+
+            if let Some(index) = index_opt.take() {
+                lints.push((HsLint::MissingIndexName, index));
+            }
+
+            if name == "index" {
+                index_opt = Some(key_range);
+            } else if HS_KEYS.iter().all(|&(key, _)| key != name) {
+                lints.push((HsLint::UnknownKey, key_range));
+            }
+            continue;
+        }
+
+        if index_opt.is_some() && !str_is_whitespace(line) {
+            index_opt = None;
+            if !names.insert(line.trim()) {
+                lints.push((HsLint::DuplicateIndex, line_range));
+            }
+        }
+    }
+
+    if let Some(index) = index_opt {
+        lints.push((HsLint::MissingIndexName, index));
+    }
+
+    if let Some(html) = html_opt {
+        lints.push((HsLint::UnclosedHtml, html));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -354,4 +487,18 @@ n 数値"#;
                 .collect::<Vec<_>>()
         ));
     }
+
+    #[test]
+    fn test_parse_own_version() {
+        // ファイルの先頭の `%ver` はシンボルの版とみなさない。
+        let content = "%ver\n3.6\n%index\nfoo\n%index\nbar\n%ver\n3.7\n";
+
+        let mut symbols = vec![];
+        parse_for_symbols(content, &mut symbols, &mut vec![]);
+        let versions = symbols
+            .iter()
+            .map(|s| (s.name.as_str(), s.version_opt.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(versions, [("foo", None), ("bar", Some("3.7"))]);
+    }
 }
@@ -0,0 +1,75 @@
+//! HSPの版
+
+use std::fmt::{self, Display, Formatter};
+
+/// HSPの版 (`3.6`, `3.51` など)
+///
+/// (`3.51` は 3.5 と 3.6 の間の版なので、小数点以下の1桁目と2桁目以降を分けて持つ。)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct HspVersion {
+    pub(crate) major: u32,
+    pub(crate) minor: u32,
+    pub(crate) patch: u32,
+}
+
+impl HspVersion {
+    /// `#use` や `#var` などが追加された版
+    pub(crate) const HSP37: HspVersion = HspVersion::new(3, 7, 0);
+
+    pub(crate) const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// `3.7`, `3.51`, `3.7beta4` のような文字列を解釈する。(数字の後ろにある文字は無視する。)
+    pub(crate) fn parse(s: &str) -> Option<HspVersion> {
+        let s = s.trim();
+        let (major, rest) = s.split_once('.')?;
+        let major = major.parse().ok()?;
+
+        let digits = rest
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>();
+        let (minor, patch) = match digits.len() {
+            0 => return None,
+            1 => (digits.parse().ok()?, 0),
+            _ => (digits[..1].parse().ok()?, digits[1..].parse().ok()?),
+        };
+        Some(HspVersion::new(major, minor, patch))
+    }
+}
+
+impl Display for HspVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.patch == 0 {
+            write!(f, "{}.{}", self.major, self.minor)
+        } else {
+            write!(f, "{}.{}{}", self.major, self.minor, self.patch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let parse = |s: &str| HspVersion::parse(s).map(|v| v.to_string());
+        assert_eq!(parse("3.7").as_deref(), Some("3.7"));
+        assert_eq!(parse(" 3.51 ").as_deref(), Some("3.51"));
+        assert_eq!(parse("3.7beta4").as_deref(), Some("3.7"));
+        assert_eq!(parse("3.6β").as_deref(), Some("3.6"));
+        assert_eq!(parse("3"), None);
+        assert_eq!(parse("v3.7"), None);
+
+        let v = |s: &str| HspVersion::parse(s).unwrap();
+        assert!(v("3.5") < v("3.51"));
+        assert!(v("3.51") < v("3.6"));
+        assert!(v("3.6") < HspVersion::HSP37);
+    }
+}
@@ -0,0 +1,436 @@
+//! メッセージの翻訳
+//!
+//! ユーザーに表示する文字列はキーで指定して、表示言語の表から引く。
+//! 表示言語は LSP の `initialize` リクエストの `locale` (CLI では `--lang`) で決まる。(既定は日本語)
+//! (表示言語はアナライザーが保持する。`AnalyzerRef::locale` を参照)
+
+use std::fmt::Display;
+
+/// 表示言語
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    Ja,
+    En,
+}
+
+impl Locale {
+    /// 言語タグ (`ja`, `en-US` など) から表示言語を決める。日本語でなければ英語にする。
+    pub fn from_tag(tag: &str) -> Locale {
+        if tag.to_ascii_lowercase().starts_with("ja") {
+            Locale::Ja
+        } else {
+            Locale::En
+        }
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::Ja => JA,
+            Locale::En => EN,
+        }
+    }
+}
+
+/// 指定した言語のメッセージを引く。(表にないキーはそのまま返す。)
+pub fn tr(locale: Locale, key: &'static str) -> &'static str {
+    locale
+        .table()
+        .iter()
+        .find(|&&(k, _)| k == key)
+        .map_or(key, |&(_, message)| message)
+}
+
+/// 指定した言語のメッセージを引いて、`{}` を引数で順番に置き換える。
+pub(crate) fn tr_fmt(locale: Locale, key: &'static str, args: &[&dyn Display]) -> String {
+    let mut pieces = tr(locale, key).split("{}");
+    let mut s = pieces.next().unwrap_or_default().to_string();
+    for (i, piece) in pieces.enumerate() {
+        if let Some(arg) = args.get(i) {
+            s += &arg.to_string();
+        }
+        s += piece;
+    }
+    s
+}
+
+static JA: &[(&str, &str)] = &[
+    // シンボルの種類
+    ("symbol.unresolved", "未解決"),
+    ("symbol.unknown", "不明"),
+    ("symbol.const", "定数"),
+    ("symbol.enum", "列挙子"),
+    ("symbol.macro", "マクロ"),
+    ("symbol.ctype_macro", "関数形式マクロ"),
+    ("symbol.deffunc", "命令"),
+    ("symbol.defcfunc", "関数"),
+    ("symbol.modfunc", "命令(モジュール変数)"),
+    ("symbol.modcfunc", "関数(モジュール変数)"),
+    ("symbol.param", "パラメータ"),
+    ("symbol.libfunc", "ライブラリ関数"),
+    ("symbol.plugin_cmd", "プラグインコマンド"),
+    ("symbol.module", "モジュール"),
+    ("symbol.field", "モジュール変数"),
+    ("symbol.label", "ラベル"),
+    ("symbol.static_var", "変数"),
+    ("symbol.com_interface", "COMインターフェイス"),
+    ("symbol.com_func", "COMメソッド"),
+    // 診断
+    ("lint.return_in_loop", "repeatループの中ではreturnできません。"),
+    ("lint.undefined", "定義が見つかりません"),
+    ("lint.var_required", "変数か配列の要素が必要です。"),
+    (
+        "lint.format_arg_count",
+        "書式指定の数 ({}) と引数の数 ({}) が一致しません。",
+    ),
+    ("lint.format_arg_type", "書式指定 {} に文字列が渡されています。"),
+    ("lint.too_many_args", "引数が多すぎます。(パラメータは {} 個です)"),
+    (
+        "lint.name_casing",
+        "大文字・小文字の書きかたが定義 ({}) と異なります。",
+    ),
+    (
+        "lint.requires_hsp_version",
+        "{} は HSP {} 以降で使えます。(対象の HSP は {} です)",
+    ),
+    ("lint.division_by_zero", "0で除算しています。"),
+    ("lint.overflow", "整数の範囲を超えています。"),
+    ("lint.not_constant", "定数式ではありません。"),
+    ("lint.malformed_number", "数値の書きかたが正しくありません。"),
+    ("lint.full_width_space", "全角空白があります。"),
+    ("lint.bad_char", "使用できない文字があります。"),
+    ("lint.not_sjis", "shift_jis で表現できない文字があります。"),
+    (
+        "lint.utf8_without_hsp3utf",
+        "このファイルは UTF-8 で保存されていますが、HSP は shift_jis を想定しています。(UTF-8 を使うときは hsp3utf.as を #include してください。)",
+    ),
+    (
+        "lint.sjis_with_hsp3utf",
+        "このファイルは shift_jis で保存されていますが、hsp3utf は UTF-8 を想定しています。",
+    ),
+    ("lint.hs_unclosed_html", "html{ に対応する }html がありません。"),
+    (
+        "lint.hs_missing_index_name",
+        "%index の次の行にキーワードがありません。",
+    ),
+    ("lint.hs_unknown_key", "不明なキーです。"),
+    (
+        "lint.hs_duplicate_index",
+        "同じキーワードの %index がすでにあります。",
+    ),
+    // ホバー
+    ("hover.value", "値: {}"),
+    ("hover.include_guard", "インクルードガード"),
+    // 名前の変更
+    (
+        "rename.contains_at",
+        "新しい名前に @ を含めることはできません。",
+    ),
+    ("rename.collision", "{} という名前はすでに使われています。"),
+    // コードアクション
+    ("action.flip_comma", "カンマの両側を交換"),
+    ("action.generate_include_guard", "インクルードガードを生成する"),
+    ("action.replace_full_width_space", "全角空白を半角空白に置き換える"),
+    ("action.remove_bad_char", "使用できない文字を削除する"),
+    ("action.replace_not_sjis", "shift_jis で表現できない文字を ? に置き換える"),
+    ("action.unify_name_casing", "大文字・小文字を定義に揃える ({})"),
+    (
+        "action.unify_name_casing_all",
+        "ファイル内の大文字・小文字をすべて定義に揃える",
+    ),
+    // コードレンズ
+    ("lens.run", "▶ 実行"),
+    ("lens.references", "{} 件の参照"),
+    // ヘルプソースファイルのキー
+    ("hs_key.dll", "プラグイン・モジュール名"),
+    ("hs_key.ver", "バージョン"),
+    ("hs_key.date", "日付"),
+    ("hs_key.author", "著作者"),
+    ("hs_key.url", "関連URL"),
+    ("hs_key.note", "備考"),
+    ("hs_key.type", "タイプ"),
+    ("hs_key.group", "グループ"),
+    ("hs_key.port", "対応環境"),
+    ("hs_key.portinfo", "移植のヒント"),
+    ("hs_key.index", "キーワードと概要 (項目の始まり)"),
+    ("hs_key.prm", "パラメータリスト"),
+    ("hs_key.inst", "解説文"),
+    ("hs_key.sample", "サンプルスクリプト"),
+    ("hs_key.href", "関連項目"),
+    // ヘルプソースファイルの生成 (gen-help)
+    ("gen_help.header", "{} のヘルプソースファイル"),
+    ("gen_help.generated_by", "(`ham gen-help` で生成されました)"),
+    ("gen_help.type", "ユーザー拡張命令"),
+    ("gen_help.note", "{} をインクルードしてください。"),
+    ("gen_help.missing_in_help", "{}: ヘルプソースに記載がありません。"),
+    (
+        "gen_help.param_mismatch",
+        "{}: パラメータが一致しません。(コード: {}, ヘルプソース: {})",
+    ),
+    ("gen_help.missing_in_code", "{}: コードに定義がありません。"),
+    ("gen_help.cannot_open", "{}: ヘルプソースファイルを開けません。"),
+    ("gen_help.check_stdin", "--check には標準入力を指定できません"),
+    ("gen_help.generated", "{} を生成しました。"),
+    // CLI
+    (
+        "cli.help",
+        r#"ham {version}
+
+    USAGE: ham [OPTIONS] [SUBCOMMAND]
+
+    EXAMPLE: ham --hsp "C:/hsp37" profile-parse
+
+    SUBCOMMANDS:
+        lsp
+            LSPサーバーとして起動する (標準入出力でメッセージを送受信する)
+            (HSPインストールディレクトリの指定がなければ、同梱の標準命令のデータを使う)
+            環境変数はtopics.mdを参照
+
+        parse [FILES...]
+
+        profile-parse
+            (HSPインストールディレクトリの指定が必須)
+
+        gen-builtin
+            hsphelp から標準命令などのデータ (ham-core/data/builtin.hs) を生成し、標準出力に書く
+            (HSPインストールディレクトリの指定が必須)
+
+        format-comments [FILES]
+            (**注意**: ファイルは上書きされます。必ずバックアップしてください)
+            HSPのスクリプトのコメントを // 形式から ; 形式に変更し、
+            入力スクリプトファイルを上書きします
+
+        gen-help [--check] [FILES]
+            モジュールの定義とドキュメンテーションコメントから
+            ヘルプソースファイル (.hs) を生成し、入力ファイルと同じディレクトリに保存します
+            (--check: 生成する代わりに、既存の .hs ファイルとコードの食い違いを報告します)
+
+    OPTIONS:
+        -h, --help      Print help
+        -V, --version   Print Version
+            --hsp       HSPインストールディレクトリを指定
+            --lang      表示言語を指定 (ja, en)
+
+    ENV:
+        HSP3_ROOT       HSPインストールディレクトリを指定 (--hsp より優先度低)
+"#,
+    ),
+    (
+        "cli.hsp3_root_missing",
+        r#"HSPのインストールディレクトリを指定してください。(例: コマンドライン引数に --hsp "C:/hsp37" のように指定する、あるいは環境変数 HSP3_ROOT にパスを指定する)"#,
+    ),
+    ("cli.hsp3_root_not_found", "HSP3_ROOTディレクトリがみつかりません"),
+    ("cli.no_input_files", "入力ファイルが指定されていません"),
+];
+
+static EN: &[(&str, &str)] = &[
+    // シンボルの種類
+    ("symbol.unresolved", "unresolved"),
+    ("symbol.unknown", "unknown"),
+    ("symbol.const", "constant"),
+    ("symbol.enum", "enumerator"),
+    ("symbol.macro", "macro"),
+    ("symbol.ctype_macro", "function-like macro"),
+    ("symbol.deffunc", "command"),
+    ("symbol.defcfunc", "function"),
+    ("symbol.modfunc", "command (module variable)"),
+    ("symbol.modcfunc", "function (module variable)"),
+    ("symbol.param", "parameter"),
+    ("symbol.libfunc", "library function"),
+    ("symbol.plugin_cmd", "plugin command"),
+    ("symbol.module", "module"),
+    ("symbol.field", "module variable"),
+    ("symbol.label", "label"),
+    ("symbol.static_var", "variable"),
+    ("symbol.com_interface", "COM interface"),
+    ("symbol.com_func", "COM method"),
+    // 診断
+    ("lint.return_in_loop", "Cannot return inside a repeat loop."),
+    ("lint.undefined", "Definition not found"),
+    ("lint.var_required", "A variable or an array element is required."),
+    (
+        "lint.format_arg_count",
+        "The number of format specifiers ({}) does not match the number of arguments ({}).",
+    ),
+    ("lint.format_arg_type", "A string is passed to the format specifier {}."),
+    ("lint.too_many_args", "Too many arguments. (It takes {} parameters.)"),
+    (
+        "lint.name_casing",
+        "The letter case differs from the definition ({}).",
+    ),
+    (
+        "lint.requires_hsp_version",
+        "{} requires HSP {} or later. (The target is HSP {}.)",
+    ),
+    ("lint.division_by_zero", "Division by zero."),
+    ("lint.overflow", "The value is out of the integer range."),
+    ("lint.not_constant", "Not a constant expression."),
+    ("lint.malformed_number", "The number literal is malformed."),
+    ("lint.full_width_space", "Full-width space found."),
+    ("lint.bad_char", "Invalid character found."),
+    ("lint.not_sjis", "Character not representable in Shift_JIS."),
+    (
+        "lint.utf8_without_hsp3utf",
+        "This file is saved in UTF-8, but HSP expects Shift_JIS. (To use UTF-8, #include hsp3utf.as.)",
+    ),
+    (
+        "lint.sjis_with_hsp3utf",
+        "This file is saved in Shift_JIS, but hsp3utf expects UTF-8.",
+    ),
+    ("lint.hs_unclosed_html", "html{ has no matching }html."),
+    (
+        "lint.hs_missing_index_name",
+        "The line after %index has no keyword.",
+    ),
+    ("lint.hs_unknown_key", "Unknown key."),
+    (
+        "lint.hs_duplicate_index",
+        "An %index with the same keyword already exists.",
+    ),
+    // ホバー
+    ("hover.value", "Value: {}"),
+    ("hover.include_guard", "Include guard"),
+    // 名前の変更
+    ("rename.contains_at", "The new name cannot contain @."),
+    ("rename.collision", "The name {} is already in use."),
+    // Code actions
+    ("action.flip_comma", "Flip the sides of the comma"),
+    ("action.generate_include_guard", "Generate an include guard"),
+    (
+        "action.replace_full_width_space",
+        "Replace the full-width space with a space",
+    ),
+    ("action.remove_bad_char", "Remove the invalid character"),
+    (
+        "action.replace_not_sjis",
+        "Replace characters not representable in Shift_JIS with ?",
+    ),
+    (
+        "action.unify_name_casing",
+        "Match the letter case to the definition ({})",
+    ),
+    (
+        "action.unify_name_casing_all",
+        "Match the letter case to the definitions in the file",
+    ),
+    // Code lens
+    ("lens.run", "▶ Run"),
+    ("lens.references", "{} references"),
+    // Help source keys
+    ("hs_key.dll", "Plugin or module name"),
+    ("hs_key.ver", "Version"),
+    ("hs_key.date", "Date"),
+    ("hs_key.author", "Author"),
+    ("hs_key.url", "Related URL"),
+    ("hs_key.note", "Notes"),
+    ("hs_key.type", "Type"),
+    ("hs_key.group", "Group"),
+    ("hs_key.port", "Supported platforms"),
+    ("hs_key.portinfo", "Porting hints"),
+    ("hs_key.index", "Keyword and summary (start of an entry)"),
+    ("hs_key.prm", "Parameter list"),
+    ("hs_key.inst", "Description"),
+    ("hs_key.sample", "Sample script"),
+    ("hs_key.href", "Related entries"),
+    // Help source generation (gen-help)
+    ("gen_help.header", "Help source file for {}"),
+    ("gen_help.generated_by", "(Generated by `ham gen-help`)"),
+    ("gen_help.type", "User extension command"),
+    ("gen_help.note", "Include {} to use these."),
+    ("gen_help.missing_in_help", "{}: Not documented in the help source."),
+    (
+        "gen_help.param_mismatch",
+        "{}: Parameters do not match. (code: {}, help source: {})",
+    ),
+    ("gen_help.missing_in_code", "{}: Not defined in the code."),
+    ("gen_help.cannot_open", "{}: Cannot open the help source file."),
+    ("gen_help.check_stdin", "--check cannot be used with standard input"),
+    ("gen_help.generated", "Generated {}."),
+    // CLI
+    (
+        "cli.help",
+        r#"ham {version}
+
+    USAGE: ham [OPTIONS] [SUBCOMMAND]
+
+    EXAMPLE: ham --hsp "C:/hsp37" profile-parse
+
+    SUBCOMMANDS:
+        lsp
+            Start as an LSP server (communicates over stdin/stdout)
+            (Without the HSP install directory, the bundled builtin data is used)
+            See topics.md for environment variables
+
+        parse [FILES...]
+
+        profile-parse
+            (The HSP install directory is required)
+
+        gen-builtin
+            Generate the builtin data (ham-core/data/builtin.hs) from hsphelp and write it to stdout
+            (The HSP install directory is required)
+
+        format-comments [FILES]
+            (**CAUTION**: Files are overwritten. Make a backup first)
+            Convert // comments to ; comments in HSP scripts
+            and overwrite the input script files
+
+        gen-help [--check] [FILES]
+            Generate help source files (.hs) from module definitions
+            and documentation comments, next to the input files
+            (--check: Instead of generating, report differences between the code and existing .hs files)
+
+    OPTIONS:
+        -h, --help      Print help
+        -V, --version   Print Version
+            --hsp       Specify the HSP install directory
+            --lang      Specify the display language (ja, en)
+
+    ENV:
+        HSP3_ROOT       Specify the HSP install directory (lower priority than --hsp)
+"#,
+    ),
+    (
+        "cli.hsp3_root_missing",
+        r#"Specify the HSP install directory. (e.g. pass --hsp "C:/hsp37" on the command line, or set the path to the HSP3_ROOT environment variable)"#,
+    ),
+    ("cli.hsp3_root_not_found", "HSP3_ROOT directory not found"),
+    ("cli.no_input_files", "No input files given"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_exist_in_all_locales() {
+        for &(key, _) in JA {
+            assert!(EN.iter().any(|&(k, _)| k == key), "missing in en: {}", key);
+        }
+        for &(key, _) in EN {
+            assert!(JA.iter().any(|&(k, _)| k == key), "missing in ja: {}", key);
+        }
+    }
+
+    #[test]
+    fn test_placeholders_match() {
+        for &(key, ja) in JA {
+            let en = tr(Locale::En, key);
+            assert_eq!(
+                ja.matches("{}").count(),
+                en.matches("{}").count(),
+                "placeholders differ: {}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_tag() {
+        assert_eq!(Locale::from_tag("ja"), Locale::Ja);
+        assert_eq!(Locale::from_tag("ja-JP"), Locale::Ja);
+        assert_eq!(Locale::from_tag("en-US"), Locale::En);
+        assert_eq!(Locale::from_tag("zh-cn"), Locale::En);
+        assert_eq!(tr(Locale::En, "symbol.const"), "constant");
+    }
+}
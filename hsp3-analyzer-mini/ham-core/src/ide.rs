@@ -1,5 +1,6 @@
 //! IDE機能
 
+pub(crate) mod code_lens;
 pub(crate) mod completion;
 pub(crate) mod definitions;
 pub(crate) mod diagnose;
@@ -9,11 +10,13 @@ pub(crate) mod formatting;
 pub(crate) mod hover;
 pub(crate) mod references;
 pub(crate) mod rename;
+pub(crate) mod rename_files;
 pub(crate) mod semantic_tokens;
 pub(crate) mod signature_help;
 pub(crate) mod workspace_symbol;
 
 pub(crate) mod lsp {
+    pub(crate) mod convert_positions;
     pub(crate) mod from_proto;
     pub(crate) mod to_proto;
 }
@@ -23,6 +26,8 @@ pub(crate) mod code_actions {
 
     pub(crate) mod flip_comma;
     pub(crate) mod generate_include_guard;
+    pub(crate) mod replace_bad_chars;
+    pub(crate) mod unify_name_casing;
 }
 
 use super::*;
@@ -1,7 +1,7 @@
 //! カンマの両側を交換するアクション
 
 use super::*;
-use crate::parse::*;
+use crate::{i18n::tr, parse::*};
 use lsp_types::{
     CodeAction, DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier, Range,
     TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
@@ -72,7 +72,9 @@ pub(crate) fn flip_comma(
     let (doc, pos) = from_document_position(doc_interner, uri, range.start)?;
     let version = docs.get_version(doc);
 
-    let DocSyntax { text, tokens, root } = an.get_syntax(doc)?;
+    let DocSyntax {
+        text, tokens, root, ..
+    } = an.get_syntax(doc)?;
 
     // 補完位置に隣接しているカンマをみつける。
     let (comma_index, comma) = {
@@ -130,7 +132,7 @@ pub(crate) fn flip_comma(
     let r_text = &text[r_range.start().index as usize..r_range.end().index as usize];
 
     Some(vec![CodeAction {
-        title: "カンマの両側を交換".into(),
+        title: tr(an.locale(), "action.flip_comma").to_string(),
         kind: Some("refactor.rewrite".into()),
         edit: Some(WorkspaceEdit {
             document_changes: Some(DocumentChanges::Edits(vec![TextDocumentEdit {
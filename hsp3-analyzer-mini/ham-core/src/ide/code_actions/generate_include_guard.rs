@@ -1,6 +1,7 @@
 //! インクルードガードを生成するアクション
 
 use super::*;
+use crate::i18n::tr;
 use lsp_types::{
     CodeAction, DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier, Range,
     TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
@@ -41,7 +42,7 @@ pub(crate) fn generate_include_guard(
     );
 
     Some(vec![CodeAction {
-        title: "インクルードガードを生成する".into(),
+        title: tr(an.locale(), "action.generate_include_guard").to_string(),
         kind: Some("refactor.rewrite".into()),
         edit: Some(WorkspaceEdit {
             document_changes: Some(DocumentChanges::Edits(vec![TextDocumentEdit {
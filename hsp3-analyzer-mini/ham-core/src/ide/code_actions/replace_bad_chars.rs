@@ -0,0 +1,135 @@
+//! 全角空白や使用できない文字を置き換えるアクション
+
+use super::*;
+use crate::{i18n::tr, ide::diagnose::diagnose_token_lints, token::TokenLint};
+use lsp_types::{
+    CodeAction, CodeActionKind, DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier,
+    Range, TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
+};
+
+pub(crate) fn replace_bad_chars(
+    an: &AnalyzerRef<'_>,
+    doc_interner: &DocInterner,
+    docs: &Docs,
+    uri: &Url,
+    range: Range,
+) -> Option<Vec<CodeAction>> {
+    let (doc, pos) = from_document_position(doc_interner, uri, range.start)?;
+    let version = docs.get_version(doc);
+    let text = &an.doc_analysis_map.get(&doc)?.text;
+
+    let mut lints = vec![];
+    diagnose_token_lints(an, &mut lints);
+
+    let mut actions = vec![];
+    for (lint, loc) in lints {
+        if loc.doc != doc || !loc.is_touched(doc, pos) {
+            continue;
+        }
+
+        let old_text = &text[loc.range.start().index as usize..loc.range.end().index as usize];
+        let new_text = lint.fix(old_text);
+
+        let title_key = match lint {
+            TokenLint::FullWidthSpace => "action.replace_full_width_space",
+            TokenLint::BadChar => "action.remove_bad_char",
+            TokenLint::NotShiftJis => "action.replace_not_sjis",
+        };
+
+        actions.push(CodeAction {
+            title: tr(an.locale(), title_key).to_string(),
+            kind: Some(CodeActionKind::QUICKFIX),
+            edit: Some(WorkspaceEdit {
+                document_changes: Some(DocumentChanges::Edits(vec![TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier {
+                        uri: uri.clone(),
+                        version,
+                    },
+                    edits: vec![OneOf::Left(TextEdit {
+                        range: loc_to_range(loc),
+                        new_text,
+                    })],
+                }])),
+                ..WorkspaceEdit::default()
+            }),
+            ..Default::default()
+        });
+    }
+
+    if actions.is_empty() {
+        return None;
+    }
+    Some(actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{analyzer::Analyzer, lsp_server::NO_VERSION};
+    use expect_test::expect;
+    use lsp_types::{CodeActionContext, DocumentChanges, OneOf, Position, Range};
+    use std::fmt::Write as _;
+
+    fn dummy_url(s: &str) -> lsp_types::Url {
+        let workspace_dir = crate::test_utils::dummy_path().join("ws");
+        lsp_types::Url::from_file_path(workspace_dir.join(s)).unwrap()
+    }
+
+    /// 指定した位置で要求されたアクションのタイトルと編集を並べる
+    fn format_actions(text: &str, pos: Position) -> String {
+        let mut an = Analyzer::new_standalone();
+        let url = dummy_url("main.hsp");
+        an.open_doc(url.clone(), NO_VERSION, text.into());
+
+        let actions =
+            an.compute_ref()
+                .code_action(url, Range::new(pos, pos), CodeActionContext::default());
+
+        let mut formatted = String::new();
+        for action in actions {
+            let edits = match action.edit.and_then(|edit| edit.document_changes) {
+                Some(DocumentChanges::Edits(edits)) => edits,
+                _ => continue,
+            };
+            writeln!(formatted, "{}", action.title).unwrap();
+            for edit in edits.into_iter().flat_map(|edit| edit.edits) {
+                if let OneOf::Left(edit) = edit {
+                    let (start, end) = (edit.range.start, edit.range.end);
+                    writeln!(
+                        formatted,
+                        "  {}:{}-{}:{} {:?}",
+                        start.line + 1,
+                        start.character + 1,
+                        end.line + 1,
+                        end.character + 1,
+                        edit.new_text
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        formatted
+    }
+
+    #[test]
+    fn test_replace_bad_chars() {
+        let text = "\tmes \"ok\"\u{3000}\u{3000}: mes 1\n\tx = 1 ~ 2\n\tmes \"🐧🐧\"\n";
+
+        expect![[r#"
+            全角空白を半角空白に置き換える
+              1:10-1:12 "  "
+        "#]]
+        .assert_eq(&format_actions(text, Position::new(0, 10)));
+        expect![[r#"
+            使用できない文字を削除する
+              2:8-2:9 ""
+        "#]]
+        .assert_eq(&format_actions(text, Position::new(1, 7)));
+
+        // shift_jis で表現できない文字は、文字ごとに ? にする。
+        expect![[r#"
+            shift_jis で表現できない文字を ? に置き換える
+              3:7-3:11 "??"
+        "#]]
+        .assert_eq(&format_actions(text, Position::new(2, 7)));
+    }
+}
@@ -0,0 +1,203 @@
+//! 名前の大文字・小文字の書きかたを定義に揃えるアクション
+
+use super::*;
+use crate::{
+    i18n::{tr, tr_fmt},
+    ide::diagnose::diagnose_name_casing,
+};
+use lsp_types::{
+    CodeAction, CodeActionContext, CodeActionKind, DocumentChanges, OneOf,
+    OptionalVersionedTextDocumentIdentifier, Range, TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
+};
+
+fn new_action(
+    title: String,
+    kind: CodeActionKind,
+    uri: &Url,
+    version: Option<i32>,
+    edits: Vec<TextEdit>,
+) -> CodeAction {
+    CodeAction {
+        title,
+        kind: Some(kind),
+        edit: Some(WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Edits(vec![TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri: uri.clone(),
+                    version,
+                },
+                edits: edits.into_iter().map(OneOf::Left).collect(),
+            }])),
+            ..WorkspaceEdit::default()
+        }),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn unify_name_casing(
+    an: &AnalyzerRef<'_>,
+    doc_interner: &DocInterner,
+    docs: &Docs,
+    uri: &Url,
+    range: Range,
+    context: &CodeActionContext,
+) -> Option<Vec<CodeAction>> {
+    let (doc, pos) = from_document_position(doc_interner, uri, range.start)?;
+    let version = docs.get_version(doc);
+
+    let mut lints = vec![];
+    diagnose_name_casing(an, &mut lints);
+    lints.retain(|(_, _, loc)| loc.doc == doc);
+    if lints.is_empty() {
+        return None;
+    }
+
+    // 要求された種類 (`only`) に含まれるか？ (指定がなければすべて含まれる)
+    let is_requested = |kind: &CodeActionKind| match &context.only {
+        Some(only) => only
+            .iter()
+            .any(|requested| kind.as_str().starts_with(requested.as_str())),
+        None => true,
+    };
+
+    // 保存時などに一括修正 (source.fixAll) を要求されたか？
+    let fix_all_requested = context.only.is_some() && is_requested(&CodeActionKind::SOURCE_FIX_ALL);
+
+    let mut actions = vec![];
+
+    let touched = lints.iter().find(|(_, _, loc)| loc.is_touched(doc, pos));
+    if touched.is_none() && !fix_all_requested {
+        return None;
+    }
+
+    if let Some((name, new_text, loc)) = touched {
+        if is_requested(&CodeActionKind::QUICKFIX) {
+            actions.push(new_action(
+                tr_fmt(an.locale(), "action.unify_name_casing", &[name]),
+                CodeActionKind::QUICKFIX,
+                uri,
+                version,
+                vec![TextEdit {
+                    range: loc_to_range(*loc),
+                    new_text: new_text.clone(),
+                }],
+            ));
+        }
+    }
+
+    if is_requested(&CodeActionKind::SOURCE_FIX_ALL) {
+        actions.push(new_action(
+            tr(an.locale(), "action.unify_name_casing_all").to_string(),
+            CodeActionKind::SOURCE_FIX_ALL,
+            uri,
+            version,
+            lints
+                .into_iter()
+                .map(|(_, new_text, loc)| TextEdit {
+                    range: loc_to_range(loc),
+                    new_text,
+                })
+                .collect(),
+        ));
+    }
+
+    if actions.is_empty() {
+        return None;
+    }
+    Some(actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{analyzer::Analyzer, lsp_server::NO_VERSION};
+    use expect_test::expect;
+    use lsp_types::{CodeActionContext, CodeActionKind, Position, Range};
+    use std::fmt::Write as _;
+
+    fn dummy_url(s: &str) -> lsp_types::Url {
+        let workspace_dir = crate::test_utils::dummy_path().join("ws");
+        lsp_types::Url::from_file_path(workspace_dir.join(s)).unwrap()
+    }
+
+    /// 指定した位置で要求されたアクションの種類とタイトルを並べる
+    fn format_actions(text: &str, pos: Position, only: Option<Vec<CodeActionKind>>) -> String {
+        let mut an = Analyzer::new_standalone();
+        let url = dummy_url("main.hsp");
+        an.open_doc(url.clone(), NO_VERSION, text.into());
+
+        let context = CodeActionContext {
+            only,
+            ..CodeActionContext::default()
+        };
+        let actions = an
+            .compute_ref()
+            .code_action(url, Range::new(pos, pos), context);
+
+        let mut formatted = String::new();
+        for action in actions {
+            if !action.title.contains("大文字・小文字") {
+                continue;
+            }
+            writeln!(
+                formatted,
+                "{} {}",
+                action.kind.unwrap().as_str(),
+                action.title
+            )
+            .unwrap();
+        }
+        formatted
+    }
+
+    #[test]
+    fn test_only() {
+        let text = "    x = 1\n    X += 1\n";
+        let pos = Position::new(1, 4);
+
+        expect![[r#"
+            quickfix 大文字・小文字を定義に揃える (x)
+            source.fixAll ファイル内の大文字・小文字をすべて定義に揃える
+        "#]]
+        .assert_eq(&format_actions(text, pos, None));
+
+        // 要求されていない種類のアクションは返さない。
+        expect![[r#"
+            quickfix 大文字・小文字を定義に揃える (x)
+        "#]]
+        .assert_eq(&format_actions(
+            text,
+            pos,
+            Some(vec![CodeActionKind::QUICKFIX]),
+        ));
+        expect![[r#"
+            source.fixAll ファイル内の大文字・小文字をすべて定義に揃える
+        "#]]
+        .assert_eq(&format_actions(
+            text,
+            pos,
+            Some(vec![CodeActionKind::SOURCE]),
+        ));
+        expect![[r#""#]].assert_eq(&format_actions(
+            text,
+            pos,
+            Some(vec![CodeActionKind::REFACTOR]),
+        ));
+    }
+
+    #[test]
+    fn test_module_qualifier() {
+        let text = r#"#module m_Foo
+#deffunc local myFunc
+    return
+#global
+    myFunc@m_foo
+"#;
+
+        // 修飾子の書きかたもモジュールの定義に揃える。
+        expect![[r#"
+            quickfix 大文字・小文字を定義に揃える (myFunc@m_Foo)
+            source.fixAll ファイル内の大文字・小文字をすべて定義に揃える
+        "#]]
+        .assert_eq(&format_actions(text, Position::new(4, 4), None));
+    }
+}
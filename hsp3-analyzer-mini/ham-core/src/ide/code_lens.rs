@@ -0,0 +1,241 @@
+//! コードレンズ (参照の件数や、スクリプトの実行ボタンの表示)
+
+use super::*;
+use crate::i18n::{tr, tr_fmt};
+use lsp_types::{CodeLens, Command};
+use serde::{Deserialize, Serialize};
+
+/// スクリプトを実行するコマンド (`workspace/executeCommand`)
+///
+/// (コンパイルと実行はクライアント側 (VSCode拡張機能) で行う。引数はスクリプトのURI)
+pub(crate) const RUN_SCRIPT_COMMAND: &str = "hsp3-analyzer-mini.runScript";
+
+/// 参照を表示するコマンド (クライアント側のコマンド)
+///
+/// 引数は URI, 位置, 参照箇所のリスト
+pub(crate) const SHOW_REFERENCES_COMMAND: &str = "hsp3-analyzer-mini.showReferences";
+
+#[derive(Serialize, Deserialize)]
+struct CodeLensData {
+    // 参照の件数を計算するシンボルの定義箇所:
+    uri: Url,
+    position: Position,
+}
+
+/// 参照の件数を表示する対象のシンボルか？
+fn is_lens_target(kind: HspSymbolKind) -> bool {
+    matches!(
+        kind,
+        HspSymbolKind::Label
+            | HspSymbolKind::Module
+            | HspSymbolKind::DefFunc
+            | HspSymbolKind::DefCFunc
+            | HspSymbolKind::ModFunc
+            | HspSymbolKind::ModCFunc
+    )
+}
+
+/// スクリプトが **エントリーポイント** (直接実行されるもの) であるとみなせるか？
+///
+/// アクティブであり、commonのファイルではなく、ほかのファイルから `#include` されていないものをエントリーポイントとみなす。
+/// (インクルードガードがあるものはモジュールとみなして除く。)
+fn is_entry_point(an: &AnalyzerRef<'_>, doc: DocId) -> bool {
+    an.is_active_doc(doc)
+        && !an.is_common_doc(doc)
+        && !an.has_include_guard(doc)
+        && !an
+            .include_resolution
+            .iter()
+            .any(|&(loc, dest_doc)| dest_doc == doc && loc.doc != doc)
+}
+
+pub(crate) fn code_lens(
+    an: &AnalyzerRef<'_>,
+    doc_interner: &DocInterner,
+    uri: Url,
+) -> Option<Vec<CodeLens>> {
+    let doc = doc_interner.get_doc(&CanonicalUri::from_url(&uri))?;
+
+    let mut lenses = vec![];
+
+    if is_entry_point(an, doc) {
+        lenses.push(CodeLens {
+            range: lsp_types::Range::default(),
+            command: Some(Command {
+                title: tr(an.locale(), "lens.run").to_string(),
+                command: RUN_SCRIPT_COMMAND.to_string(),
+                arguments: Some(vec![serde_json::to_value(&uri).unwrap()]),
+            }),
+            data: None,
+        });
+    }
+
+    let mut symbols = vec![];
+    collect_doc_symbols(an, doc, &mut symbols);
+    symbols.retain(|(s, _)| is_lens_target(s.kind) && !s.name().is_empty());
+    symbols.sort_by_key(|(_, loc)| loc.start());
+
+    // 参照の件数は resolve で計算する。
+    for (_, loc) in symbols {
+        let range = loc_to_range(loc);
+        let data = CodeLensData {
+            uri: uri.clone(),
+            position: range.start,
+        };
+        lenses.push(CodeLens {
+            range,
+            command: None,
+            data: Some(serde_json::to_value(&data).unwrap()),
+        });
+    }
+
+    Some(lenses)
+}
+
+pub(crate) fn code_lens_resolve(
+    an: &AnalyzerRef<'_>,
+    doc_interner: &DocInterner,
+    mut lens: CodeLens,
+) -> CodeLens {
+    let data: CodeLensData = match lens
+        .data
+        .take()
+        .and_then(|data| serde_json::from_value(data).ok())
+    {
+        Some(it) => it,
+        None => return lens,
+    };
+
+    let locations = (|| {
+        let (doc, pos) = from_document_position(doc_interner, &data.uri, data.position)?;
+        let (symbol, _) = an.locate_symbol(doc, pos)?;
+
+        let mut locs = vec![];
+        collect_symbol_occurrences(
+            an,
+            CollectSymbolOptions {
+                include_def: false,
+                include_use: true,
+            },
+            &symbol,
+            &mut locs,
+        );
+        locs.sort();
+        locs.dedup();
+
+        Some(
+            locs.into_iter()
+                .filter_map(|loc| loc_to_location(doc_interner, loc))
+                .collect::<Vec<_>>(),
+        )
+    })()
+    .unwrap_or_default();
+
+    lens.command = Some(Command {
+        title: tr_fmt(an.locale(), "lens.references", &[&locations.len()]),
+        command: SHOW_REFERENCES_COMMAND.to_string(),
+        arguments: Some(vec![
+            serde_json::to_value(&data.uri).unwrap(),
+            serde_json::to_value(data.position).unwrap(),
+            serde_json::to_value(&locations).unwrap(),
+        ]),
+    });
+    lens
+}
+
+// ===============================================
+
+#[cfg(test)]
+mod tests {
+    use crate::{analyzer::Analyzer, i18n::Locale, lsp_server::NO_VERSION};
+    use expect_test::expect;
+    use std::fmt::Write as _;
+
+    fn dummy_url(s: &str) -> lsp_types::Url {
+        let workspace_dir = crate::test_utils::dummy_path().join("ws");
+        lsp_types::Url::from_file_path(workspace_dir.join(s)).unwrap()
+    }
+
+    #[test]
+    fn test_code_lens() {
+        let mut an = Analyzer::new_standalone();
+
+        let main_url = dummy_url("main.hsp");
+        an.open_doc(
+            main_url.clone(),
+            NO_VERSION,
+            r#"#include "mod_a.hsp"
+*l_main
+    a_hello
+    a_hello
+    goto *l_main
+"#
+            .into(),
+        );
+
+        let mod_url = dummy_url("mod_a.hsp");
+        an.open_doc(
+            mod_url.clone(),
+            NO_VERSION,
+            r#"#module m_a
+#deffunc a_hello
+    return
+#global
+"#
+            .into(),
+        );
+
+        let an = an.compute_ref();
+
+        let mut formatted = String::new();
+        for url in [main_url, mod_url] {
+            for lens in an.code_lens(url.clone()) {
+                let lens = an.code_lens_resolve(lens);
+                let command = lens.command.unwrap();
+                writeln!(
+                    formatted,
+                    "{} {}:{} {:?} {}",
+                    url.path_segments().unwrap().next_back().unwrap(),
+                    lens.range.start.line + 1,
+                    lens.range.start.character + 1,
+                    command.title,
+                    command.command
+                )
+                .unwrap();
+            }
+        }
+
+        expect![[r#"
+            main.hsp 1:1 "▶ 実行" hsp3-analyzer-mini.runScript
+            main.hsp 2:1 "1 件の参照" hsp3-analyzer-mini.showReferences
+            mod_a.hsp 1:9 "0 件の参照" hsp3-analyzer-mini.showReferences
+            mod_a.hsp 2:10 "2 件の参照" hsp3-analyzer-mini.showReferences
+        "#]]
+        .assert_eq(&formatted);
+    }
+
+    #[test]
+    fn test_locale_per_analyzer() {
+        // 表示言語は解析器ごとに持つ。
+        let titles = |locale_opt: Option<Locale>| {
+            let mut an = Analyzer::new_standalone();
+            if let Some(locale) = locale_opt {
+                an.set_locale(locale);
+            }
+
+            let url = dummy_url("main.hsp");
+            an.open_doc(url.clone(), NO_VERSION, "mes 1\n".into());
+
+            let an = an.compute_ref();
+            an.code_lens(url)
+                .into_iter()
+                .map(|lens| lens.command.unwrap().title)
+                .collect::<Vec<_>>()
+        };
+
+        let en = titles(Some(Locale::En));
+        let ja = titles(None);
+        assert_eq!(en, vec!["▶ Run".to_string()]);
+        assert_eq!(ja, vec!["▶ 実行".to_string()]);
+    }
+}
@@ -3,6 +3,8 @@
 use super::*;
 use crate::{
     analysis::{HspSymbolKind, Scope, SymbolRc},
+    help_source::HS_KEYS,
+    i18n::{tr, Locale},
     ide::from_document_position,
     parse::p_param_ty::PParamCategory,
 };
@@ -14,18 +16,58 @@ use std::collections::HashSet;
 /// `hsphelp` を参照して入力補完候補を列挙する (プリプロセッサ関連は除く)
 fn collect_hsphelp_completion_items(
     an: &AnalyzerRef<'_>,
+    doc: DocId,
     completion_items: &mut Vec<lsp_types::CompletionItem>,
 ) {
     completion_items.extend(
-        an.hsphelp_info()
-            .doc_symbols
-            .iter()
-            .filter(|(&doc, _)| an.is_active_help_doc(doc))
-            .flat_map(|(_, symbols)| symbols.iter().filter(|s| !s.label.starts_with("#")))
+        an.hsphelp_info_of(doc)
+            .completion_items(|doc| an.is_active_help_doc(doc))
+            .filter(|s| !s.label.starts_with("#"))
             .cloned(),
     );
 }
 
+/// ヘルプファイルの `%` で始まるキーを入力補完候補として列挙する
+///
+/// (行頭に `%` とキーの一部が書かれている位置でのみ列挙する)
+fn collect_help_source_key_items(
+    text: &str,
+    pos: Pos16,
+    locale: Locale,
+    completion_items: &mut Vec<lsp_types::CompletionItem>,
+) {
+    let line = match text.lines().nth(pos.row as usize) {
+        Some(it) => it,
+        None => return,
+    };
+
+    // カーソルより前の部分
+    let mut column = 0;
+    let head = line
+        .chars()
+        .take_while(|c| {
+            column += c.len_utf16() as u32;
+            column <= pos.column
+        })
+        .collect::<String>();
+
+    match head.strip_prefix('%') {
+        Some(key) if key.chars().all(|c| c.is_ascii_alphabetic()) => {}
+        _ => return,
+    }
+
+    for &(key, detail_key) in HS_KEYS {
+        completion_items.push(CompletionItem {
+            kind: Some(CompletionItemKind::KEYWORD),
+            label: format!("%{}", key),
+            detail: Some(tr(locale, detail_key).to_string()),
+            filter_text: Some(key.to_string()),
+            insert_text: Some(key.to_string()),
+            ..Default::default()
+        });
+    }
+}
+
 fn to_completion_symbol_kind(kind: HspSymbolKind) -> CompletionItemKind {
     // textDocument/documentSymbol, workspace/symbol も参照
     use CompletionItemKind as K;
@@ -121,12 +163,17 @@ fn do_completion(
 
     let (doc, pos) = from_document_position(doc_interner, uri, position)?;
 
+    if let Some(text) = an.help_text(doc) {
+        collect_help_source_key_items(text, pos, an.locale(), &mut items);
+        return Some(new_completion_list(items));
+    }
+
     if an.in_str_or_comment(doc, pos).unwrap_or(true) {
         return None;
     }
 
     if an.in_preproc(doc, pos).unwrap_or(false) {
-        collect_preproc_completion_items(an, &mut items);
+        collect_preproc_completion_items(an, doc, &mut items);
         return Some(new_completion_list(items));
     }
 
@@ -144,7 +191,7 @@ fn do_completion(
         }
     }
 
-    collect_hsphelp_completion_items(an, &mut items);
+    collect_hsphelp_completion_items(an, doc, &mut items);
 
     // HACK: 不要な候補を削除する。(__hspdef__ はスクリプトの記述的にインクルードガードとみなされないので有効なシンボルとして登録されてしまう。)
     if let Some(i) = items.iter().position(|item| item.label == "__hspdef__") {
@@ -309,4 +356,42 @@ mod tests {
         "#]]
         .assert_eq(&formatted);
     }
+
+    #[test]
+    fn test_help_source_keys() {
+        let mut an = Analyzer::new_standalone();
+
+        let hs_url = dummy_url("mod_foo.hs");
+        an.open_doc(hs_url.clone(), NO_VERSION, "%index\nfoo\n%in\n".into());
+
+        let mut formatted = String::new();
+        // `%in<|>`
+        let res = an
+            .compute_ref()
+            .completion(hs_url.clone(), Position::new(2, 3));
+        format_response(&mut formatted, res);
+
+        expect![[r#"
+            %dll Keyword filter:"dll"
+            %ver Keyword filter:"ver"
+            %date Keyword filter:"date"
+            %author Keyword filter:"author"
+            %url Keyword filter:"url"
+            %note Keyword filter:"note"
+            %type Keyword filter:"type"
+            %group Keyword filter:"group"
+            %port Keyword filter:"port"
+            %portinfo Keyword filter:"portinfo"
+            %index Keyword filter:"index"
+            %prm Keyword filter:"prm"
+            %inst Keyword filter:"inst"
+            %sample Keyword filter:"sample"
+            %href Keyword filter:"href"
+        "#]]
+        .assert_eq(&formatted);
+
+        // キーワードの行では列挙しない
+        let res = an.compute_ref().completion(hs_url, Position::new(1, 3));
+        assert_eq!(res.items.len(), 0);
+    }
 }
@@ -1,7 +1,7 @@
-//! 定義へ移動
+//! 定義・宣言・型定義・実装へ移動
 
 use super::*;
-use crate::ide::from_document_position;
+use crate::{ide::from_document_position, parse::*};
 use lsp_types::{Location, Position, Url};
 
 // (順不同、重複あり)
@@ -51,15 +51,302 @@ pub(crate) fn definitions(
         return None;
     }
 
+    Some(to_locations(doc_interner, locs))
+}
+
+fn to_locations(doc_interner: &DocInterner, mut locs: Vec<Loc>) -> Vec<Location> {
     // ソートして重複を取り除く
     locs.sort();
     locs.dedup();
 
-    Some(
-        locs.into_iter()
-            .filter_map(|loc| loc_to_location(doc_interner, loc))
-            .collect(),
-    )
+    locs.into_iter()
+        .filter_map(|loc| loc_to_location(doc_interner, loc))
+        .collect()
+}
+
+fn doc_root<'a>(an: &AnalyzerRef<'a>, doc: DocId) -> Option<&'a PRoot> {
+    an.doc_analysis_map.get(&doc)?.tree_opt.as_ref()
+}
+
+// -----------------------------------------------
+// 宣言
+// -----------------------------------------------
+
+/// `#uselib`, `#func` などの文の位置を出現順に集めるビジター
+#[derive(Default)]
+struct LibStmtCollector {
+    // (`#uselib` または `#usecom` なら true, 位置)
+    stmts: Vec<(bool, Loc)>,
+}
+
+impl PVisitor for LibStmtCollector {
+    fn on_stmt(&mut self, stmt: &PStmt) {
+        match stmt {
+            PStmt::UseLib(PUseLibStmt { hash, keyword, .. })
+            | PStmt::UseCom(PUseComStmt { hash, keyword, .. }) => {
+                self.stmts
+                    .push((true, hash.body.loc.unite(&keyword.body.loc)));
+            }
+            PStmt::LibFunc(PLibFuncStmt {
+                name_opt: Some(name),
+                ..
+            })
+            | PStmt::ComFunc(PComFuncStmt {
+                name_opt: Some(name),
+                ..
+            }) => {
+                self.stmts.push((false, name.body.loc));
+            }
+            _ => {}
+        }
+        self.on_stmt_default(stmt);
+    }
+}
+
+/// `#func` から `#uselib` へ、`#comfunc` から `#usecom` へ移動する
+fn goto_lib_declaration(
+    an: &AnalyzerRef<'_>,
+    doc: DocId,
+    pos: Pos16,
+    locs: &mut Vec<Loc>,
+) -> Option<()> {
+    let (symbol, _) = an.locate_symbol(doc, pos)?;
+    if !matches!(symbol.kind, HspSymbolKind::LibFunc | HspSymbolKind::ComFunc) {
+        return None;
+    }
+
+    let def_loc = symbol.preproc_def_site_opt?;
+    let mut v = LibStmtCollector::default();
+    v.on_root(doc_root(an, def_loc.doc)?);
+
+    // 定義の直前にある `#uselib` (`#usecom`) が宣言にあたる。
+    let index = v.stmts.iter().position(|&(_, loc)| loc == def_loc)?;
+    let (_, loc) = v.stmts[..index]
+        .iter()
+        .rev()
+        .find(|&&(is_header, _)| is_header)?;
+    locs.push(*loc);
+    Some(())
+}
+
+pub(crate) fn declarations(
+    an: &AnalyzerRef<'_>,
+    doc_interner: &DocInterner,
+    uri: Url,
+    position: Position,
+) -> Option<Vec<Location>> {
+    let (doc, pos) = from_document_position(doc_interner, &uri, position)?;
+    let mut locs = vec![];
+
+    // 宣言と定義の区別がないものは、定義へ移動する。
+    let ok = goto_lib_declaration(an, doc, pos, &mut locs).is_some()
+        || goto_symbol_definition(an, doc, pos, &mut locs).is_some()
+        || goto_include_target(an, doc, pos, &mut locs).is_some();
+    if !ok {
+        return None;
+    }
+
+    Some(to_locations(doc_interner, locs))
+}
+
+// -----------------------------------------------
+// 型定義
+// -----------------------------------------------
+
+/// モジュールの名前からモジュールの定義箇所を探す
+fn find_module_def_sites(an: &AnalyzerRef<'_>, name: &str, locs: &mut Vec<Loc>) {
+    for (&doc, da) in an.doc_analysis_map.iter() {
+        if !an.is_active_doc(doc) {
+            continue;
+        }
+
+        for module in da.module_map.values() {
+            if matches!(&module.name_opt, Some(it) if it.eq_ignore_ascii_case(name)) {
+                locs.push(module_def_site(an, module));
+            }
+        }
+    }
+}
+
+/// モジュールの定義箇所 (`#module` の名前の位置。名前がなければ `#module` の位置)
+fn module_def_site(an: &AnalyzerRef<'_>, module: &ModuleData) -> Loc {
+    an.def_sites
+        .iter()
+        .find(|(symbol, loc)| {
+            symbol.kind == HspSymbolKind::Module
+                && loc.doc == module.content_loc.doc
+                && module.content_loc.range.contains_inclusive(loc.start())
+        })
+        .map_or(module.content_loc.ahead(), |&(_, loc)| loc)
+}
+
+/// `newmod` 命令の引数からモジュールの名前を取り出す
+fn newmod_module_name(arg: &PArg) -> Option<RcStr> {
+    match arg.expr_opt.as_ref()? {
+        PExpr::Compound(PCompound::Name(name)) => Some(NamePath::new(&name.body.text).base),
+        PExpr::Literal(token) => module_name_as_ident(&token.body),
+        _ => None,
+    }
+}
+
+/// `newmod` 命令を集めるビジター
+struct NewmodCollector<'a> {
+    var_locs: &'a [Loc],
+    module_names: Vec<RcStr>,
+}
+
+impl PVisitor for NewmodCollector<'_> {
+    fn on_stmt(&mut self, stmt: &PStmt) {
+        if let PStmt::Command(PCommandStmt { command, args, .. }) = stmt {
+            if command.body_text().eq_ignore_ascii_case("newmod") && args.len() >= 2 {
+                let is_target = match &args[0].expr_opt {
+                    Some(PExpr::Compound(compound)) => {
+                        self.var_locs.contains(&compound.name().body.loc)
+                    }
+                    _ => false,
+                };
+                if is_target {
+                    self.module_names.extend(newmod_module_name(&args[1]));
+                }
+            }
+        }
+        self.on_stmt_default(stmt);
+    }
+}
+
+/// モジュール型の変数から、そのモジュールの定義へ移動する
+///
+/// - `newmod` 命令でインスタンスが作られている変数なら、`newmod` に指定されたモジュール
+/// - `modvar` パラメータや `thismod` なら、それが属するモジュール
+fn goto_module_of_var(
+    an: &AnalyzerRef<'_>,
+    doc: DocId,
+    pos: Pos16,
+    locs: &mut Vec<Loc>,
+) -> Option<()> {
+    let (symbol, _) = an.locate_symbol(doc, pos)?;
+    let len = locs.len();
+
+    match symbol.kind {
+        HspSymbolKind::Param(Some(PParamTy::Modvar)) => {
+            if let Some(Scope::Local(LocalScope {
+                module_opt: Some(m),
+                ..
+            })) = &symbol.scope_opt
+            {
+                let module = an.doc_analysis_map.get(&m.doc)?.module_map.get(m)?;
+                locs.push(module_def_site(an, module));
+            }
+        }
+        HspSymbolKind::StaticVar | HspSymbolKind::Field => {
+            let mut var_locs = vec![];
+            collect_symbol_occurrences(
+                an,
+                CollectSymbolOptions {
+                    include_def: true,
+                    include_use: true,
+                },
+                &symbol,
+                &mut var_locs,
+            );
+
+            let mut v = NewmodCollector {
+                var_locs: &var_locs,
+                module_names: vec![],
+            };
+            for (&doc, da) in an.doc_analysis_map.iter() {
+                if let (true, Some(root)) = (an.is_active_doc(doc), &da.tree_opt) {
+                    v.on_root(root);
+                }
+            }
+
+            v.module_names.sort();
+            v.module_names.dedup();
+            for name in v.module_names {
+                find_module_def_sites(an, &name, locs);
+            }
+        }
+        _ => {}
+    }
+
+    if locs.len() == len {
+        return None;
+    }
+    Some(())
+}
+
+pub(crate) fn type_definitions(
+    an: &AnalyzerRef<'_>,
+    doc_interner: &DocInterner,
+    uri: Url,
+    position: Position,
+) -> Option<Vec<Location>> {
+    let (doc, pos) = from_document_position(doc_interner, &uri, position)?;
+    let mut locs = vec![];
+    goto_module_of_var(an, doc, pos, &mut locs)?;
+    Some(to_locations(doc_interner, locs))
+}
+
+// -----------------------------------------------
+// 実装
+// -----------------------------------------------
+
+/// モジュールから、その `#modinit`, `#modterm` へ移動する
+fn goto_module_constructors(
+    an: &AnalyzerRef<'_>,
+    doc: DocId,
+    pos: Pos16,
+    locs: &mut Vec<Loc>,
+) -> Option<()> {
+    let (symbol, _) = an.locate_symbol(doc, pos)?;
+    if symbol.kind != HspSymbolKind::Module {
+        return None;
+    }
+
+    let def_loc = symbol.preproc_def_site_opt?;
+    let content_loc = an
+        .doc_analysis_map
+        .get(&def_loc.doc)?
+        .module_map
+        .values()
+        .map(|module| module.content_loc)
+        .find(|loc| loc.range.contains_inclusive(def_loc.start()))?;
+
+    let stmts = doc_root(an, def_loc.doc)?
+        .stmts
+        .iter()
+        .find_map(|stmt| match stmt {
+            PStmt::Module(stmt) if stmt.hash.body.loc.start() == content_loc.start() => {
+                Some(&stmt.stmts)
+            }
+            _ => None,
+        })?;
+
+    let len = locs.len();
+    for stmt in stmts {
+        if let PStmt::DefFunc(stmt) = stmt {
+            if stmt.kind.is_anonymous() {
+                locs.push(stmt.hash.body.loc.unite(&stmt.keyword.body.loc));
+            }
+        }
+    }
+
+    if locs.len() == len {
+        return None;
+    }
+    Some(())
+}
+
+pub(crate) fn implementations(
+    an: &AnalyzerRef<'_>,
+    doc_interner: &DocInterner,
+    uri: Url,
+    position: Position,
+) -> Option<Vec<Location>> {
+    let (doc, pos) = from_document_position(doc_interner, &uri, position)?;
+    let mut locs = vec![];
+    goto_module_constructors(an, doc, pos, &mut locs)?;
+    Some(to_locations(doc_interner, locs))
 }
 
 // ===============================================
@@ -145,20 +432,22 @@ mod tests {
         let kernel32 = dummy_common_url("kernel32.as");
         an.open_doc(kernel32.clone(), NO_VERSION, "".into());
 
-        an.common_docs.insert(
-            "kernel32.as".to_string(),
-            an.find_doc_by_uri(&CanonicalUri::from_url(&kernel32))
-                .unwrap(),
-        );
+        let kernel32_doc = an
+            .find_doc_by_uri(&CanonicalUri::from_url(&kernel32))
+            .unwrap();
+        an.hsp3_roots[0]
+            .common_docs
+            .insert("kernel32.as".to_string(), kernel32_doc);
 
         let d3m = dummy_common_url("d3m.hsp");
         an.open_doc(d3m, NO_VERSION, "".into());
 
-        an.common_docs.insert(
-            "d3m.hsp".to_string(),
-            an.find_doc_by_uri(&CanonicalUri::from_url(&kernel32))
-                .unwrap(),
-        );
+        let d3m_doc = an
+            .find_doc_by_uri(&CanonicalUri::from_url(&kernel32))
+            .unwrap();
+        an.hsp3_roots[0]
+            .common_docs
+            .insert("d3m.hsp".to_string(), d3m_doc);
 
         let main_url = dummy_url("main.hsp");
         an.open_doc(
@@ -207,4 +496,139 @@ mod tests {
         "#]]
         .assert_eq(&formatted);
     }
+
+    #[test]
+    fn test_declaration_type_definition_implementation() {
+        let mut an = Analyzer::new_standalone();
+
+        let main_url = dummy_url("main.hsp");
+        an.open_doc(
+            main_url.clone(),
+            NO_VERSION,
+            r#"#uselib "user32.dll"
+#func MessageBoxA "MessageBoxA" int, sptr, sptr, int
+
+#module m_point x
+#modinit int a
+    x = a
+    return
+#modfunc point_print
+    mes x
+    return
+#modterm
+    return
+#global
+
+    newmod p, m_point, 1
+    MessageBoxA 0, "", "", 0
+"#
+            .into(),
+        );
+        let an = an.compute_ref();
+
+        let mut formatted = String::new();
+
+        formatted += "[declaration MessageBoxA]\n";
+        format_response(
+            &mut formatted,
+            &an.declarations(main_url.clone(), lsp_types::Position::new(15, 5)),
+        );
+
+        formatted += "[typeDefinition p]\n";
+        format_response(
+            &mut formatted,
+            &an.type_definitions(main_url.clone(), lsp_types::Position::new(14, 11)),
+        );
+
+        formatted += "[implementation m_point]\n";
+        format_response(
+            &mut formatted,
+            &an.implementations(main_url, lsp_types::Position::new(14, 15)),
+        );
+
+        expect![[r#"
+            [declaration MessageBoxA]
+            1:1
+            [typeDefinition p]
+            4:9
+            [implementation m_point]
+            5:1
+            11:1
+        "#]]
+        .assert_eq(&formatted);
+    }
+
+    #[test]
+    fn test_common_restored_from_cache() {
+        use crate::analyzer::{index_cache::IndexCache, vfs::MemoryFs};
+        use std::rc::Rc;
+
+        set_test_logger();
+        let cache_path = std::env::temp_dir()
+            .join(format!("ham-definitions-cache-test-{}", std::process::id()))
+            .join("index.json");
+        let hsp3_root = crate::test_utils::dummy_path().join("hsp3");
+        let vfs = Rc::new(MemoryFs::default());
+        vfs.write(
+            hsp3_root.join("common/m.as"),
+            ";; 挨拶する\n#deffunc hello\n\treturn\n",
+        );
+        vfs.write(
+            hsp3_root.join("common/unused.as"),
+            "#deffunc unused\n\treturn\n",
+        );
+
+        // (ファイルの最終更新日時が一致するように、同じファイルシステムを使う)
+        let new_analyzer = || {
+            let mut an = Analyzer::with_vfs(Some(hsp3_root.clone()), vfs.clone());
+            an.hsp3_roots[0].index_cache =
+                IndexCache::load_from(&hsp3_root, Some(cache_path.clone()));
+            an.did_initialize();
+            an
+        };
+
+        // 1回目は common のファイルを読み込んで、解析結果をキャッシュに保存する。
+        let mut an = new_analyzer();
+        let loaded = an
+            .compute_ref()
+            .doc_analysis_map
+            .values()
+            .filter(|da| da.tree_opt.is_some())
+            .count();
+        assert_eq!(loaded, 2);
+        drop(an);
+
+        // 2回目はキャッシュから復元する。(ファイルは読み込まない)
+        let mut an = new_analyzer();
+        let main_url = dummy_url("main.hsp");
+        an.open_doc(
+            main_url.clone(),
+            NO_VERSION,
+            "#include \"m.as\"\n\thello\n".into(),
+        );
+        let an = an.compute_ref();
+
+        let mut formatted = String::new();
+        format_response(
+            &mut formatted,
+            &an.definitions(main_url.clone(), lsp_types::Position::new(1, 2)),
+        );
+        let loaded = an
+            .doc_analysis_map
+            .values()
+            .filter(|da| da.tree_opt.is_some())
+            .count();
+        write!(formatted, "loaded={}", loaded).unwrap();
+
+        expect![[r#"
+            2:10
+            loaded=1"#]]
+        .assert_eq(&formatted);
+
+        // 定義の直前にあるコメントも復元される。
+        let hover = an.hover(main_url, lsp_types::Position::new(1, 2)).unwrap();
+        assert!(format!("{:?}", hover.contents).contains("挨拶する"));
+
+        std::fs::remove_dir_all(cache_path.parent().unwrap()).unwrap();
+    }
 }
@@ -4,10 +4,22 @@ use super::*;
 use crate::{
     analysis,
     analyzer::{doc_interner::DocInterner, docs::Docs},
+    help_source::HsLint,
+    hsp_version::HspVersion,
+    i18n::{tr, tr_fmt},
     ide::{loc_to_range, to_lsp_range},
     lsp_server::TextDocumentVersion,
+    token::{lint_tokens, TokenLint},
+    utils::read_file::FileEncoding,
 };
-use lsp_types::DiagnosticSeverity;
+use lsp_types::{
+    DiagnosticSeverity, DocumentDiagnosticReport, FullDocumentDiagnosticReport, PreviousResultId,
+    RelatedFullDocumentDiagnosticReport, RelatedUnchangedDocumentDiagnosticReport,
+    UnchangedDocumentDiagnosticReport, WorkspaceDiagnosticReport,
+    WorkspaceDocumentDiagnosticReport, WorkspaceFullDocumentDiagnosticReport,
+    WorkspaceUnchangedDocumentDiagnosticReport,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 // -----------------------------------------------
 // Computation
@@ -35,6 +47,254 @@ pub(crate) fn diagnose_syntax_lints(an: &AnalyzerRef<'_>, lints: &mut Vec<(Synta
     }
 }
 
+/// ヘルプファイルの書きかたの誤りを列挙する
+pub(crate) fn diagnose_help_source_lints(an: &AnalyzerRef<'_>, lints: &mut Vec<(HsLint, Loc)>) {
+    for root in an.hsp3_roots() {
+        for (&doc, doc_lints) in root.hsphelp_info.doc_lints.iter() {
+            lints.extend(
+                doc_lints
+                    .iter()
+                    .map(|&(lint, range)| (lint, Loc { doc, range })),
+            );
+        }
+    }
+}
+
+/// 名前の大文字・小文字の書きかたが定義と異なる箇所を列挙する
+///
+/// (HSPの識別子は大文字・小文字を区別しないが、書きかたの揺れは読みにくいので警告する。)
+/// 定義での名前、それに揃えたテキスト、その位置を列挙する。
+pub(crate) fn diagnose_name_casing(an: &AnalyzerRef<'_>, lints: &mut Vec<(RcStr, String, Loc)>) {
+    let mut found = vec![];
+
+    for (symbol, loc) in an.def_sites.iter().chain(an.use_sites) {
+        if !an.is_active_doc(loc.doc) {
+            continue;
+        }
+
+        let text = match an.doc_analysis_map.get(&loc.doc) {
+            Some(da) => &da.text[loc.range.start().index as usize..loc.range.end().index as usize],
+            None => continue,
+        };
+
+        let (base, qual_opt) = match text.rfind('@') {
+            Some(i) => (&text[..i], Some(&text[i + 1..])),
+            None => (text, None),
+        };
+
+        let name = symbol.name();
+        let base_ok = base == name.as_str() || !base.eq_ignore_ascii_case(&name);
+
+        // 修飾子 (`@` 以降) はモジュールの定義での名前に揃える。
+        let qual = match qual_opt {
+            Some(qual) if !qual.is_empty() => {
+                let module_name = module_name_spelling(an, qual);
+                let qual_ok = !matches!(&module_name, Some(m) if m.as_str() != qual);
+                if base_ok && qual_ok {
+                    continue;
+                }
+                Some(module_name.unwrap_or_else(|| qual.into()))
+            }
+            _ => {
+                if base_ok {
+                    continue;
+                }
+                qual_opt.map(RcStr::from)
+            }
+        };
+
+        let new_base = if base_ok { base } else { name.as_str() };
+        let new_text = match &qual {
+            Some(qual) => format!("{}@{}", new_base, qual),
+            None => new_base.to_string(),
+        };
+        // 修飾されているときは、修飾子を含めて定義での書きかたを示す。
+        let shown = match qual {
+            Some(qual) if !qual.is_empty() => format!("{}@{}", name, qual).into(),
+            _ => name,
+        };
+        found.push((shown, new_text, *loc));
+    }
+
+    // 定義箇所と使用箇所の両方に含まれる位置があるので、重複を取り除く。
+    found.sort_by_key(|&(_, _, loc)| loc);
+    found.dedup_by_key(|&mut (_, _, loc)| loc);
+    lints.extend(found);
+}
+
+/// 修飾子と大文字・小文字を無視して一致する名前のモジュールを探し、定義での名前を返す
+///
+/// (同じ書きかたのモジュールがあればそれを優先する。なければNone)
+fn module_name_spelling(an: &AnalyzerRef<'_>, qual: &str) -> Option<RcStr> {
+    let mut found = None;
+    for (&doc, da) in an.doc_analysis_map.iter() {
+        if !an.is_active_doc(doc) {
+            continue;
+        }
+        for module in da.module_map.values() {
+            let name = match &module.name_opt {
+                Some(it) if it.eq_ignore_ascii_case(qual) => it,
+                _ => continue,
+            };
+            if name.as_str() == qual {
+                return Some(name.clone());
+            }
+            found = Some(name.clone());
+        }
+    }
+    found
+}
+
+/// 定数 (`#const`, `#enum`) の値を計算して、0による除算やオーバーフローなどを列挙する
+pub(crate) fn diagnose_const_eval(an: &AnalyzerRef<'_>, errors: &mut Vec<(ConstEvalError, Loc)>) {
+    let mut found = vec![];
+    collect_const_eval_errors(an, &mut found);
+    errors.extend(
+        found
+            .into_iter()
+            .filter(|(_, loc)| an.is_active_doc(loc.doc)),
+    );
+}
+
+/// HSP3.7 で追加されたプリプロセッサ命令
+static HSP37_DIRECTIVES: &[&str] = &["use", "var", "vardouble", "varint", "varlabel", "varstr"];
+
+/// ドキュメントが対象とするHSPの版では使えない構文や命令を列挙する
+///
+/// (`#use` などのプリプロセッサ命令と、ヘルプファイルの `%ver` で版が書かれている命令を調べる。
+///  対象の版が分からないドキュメントは調べない。)
+/// 名前、それが使えるようになった版、対象の版、その位置を列挙する。
+pub(crate) fn diagnose_version(
+    an: &AnalyzerRef<'_>,
+    lints: &mut Vec<(String, HspVersion, HspVersion, Loc)>,
+) {
+    for (&doc, da) in an.doc_analysis_map.iter() {
+        if !an.is_active_doc(doc) {
+            continue;
+        }
+
+        let target = match an.target_of(doc).version_opt {
+            Some(it) if it < HspVersion::HSP37 => it,
+            _ => continue,
+        };
+
+        for w in da.tokens.windows(2) {
+            if w[0].kind() == TokenKind::Hash
+                && w[1].kind() == TokenKind::Ident
+                && HSP37_DIRECTIVES
+                    .iter()
+                    .any(|name| w[1].body_text().eq_ignore_ascii_case(name))
+            {
+                let name = format!("#{}", w[1].body_text());
+                let loc = w[0].body.loc.unite(&w[1].body.loc);
+                lints.push((name, HspVersion::HSP37, target, loc));
+            }
+        }
+    }
+
+    for (symbol, loc) in an.use_sites {
+        if !an.is_active_doc(loc.doc) {
+            continue;
+        }
+
+        let since = match symbol.since_opt {
+            Some(it) => it,
+            None => continue,
+        };
+        match an.target_of(loc.doc).version_opt {
+            Some(target) if target < since => {
+                lints.push((symbol.name().to_string(), since, target, *loc));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// UTF-8 版のランタイム (hsp3utf) を使うドキュメントを列挙する
+///
+/// エントリーポイント (ほかのドキュメントからインクルードされていないドキュメント) ごとに、
+/// そこからインクルードされるドキュメントのどれかに `#include "hsp3utf.as"` や `#runtime "hsp3utf"` があれば、
+/// それらのドキュメントはすべて hsp3utf を使うとみなす。
+/// (common にあるファイルは複数のスクリプトから共有されるので、インクルードをたどらない。)
+fn collect_utf8_docs(an: &AnalyzerRef<'_>) -> HashSet<DocId> {
+    let is_hsp3utf = |name: &str| name.to_ascii_lowercase().contains("hsp3utf");
+    let declares_hsp3utf = |doc: DocId| match an.doc_analysis_map.get(&doc) {
+        Some(da) => {
+            da.includes.iter().any(|(name, _)| is_hsp3utf(name))
+                || da.tokens.windows(3).any(|w| {
+                    w[0].kind() == TokenKind::Hash
+                        && w[1].body_text().eq_ignore_ascii_case("runtime")
+                        && w[2].kind() == TokenKind::Str
+                        && is_hsp3utf(w[2].body_text())
+                })
+        }
+        None => false,
+    };
+
+    let mut edges: HashMap<DocId, Vec<DocId>> = HashMap::new();
+    let mut included = HashSet::new();
+    for &(loc, dest_doc) in an.include_resolution {
+        if an.is_common_doc(dest_doc) {
+            continue;
+        }
+        edges.entry(loc.doc).or_default().push(dest_doc);
+        included.insert(dest_doc);
+    }
+
+    let mut utf8_docs = HashSet::new();
+    for &doc in an.doc_analysis_map.keys() {
+        if !an.is_active_doc(doc) || an.is_common_doc(doc) || included.contains(&doc) {
+            continue;
+        }
+
+        // エントリーポイントからインクルードされるドキュメント
+        let mut reached = HashSet::new();
+        let mut stack = vec![doc];
+        while let Some(doc) = stack.pop() {
+            if reached.insert(doc) {
+                stack.extend(edges.get(&doc).into_iter().flatten().copied());
+            }
+        }
+
+        if reached.iter().any(|&doc| declares_hsp3utf(doc)) {
+            utf8_docs.extend(reached);
+        }
+    }
+    utf8_docs
+}
+
+/// 全角空白や使用できない文字、shift_jis で表現できない文字を列挙する
+pub(crate) fn diagnose_token_lints(an: &AnalyzerRef<'_>, lints: &mut Vec<(TokenLint, Loc)>) {
+    let utf8_docs = collect_utf8_docs(an);
+
+    for (&doc, da) in an.doc_analysis_map.iter() {
+        if !an.is_active_doc(doc) {
+            continue;
+        }
+
+        let sjis = !utf8_docs.contains(&doc);
+        lint_tokens(da.tokens.iter().flat_map(|t| t.iter()), sjis, lints);
+    }
+}
+
+/// ファイルの文字コードが、ランタイムが想定するものと異なるものを列挙する
+pub(crate) fn diagnose_encoding(an: &AnalyzerRef<'_>, lints: &mut Vec<(&'static str, Loc)>) {
+    let utf8_docs = collect_utf8_docs(an);
+
+    for (&doc, da) in an.doc_analysis_map.iter() {
+        if !an.is_active_doc(doc) {
+            continue;
+        }
+
+        let msg = match (da.encoding_opt, utf8_docs.contains(&doc)) {
+            (Some(FileEncoding::Utf8), false) => tr(an.locale(), "lint.utf8_without_hsp3utf"),
+            (Some(FileEncoding::ShiftJis), true) => tr(an.locale(), "lint.sjis_with_hsp3utf"),
+            _ => continue,
+        };
+        lints.push((msg, Loc::from_doc(doc)));
+    }
+}
+
 pub(crate) fn diagnose_precisely(an: &AnalyzerRef<'_>, diagnostics: &mut Vec<(String, Loc)>) {
     let use_site_map = an
         .use_sites
@@ -60,12 +320,17 @@ pub(crate) fn diagnose_precisely(an: &AnalyzerRef<'_>, diagnostics: &mut Vec<(St
         ctx.on_root(root);
     }
 
+    let locale = an.locale();
     diagnostics.extend(ctx.diagnostics.into_iter().map(|(d, loc)| {
         let msg = match d {
-            Diagnostic::Undefined => "定義が見つかりません",
-            Diagnostic::VarRequired => "変数か配列の要素が必要です。",
-        }
-        .to_string();
+            Diagnostic::Undefined => tr(locale, "lint.undefined").to_string(),
+            Diagnostic::VarRequired => tr(locale, "lint.var_required").to_string(),
+            Diagnostic::FormatArgCount(expected, actual) => {
+                tr_fmt(locale, "lint.format_arg_count", &[&expected, &actual])
+            }
+            Diagnostic::FormatArgType(spec) => tr_fmt(locale, "lint.format_arg_type", &[&spec]),
+            Diagnostic::TooManyArgs(count) => tr_fmt(locale, "lint.too_many_args", &[&count]),
+        };
         (msg, loc)
     }));
 }
@@ -119,20 +384,203 @@ pub(crate) fn filter_diagnostics(
     cache.map2 = map;
 }
 
+// -----------------------------------------------
+// Pull
+// -----------------------------------------------
+
+/// プル型の診断 (`textDocument/diagnostic`, `workspace/diagnostic`) で返した結果を覚えておくもの
+///
+/// (結果ごとにIDを振っておき、クライアントが前回の結果のIDを送ってきたとき、
+///  内容が変わっていなければ「変更なし」と応答する。)
+///
+/// IDにはキャッシュを作るたびに変わる値 (nonce) を含める。
+/// (サーバーを再起動した後に、クライアントが以前のプロセスの振ったIDを送ってきても一致しないように。)
+pub(crate) struct PullDiagnosticsCache {
+    nonce: String,
+    last_id: usize,
+    /// ドキュメントごとの、最後に返した結果のIDと内容
+    map: HashMap<CanonicalUri, (String, String)>,
+}
+
+impl Default for PullDiagnosticsCache {
+    fn default() -> Self {
+        Self {
+            nonce: new_nonce(),
+            last_id: 0,
+            map: HashMap::new(),
+        }
+    }
+}
+
+fn new_nonce() -> String {
+    // テストでは応答が変わらないように固定する。
+    if cfg!(test) {
+        return "test".to_string();
+    }
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    format!("{:x}{:08x}", std::process::id(), nanos)
+}
+
+impl PullDiagnosticsCache {
+    /// 診断の結果にIDを振る。
+    ///
+    /// 内容がこのキャッシュの前回の結果と同じならそのIDを返す。
+    /// さらに `previous_id` がそのIDに一致するなら、`true` (変更なし) を返す。
+    /// (このキャッシュがこのドキュメントに振ったのではないIDは、一致しない。)
+    fn result_id(
+        &mut self,
+        uri: &Url,
+        previous_id: Option<&str>,
+        diagnostics: &[lsp_types::Diagnostic],
+    ) -> (String, bool) {
+        let text = serde_json::to_string(diagnostics).unwrap_or_default();
+        let uri = CanonicalUri::from_url(uri);
+        if let Some((id, old)) = self.map.get(&uri) {
+            if *old == text {
+                return (id.clone(), previous_id == Some(id.as_str()));
+            }
+        }
+
+        self.last_id += 1;
+        let id = format!("{}-{}", self.nonce, self.last_id);
+        self.map.insert(uri, (id.clone(), text));
+        (id, false)
+    }
+}
+
+/// ドキュメントの診断の結果を報告する。(`textDocument/diagnostic`)
+///
+/// (`diagnostics` は `diagnose` の結果。指定されたドキュメントのもの以外は無視する)
+pub(crate) fn document_diagnostic_report(
+    cache: &mut PullDiagnosticsCache,
+    uri: &Url,
+    previous_result_id: Option<&str>,
+    diagnostics: Vec<(Url, Option<TextDocumentVersion>, Vec<lsp_types::Diagnostic>)>,
+) -> DocumentDiagnosticReport {
+    let canonical_uri = CanonicalUri::from_url(uri);
+    let mut items = diagnostics
+        .into_iter()
+        .find(|(u, _, _)| CanonicalUri::from_url(u) == canonical_uri)
+        .map(|(_, _, items)| items)
+        .unwrap_or_default();
+    items.sort_by_key(|d| (d.range.start, d.range.end));
+
+    let (result_id, unchanged) = cache.result_id(uri, previous_result_id, &items);
+    if unchanged {
+        return DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+            related_documents: None,
+            unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+        });
+    }
+
+    DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+        related_documents: None,
+        full_document_diagnostic_report: FullDocumentDiagnosticReport {
+            result_id: Some(result_id),
+            items,
+        },
+    })
+}
+
+/// ワークスペース全体の診断の結果を報告する。(`workspace/diagnostic`)
+///
+/// 診断のあるドキュメントと、クライアントが前回の結果を持っているドキュメントについて報告する。
+/// (後者のうち診断がなくなったものには、空の結果を返して消させる)
+pub(crate) fn workspace_diagnostic_report(
+    cache: &mut PullDiagnosticsCache,
+    previous_result_ids: Vec<PreviousResultId>,
+    diagnostics: Vec<(Url, Option<TextDocumentVersion>, Vec<lsp_types::Diagnostic>)>,
+) -> WorkspaceDiagnosticReport {
+    let mut previous = previous_result_ids
+        .into_iter()
+        .map(|p| (CanonicalUri::from_url(&p.uri), (p.uri, p.value)))
+        .collect::<HashMap<_, _>>();
+
+    let mut reports = diagnostics
+        .into_iter()
+        .map(|(uri, version, items)| {
+            let previous_id = previous
+                .remove(&CanonicalUri::from_url(&uri))
+                .map(|(_, id)| id);
+            (uri, version, items, previous_id)
+        })
+        .collect::<Vec<_>>();
+    reports.extend(
+        previous
+            .into_values()
+            .map(|(uri, id)| (uri, None, vec![], Some(id))),
+    );
+    reports.sort_by(|(l, ..), (r, ..)| l.cmp(r));
+
+    let items = reports
+        .into_iter()
+        .map(|(uri, version, mut items, previous_id)| {
+            items.sort_by_key(|d| (d.range.start, d.range.end));
+            let version = version.map(i64::from);
+
+            let (result_id, unchanged) = cache.result_id(&uri, previous_id.as_deref(), &items);
+            if unchanged {
+                return WorkspaceDocumentDiagnosticReport::Unchanged(
+                    WorkspaceUnchangedDocumentDiagnosticReport {
+                        uri,
+                        version,
+                        unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+                            result_id,
+                        },
+                    },
+                );
+            }
+
+            WorkspaceDocumentDiagnosticReport::Full(WorkspaceFullDocumentDiagnosticReport {
+                uri,
+                version,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                    result_id: Some(result_id),
+                    items,
+                },
+            })
+        })
+        .collect();
+
+    WorkspaceDiagnosticReport { items }
+}
+
 // ===============================================
 
 pub(crate) fn diagnose(
     an: &AnalyzerRef<'_>,
-    hsp3_root: &Path,
     doc_interner: &DocInterner,
     docs: &Docs,
 ) -> Vec<(Url, Option<i32>, Vec<lsp_types::Diagnostic>)> {
+    let locale = an.locale();
+
     let mut dd = vec![];
     diagnose_precisely(an, &mut dd);
 
     let mut lints = vec![];
     diagnose_syntax_lints(an, &mut lints);
 
+    let mut hs_lints = vec![];
+    diagnose_help_source_lints(an, &mut hs_lints);
+
+    let mut casing_lints = vec![];
+    diagnose_name_casing(an, &mut casing_lints);
+
+    let mut const_errors = vec![];
+    diagnose_const_eval(an, &mut const_errors);
+
+    let mut token_lints = vec![];
+    diagnose_token_lints(an, &mut token_lints);
+
+    let mut encoding_lints = vec![];
+    diagnose_encoding(an, &mut encoding_lints);
+
+    let mut version_lints = vec![];
+    diagnose_version(an, &mut version_lints);
+
     let mut map: HashMap<DocId, Vec<lsp_types::Diagnostic>> = HashMap::new();
     for (message, loc) in dd {
         let d = lsp_types::Diagnostic {
@@ -146,7 +594,71 @@ pub(crate) fn diagnose(
     }
     for (lint, loc) in lints {
         let d = lsp_types::Diagnostic {
-            message: lint.as_str().to_string(),
+            message: lint.as_str(locale).to_string(),
+            severity: Some(DiagnosticSeverity::WARNING),
+            range: loc_to_range(loc),
+            source: source(),
+            ..Default::default()
+        };
+        map.entry(loc.doc).or_default().push(d);
+    }
+    for (lint, loc) in token_lints {
+        let d = lsp_types::Diagnostic {
+            message: lint.as_str(locale).to_string(),
+            severity: Some(DiagnosticSeverity::WARNING),
+            range: loc_to_range(loc),
+            source: source(),
+            ..Default::default()
+        };
+        map.entry(loc.doc).or_default().push(d);
+    }
+    for (msg, loc) in encoding_lints {
+        let d = lsp_types::Diagnostic {
+            message: msg.to_string(),
+            severity: Some(DiagnosticSeverity::WARNING),
+            range: loc_to_range(loc),
+            source: source(),
+            ..Default::default()
+        };
+        map.entry(loc.doc).or_default().push(d);
+    }
+    for (err, loc) in const_errors {
+        let d = lsp_types::Diagnostic {
+            message: err.as_str(locale).to_string(),
+            severity: Some(DiagnosticSeverity::WARNING),
+            range: loc_to_range(loc),
+            source: source(),
+            ..Default::default()
+        };
+        map.entry(loc.doc).or_default().push(d);
+    }
+    for (name, since, target, loc) in version_lints {
+        let d = lsp_types::Diagnostic {
+            message: tr_fmt(
+                locale,
+                "lint.requires_hsp_version",
+                &[&name, &since, &target],
+            ),
+            severity: Some(DiagnosticSeverity::WARNING),
+            range: loc_to_range(loc),
+            source: source(),
+            ..Default::default()
+        };
+        map.entry(loc.doc).or_default().push(d);
+    }
+    for (name, _, loc) in casing_lints {
+        let d = lsp_types::Diagnostic {
+            message: tr_fmt(locale, "lint.name_casing", &[&name]),
+            severity: Some(DiagnosticSeverity::INFORMATION),
+            range: loc_to_range(loc),
+            source: source(),
+            ..Default::default()
+        };
+        map.entry(loc.doc).or_default().push(d);
+    }
+    for (lint, loc) in hs_lints {
+        let d = lsp_types::Diagnostic {
+            message: lint.as_str(locale).to_string(),
             severity: Some(DiagnosticSeverity::WARNING),
             range: loc_to_range(loc),
             source: source(),
@@ -161,23 +673,22 @@ pub(crate) fn diagnose(
             Some(it) => it.clone().into_url(),
             None => continue,
         };
-        let version = docs.get_version(doc);
-
-        doc_diagnostics.push((uri, version, diagnostics));
-    }
-
-    // hsp3のファイルにdiagnosticsを出さない。
-    doc_diagnostics.retain(|(uri, _, _)| {
-        let ok = uri
-            .to_file_path()
-            .map_or(true, |path| !path.starts_with(&hsp3_root));
 
+        // hsp3のファイルにdiagnosticsを出さない。
+        // (ただしエディタで編集中のヘルプファイルは除く)
+        let editing_help = an.is_help_doc(doc) && docs.is_open_in_editor(doc);
+        let ok = editing_help
+            || uri
+                .to_file_path()
+                .map_or(true, |path| !an.is_in_hsp3_root(&path));
         if !ok {
             debug!("Diagnostics ignored {:?}", uri);
+            continue;
         }
 
-        ok
-    });
+        let version = docs.get_version(doc);
+        doc_diagnostics.push((uri, version, diagnostics));
+    }
 
     doc_diagnostics
 }
@@ -261,4 +772,508 @@ loop
         "#]]
         .assert_eq(&formatted);
     }
+
+    #[test]
+    fn test_help_source_lints() {
+        let mut an = Analyzer::new_standalone();
+
+        an.open_doc(
+            dummy_url("mod_foo.hs"),
+            NO_VERSION,
+            r#"%dll
+mod_foo
+%author
+me
+%index
+foo
+概要
+%prm
+%inst
+html{
+%unknown
+}html
+%index
+foo
+%index
+%groop
+html{
+"#
+            .into(),
+        );
+
+        let an = an.compute_ref();
+
+        let mut formatted = String::new();
+        format_response(&mut formatted, &an.diagnose());
+
+        expect![[r#"
+            file: "mod_foo.hs"@1 (4)
+              14:1 Warning "同じキーワードの %index がすでにあります。"
+              15:1 Warning "%index の次の行にキーワードがありません。"
+              16:1 Warning "不明なキーです。"
+              17:1 Warning "html{ に対応する }html がありません。"
+
+        "#]]
+        .assert_eq(&formatted);
+    }
+
+    #[test]
+    fn test_name_casing() {
+        let mut an = Analyzer::new_standalone();
+
+        // 大文字・小文字が異なっても同じシンボルとして解決される。
+        an.open_doc(
+            dummy_url("main.hsp"),
+            NO_VERSION,
+            r#"#module m_Foo
+#deffunc local myFunc
+    return
+#global
+    MyFunc@M_FOO
+    myFunc@m_foo
+    x = 1
+    X += 1
+"#
+            .into(),
+        );
+
+        let an = an.compute_ref();
+
+        let mut formatted = String::new();
+        format_response(&mut formatted, &an.diagnose());
+
+        expect![[r#"
+            file: "main.hsp"@1 (3)
+              5:5 Information "大文字・小文字の書きかたが定義 (myFunc@m_Foo) と異なります。"
+              6:5 Information "大文字・小文字の書きかたが定義 (myFunc@m_Foo) と異なります。"
+              8:5 Information "大文字・小文字の書きかたが定義 (x) と異なります。"
+
+        "#]]
+        .assert_eq(&formatted);
+    }
+
+    #[test]
+    fn test_const_eval() {
+        let mut an = Analyzer::new_standalone();
+
+        an.open_doc(
+            dummy_url("main.hsp"),
+            NO_VERSION,
+            r#"
+#const A 1 / 0
+#const B 0x7fffffff + 1
+#const C 3000000000
+    x = 1
+#const D x + 1
+#const E A + 1
+#const F 1.0 \ 0
+#enum G = 0x7fffffff
+#enum H
+#define M 1
+#const I M + 1
+#const J %1010 + 0x
+"#
+            .into(),
+        );
+
+        let an = an.compute_ref();
+
+        let mut formatted = String::new();
+        format_response(&mut formatted, &an.diagnose());
+
+        expect![[r#"
+            file: "main.hsp"@1 (7)
+              2:12 Warning "0で除算しています。"
+              3:21 Warning "整数の範囲を超えています。"
+              4:10 Warning "整数の範囲を超えています。"
+              6:10 Warning "定数式ではありません。"
+              8:14 Warning "0で除算しています。"
+              10:7 Warning "整数の範囲を超えています。"
+              13:18 Warning "数値の書きかたが正しくありません。"
+
+        "#]]
+        .assert_eq(&formatted);
+    }
+
+    #[test]
+    fn test_format_args() {
+        let mut an = Analyzer::new_standalone();
+
+        an.open_doc(
+            dummy_url("main.hsp"),
+            NO_VERSION,
+            r#"
+#module
+#deffunc f str s, int n
+    logmes strf("%d: %s", n)
+    logmes strf("%d: %s", s, s)
+    mes strf("%5.2f%%, %-3s", n, n)
+    mes strf("%d", n, 1)
+    return
+#global
+"#
+            .into(),
+        );
+
+        let an = an.compute_ref();
+
+        let mut formatted = String::new();
+        format_response(&mut formatted, &an.diagnose());
+
+        expect![[r#"
+            file: "main.hsp"@1 (3)
+              4:17 Error "書式指定の数 (2) と引数の数 (1) が一致しません。"
+              5:27 Error "書式指定 %d に文字列が渡されています。"
+              7:14 Error "書式指定の数 (1) と引数の数 (2) が一致しません。"
+
+        "#]]
+        .assert_eq(&formatted);
+    }
+
+    #[test]
+    fn test_token_lints() {
+        let mut an = Analyzer::new_standalone();
+
+        an.open_doc(
+            dummy_url("main.hsp"),
+            NO_VERSION,
+            "\tmes \"ok\"\u{3000}\u{3000}: mes 1\n\u{3000}x = 1 ~ 2\n\tmes \"🐧\"\n".into(),
+        );
+
+        let an = an.compute_ref();
+
+        let mut formatted = String::new();
+        format_response(&mut formatted, &an.diagnose());
+
+        expect![[r#"
+            file: "main.hsp"@1 (4)
+              1:10 Warning "全角空白があります。"
+              2:1 Warning "全角空白があります。"
+              2:8 Warning "使用できない文字があります。"
+              3:7 Warning "shift_jis で表現できない文字があります。"
+
+        "#]]
+        .assert_eq(&formatted);
+    }
+
+    #[test]
+    fn test_encoding() {
+        use crate::analyzer::vfs::MemoryFs;
+        use encoding::{EncoderTrap, Encoding};
+        use std::rc::Rc;
+
+        let sjis = |text: &str| {
+            encoding::all::WINDOWS_31J
+                .encode(text, EncoderTrap::Strict)
+                .unwrap()
+        };
+        let path = |name: &str| crate::test_utils::dummy_path().join("ws").join(name);
+
+        // a_main.hsp は hsp3utf を使う。それがインクルードする a_mod.hsp も同じ。
+        // b_main.hsp は a_main.hsp と関係がないので、hsp3utf を使わない。
+        let a_main = "#include \"hsp3utf.as\"\n#include \"a_mod.hsp\"\n\tmes \"あ\"\n";
+        let a_mod = "\tmes \"い\"\n";
+        let b_main = "\tmes \"う\"\n";
+
+        let fs = Rc::new(MemoryFs::default());
+        fs.write(path("a_main.hsp"), a_main);
+        fs.write(path("a_mod.hsp"), sjis(a_mod));
+        fs.write(path("b_main.hsp"), b_main);
+
+        let mut an = Analyzer::with_vfs(None, fs.clone());
+        for (name, text) in [
+            ("a_main.hsp", a_main),
+            ("a_mod.hsp", a_mod),
+            ("b_main.hsp", b_main),
+        ] {
+            an.open_doc(dummy_url(name), NO_VERSION, text.into());
+        }
+
+        let mut res = an.compute_ref().diagnose();
+        res.sort_by(|l, r| l.0.cmp(&r.0));
+        let mut formatted = String::new();
+        format_response(&mut formatted, &res);
+        expect![[r#"
+            file: "a_mod.hsp"@1 (1)
+              1:1 Warning "このファイルは shift_jis で保存されていますが、hsp3utf は UTF-8 を想定しています。"
+
+            file: "b_main.hsp"@1 (1)
+              1:1 Warning "このファイルは UTF-8 で保存されていますが、HSP は shift_jis を想定しています。(UTF-8 を使うときは hsp3utf.as を #include してください。)"
+
+        "#]]
+        .assert_eq(&formatted);
+
+        // 保存されたファイルの文字コードが変わったら、調べなおす。
+        fs.write(path("b_main.hsp"), sjis(b_main));
+        an.on_file_changed(dummy_url("b_main.hsp"));
+        fs.write(path("a_mod.hsp"), a_mod);
+        an.change_doc(dummy_url("a_mod.hsp"), 2, a_mod.into());
+
+        let mut res = an.compute_ref().diagnose();
+        res.sort_by(|l, r| l.0.cmp(&r.0));
+        let mut formatted = String::new();
+        format_response(&mut formatted, &res);
+        expect![[r#""#]].assert_eq(&formatted);
+    }
+
+    #[test]
+    fn test_bundled_builtin_arity() {
+        let mut an = Analyzer::new_standalone();
+
+        // HSPのインストールがないので、同梱のデータベースが使われる。
+        an.did_initialize();
+
+        an.open_doc(
+            dummy_url("main.hsp"),
+            NO_VERSION,
+            r#"
+                mes "hello", 1
+                pos 1, 2, 3
+                s = strmid("hello", 1, 2)
+                n = strlen(s, 1)
+            "#
+            .into(),
+        );
+
+        let an = an.compute_ref();
+
+        let mut formatted = String::new();
+        format_response(&mut formatted, &an.diagnose());
+
+        expect![[r#"
+            file: "main.hsp"@1 (2)
+              3:27 Error "引数が多すぎます。(パラメータは 2 個です)"
+              5:31 Error "引数が多すぎます。(パラメータは 1 個です)"
+
+        "#]]
+        .assert_eq(&formatted);
+    }
+
+    #[test]
+    fn test_hsphelp_builtin_arity() {
+        let mut an = Analyzer::new_with_hsp3_files(&[(
+            "hsphelp/i_test.hs",
+            "%index\nfoo\nテスト\n%prm\np1, p2\n%note\n標準命令です。\n",
+        )]);
+
+        // インストールディレクトリにあるヘルプファイルが使われる。(同梱のデータベースは使われない。)
+        an.did_initialize();
+
+        an.open_doc(
+            dummy_url("main.hsp"),
+            NO_VERSION,
+            r#"
+                foo 1, 2, 3
+                mes "hello", 1, 2, 3
+            "#
+            .into(),
+        );
+
+        let an = an.compute_ref();
+
+        let mut formatted = String::new();
+        format_response(&mut formatted, &an.diagnose());
+
+        expect![[r#"
+            file: "main.hsp"@1 (1)
+              2:27 Error "引数が多すぎます。(パラメータは 2 個です)"
+
+        "#]]
+        .assert_eq(&formatted);
+    }
+
+    #[test]
+    fn test_hsp_version() {
+        use crate::analyzer::vfs::MemoryFs;
+        use std::rc::Rc;
+
+        let root = crate::test_utils::dummy_path();
+        let fs = MemoryFs::default();
+        fs.write(
+            root.join("hsp3/hsphelp/i_test.hs"),
+            "%ver\n3.7\n%index\nmes\n%index\nnewcmd\n%ver\n3.7\n",
+        );
+        // ws は HSP3.6 を対象にする。(ws37 はインストールされている HSP3.7 を対象にする。)
+        fs.write(
+            root.join("ws/hsp3-analyzer-mini.json"),
+            r#"{ "hspVersion": "3.6" }"#,
+        );
+
+        let mut an = Analyzer::with_vfs(Some(root.join("hsp3")), Rc::new(fs));
+        for name in ["ws", "ws37"] {
+            an.add_workspace_folder(lsp_types::WorkspaceFolder {
+                uri: lsp_types::Url::from_directory_path(root.join(name)).unwrap(),
+                name: name.to_string(),
+            });
+        }
+        an.did_initialize();
+
+        let text = "#use kernel32\n#varint x\nmes\nnewcmd\n";
+        an.open_doc(dummy_url("main.hsp"), NO_VERSION, text.into());
+        let ws37_url = lsp_types::Url::from_file_path(root.join("ws37/main.hsp")).unwrap();
+        an.open_doc(ws37_url, NO_VERSION, text.into());
+
+        let an = an.compute_ref();
+
+        let mut formatted = String::new();
+        format_response(&mut formatted, &an.diagnose());
+
+        expect![[r##"
+            file: "main.hsp"@1 (3)
+              1:1 Warning "#use は HSP 3.7 以降で使えます。(対象の HSP は 3.6 です)"
+              2:1 Warning "#varint は HSP 3.7 以降で使えます。(対象の HSP は 3.6 です)"
+              4:1 Warning "newcmd は HSP 3.7 以降で使えます。(対象の HSP は 3.6 です)"
+
+        "##]]
+        .assert_eq(&formatted);
+    }
+
+    #[test]
+    fn test_pull_diagnostics() {
+        use super::{
+            document_diagnostic_report, workspace_diagnostic_report, PullDiagnosticsCache,
+        };
+        use lsp_types::{
+            DocumentDiagnosticReport, PreviousResultId, WorkspaceDocumentDiagnosticReport,
+        };
+
+        fn format_document_report(report: &DocumentDiagnosticReport) -> String {
+            match report {
+                DocumentDiagnosticReport::Full(r) => {
+                    let r = &r.full_document_diagnostic_report;
+                    format!(
+                        "full {} ({})",
+                        r.result_id.as_deref().unwrap(),
+                        r.items.len()
+                    )
+                }
+                DocumentDiagnosticReport::Unchanged(r) => {
+                    format!(
+                        "unchanged {}",
+                        r.unchanged_document_diagnostic_report.result_id
+                    )
+                }
+            }
+        }
+
+        fn format_workspace_report(items: &[WorkspaceDocumentDiagnosticReport]) -> String {
+            let mut w = String::new();
+            for item in items {
+                let (uri, line) = match item {
+                    WorkspaceDocumentDiagnosticReport::Full(r) => {
+                        let d = &r.full_document_diagnostic_report;
+                        let line = format!(
+                            "full {} ({})",
+                            d.result_id.as_deref().unwrap(),
+                            d.items.len()
+                        );
+                        (&r.uri, line)
+                    }
+                    WorkspaceDocumentDiagnosticReport::Unchanged(r) => {
+                        let line = format!(
+                            "unchanged {}",
+                            r.unchanged_document_diagnostic_report.result_id
+                        );
+                        (&r.uri, line)
+                    }
+                };
+                let name = uri.to_file_path().unwrap();
+                writeln!(w, "{:?} {}", name.file_name().unwrap(), line).unwrap();
+            }
+            w
+        }
+
+        let mut an = Analyzer::new_standalone();
+        let mut cache = PullDiagnosticsCache::default();
+
+        let main_url = dummy_url("main.hsp");
+        let other_url = dummy_url("other.hsp");
+        an.open_doc(main_url.clone(), 1, "repeat\n    return\nloop\n".into());
+        an.open_doc(other_url.clone(), 1, "#const A 1 / 0\n".into());
+
+        let report = |an: &mut Analyzer, cache: &mut PullDiagnosticsCache, id: Option<&str>| {
+            let diagnostics = an.compute_ref().diagnose();
+            format_document_report(&document_diagnostic_report(
+                cache,
+                &main_url,
+                id,
+                diagnostics,
+            ))
+        };
+
+        // 内容が変わっていなければ、同じIDを返す。
+        assert_eq!(report(&mut an, &mut cache, None), "full test-1 (1)");
+        assert_eq!(
+            report(&mut an, &mut cache, Some("test-1")),
+            "unchanged test-1"
+        );
+
+        an.change_doc(main_url.clone(), 2, "repeat\nloop\n".into());
+        assert_eq!(
+            report(&mut an, &mut cache, Some("test-1")),
+            "full test-2 (0)"
+        );
+
+        // ワークスペース全体: 診断がなくなったドキュメントにも、前回の結果を持っていれば報告する。
+        an.change_doc(other_url.clone(), 2, "#const A 1 / 2\n".into());
+        let diagnostics = an.compute_ref().diagnose();
+        let report = workspace_diagnostic_report(
+            &mut cache,
+            vec![
+                PreviousResultId {
+                    uri: main_url.clone(),
+                    value: "test-2".to_string(),
+                },
+                PreviousResultId {
+                    uri: other_url.clone(),
+                    value: "test-0".to_string(),
+                },
+            ],
+            diagnostics,
+        );
+        expect![[r#"
+            "main.hsp" unchanged test-2
+            "other.hsp" full test-3 (0)
+        "#]]
+        .assert_eq(&format_workspace_report(&report.items));
+    }
+
+    #[test]
+    fn test_pull_diagnostics_unknown_result_id() {
+        use super::{document_diagnostic_report, PullDiagnosticsCache};
+        use lsp_types::DocumentDiagnosticReport;
+
+        let mut an = Analyzer::new_standalone();
+        let main_url = dummy_url("main.hsp");
+        let other_url = dummy_url("other.hsp");
+        an.open_doc(main_url.clone(), 1, "repeat\n    return\nloop\n".into());
+        an.open_doc(other_url.clone(), 1, "repeat\n    return\nloop\n".into());
+
+        let is_full = |an: &mut Analyzer, cache: &mut PullDiagnosticsCache, id: Option<&str>| {
+            let diagnostics = an.compute_ref().diagnose();
+            let report = document_diagnostic_report(cache, &main_url, id, diagnostics);
+            matches!(report, DocumentDiagnosticReport::Full(_))
+        };
+
+        // 別のプロセスが振ったIDが、このキャッシュの振ったIDと同じ形をしていても、全体を返す。
+        let mut cache = PullDiagnosticsCache {
+            nonce: "other".to_string(),
+            ..Default::default()
+        };
+        assert!(is_full(&mut an, &mut cache, None));
+        let mut cache = PullDiagnosticsCache::default();
+        assert!(is_full(&mut an, &mut cache, Some("other-1")));
+
+        // このキャッシュが振ったことのないIDには、全体を返す。
+        assert!(is_full(&mut an, &mut cache, Some("test-2")));
+        assert!(is_full(&mut an, &mut cache, Some("1")));
+
+        // ほかのドキュメントに振ったIDにも、全体を返す。
+        let diagnostics = an.compute_ref().diagnose();
+        document_diagnostic_report(&mut cache, &other_url, None, diagnostics);
+        assert!(is_full(&mut an, &mut cache, Some("test-2")));
+
+        // このキャッシュがこのドキュメントに振ったIDなら、変更なしと答える。
+        assert!(!is_full(&mut an, &mut cache, Some("test-1")));
+    }
 }
@@ -13,10 +13,15 @@ pub(crate) fn document_highlight(
     let (doc, pos) = from_document_position(doc_interner, &uri, position)?;
     let (symbol, _) = an.locate_symbol(doc, pos)?;
 
+    // 参照渡しの引数として渡されている箇所は書き込みとみなす。
+    let mut by_ref_sites = vec![];
+    collect_by_ref_arg_sites(an, doc, &mut by_ref_sites);
+
     let mut highlights = vec![];
     collect_highlights(an, doc, &symbol, |kind, loc| {
         let kind = match kind {
             DefOrUse::Def => DocumentHighlightKind::WRITE,
+            DefOrUse::Use if by_ref_sites.contains(&loc) => DocumentHighlightKind::WRITE,
             DefOrUse::Use => DocumentHighlightKind::READ,
         };
 
@@ -329,7 +329,9 @@ pub(crate) fn formatting(
     uri: Url,
 ) -> Option<Vec<TextEdit>> {
     let doc = doc_interner.get_doc(&CanonicalUri::from_url(&uri))?;
-    let DocSyntax { text, tokens, root } = an.get_syntax(doc)?;
+    let DocSyntax {
+        text, tokens, root, ..
+    } = an.get_syntax(doc)?;
 
     let mut ctx = V {
         ground_depth: 1,
@@ -1,6 +1,7 @@
 //! ホバー
 
 use super::*;
+use crate::i18n::{tr, tr_fmt, Locale};
 use lsp_types::{
     Documentation, Hover, HoverContents, MarkedString, MarkupContent, MarkupKind, Position, Url,
 };
@@ -15,7 +16,7 @@ pub(crate) fn hover(
 
     let (contents, loc) = (|| -> Option<_> {
         let (symbol, symbol_loc) = an.locate_symbol(doc, pos)?;
-        let (name, kind, details) = get_symbol_details(&symbol)?;
+        let (name, kind, details) = get_symbol_details(&symbol, an.locale())?;
 
         let mut contents = vec![];
         contents.push(plain_text_to_marked_string(format!("{} ({})", name, kind)));
@@ -24,6 +25,14 @@ pub(crate) fn hover(
             contents.push(plain_text_to_marked_string(desc.to_string()));
         }
 
+        if let Some(value) = eval_const_symbol(an, &symbol) {
+            contents.push(plain_text_to_marked_string(tr_fmt(
+                an.locale(),
+                "hover.value",
+                &[&value],
+            )));
+        }
+
         contents.extend(details.docs.into_iter().map(plain_text_to_marked_string));
 
         Some((contents, symbol_loc))
@@ -34,7 +43,7 @@ pub(crate) fn hover(
 
         let mut completion_items = vec![];
         if doc_analysis::in_preproc(pos, &tokens) {
-            collect_preproc_completion_items(an, &mut completion_items);
+            collect_preproc_completion_items(an, doc, &mut completion_items);
         }
 
         let item = completion_items
@@ -58,7 +67,7 @@ pub(crate) fn hover(
         if let Some(loc) = an.on_include_guard(doc, pos) {
             Some((
                 vec![plain_text_to_marked_string(
-                    "インクルードガード".to_string(),
+                    tr(an.locale(), "hover.include_guard").to_string(),
                 )],
                 loc,
             ))
@@ -73,10 +82,13 @@ pub(crate) fn hover(
     })
 }
 
-fn get_symbol_details(symbol: &SymbolRc) -> Option<(RcStr, &'static str, SymbolDetails)> {
+fn get_symbol_details(
+    symbol: &SymbolRc,
+    locale: Locale,
+) -> Option<(RcStr, &'static str, SymbolDetails)> {
     Some((
         symbol.name(),
-        symbol.kind.as_str(),
+        symbol.kind.as_str(locale),
         symbol.compute_details(),
     ))
 }
@@ -241,4 +253,54 @@ mod tests {
             "On `<|>ctype`:\n@2:9 [1] \"ctype\"; [2] \"関数形式のマクロを表す\""
         );
     }
+
+    #[test]
+    fn const_value_test() {
+        let mut an = Analyzer::new_standalone();
+
+        let main_uri = dummy_url("main.hsp");
+        let src = r#"
+#const A 0x10
+#const B (A + 2) * 3
+#const double C A / 3
+#const D "a" + A
+#enum E1 = B
+#enum E2
+#const global F -$ffffffff << 4
+#const G %1010 | 1
+"#;
+        an.open_doc(main_uri.clone(), NO_VERSION, src.to_string());
+        let an = an.compute_ref();
+
+        let mut w = String::new();
+        for (row, column) in [
+            (1, 7),
+            (2, 7),
+            (3, 14),
+            (4, 7),
+            (5, 6),
+            (6, 6),
+            (7, 14),
+            (8, 7),
+        ] {
+            format_response(
+                &mut w,
+                an.hover(main_uri.clone(), to_proto::pos(pos_at(src, row, column)))
+                    .as_ref(),
+            );
+            w += "\n";
+        }
+
+        expect_test::expect![[r#"
+            @2:8 [1] "A (定数)"; [2] "値: 16 (0x10)"
+            @3:8 [1] "B (定数)"; [2] "値: 54 (0x36)"
+            @4:15 [1] "C (定数)"; [2] "値: 5.0"
+            @5:8 [1] "D (定数)"; [2] "値: \"a16\""
+            @6:7 [1] "E1 (列挙子)"; [2] "値: 54 (0x36)"
+            @7:7 [1] "E2 (列挙子)"; [2] "値: 55 (0x37)"
+            @8:15 [1] "F (定数)"; [2] "値: 16 (0x10)"
+            @9:8 [1] "G (定数)"; [2] "値: 11 (0xB)"
+        "#]]
+        .assert_eq(&w);
+    }
 }
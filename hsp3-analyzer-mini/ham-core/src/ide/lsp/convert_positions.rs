@@ -0,0 +1,374 @@
+//! 位置情報の列番号の変換
+//!
+//! IDE機能は列番号を UTF-16 で扱う。
+//! クライアントが異なる単位を使うときは、リクエストとレスポンスに含まれる位置情報をここで変換する。
+
+use crate::{
+    analysis::{DocAnalysis, DocAnalysisMap},
+    analyzer::doc_interner::DocInterner,
+    ide::code_lens::SHOW_REFERENCES_COMMAND,
+    parse::PToken,
+    source::{shift_jis_advance, shift_jis_len, Pos, PositionEncoding},
+    utils::canonical_uri::CanonicalUri,
+};
+use lsp_types::*;
+
+/// 値に含まれる位置情報を列挙するもの
+pub(crate) trait MapPositions {
+    /// 値に含まれる位置情報をすべて変換する。
+    ///
+    /// (`uri` は値が属するドキュメント。`f` は位置情報とそれが属するドキュメントを受け取る。)
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position));
+}
+
+/// クライアントの単位と UTF-16 の間で列番号を変換するもの
+pub(crate) struct PositionConverter<'a> {
+    encoding: PositionEncoding,
+    doc_interner: &'a DocInterner,
+    doc_analysis_map: &'a DocAnalysisMap,
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    ToClient,
+    FromClient,
+}
+
+impl<'a> PositionConverter<'a> {
+    pub(crate) fn new(
+        encoding: PositionEncoding,
+        doc_interner: &'a DocInterner,
+        doc_analysis_map: &'a DocAnalysisMap,
+    ) -> Self {
+        Self {
+            encoding,
+            doc_interner,
+            doc_analysis_map,
+        }
+    }
+
+    fn doc_analysis(&self, uri: &Url) -> Option<&'a DocAnalysis> {
+        let doc = self.doc_interner.get_doc(&CanonicalUri::from_url(uri))?;
+        self.doc_analysis_map.get(&doc)
+    }
+
+    fn convert_position(&mut self, uri: &Url, position: &mut Position, dir: Direction) {
+        let da = match self.doc_analysis(uri) {
+            Some(it) => it,
+            None => return,
+        };
+        let row = position.line;
+        let column = position.character;
+        let utf16 = |p: Pos| (p.row, p.column16);
+        let utf8 = |p: Pos| (p.row, p.column);
+
+        let (pos, character) = match (self.encoding, dir) {
+            (PositionEncoding::Utf16, _) => return,
+            (PositionEncoding::Utf8, Direction::ToClient) => {
+                let pos = find_pos(&da.tokens, (row, column), utf16);
+                (pos, pos.column)
+            }
+            (PositionEncoding::Utf8, Direction::FromClient) => {
+                let pos = find_pos(&da.tokens, (row, column), utf8);
+                (pos, pos.column16)
+            }
+            (PositionEncoding::ShiftJis, Direction::ToClient) => {
+                let pos = find_pos(&da.tokens, (row, column), utf16);
+                let line = &da.text[(pos.index - pos.column) as usize..pos.index as usize];
+                (pos, shift_jis_len(line))
+            }
+            (PositionEncoding::ShiftJis, Direction::FromClient) => {
+                let line_start = find_pos(&da.tokens, (row, 0), utf16);
+                let pos = shift_jis_advance(&da.text, line_start, column);
+                (pos, pos.column16)
+            }
+        };
+
+        // ドキュメントの範囲外の行は変換しない。
+        if pos.row == row {
+            position.character = character;
+        }
+    }
+
+    fn convert<T: MapPositions + ?Sized>(&mut self, uri: &Url, value: &mut T, dir: Direction) {
+        if self.encoding == PositionEncoding::Utf16 {
+            return;
+        }
+        value.map_positions(uri, &mut |uri, position| {
+            self.convert_position(uri, position, dir)
+        });
+    }
+
+    /// レスポンスに含まれる位置情報をクライアントの単位に変換する
+    pub(crate) fn map_to_client<T: MapPositions + ?Sized>(&mut self, uri: &Url, value: &mut T) {
+        self.convert(uri, value, Direction::ToClient);
+    }
+
+    /// リクエストに含まれる位置情報をクライアントの単位から変換する
+    pub(crate) fn map_from_client<T: MapPositions + ?Sized>(&mut self, uri: &Url, value: &mut T) {
+        self.convert(uri, value, Direction::FromClient);
+    }
+
+    /// セマンティックトークンの位置と長さをクライアントの単位に変換する
+    ///
+    /// (トークンは直前のトークンからの相対位置で表されているため、いったん絶対位置に戻してから変換する。)
+    pub(crate) fn semantic_tokens_to_client(&mut self, uri: &Url, tokens: &mut [SemanticToken]) {
+        if self.encoding == PositionEncoding::Utf16 {
+            return;
+        }
+
+        let mut row = 0;
+        let mut column16 = 0;
+        let mut last_row = 0;
+        let mut last_column = 0;
+        for token in tokens {
+            if token.delta_line != 0 {
+                row += token.delta_line;
+                column16 = 0;
+            }
+            column16 += token.delta_start;
+
+            let mut start = Position::new(row, column16);
+            let mut end = Position::new(row, column16 + token.length);
+            self.convert_position(uri, &mut start, Direction::ToClient);
+            self.convert_position(uri, &mut end, Direction::ToClient);
+
+            token.delta_start = if row == last_row {
+                start.character - last_column
+            } else {
+                start.character
+            };
+            token.length = end.character - start.character;
+            last_row = row;
+            last_column = start.character;
+        }
+    }
+}
+
+/// 字句の列を使って、行・列番号が指す位置を求める
+///
+/// `key` は位置を (行, 列番号) に変換する関数で、列番号の単位を決める。
+/// 位置を含む字句を二分探索で見つけて、その字句の中だけを数える。
+/// (文字の途中を指しているときは、その文字の先頭とみなす。行末を越えるときは行末になる。)
+fn find_pos(tokens: &[PToken], target: (u32, u32), key: fn(Pos) -> (u32, u32)) -> Pos {
+    let i = tokens
+        .partition_point(|t| key(t.ahead().start()) <= target)
+        .saturating_sub(1);
+    let token = match tokens.get(i) {
+        Some(it) => it,
+        None => return Pos::default(),
+    };
+
+    let mut pos = token.ahead().start();
+    for t in token.iter() {
+        for (j, c) in t.text.char_indices() {
+            let next = pos + Pos::from(&t.text[j..j + c.len_utf8()]);
+            if key(next) > target {
+                return pos;
+            }
+            pos = next;
+        }
+    }
+    pos
+}
+
+impl MapPositions for Position {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        f(uri, self);
+    }
+}
+
+impl MapPositions for Range {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        f(uri, &mut self.start);
+        f(uri, &mut self.end);
+    }
+}
+
+impl<T: MapPositions> MapPositions for Option<T> {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        if let Some(value) = self {
+            value.map_positions(uri, f);
+        }
+    }
+}
+
+impl<T: MapPositions> MapPositions for [T] {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        for value in self {
+            value.map_positions(uri, f);
+        }
+    }
+}
+
+impl<T: MapPositions> MapPositions for Vec<T> {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        self.as_mut_slice().map_positions(uri, f);
+    }
+}
+
+impl MapPositions for Location {
+    fn map_positions(&mut self, _uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        self.range.map_positions(&self.uri, f);
+    }
+}
+
+impl MapPositions for TextEdit {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        self.range.map_positions(uri, f);
+    }
+}
+
+impl MapPositions for TextDocumentEdit {
+    fn map_positions(&mut self, _uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        let uri = &self.text_document.uri;
+        for edit in &mut self.edits {
+            match edit {
+                OneOf::Left(edit) => edit.map_positions(uri, f),
+                OneOf::Right(edit) => edit.text_edit.map_positions(uri, f),
+            }
+        }
+    }
+}
+
+impl MapPositions for WorkspaceEdit {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        if let Some(changes) = &mut self.changes {
+            for (uri, edits) in changes {
+                edits.map_positions(uri, f);
+            }
+        }
+
+        match &mut self.document_changes {
+            Some(DocumentChanges::Edits(edits)) => edits.map_positions(uri, f),
+            Some(DocumentChanges::Operations(operations)) => {
+                for operation in operations {
+                    if let DocumentChangeOperation::Edit(edit) = operation {
+                        edit.map_positions(uri, f);
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+impl MapPositions for Diagnostic {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        self.range.map_positions(uri, f);
+        if let Some(related) = &mut self.related_information {
+            for info in related {
+                info.location.map_positions(uri, f);
+            }
+        }
+    }
+}
+
+impl MapPositions for CodeActionContext {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        self.diagnostics.map_positions(uri, f);
+    }
+}
+
+impl MapPositions for CodeAction {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        self.diagnostics.map_positions(uri, f);
+        self.edit.map_positions(uri, f);
+    }
+}
+
+impl MapPositions for CodeLens {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        self.range.map_positions(uri, f);
+
+        // 参照を表示するコマンドの引数 (URI, 位置, 参照箇所のリスト) も変換する。
+        // (`data` はサーバー側で解釈するものなので変換しない。)
+        let args = match &mut self.command {
+            Some(Command {
+                command,
+                arguments: Some(args),
+                ..
+            }) if command == SHOW_REFERENCES_COMMAND && args.len() == 3 => args,
+            _ => return,
+        };
+        let (uri, mut position, mut locations) = match (
+            serde_json::from_value::<Url>(args[0].clone()),
+            serde_json::from_value::<Position>(args[1].clone()),
+            serde_json::from_value::<Vec<Location>>(args[2].clone()),
+        ) {
+            (Ok(uri), Ok(position), Ok(locations)) => (uri, position, locations),
+            _ => return,
+        };
+        position.map_positions(&uri, f);
+        locations.map_positions(&uri, f);
+        args[1] = serde_json::to_value(position).unwrap();
+        args[2] = serde_json::to_value(locations).unwrap();
+    }
+}
+
+impl MapPositions for CompletionItem {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        match &mut self.text_edit {
+            Some(CompletionTextEdit::Edit(edit)) => edit.map_positions(uri, f),
+            Some(CompletionTextEdit::InsertAndReplace(edit)) => {
+                edit.insert.map_positions(uri, f);
+                edit.replace.map_positions(uri, f);
+            }
+            None => {}
+        }
+        self.additional_text_edits.map_positions(uri, f);
+    }
+}
+
+impl MapPositions for CompletionList {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        self.items.map_positions(uri, f);
+    }
+}
+
+impl MapPositions for DocumentHighlight {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        self.range.map_positions(uri, f);
+    }
+}
+
+impl MapPositions for DocumentSymbol {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        self.range.map_positions(uri, f);
+        self.selection_range.map_positions(uri, f);
+        self.children.map_positions(uri, f);
+    }
+}
+
+impl MapPositions for SymbolInformation {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        self.location.map_positions(uri, f);
+    }
+}
+
+impl MapPositions for DocumentSymbolResponse {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        match self {
+            DocumentSymbolResponse::Flat(symbols) => symbols.map_positions(uri, f),
+            DocumentSymbolResponse::Nested(symbols) => symbols.map_positions(uri, f),
+        }
+    }
+}
+
+impl MapPositions for Hover {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        self.range.map_positions(uri, f);
+    }
+}
+
+impl MapPositions for PrepareRenameResponse {
+    fn map_positions(&mut self, uri: &Url, f: &mut dyn FnMut(&Url, &mut Position)) {
+        match self {
+            PrepareRenameResponse::Range(range) => range.map_positions(uri, f),
+            PrepareRenameResponse::RangeWithPlaceholder { range, .. } => {
+                range.map_positions(uri, f)
+            }
+            PrepareRenameResponse::DefaultBehavior { .. } => {}
+        }
+    }
+}
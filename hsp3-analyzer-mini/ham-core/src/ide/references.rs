@@ -34,6 +34,64 @@ pub(crate) fn references(
     )
 }
 
+/// 書き込みの箇所だけを列挙する (LSPの拡張)
+///
+/// 定義箇所 (代入や `dim` など) と、参照渡しの引数として渡されている箇所を書き込みとみなす。
+pub(crate) fn write_references(
+    an: &AnalyzerRef<'_>,
+    doc_interner: &DocInterner,
+    uri: Url,
+    position: Position,
+) -> Option<Vec<Location>> {
+    let (doc, pos) = from_document_position(doc_interner, &uri, position)?;
+    let (symbol, _) = an.locate_symbol(doc, pos)?;
+
+    let mut locs = vec![];
+    collect_symbol_occurrences(
+        an,
+        CollectSymbolOptions {
+            include_def: true,
+            include_use: false,
+        },
+        &symbol,
+        &mut locs,
+    );
+
+    let mut use_locs = vec![];
+    collect_symbol_occurrences(
+        an,
+        CollectSymbolOptions {
+            include_def: false,
+            include_use: true,
+        },
+        &symbol,
+        &mut use_locs,
+    );
+
+    let mut by_ref_sites = vec![];
+    let mut docs = use_locs.iter().map(|loc| loc.doc).collect::<Vec<_>>();
+    docs.sort();
+    docs.dedup();
+    for doc in docs {
+        collect_by_ref_arg_sites(an, doc, &mut by_ref_sites);
+    }
+    locs.extend(
+        use_locs
+            .into_iter()
+            .filter(|loc| by_ref_sites.contains(loc)),
+    );
+
+    // ソートして重複を取り除く
+    locs.sort();
+    locs.dedup();
+
+    Some(
+        locs.into_iter()
+            .filter_map(|loc| loc_to_location(doc_interner, loc))
+            .collect(),
+    )
+}
+
 // ===============================================
 
 #[cfg(test)]
@@ -137,4 +195,49 @@ mod tests {
         "#]]
         .assert_eq(&formatted);
     }
+
+    #[test]
+    fn test_write_references() {
+        let mut an = Analyzer::new_standalone();
+
+        let main_url = dummy_url("main.hsp");
+        an.open_doc(
+            main_url.clone(),
+            NO_VERSION,
+            r#"#module
+#deffunc set_one var x
+    x = 1
+    return
+#defcfunc peek_one int x
+    return x
+#defcfunc inc_one var x
+    x++
+    return x
+#global
+
+    g = 0
+    set_one g
+    mes peek_one(g)
+    dim g, 2
+    n = inc_one((g))
+"#
+            .into(),
+        );
+        let an = an.compute_ref();
+
+        let mut formatted = String::new();
+        format_response(
+            &mut formatted,
+            // `g = 0` の `g`
+            &an.write_references(main_url, lsp_types::Position::new(11, 4)),
+        );
+
+        expect![[r#"
+            main.hsp:12:5
+            main.hsp:13:13
+            main.hsp:15:9
+            main.hsp:16:18
+        "#]]
+        .assert_eq(&formatted);
+    }
 }
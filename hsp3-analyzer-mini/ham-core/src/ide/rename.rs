@@ -1,7 +1,11 @@
 //! 名前変更
 
 use super::*;
-use crate::lsp_server::NO_VERSION;
+use crate::{
+    analysis::{NamePath, Qual},
+    i18n::{tr, tr_fmt},
+    lsp_server::NO_VERSION,
+};
 use lsp_types::{
     DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier, Position,
     PrepareRenameResponse, TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
@@ -22,6 +26,98 @@ pub(crate) fn prepare_rename(
     Some(PrepareRenameResponse::Range(range))
 }
 
+/// 名前の変更によって、同じ環境にある既存のシンボルと名前が衝突するか？
+///
+/// (同じスコープ、または同じ名前空間に同じ名前のシンボルがあれば衝突とみなす。)
+fn collides(an: &AnalyzerRef<'_>, symbol: &SymbolRc, new_name: &str) -> bool {
+    let same_env = |other: &SymbolRc| {
+        let same_scope = match (&symbol.scope_opt, &other.scope_opt) {
+            (Some(Scope::Global), Some(Scope::Global)) => true,
+            (Some(Scope::Local(l)), Some(Scope::Local(r))) => l == r,
+            _ => false,
+        };
+        let same_ns = symbol.ns_opt.is_some() && symbol.ns_opt == other.ns_opt;
+        same_scope || same_ns
+    };
+
+    an.doc_symbols_map
+        .iter()
+        .filter(|&(&doc, _)| an.is_active_doc(doc))
+        .flat_map(|(_, symbols)| symbols.iter())
+        .any(|other| {
+            other != symbol && other.name.eq_ignore_ascii_case(new_name) && same_env(other)
+        })
+}
+
+/// 名前を書き換える箇所を列挙する
+///
+/// - 修飾付きの名前 `name@mod` の出現箇所では、`@mod` の部分を維持する
+/// - モジュールの名前を変更するときは、そのモジュールで修飾された名前 `xxx@mod` と `#use` も書き換える
+fn collect_rename_edits(
+    an: &AnalyzerRef<'_>,
+    symbol: &SymbolRc,
+    new_name: &str,
+    edits: &mut Vec<(Loc, String)>,
+) {
+    let mut locs = vec![];
+    collect_symbol_occurrences(
+        an,
+        CollectSymbolOptions {
+            include_def: true,
+            include_use: true,
+        },
+        symbol,
+        &mut locs,
+    );
+
+    for loc in locs {
+        let text = match an.doc_analysis_map.get(&loc.doc) {
+            Some(da) => &da.text[loc.range.start().index as usize..loc.range.end().index as usize],
+            None => continue,
+        };
+
+        let suffix = match text.rfind('@') {
+            Some(i) => &text[i..],
+            None => "",
+        };
+        edits.push((loc, format!("{}{}", new_name, suffix)));
+    }
+
+    if symbol.kind != HspSymbolKind::Module {
+        return;
+    }
+
+    let old_name = symbol.name();
+    for (&doc, da) in an.doc_analysis_map.iter() {
+        if !an.is_active_doc(doc) {
+            continue;
+        }
+
+        // 修飾付きの名前
+        for token in da.tokens.iter() {
+            if token.body.kind != TokenKind::Ident {
+                continue;
+            }
+
+            let name = NamePath::new(&token.body.text);
+            let is_target = match &name.qual {
+                Qual::Module(ns) => ns.eq_ignore_ascii_case(&old_name),
+                _ => false,
+            };
+            if is_target {
+                edits.push((token.body.loc, format!("{}@{}", name.base, new_name)));
+            }
+        }
+
+        // `#use`
+        for (used_name, loc) in &da.uses {
+            if *used_name == old_name.to_ascii_lowercase() {
+                edits.push((*loc, new_name.to_string()));
+            }
+        }
+    }
+}
+
 pub(crate) fn rename(
     an: &AnalyzerRef<'_>,
     doc_interner: &DocInterner,
@@ -29,38 +125,40 @@ pub(crate) fn rename(
     uri: Url,
     position: Position,
     new_name: String,
-) -> Option<WorkspaceEdit> {
-    // カーソルの下にある識別子と同一のシンボルの出現箇所 (定義箇所および使用箇所) を列挙する。
-    let locs = {
-        let (doc, pos) = from_document_position(doc_interner, &uri, position)?;
-        let (symbol, _) = an.locate_symbol(doc, pos)?;
-
-        let mut locs = vec![];
-        collect_symbol_occurrences(
-            an,
-            CollectSymbolOptions {
-                include_def: true,
-                include_use: true,
-            },
-            &symbol,
-            &mut locs,
-        );
-        if locs.is_empty() {
-            return None;
-        }
+) -> Result<Option<WorkspaceEdit>, String> {
+    let (doc, pos) = match from_document_position(doc_interner, &uri, position) {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    let symbol = match an.locate_symbol(doc, pos) {
+        Some((symbol, _)) => symbol,
+        None => return Ok(None),
+    };
 
-        // ソートして重複を取り除く
-        // (重複した変更をレスポンスに含めると名前の変更に失敗する)
-        locs.sort();
-        locs.dedup();
+    if new_name.contains('@') {
+        return Err(tr(an.locale(), "rename.contains_at").to_string());
+    }
 
-        locs
-    };
+    if collides(an, &symbol, &new_name) {
+        return Err(tr_fmt(an.locale(), "rename.collision", &[&new_name]));
+    }
+
+    // 書き換える箇所を列挙する。
+    let mut locs = vec![];
+    collect_rename_edits(an, &symbol, &new_name, &mut locs);
+    if locs.is_empty() {
+        return Ok(None);
+    }
 
-    // 名前変更の編集手順を構築する。(シンボルが書かれている位置をすべて新しい名前で置き換える。)
+    // ソートして重複を取り除く
+    // (重複した変更をレスポンスに含めると名前の変更に失敗する)
+    locs.sort_by_key(|&(loc, _)| loc);
+    locs.dedup_by_key(|&mut (loc, _)| loc);
+
+    // 名前変更の編集手順を構築する。
     let changes = {
         let mut edits = vec![];
-        for loc in locs {
+        for (loc, new_text) in locs {
             let location = match loc_to_location(doc_interner, loc) {
                 Some(location) => location,
                 None => continue,
@@ -70,7 +168,7 @@ pub(crate) fn rename(
 
             // common ディレクトリのファイルは変更しない。
             if uri.as_str().contains("common") {
-                return None;
+                return Ok(None);
             }
 
             let version = docs.get_version(loc.doc).unwrap_or(NO_VERSION);
@@ -79,10 +177,7 @@ pub(crate) fn rename(
                 uri,
                 version: Some(version),
             };
-            let text_edit = TextEdit {
-                range,
-                new_text: new_name.to_string(),
-            };
+            let text_edit = TextEdit { range, new_text };
 
             edits.push(TextDocumentEdit {
                 text_document,
@@ -93,8 +188,108 @@ pub(crate) fn rename(
         DocumentChanges::Edits(edits)
     };
 
-    Some(WorkspaceEdit {
+    Ok(Some(WorkspaceEdit {
         document_changes: Some(changes),
         ..WorkspaceEdit::default()
-    })
+    }))
+}
+
+// ===============================================
+
+#[cfg(test)]
+mod tests {
+    use crate::{analyzer::Analyzer, lsp_server::NO_VERSION};
+    use expect_test::expect;
+    use lsp_types::{DocumentChanges, OneOf, Position, WorkspaceEdit};
+    use std::fmt::Write as _;
+
+    fn dummy_url(s: &str) -> lsp_types::Url {
+        let workspace_dir = crate::test_utils::dummy_path().join("ws");
+        lsp_types::Url::from_file_path(workspace_dir.join(s)).unwrap()
+    }
+
+    fn format_response(w: &mut String, res: Result<Option<WorkspaceEdit>, String>) {
+        let edit = match res {
+            Ok(Some(it)) => it,
+            Ok(None) => {
+                *w += "None\n";
+                return;
+            }
+            Err(err) => {
+                writeln!(w, "Err({})", err).unwrap();
+                return;
+            }
+        };
+
+        if let Some(DocumentChanges::Edits(edits)) = edit.document_changes {
+            for e in edits {
+                for edit in e.edits {
+                    if let OneOf::Left(edit) = edit {
+                        let start = edit.range.start;
+                        writeln!(
+                            w,
+                            "{}:{} {}",
+                            start.line + 1,
+                            start.character + 1,
+                            edit.new_text
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    const TEXT: &str = r#"#module m_foo
+#deffunc local foo_hello
+    return
+#deffunc local foo_bye
+    foo_hello
+    return
+#global
+
+#use m_foo
+    foo_hello@m_foo
+    newmod x, m_foo
+"#;
+
+    #[test]
+    fn test_rename_qualified() {
+        let mut an = Analyzer::new_standalone();
+        let main_url = dummy_url("main.hsp");
+        an.open_doc(main_url.clone(), NO_VERSION, TEXT.into());
+
+        let mut formatted = String::new();
+        let an = an.compute_ref();
+
+        // `foo_hello@m_foo` の `@m_foo` は維持される。
+        formatted += "# foo_hello -> hi\n";
+        let res = an.rename(main_url.clone(), Position::new(1, 16), "hi".to_string());
+        format_response(&mut formatted, res);
+
+        // モジュールの名前を変更すると、修飾付きの名前や #use も書き換わる。
+        formatted += "# m_foo -> m_bar\n";
+        let res = an.rename(main_url.clone(), Position::new(0, 9), "m_bar".to_string());
+        format_response(&mut formatted, res);
+
+        // 同じ環境にある名前とは衝突する。
+        formatted += "# foo_hello -> foo_bye\n";
+        let res = an.rename(main_url, Position::new(1, 16), "foo_bye".to_string());
+        format_response(&mut formatted, res);
+
+        expect![[r#"
+            # foo_hello -> hi
+            2:16 hi
+            5:5 hi
+            10:5 hi@m_foo
+            # m_foo -> m_bar
+            1:9 m_bar
+            9:6 m_bar
+            10:5 foo_hello@m_bar
+            11:15 m_bar
+            # foo_hello -> foo_bye
+            Err(foo_bye という名前はすでに使われています。)
+        "#]]
+        .assert_eq(&formatted);
+    }
 }
@@ -0,0 +1,337 @@
+//! ファイル名の変更・移動 (`#include` などのファイル名の書き換え)
+
+use super::*;
+use crate::{analyzer::docs::resolve_included_name, lsp_server::NO_VERSION};
+use lsp_types::{
+    DocumentChanges, FileRename, OneOf, OptionalVersionedTextDocumentIdentifier, TextDocumentEdit,
+    TextEdit, WorkspaceEdit,
+};
+use std::path::Component;
+
+/// `base_dir` から `target` への相対パスを `/` 区切りで表す
+///
+/// (ドライブが異なるなど、相対パスで表せないときはNone)
+fn relative_path(base_dir: &Path, target: &Path) -> Option<String> {
+    let base = base_dir.components().collect::<Vec<_>>();
+    let target = target.components().collect::<Vec<_>>();
+
+    let common_len = base.iter().zip(&target).take_while(|(l, r)| l == r).count();
+    if common_len == 0 {
+        return None;
+    }
+
+    let mut parts = vec![];
+    for _ in &base[common_len..] {
+        parts.push("..".to_string());
+    }
+    for c in &target[common_len..] {
+        match c {
+            Component::Normal(name) => parts.push(name.to_string_lossy().to_string()),
+            _ => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// 名前を変更されるドキュメントの、変更後のファイルパスを列挙する
+///
+/// (フォルダの名前が変更されるときは、そのフォルダに含まれるドキュメントをすべて列挙する)
+fn collect_renamed_docs(
+    an: &AnalyzerRef<'_>,
+    doc_interner: &DocInterner,
+    files: &[FileRename],
+) -> HashMap<DocId, PathBuf> {
+    let mut renamed = HashMap::new();
+
+    for file in files {
+        let path_of = |uri: &str| {
+            let url = Url::parse(uri).ok()?;
+            CanonicalUri::from_url(&url).to_file_path()
+        };
+        let (old_path, new_path) = match (path_of(&file.old_uri), path_of(&file.new_uri)) {
+            (Some(old_path), Some(new_path)) => (old_path, new_path),
+            _ => continue,
+        };
+
+        for &doc in an.doc_analysis_map.keys() {
+            let path = match doc_interner.get_uri(doc).and_then(|uri| uri.to_file_path()) {
+                Some(it) => it,
+                None => continue,
+            };
+
+            if let Ok(rest) = path.strip_prefix(&old_path) {
+                renamed.insert(doc, new_path.join(rest));
+            }
+        }
+    }
+
+    renamed
+}
+
+pub(crate) fn will_rename_files(
+    an: &AnalyzerRef<'_>,
+    doc_interner: &DocInterner,
+    docs: &Docs,
+    files: Vec<FileRename>,
+) -> Option<WorkspaceEdit> {
+    let renamed = collect_renamed_docs(an, doc_interner, &files);
+    if renamed.is_empty() {
+        return None;
+    }
+
+    let path_of = |doc: DocId| -> Option<PathBuf> {
+        match renamed.get(&doc) {
+            Some(path) => Some(path.clone()),
+            None => doc_interner.get_uri(doc)?.to_file_path(),
+        }
+    };
+
+    let mut doc_edits: HashMap<DocId, Vec<TextEdit>> = HashMap::new();
+
+    for &(loc, dest_doc) in an.include_resolution {
+        let src_doc = loc.doc;
+        if !renamed.contains_key(&src_doc) && !renamed.contains_key(&dest_doc) {
+            continue;
+        }
+
+        let da = match an.doc_analysis_map.get(&src_doc) {
+            Some(it) => it,
+            None => continue,
+        };
+
+        // `#use` の場合: モジュール名をファイル名に合わせる
+        if let Some((used_name, _)) = da.uses.iter().find(|(_, l)| *l == loc) {
+            let new_stem = match renamed.get(&dest_doc).and_then(|path| path.file_stem()) {
+                Some(it) => it.to_string_lossy().to_string(),
+                None => continue,
+            };
+            if new_stem.to_ascii_lowercase() != *used_name {
+                doc_edits.entry(src_doc).or_default().push(TextEdit {
+                    range: loc_to_range(loc),
+                    new_text: new_stem,
+                });
+            }
+            continue;
+        }
+
+        // `#include` の場合: ファイルパスの文字列を書き換える
+        let included_name = match da.includes.iter().find(|(_, l)| *l == loc) {
+            Some((name, _)) => name,
+            None => continue,
+        };
+        let token = match da.tokens.iter().find(|t| {
+            t.body.kind == TokenKind::Str && loc.range.contains_inclusive(t.body.loc.start())
+        }) {
+            Some(it) => it,
+            None => continue,
+        };
+
+        let dest_path = match path_of(dest_doc) {
+            Some(it) => it,
+            None => continue,
+        };
+
+        // 相対パスで書かれていたら相対パスのまま、
+        // commonにあるファイルとして書かれていたらcommonからのパスのまま書き換える
+        let is_relative = resolve_included_name(doc_interner, included_name, src_doc).is_some();
+        let new_name_opt = if is_relative {
+            path_of(src_doc).and_then(|src_path| relative_path(src_path.parent()?, &dest_path))
+        } else if renamed.contains_key(&dest_doc) {
+            // (commonはインクルードする側が対象とするHSPのもの)
+            an.hsp3_root_of(src_doc)
+                .dir_opt
+                .as_ref()
+                .and_then(|hsp3_root| relative_path(&hsp3_root.join("common"), &dest_path))
+        } else {
+            None
+        };
+        let mut new_name = match new_name_opt {
+            Some(it) => it,
+            None => continue,
+        };
+
+        // 変化がなければ書き換えない
+        if new_name.to_ascii_lowercase() == **included_name {
+            continue;
+        }
+
+        // 区切り文字の書きかたを維持する
+        if token.body_text().contains('\\') {
+            new_name = new_name.replace('/', "\\\\");
+        }
+
+        doc_edits.entry(src_doc).or_default().push(TextEdit {
+            range: loc_to_range(token.body.loc),
+            new_text: format!("\"{}\"", new_name),
+        });
+    }
+
+    if doc_edits.is_empty() {
+        return None;
+    }
+
+    let mut edits = vec![];
+    for (doc, mut text_edits) in doc_edits {
+        let uri = match doc_interner.get_uri(doc) {
+            Some(uri) => uri.clone().into_url(),
+            None => continue,
+        };
+
+        // HSPのディレクトリにあるファイルは変更しない。
+        let in_hsp3_root = match uri.to_file_path() {
+            Ok(path) => an.is_in_hsp3_root(&path),
+            Err(()) => false,
+        };
+        if in_hsp3_root {
+            continue;
+        }
+
+        text_edits.sort_by_key(|e| (e.range.start, e.range.end));
+        let version = docs.get_version(doc).unwrap_or(NO_VERSION);
+        edits.push(TextDocumentEdit {
+            text_document: OptionalVersionedTextDocumentIdentifier {
+                uri,
+                version: Some(version),
+            },
+            edits: text_edits.into_iter().map(OneOf::Left).collect(),
+        });
+    }
+    edits.sort_by(|l, r| l.text_document.uri.cmp(&r.text_document.uri));
+
+    Some(WorkspaceEdit {
+        document_changes: Some(DocumentChanges::Edits(edits)),
+        ..WorkspaceEdit::default()
+    })
+}
+
+// ===============================================
+
+#[cfg(test)]
+mod tests {
+    use super::relative_path;
+    use crate::{analyzer::Analyzer, lsp_server::NO_VERSION};
+    use expect_test::expect;
+    use lsp_types::{DocumentChanges, FileRename, OneOf};
+    use std::{fmt::Write as _, path::Path};
+
+    fn dummy_url(s: &str) -> lsp_types::Url {
+        let workspace_dir = crate::test_utils::dummy_path().join("ws");
+        lsp_types::Url::from_file_path(workspace_dir.join(s)).unwrap()
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let p = |base: &str, target: &str| relative_path(Path::new(base), Path::new(target));
+        assert_eq!(p("/ws", "/ws/a.as").as_deref(), Some("a.as"));
+        assert_eq!(p("/ws/x", "/ws/y/a.as").as_deref(), Some("../y/a.as"));
+        assert_eq!(p("/ws/x/y", "/ws/a.as").as_deref(), Some("../../a.as"));
+    }
+
+    #[test]
+    fn test_will_rename_files() {
+        let mut an = Analyzer::new_standalone();
+
+        an.open_doc(
+            dummy_url("main.hsp"),
+            NO_VERSION,
+            r#"#include "mod_a.as"
+#include "sub/mod_b.as"
+    a_hello
+"#
+            .into(),
+        );
+        an.open_doc(
+            dummy_url("mod_a.as"),
+            NO_VERSION,
+            "#module m_a\n#deffunc a_hello\n    return\n#global\n".into(),
+        );
+        an.open_doc(
+            dummy_url("sub/mod_b.as"),
+            NO_VERSION,
+            "#include \"../mod_a.as\"\n".into(),
+        );
+
+        let res = an.compute_ref().will_rename_files(vec![FileRename {
+            old_uri: dummy_url("mod_a.as").to_string(),
+            new_uri: dummy_url("lib/mod_x.as").to_string(),
+        }]);
+
+        let mut formatted = String::new();
+        if let Some(DocumentChanges::Edits(edits)) = res.and_then(|e| e.document_changes) {
+            for e in edits {
+                let uri = e.text_document.uri;
+                for edit in e.edits {
+                    if let OneOf::Left(edit) = edit {
+                        writeln!(
+                            formatted,
+                            "{} {}:{} {}",
+                            uri.path_segments().unwrap().next_back().unwrap(),
+                            edit.range.start.line + 1,
+                            edit.range.start.character + 1,
+                            edit.new_text
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+        }
+
+        expect![[r#"
+            main.hsp 1:10 "lib/mod_x.as"
+            mod_b.as 1:10 "../lib/mod_x.as"
+        "#]]
+        .assert_eq(&formatted);
+    }
+
+    #[test]
+    fn test_will_rename_files_use() {
+        let mut an = Analyzer::new_with_hsp3_files(&[(
+            "common/mod_a.as",
+            "#module m_a\n#deffunc a_hello\n    return\n#global\n",
+        )]);
+        an.did_initialize();
+
+        an.open_doc(
+            dummy_url("main.hsp"),
+            NO_VERSION,
+            "#use mod_a\n    a_hello\n".into(),
+        );
+
+        let common_url = |s: &str| {
+            let common_dir = crate::test_utils::dummy_path().join("hsp3/common");
+            lsp_types::Url::from_file_path(common_dir.join(s)).unwrap()
+        };
+        let res = an.compute_ref().will_rename_files(vec![FileRename {
+            old_uri: common_url("mod_a.as").to_string(),
+            new_uri: common_url("Mod_X.as").to_string(),
+        }]);
+
+        let mut formatted = String::new();
+        if let Some(DocumentChanges::Edits(edits)) = res.and_then(|e| e.document_changes) {
+            for e in edits {
+                let uri = e.text_document.uri;
+                for edit in e.edits {
+                    if let OneOf::Left(edit) = edit {
+                        writeln!(
+                            formatted,
+                            "{} {}:{}..{}:{} {}",
+                            uri.path_segments().unwrap().next_back().unwrap(),
+                            edit.range.start.line + 1,
+                            edit.range.start.character + 1,
+                            edit.range.end.line + 1,
+                            edit.range.end.character + 1,
+                            edit.new_text
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+        }
+
+        // `#use` のモジュール名だけが書き換わる。(commonにあるファイルは変更しない)
+        expect![[r#"
+            main.hsp 1:6..1:11 Mod_X
+        "#]]
+        .assert_eq(&formatted);
+    }
+}
@@ -1,87 +1,308 @@
 //! セマンティックハイライト
 
 use super::*;
-use crate::{analysis::*, parse::p_param_ty::PParamCategory};
+use crate::{analysis::*, parse::p_param_ty::PParamCategory, token::TokenData};
+use lsp_types::{
+    SemanticToken, SemanticTokens, SemanticTokensDelta, SemanticTokensEdit,
+    SemanticTokensFullDeltaResult,
+};
 
 // SemanticTokensLegend を参照
+pub(crate) mod token_types {
+    pub(crate) const PARAMETER: u32 = 0;
+    pub(crate) const VARIABLE: u32 = 1;
+    pub(crate) const FUNCTION: u32 = 2;
+    pub(crate) const MACRO: u32 = 3;
+    pub(crate) const NAMESPACE: u32 = 4;
+    pub(crate) const KEYWORD: u32 = 5;
+    pub(crate) const LABEL: u32 = 6;
+    pub(crate) const METHOD: u32 = 7;
+    pub(crate) const ENUM_MEMBER: u32 = 8;
+    pub(crate) const STRING: u32 = 9;
+    pub(crate) const NUMBER: u32 = 10;
+    pub(crate) const COMMENT: u32 = 11;
+    /// `#func` で定義されたDLLの関数
+    pub(crate) const DLL_FUNCTION: u32 = 12;
+}
+
+// SemanticTokensLegend を参照
+pub(crate) mod token_modifiers {
+    pub(crate) const READONLY: u32 = 1 << 0;
+    pub(crate) const STATIC: u32 = 1 << 1;
+    pub(crate) const DEFAULT_LIBRARY: u32 = 1 << 2;
+    pub(crate) const DEPRECATED: u32 = 1 << 3;
+    pub(crate) const DECLARATION: u32 = 1 << 4;
+}
+
+use self::{token_modifiers as m, token_types as t};
+
 fn to_semantic_token_kind(symbol: &SymbolRc) -> Option<(u32, u32)> {
     let (ty, modifiers) = match symbol.kind {
         HspSymbolKind::Param(Some(param)) => match param.category() {
-            PParamCategory::ByValue => (1, 0b01), // readonly variable
-            PParamCategory::ByRef => (0, 0),      // parameter
-            PParamCategory::Local => (1, 0),      // variable
+            PParamCategory::ByValue => (t::VARIABLE, m::READONLY),
+            PParamCategory::ByRef => (t::PARAMETER, 0),
+            PParamCategory::Local => (t::VARIABLE, 0),
             PParamCategory::Auto => return None,
         },
-        HspSymbolKind::StaticVar => (1, 0b10), // static variable
-        HspSymbolKind::Const | HspSymbolKind::Enum => (1, 0b01), // readonly variable
+        HspSymbolKind::StaticVar => (t::VARIABLE, m::STATIC),
+        HspSymbolKind::Const => (t::VARIABLE, m::READONLY),
+        HspSymbolKind::Enum => (t::ENUM_MEMBER, m::READONLY),
         HspSymbolKind::DefFunc
         | HspSymbolKind::DefCFunc
         | HspSymbolKind::ModFunc
-        | HspSymbolKind::ModCFunc
-        | HspSymbolKind::LibFunc
-        | HspSymbolKind::ComFunc => (2, 0), // function
-        HspSymbolKind::Macro { .. } => (3, 0), // macro
-        HspSymbolKind::Module => (4, 0),       // namespace
-        HspSymbolKind::PluginCmd => (5, 0),    // keyword
+        | HspSymbolKind::ModCFunc => (t::FUNCTION, 0),
+        HspSymbolKind::LibFunc => (t::DLL_FUNCTION, 0),
+        HspSymbolKind::ComFunc => (t::METHOD, 0),
+        HspSymbolKind::Macro { .. } => (t::MACRO, 0),
+        HspSymbolKind::Module => (t::NAMESPACE, 0),
+        HspSymbolKind::PluginCmd => (t::KEYWORD, 0),
+        HspSymbolKind::Label => (t::LABEL, 0),
+
+        // 標準命令など (hsphelpに書いてあるもの)
+        HspSymbolKind::Unknown if symbol.builtin => (t::FUNCTION, m::DEFAULT_LIBRARY),
 
         // Not supported:
         // HspSymbolKind::ComInterface => ?,
         _ => return None,
     };
+
+    let modifiers = if is_deprecated(symbol) {
+        modifiers | m::DEPRECATED
+    } else {
+        modifiers
+    };
     Some((ty, modifiers))
 }
 
-pub(crate) fn full(
-    an: &AnalyzerRef<'_>,
-    doc_interner: &DocInterner,
-    uri: Url,
-) -> Option<Vec<lsp_types::SemanticToken>> {
-    let doc = doc_interner.get_doc(&CanonicalUri::from_url(&uri))?;
+/// 廃止されたシンボルか？ (説明に「廃止」「非推奨」と書かれているもの)
+fn is_deprecated(symbol: &SymbolRc) -> bool {
+    // 説明の計算はコメントの解析を伴うので、ヘルプファイルのシンボルに限る。
+    if symbol.kind != HspSymbolKind::Unknown {
+        return false;
+    }
+
+    let details = symbol.compute_details();
+    details
+        .desc
+        .iter()
+        .map(|s| s.as_str())
+        .chain(details.docs.iter().map(|s| s.as_str()))
+        .any(|s| s.contains("廃止") || s.contains("非推奨"))
+}
+
+/// トークン (位置は差分でなく絶対位置)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct AbsToken {
+    line: u32,
+    start: u32,
+    length: u32,
+    ty: u32,
+    modifiers: u32,
+}
+
+/// リテラルやコメントのトークンを列挙する
+///
+/// (複数行にまたがるトークンは行ごとに分割する。)
+fn collect_literal_tokens(text: &str, token: &TokenData, tokens: &mut Vec<AbsToken>) {
+    let ty = match token.kind {
+        TokenKind::Str | TokenKind::Char => t::STRING,
+        TokenKind::Number => t::NUMBER,
+        TokenKind::Comment => t::COMMENT,
+        _ => return,
+    };
 
-    let mut symbols = vec![];
-    collect_symbol_occurrences_in_doc(an, doc, &mut symbols);
+    let range = token.loc.range;
+    let body = &text[range.start().index as usize..range.end().index as usize];
 
-    let mut tokens: Vec<lsp_types::SemanticToken> = vec![];
+    let Position {
+        mut line,
+        character: mut start,
+    } = to_position(range.start());
+    for part in body.split('\n') {
+        let part = part.strip_suffix('\r').unwrap_or(part);
+        let length = part.encode_utf16().count() as u32;
+        if length != 0 {
+            tokens.push(AbsToken {
+                line,
+                start,
+                length,
+                ty,
+                modifiers: 0,
+            });
+        }
+        line += 1;
+        start = 0;
+    }
+}
 
-    for (symbol, loc) in symbols {
-        assert_eq!(loc.doc, doc);
+fn collect_tokens(an: &AnalyzerRef<'_>, doc: DocId) -> Vec<AbsToken> {
+    let mut tokens = vec![];
 
-        let (token_type, token_modifiers_bitset) = match to_semantic_token_kind(&symbol) {
+    let occurrences = an
+        .def_sites
+        .iter()
+        .map(|(symbol, loc)| (symbol, loc, m::DECLARATION))
+        .chain(an.use_sites.iter().map(|(symbol, loc)| (symbol, loc, 0)));
+
+    for (symbol, loc, declaration) in occurrences {
+        if loc.doc != doc {
+            continue;
+        }
+
+        let (ty, modifiers) = match to_semantic_token_kind(symbol) {
             Some(it) => it,
             None => continue,
         };
 
-        let location = loc_to_location(doc_interner, loc)?;
-
-        let Position {
-            line: y1,
-            character: x1,
-        } = location.range.start;
-
-        tokens.push(lsp_types::SemanticToken {
-            delta_line: y1,
-            delta_start: x1,
+        let Position { line, character } = loc_to_range(*loc).start;
+        tokens.push(AbsToken {
+            line,
+            start: character,
             length: symbol.name().encode_utf16().count() as u32,
-            token_type,
-            token_modifiers_bitset,
+            ty,
+            modifiers: modifiers | declaration,
         });
     }
 
-    // Compute delta.
-    tokens.sort_by_key(|t| (t.delta_line, t.delta_start + t.length));
+    if let Some(da) = an.doc_analysis_map.get(&doc) {
+        for token in da.tokens.iter() {
+            for data in token
+                .leading
+                .iter()
+                .chain(Some(&*token.body))
+                .chain(token.trailing.iter())
+            {
+                collect_literal_tokens(&da.text, data, &mut tokens);
+            }
+        }
+    }
+
+    // 同じ位置のトークンは1つにする。(定義箇所かつ使用箇所であるものは定義箇所を優先する)
+    tokens.sort_by_key(|t| (t.line, t.start, !t.modifiers & m::DECLARATION));
+    tokens.dedup_by_key(|t| (t.line, t.start));
+    tokens
+}
+
+/// 差分形式に変換する
+fn encode(tokens: &[AbsToken]) -> Vec<SemanticToken> {
+    let mut data = Vec::with_capacity(tokens.len());
+    let (mut y1, mut x1) = (0, 0);
 
-    for i in (1..tokens.len()).rev() {
-        let y1 = tokens[i - 1].delta_line;
-        let x1 = tokens[i - 1].delta_start;
-        if tokens[i].delta_line == y1 {
-            tokens[i].delta_line = 0;
-            tokens[i].delta_start = tokens[i].delta_start.saturating_sub(x1);
+    for t in tokens {
+        let (delta_line, delta_start) = if t.line == y1 {
+            (0, t.start.saturating_sub(x1))
         } else {
-            tokens[i].delta_line -= y1;
+            (t.line - y1, t.start)
+        };
+
+        data.push(SemanticToken {
+            delta_line,
+            delta_start,
+            length: t.length,
+            token_type: t.ty,
+            token_modifiers_bitset: t.modifiers,
+        });
+        y1 = t.line;
+        x1 = t.start;
+    }
+
+    data
+}
+
+pub(crate) fn full(
+    an: &AnalyzerRef<'_>,
+    doc_interner: &DocInterner,
+    uri: Url,
+) -> Option<Vec<SemanticToken>> {
+    let doc = doc_interner.get_doc(&CanonicalUri::from_url(&uri))?;
+    Some(encode(&collect_tokens(an, doc)))
+}
+
+pub(crate) fn range(
+    an: &AnalyzerRef<'_>,
+    doc_interner: &DocInterner,
+    uri: Url,
+    range: lsp_types::Range,
+) -> Option<Vec<SemanticToken>> {
+    let doc = doc_interner.get_doc(&CanonicalUri::from_url(&uri))?;
+
+    let mut tokens = collect_tokens(an, doc);
+    tokens.retain(|t| range.start.line <= t.line && t.line <= range.end.line);
+    Some(encode(&tokens))
+}
+
+// -----------------------------------------------
+// Delta
+// -----------------------------------------------
+
+/// 前回のレスポンスを覚えておき、差分を計算するためのもの
+#[derive(Default)]
+pub(crate) struct SemanticTokensCache {
+    last_id: u64,
+    map: HashMap<Url, (String, Vec<SemanticToken>)>,
+}
+
+impl SemanticTokensCache {
+    fn next_id(&mut self) -> String {
+        self.last_id += 1;
+        self.last_id.to_string()
+    }
+
+    /// フルのレスポンスを作り、結果を覚えておく
+    pub(crate) fn full(&mut self, uri: Url, data: Vec<SemanticToken>) -> SemanticTokens {
+        let result_id = self.next_id();
+        self.map.insert(uri, (result_id.clone(), data.clone()));
+        SemanticTokens {
+            result_id: Some(result_id),
+            data,
         }
     }
 
-    Some(tokens)
+    /// 前回のレスポンスからの差分を計算する
+    ///
+    /// (前回のレスポンスが見つからなければフルのレスポンスを返す。)
+    pub(crate) fn delta(
+        &mut self,
+        uri: Url,
+        previous_result_id: &str,
+        data: Vec<SemanticToken>,
+    ) -> SemanticTokensFullDeltaResult {
+        let old = match self.map.get(&uri) {
+            Some((id, old)) if id == previous_result_id => old,
+            _ => return SemanticTokensFullDeltaResult::Tokens(self.full(uri, data)),
+        };
+
+        // 前後の一致する部分を除いた部分を1つの編集とする。
+        let prefix = old.iter().zip(&data).take_while(|(l, r)| l == r).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(data[prefix..].iter().rev())
+            .take_while(|(l, r)| l == r)
+            .count();
+
+        let edits = if prefix == old.len() && prefix == data.len() {
+            vec![]
+        } else {
+            // 位置と個数はトークンでなく整数の個数で数える。(トークン1個は整数5個)
+            vec![SemanticTokensEdit {
+                start: (prefix * 5) as u32,
+                delete_count: ((old.len() - prefix - suffix) * 5) as u32,
+                data: Some(data[prefix..data.len() - suffix].to_vec()),
+            }]
+        };
+
+        let result_id = self.next_id();
+        self.map.insert(uri, (result_id.clone(), data));
+        SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+            result_id: Some(result_id),
+            edits,
+        })
+    }
+
+    pub(crate) fn remove(&mut self, uri: &Url) {
+        self.map.remove(uri);
+    }
 }
 
 #[cfg(test)]
@@ -139,6 +360,63 @@ mod tests {
             )
             .unwrap();
         }
-        assert_eq!(sb, "2:8 1/1\n3:9 3/0\n4:15 3/0\n5:7 1/1\n6:6 5/0\n8:9 4/0\n9:11 2/0\n9:17 1/1\n9:24 0/0\n9:33 1/0\n13:5 1/2\n");
+        assert_eq!(sb, "2:8 1/17\n2:12 10/0\n3:9 3/16\n4:15 3/16\n4:23 10/0\n4:28 10/0\n5:7 8/17\n5:11 10/0\n6:6 5/16\n8:9 4/16\n9:11 2/16\n9:17 1/17\n9:24 0/16\n9:33 1/16\n13:5 1/18\n13:9 10/0\n");
+    }
+
+    #[test]
+    fn utf8_tokens_test() {
+        let mut an = Analyzer::new_standalone();
+        an.set_position_encoding(crate::source::PositionEncoding::Utf8);
+
+        let main_uri = dummy_url("utf8.hsp");
+        an.open_doc(
+            main_uri.clone(),
+            NO_VERSION,
+            "\ts = \"あい\": t = s\n".into(),
+        );
+
+        // (位置, 長さ) を UTF-8 のバイト数で数える。
+        let tokens = an.compute_ref().semantic_tokens(main_uri);
+        let mut sb = String::new();
+        let mut x = 0;
+        for t in tokens.data {
+            x += t.delta_start;
+            write!(sb, "{}+{} ", x, t.length).unwrap();
+        }
+        expect_test::expect!["1+1 5+8 15+1 19+1 "].assert_eq(&sb);
+    }
+
+    #[test]
+    fn delta_test() {
+        let token = |delta_line| SemanticToken {
+            delta_line,
+            delta_start: 0,
+            length: 1,
+            token_type: 0,
+            token_modifiers_bitset: 0,
+        };
+        let uri = dummy_url("delta.hsp");
+
+        let mut cache = SemanticTokensCache::default();
+        let first = cache.full(uri.clone(), vec![token(1), token(2), token(3)]);
+        let previous_id = first.result_id.unwrap();
+
+        // 2番目のトークンだけが変化した場合
+        let delta = cache.delta(
+            uri.clone(),
+            &previous_id,
+            vec![token(1), token(5), token(3)],
+        );
+        let edits = match delta {
+            SemanticTokensFullDeltaResult::TokensDelta(delta) => delta.edits,
+            _ => panic!("expected delta"),
+        };
+        assert_eq!(edits.len(), 1);
+        assert_eq!((edits[0].start, edits[0].delete_count), (5, 5));
+        assert_eq!(edits[0].data, Some(vec![token(5)]));
+
+        // 前回の結果IDが古ければフルのレスポンスを返す
+        let delta = cache.delta(uri, &previous_id, vec![]);
+        assert!(matches!(delta, SemanticTokensFullDeltaResult::Tokens(_)));
     }
 }
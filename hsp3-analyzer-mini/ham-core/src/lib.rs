@@ -1,11 +1,14 @@
 #[macro_use]
 extern crate log;
 
+pub mod api;
 pub mod c_api;
 pub mod subcommands;
 
 mod analyzer;
 mod help_source;
+mod hsp_version;
+mod i18n;
 mod ide;
 mod lang;
 mod lsp_server;
@@ -13,6 +16,7 @@ mod lsp_server;
 #[cfg(test)]
 mod tests {
     use super::*;
+    mod lsp_replay_tests;
     mod parse_tests;
     mod symbol_tests;
     mod tokenize_tests;
@@ -28,7 +32,10 @@ pub(crate) mod test_utils {
     pub(crate) use self::test_setup::set_test_logger;
 }
 
-pub use crate::lsp_server::lsp_main::run_lsp_server;
+pub use crate::{
+    i18n::{tr, Locale},
+    lsp_server::lsp_main::run_lsp_server,
+};
 
 /// 多くのモジュールからインポートされるシンボル:
 use crate::utils::{
@@ -55,11 +62,14 @@ mod analysis {
     use super::*;
 
     mod analysis_ref;
+    mod by_ref_args;
     mod comment;
     pub(crate) mod compute_active_docs;
     pub(crate) mod compute_includes;
     pub(crate) mod compute_symbols;
+    mod const_eval;
     pub(crate) mod doc_analysis;
+    mod doc_summary;
     mod name_system;
     mod preproc;
     pub(crate) mod sema_linter;
@@ -70,13 +80,19 @@ mod analysis {
     pub(crate) use self::{
         analysis_ref::{
             collect_doc_symbols, collect_highlights, collect_preproc_completion_items,
-            collect_symbol_occurrences, collect_symbol_occurrences_in_doc,
-            collect_symbols_in_scope, collect_workspace_symbols, find_include_target,
-            CollectSymbolOptions, DefOrUse, DocAnalysisMap, DocSyntax, SignatureHelpDb,
+            collect_symbol_occurrences, collect_symbols_in_scope, collect_workspace_symbols,
+            find_include_target, CollectSymbolOptions, DefOrUse, DocAnalysisMap, DocSyntax,
+            SignatureHelpDb,
         },
+        by_ref_args::collect_by_ref_arg_sites,
+        comment::{calculate_details, collect_comments},
+        const_eval::{collect_const_eval_errors, eval_const_symbol, ConstEvalError},
         doc_analysis::DocAnalysis,
+        doc_summary::DocSummary,
         name_system::*,
-        preproc::{IncludeGuard, PreprocAnalysisResult, SignatureData},
+        preproc::{
+            new_signature_data_for_deffunc, IncludeGuard, PreprocAnalysisResult, SignatureData,
+        },
         sema_linter::{Diagnostic, SemaLinter},
         symbol::{
             module_name_as_ident, DefFuncData, DefFuncKey, DefFuncMap, ModuleData, ModuleKey,
@@ -87,8 +103,8 @@ mod analysis {
     };
 
     use crate::{
-        analyzer::search_hsphelp::HspHelpInfo,
-        parse::{PRoot, PToken},
+        analyzer::hsp3_root::{Hsp3Root, HspTarget},
+        parse::{PParamTy, PRoot, PToken},
         source::*,
         token::{TokenData, TokenKind},
     };
@@ -110,6 +126,7 @@ mod parse {
     pub(crate) mod parse_expr;
     pub(crate) mod parse_preproc;
     pub(crate) mod parse_stmt;
+    mod reparse;
 
     pub(crate) use p_const_ty::PConstTy;
     pub(crate) use p_jump_modifier::PJumpModifier;
@@ -120,6 +137,7 @@ mod parse {
     pub(crate) use p_visitor::PVisitor;
 
     pub(crate) use parse_stmt::parse_root;
+    pub(crate) use reparse::reparse;
 
     use self::parse_context::Px;
     use super::*;
@@ -129,12 +147,37 @@ mod parse {
     };
 }
 
+mod cst {
+    //! 具象構文木 (CST)
+    //!
+    //! トリビアや読み飛ばされたトークンも含めて、テキストを欠けなく表す構文木。
+    //! ([rowan](https://github.com/rust-analyzer/rowan) を使っている。)
+
+    pub(crate) mod ast;
+    mod build_cst;
+    mod line_index;
+    mod syntax_kind;
+
+    pub(crate) use build_cst::build_cst;
+    pub(crate) use line_index::LineIndex;
+    pub(crate) use syntax_kind::*;
+
+    use super::*;
+    use crate::{
+        parse::*,
+        source::*,
+        token::{TokenData, TokenKind},
+    };
+}
+
 mod source {
     //! ソースファイルの位置情報など
 
     mod loc;
+    mod position_encoding;
 
     pub(crate) use loc::*;
+    pub(crate) use position_encoding::*;
 
     pub(crate) type DocId = usize;
     pub(crate) type Pos = text_position_rs::CompositePosition;
@@ -157,7 +200,7 @@ mod token {
 
     pub(crate) use token_data::TokenData;
     pub(crate) use token_kind::TokenKind;
-    pub(crate) use tokenize_rules::tokenize;
+    pub(crate) use tokenize_rules::{lint_tokens, tokenize, tokenize_at, TokenLint};
 
     use super::*;
     use crate::source::*;
@@ -1,6 +1,7 @@
 pub(super) mod lsp_config;
 pub(crate) mod lsp_log;
 pub(super) mod lsp_main;
+pub(crate) mod lsp_record;
 
 pub(self) use lsp_config::LspConfig;
 
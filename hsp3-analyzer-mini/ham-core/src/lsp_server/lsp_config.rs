@@ -1,6 +1,30 @@
+use crate::analyzer::file_scan::FileExtensions;
+use std::{env, path::PathBuf};
+
 #[derive(Debug, Default)]
 pub(crate) struct LspConfig {
     pub(crate) document_symbol_enabled: bool,
     pub(crate) lint_enabled: bool,
     pub(crate) watcher_enabled: bool,
+    /// ワークスペースで探索・監視するファイルの拡張子
+    pub(crate) file_extensions: FileExtensions,
+    /// 通信の記録を書き出すファイル (`HAM_RECORD`)
+    pub(crate) record_path_opt: Option<PathBuf>,
+}
+
+impl LspConfig {
+    /// 環境変数から設定をロードする。
+    pub(crate) fn from_env() -> Self {
+        Self {
+            document_symbol_enabled: env::var("HAM_DOCUMENT_SYMBOL_ENABLED")
+                .map_or(true, |s| s == "1"),
+            lint_enabled: env::var("HAM_LINT").map_or(true, |s| s == "1"),
+            watcher_enabled: env::var("HAM_WATCHER_ENABLED").map_or(true, |s| s == "1"),
+            file_extensions: env::var("HAM_FILE_EXTENSIONS")
+                .map_or_else(|_| FileExtensions::default(), |s| FileExtensions::parse(&s)),
+            record_path_opt: env::var_os("HAM_RECORD")
+                .filter(|s| !s.is_empty())
+                .map(PathBuf::from),
+        }
+    }
 }
@@ -1,64 +1,161 @@
 use super::*;
 use crate::{
     analyzer::Analyzer,
-    ide::diagnose::{filter_diagnostics, DiagnosticsCache},
-    lsp_server::lsp_main::lsp_log::init_log,
+    i18n::Locale,
+    ide::{
+        diagnose::{
+            document_diagnostic_report, filter_diagnostics, workspace_diagnostic_report,
+            DiagnosticsCache, PullDiagnosticsCache,
+        },
+        semantic_tokens::SemanticTokensCache,
+    },
+    lsp_server::{lsp_main::lsp_log::init_log, lsp_record},
+    source::PositionEncoding,
 };
-use lsp_server::{Connection, ExtractError, Message, RequestId, Response};
+use lsp_server::{Connection, ExtractError, Message, ProtocolError, RequestId, Response};
 use lsp_types::{
     error_codes,
     notification::{self, Notification as _},
     request::{self, Request as _},
-    OneOf,
+    OneOf, PositionEncodingKind,
 };
 use serde::Serialize;
-use std::{env, mem, path::PathBuf};
+use std::{
+    mem,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// サーバー側でファイルの変更を検出するとき、ワークスペースを探索する間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
 
-pub fn run_lsp_server(hsp3_root: PathBuf) {
+pub fn run_lsp_server(hsp3_root_opt: Option<PathBuf>, locale_opt: Option<Locale>) {
     init_log();
 
-    debug!("run_lsp_server, hsp3_root={:?}", hsp3_root);
+    debug!("run_lsp_server, hsp3_root={:?}", hsp3_root_opt);
 
     // 環境変数から設定をロードする:
-    let lsp_config = LspConfig {
-        document_symbol_enabled: env::var("HAM_DOCUMENT_SYMBOL_ENABLED").map_or(true, |s| s == "1"),
-        lint_enabled: env::var("HAM_LINT").map_or(true, |s| s == "1"),
-        watcher_enabled: env::var("HAM_WATCHER_ENABLED").map_or(true, |s| s == "1"),
-    };
+    let lsp_config = LspConfig::from_env();
 
     // サーバーが持つ状態:
-    let mut an = Analyzer::new(hsp3_root);
-    let mut state = State::default();
+    let mut an = Analyzer::new(hsp3_root_opt);
+    if let Some(locale) = locale_opt {
+        an.set_locale(locale);
+    }
 
     // connection (クライアントとの通信手段) として標準入出力やスレッドの準備を行う
     let (cx, io_threads) = Connection::stdio();
 
-    // LSPサーバーの初期化処理を行う
-    // (`cx.initialize` によって "initialized" 通知が来るまで通信が進み、"initialize" リクエストのパラメータが返される)
-    let server_capabilities =
-        serde_json::to_value(functions::generate_server_capabilities(&lsp_config)).unwrap();
+    // 通信を記録するときは、記録を書き出すスレッドを間に挟む
+    let (cx, record_threads_opt) = match &lsp_config.record_path_opt {
+        Some(path) => match lsp_record::record(cx, path) {
+            Ok((cx, threads)) => (cx, Some(threads)),
+            Err(err) => {
+                error!("記録を開始できません。{:?} {:?}", path, err);
+                return;
+            }
+        },
+        None => (cx, None),
+    };
+
+    let result = serve(cx, &lsp_config, an);
 
-    let init_params = match cx.initialize(server_capabilities) {
-        Ok(value) => serde_json::from_value::<lsp_types::InitializeParams>(value).unwrap(),
+    // (`serve` が `cx` を破棄した後、入出力のスレッドは終了する)
+    if let Some(threads) = record_threads_opt {
+        threads.join();
+    }
+    match result {
+        Ok(()) => {
+            io_threads.join().unwrap();
+            debug!("Exiting gracefully");
+        }
         Err(err) => {
-            error!("initialize");
+            error!("通信エラー {:?}", err);
             if err.channel_is_disconnected() {
                 io_threads.join().unwrap();
             }
-            return;
         }
-    };
+    }
+}
 
-    let watchable = lsp_config.watcher_enabled
-        && init_params
-            .capabilities
-            .workspace
-            .and_then(|x| x.did_change_watched_files)
-            .and_then(|x| x.dynamic_registration)
+/// LSPサーバーとして、接続が切れるか `exit` 通知が来るまで通信を行う。
+///
+/// (テストではメモリ上の `Connection` を渡して、サーバーをプロセス内で動かす)
+pub(crate) fn serve(
+    cx: Connection,
+    lsp_config: &LspConfig,
+    mut an: Analyzer,
+) -> Result<(), ProtocolError> {
+    let mut state = State::default();
+
+    // LSPサーバーの初期化処理を行う
+    // ("initialize" リクエストを受け取り、クライアントの機能に応じてサーバーの機能を決めて応答する。
+    //  "initialized" 通知が来るまで通信が進む)
+    let init_result = cx.initialize_start().and_then(|(id, value)| {
+        let init_params =
+            serde_json::from_value::<lsp_types::InitializeParams>(value.clone()).unwrap();
+
+        let position_encoding = functions::negotiate_position_encoding(&init_params);
+        an.set_position_encoding(position_encoding);
+
+        // クライアントがプル型の診断に対応していれば、診断の結果はクライアントの要求に応じて返す。
+        // (対応していなければ、従来どおりサーバーから送信する)
+        state.pull_diagnostics = lsp_config.lint_enabled
+            && (init_params.capabilities.text_document.as_ref())
+                .and_then(|x| x.diagnostic.as_ref())
+                .is_some();
+        // (lsp-types ではフィールド名が `diagnostic` になっていて読めないため、JSONから直接読む)
+        state.diagnostic_refresh_support = value
+            .pointer("/capabilities/workspace/diagnostics/refreshSupport")
+            .and_then(|x| x.as_bool())
             .unwrap_or(false);
 
-    if watchable {
-        functions::register_file_system_watcher(&cx);
+        let mut server_capabilities = functions::generate_server_capabilities(lsp_config);
+        server_capabilities.position_encoding = Some(match position_encoding {
+            PositionEncoding::Utf8 => PositionEncodingKind::UTF8,
+            _ => PositionEncodingKind::UTF16,
+        });
+        if state.pull_diagnostics {
+            server_capabilities.diagnostic_provider = Some(
+                lsp_types::DiagnosticServerCapabilities::Options(lsp_types::DiagnosticOptions {
+                    identifier: Some(env!("CARGO_PKG_NAME").to_string()),
+                    // (インクルードされているファイルの変更によって、診断の結果が変わる)
+                    inter_file_dependencies: true,
+                    workspace_diagnostics: true,
+                    ..Default::default()
+                }),
+            );
+        }
+
+        let init_result = serde_json::json!({
+            "capabilities": server_capabilities,
+        });
+        cx.initialize_finish(id, init_result)?;
+        Ok(init_params)
+    });
+
+    let init_params = init_result?;
+
+    // メッセージの表示言語を決める。
+    if let Some(locale) = &init_params.locale {
+        an.set_locale(Locale::from_tag(locale));
+    }
+
+    // ファイルの変更を監視する。
+    // (クライアントがファイルの監視の動的な登録に対応していなければ、サーバー側で定期的にワークスペースを探索する)
+    let watchable = init_params
+        .capabilities
+        .workspace
+        .and_then(|x| x.did_change_watched_files)
+        .and_then(|x| x.dynamic_registration)
+        .unwrap_or(false);
+    let polling = lsp_config.watcher_enabled && !watchable;
+
+    if lsp_config.watcher_enabled && watchable {
+        functions::register_file_system_watcher(&cx, lsp_config, &mut state);
+    }
+    if polling {
+        an.enable_file_polling();
     }
 
     if let Some(folders) = init_params.workspace_folders {
@@ -67,23 +164,53 @@ pub fn run_lsp_server(hsp3_root: PathBuf) {
         }
     }
 
+    an.set_file_extensions(lsp_config.file_extensions.clone());
     an.did_initialize();
 
     // メインループ:
     debug!("Starting main loop");
 
-    for msg in &cx.receiver {
+    let mut last_poll = Instant::now();
+
+    loop {
+        // (ポーリングするときは、メッセージが来なくても一定時間ごとに起きる)
+        let msg_opt = if polling {
+            match cx.receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(msg) => Some(msg),
+                Err(err) if err.is_timeout() => None,
+                Err(_) => break,
+            }
+        } else {
+            match cx.receiver.recv() {
+                Ok(msg) => Some(msg),
+                Err(_) => break,
+            }
+        };
+
+        if polling && last_poll.elapsed() >= POLL_INTERVAL {
+            last_poll = Instant::now();
+            if an.poll_files() {
+                state.diagnostics_invalidated = true;
+                functions::refresh_diagnostics(&cx, &mut state);
+            }
+        }
+
+        let msg = match msg_opt {
+            Some(it) => it,
+            None => continue,
+        };
+
         match msg {
             Message::Request(req) => {
                 // debug!("got request: {req:?}");
 
                 // "shutdown" リクエストなら `true` になる。
                 // ("exit" 通知が来るまで通信が行われる)
-                if cx.handle_shutdown(&req).unwrap() {
+                if cx.handle_shutdown(&req)? {
                     break;
                 }
 
-                dispatch_request(&cx, &lsp_config, &mut an, &mut state, req);
+                dispatch_request(&cx, lsp_config, &mut an, &mut state, req);
                 continue;
             }
             Message::Response(resp) => {
@@ -102,8 +229,20 @@ pub fn run_lsp_server(hsp3_root: PathBuf) {
         }
     }
 
-    io_threads.join().unwrap();
-    debug!("Exiting gracefully");
+    Ok(())
+}
+
+// -----------------------------------------------
+// LSPの拡張
+// -----------------------------------------------
+
+/// シンボルへの書き込みの箇所 (代入や参照渡しの引数など) だけを列挙するリクエスト
+enum WriteReferencesRequest {}
+
+impl request::Request for WriteReferencesRequest {
+    type Params = lsp_types::TextDocumentPositionParams;
+    type Result = Vec<lsp_types::Location>;
+    const METHOD: &'static str = "hsp3-analyzer-mini/writeReferences";
 }
 
 // -----------------------------------------------
@@ -112,8 +251,16 @@ pub fn run_lsp_server(hsp3_root: PathBuf) {
 
 #[derive(Default)]
 struct State {
+    /// サーバーから送ったリクエストのうち、最後のもののid
+    last_request_id: i32,
+    /// プル型の診断を使うか？ (`true` ならサーバーからは診断を送信しない)
+    pull_diagnostics: bool,
+    /// クライアントが `workspace/diagnostic/refresh` に対応しているか？
+    diagnostic_refresh_support: bool,
     diagnostics_invalidated: bool,
     diagnostics_cache: DiagnosticsCache,
+    pull_diagnostics_cache: PullDiagnosticsCache,
+    semantic_tokens_cache: SemanticTokensCache,
 }
 
 /// リクエストを処理する
@@ -138,6 +285,20 @@ fn dispatch_request(
             functions::publish_diagnostics(cx, lsp_config, an, state);
             return;
         }
+        // "textDocument/codeLens"
+        request::CodeLensRequest::METHOD => {
+            let (id, params) = cast_req::<request::CodeLensRequest>(req).unwrap();
+            let result = an.compute_ref().code_lens(params.text_document.uri);
+            cx.sender.send(new_ok_response(id, result)).unwrap();
+            return;
+        }
+        // "codeLens/resolve"
+        request::CodeLensResolve::METHOD => {
+            let (id, params) = cast_req::<request::CodeLensResolve>(req).unwrap();
+            let result = an.compute_ref().code_lens_resolve(params);
+            cx.sender.send(new_ok_response(id, result)).unwrap();
+            return;
+        }
         // "textDocument/completion"
         request::Completion::METHOD => {
             let (id, params) = cast_req::<request::Completion>(req).unwrap();
@@ -172,6 +333,17 @@ fn dispatch_request(
             functions::publish_diagnostics(cx, lsp_config, an, state);
             return;
         }
+        // "textDocument/declaration"
+        request::GotoDeclaration::METHOD => {
+            let (id, params) = cast_req::<request::GotoDeclaration>(req).unwrap();
+            let p = params.text_document_position_params;
+            let locations = an
+                .compute_ref()
+                .declarations(p.text_document.uri, p.position);
+            let result = functions::to_goto_definition_response(locations);
+            cx.sender.send(new_ok_response(id, result)).unwrap();
+            return;
+        }
         // "textDocument/definition"
         request::GotoDefinition::METHOD => {
             let (id, params) = cast_req::<request::GotoDefinition>(req).unwrap();
@@ -181,13 +353,21 @@ fn dispatch_request(
             };
 
             let definitions = an.compute_ref().definitions(doc.uri, pos);
-
-            let result = if definitions.len() == 1 {
-                lsp_types::GotoDefinitionResponse::Scalar(definitions.into_iter().next().unwrap())
-            } else {
-                lsp_types::GotoDefinitionResponse::Array(definitions)
-            };
-
+            let result = functions::to_goto_definition_response(definitions);
+            cx.sender.send(new_ok_response(id, result)).unwrap();
+            return;
+        }
+        // "textDocument/diagnostic"
+        request::DocumentDiagnosticRequest::METHOD => {
+            let (id, params) = cast_req::<request::DocumentDiagnosticRequest>(req).unwrap();
+            let diagnostics = an.compute_ref().diagnose();
+            let report = document_diagnostic_report(
+                &mut state.pull_diagnostics_cache,
+                &params.text_document.uri,
+                params.previous_result_id.as_deref(),
+                diagnostics,
+            );
+            let result = lsp_types::DocumentDiagnosticReportResult::Report(report);
             cx.sender.send(new_ok_response(id, result)).unwrap();
             return;
         }
@@ -229,6 +409,17 @@ fn dispatch_request(
             functions::publish_diagnostics(cx, lsp_config, an, state);
             return;
         }
+        // "textDocument/implementation"
+        request::GotoImplementation::METHOD => {
+            let (id, params) = cast_req::<request::GotoImplementation>(req).unwrap();
+            let p = params.text_document_position_params;
+            let locations = an
+                .compute_ref()
+                .implementations(p.text_document.uri, p.position);
+            let result = functions::to_goto_definition_response(locations);
+            cx.sender.send(new_ok_response(id, result)).unwrap();
+            return;
+        }
         // "textDocument/prepareRename"
         request::PrepareRenameRequest::METHOD => {
             let (id, params) = cast_req::<request::PrepareRenameRequest>(req).unwrap();
@@ -250,6 +441,15 @@ fn dispatch_request(
             cx.sender.send(new_ok_response(id, result)).unwrap();
             return;
         }
+        // "hsp3-analyzer-mini/writeReferences" (拡張)
+        WriteReferencesRequest::METHOD => {
+            let (id, params) = cast_req::<WriteReferencesRequest>(req).unwrap();
+            let result = an
+                .compute_ref()
+                .write_references(params.text_document.uri, params.position);
+            cx.sender.send(new_ok_response(id, result)).unwrap();
+            return;
+        }
         // "textDocument/rename"
         request::Rename::METHOD => {
             let (id, params) = cast_req::<request::Rename>(req).unwrap();
@@ -257,14 +457,47 @@ fn dispatch_request(
             let result =
                 an.compute_ref()
                     .rename(pp.text_document.uri, pp.position, params.new_name);
-            cx.sender.send(new_ok_response(id, result)).unwrap();
+            let response = match result {
+                Ok(result) => new_ok_response(id, result),
+                // 名前が衝突するなど、変更できないときはエラーを返す
+                Err(msg) => Message::Response(Response::new_err(
+                    id,
+                    error_codes::REQUEST_FAILED as i32,
+                    msg,
+                )),
+            };
+            cx.sender.send(response).unwrap();
             return;
         }
         // "textDocument/semanticTokens/full"
         request::SemanticTokensFullRequest::METHOD => {
             let (id, params) = cast_req::<request::SemanticTokensFullRequest>(req).unwrap();
+            let uri = params.text_document.uri;
+            let tokens = an.compute_ref().semantic_tokens(uri.clone());
             let result = lsp_types::SemanticTokensResult::Tokens(
-                an.compute_ref().semantic_tokens(params.text_document.uri),
+                state.semantic_tokens_cache.full(uri, tokens.data),
+            );
+            cx.sender.send(new_ok_response(id, result)).unwrap();
+            return;
+        }
+        // "textDocument/semanticTokens/full/delta"
+        request::SemanticTokensFullDeltaRequest::METHOD => {
+            let (id, params) = cast_req::<request::SemanticTokensFullDeltaRequest>(req).unwrap();
+            let uri = params.text_document.uri;
+            let tokens = an.compute_ref().semantic_tokens(uri.clone());
+            let result =
+                state
+                    .semantic_tokens_cache
+                    .delta(uri, &params.previous_result_id, tokens.data);
+            cx.sender.send(new_ok_response(id, result)).unwrap();
+            return;
+        }
+        // "textDocument/semanticTokens/range"
+        request::SemanticTokensRangeRequest::METHOD => {
+            let (id, params) = cast_req::<request::SemanticTokensRangeRequest>(req).unwrap();
+            let result = lsp_types::SemanticTokensRangeResult::Tokens(
+                an.compute_ref()
+                    .semantic_tokens_range(params.text_document.uri, params.range),
             );
             cx.sender.send(new_ok_response(id, result)).unwrap();
             return;
@@ -279,6 +512,47 @@ fn dispatch_request(
             cx.sender.send(new_ok_response(id, result)).unwrap();
             return;
         }
+        // "textDocument/typeDefinition"
+        request::GotoTypeDefinition::METHOD => {
+            let (id, params) = cast_req::<request::GotoTypeDefinition>(req).unwrap();
+            let p = params.text_document_position_params;
+            let locations = an
+                .compute_ref()
+                .type_definitions(p.text_document.uri, p.position);
+            let result = functions::to_goto_definition_response(locations);
+            cx.sender.send(new_ok_response(id, result)).unwrap();
+            return;
+        }
+        // "workspace/diagnostic"
+        request::WorkspaceDiagnosticRequest::METHOD => {
+            let (id, params) = cast_req::<request::WorkspaceDiagnosticRequest>(req).unwrap();
+            let diagnostics = an.compute_ref().diagnose();
+            let report = workspace_diagnostic_report(
+                &mut state.pull_diagnostics_cache,
+                params.previous_result_ids,
+                diagnostics,
+            );
+            let result = lsp_types::WorkspaceDiagnosticReportResult::Report(report);
+            cx.sender.send(new_ok_response(id, result)).unwrap();
+            return;
+        }
+        // "workspace/executeCommand"
+        request::ExecuteCommand::METHOD => {
+            let (id, params) = cast_req::<request::ExecuteCommand>(req).unwrap();
+            // スクリプトの実行はクライアント側で行うため、サーバー側では何もしない
+            debug!("executeCommand {:?}", params.command);
+            cx.sender
+                .send(new_ok_response(id, serde_json::Value::Null))
+                .unwrap();
+            return;
+        }
+        // "workspace/willRenameFiles"
+        request::WillRenameFiles::METHOD => {
+            let (id, params) = cast_req::<request::WillRenameFiles>(req).unwrap();
+            let result = an.compute_ref().will_rename_files(params.files);
+            cx.sender.send(new_ok_response(id, result)).unwrap();
+            return;
+        }
         // "workspace/symbol"
         request::WorkspaceSymbolRequest::METHOD => {
             let (id, params) = cast_req::<request::WorkspaceSymbolRequest>(req).unwrap();
@@ -302,7 +576,7 @@ fn dispatch_request(
 
 /// 通知を処理する
 fn dispatch_notification(
-    _cx: &lsp_server::Connection,
+    cx: &lsp_server::Connection,
     an: &mut Analyzer,
     state: &mut State,
     nn: lsp_server::Notification,
@@ -343,10 +617,20 @@ fn dispatch_notification(
             let params = cast_nn::<notification::DidCloseTextDocument>(nn).unwrap();
             let d = params.text_document;
 
+            state.semantic_tokens_cache.remove(&d.uri);
             an.close_doc(d.uri);
             state.diagnostics_invalidated = true;
             return;
         }
+        // "workspace/didChangeWorkspaceFolders"
+        notification::DidChangeWorkspaceFolders::METHOD => {
+            let params = cast_nn::<notification::DidChangeWorkspaceFolders>(nn).unwrap();
+            an.change_workspace_folders(params.event.added, params.event.removed);
+
+            state.diagnostics_invalidated = true;
+            functions::refresh_diagnostics(cx, state);
+            return;
+        }
         // "workspace/didChangeWatchedFiles"
         notification::DidChangeWatchedFiles::METHOD => {
             let params = cast_nn::<notification::DidChangeWatchedFiles>(nn).unwrap();
@@ -361,6 +645,7 @@ fn dispatch_notification(
             }
 
             state.diagnostics_invalidated = true;
+            functions::refresh_diagnostics(cx, state);
             return;
         }
         _ if nn.method.starts_with("$/") => {
@@ -381,6 +666,35 @@ mod functions {
     use super::*;
     use lsp_types::*;
 
+    pub(super) fn to_goto_definition_response(
+        mut locations: Vec<Location>,
+    ) -> GotoDefinitionResponse {
+        if locations.len() == 1 {
+            GotoDefinitionResponse::Scalar(locations.pop().unwrap())
+        } else {
+            GotoDefinitionResponse::Array(locations)
+        }
+    }
+
+    /// クライアントが対応している列番号の単位から、使うものを決める
+    ///
+    /// (クライアントが挙げた順に、サーバーが対応しているものを選ぶ。なければ UTF-16 を使う)
+    pub(super) fn negotiate_position_encoding(params: &InitializeParams) -> PositionEncoding {
+        params
+            .capabilities
+            .general
+            .as_ref()
+            .and_then(|general| general.position_encodings.as_ref())
+            .and_then(|encodings| {
+                encodings.iter().find_map(|kind| match kind.as_str() {
+                    "utf-8" => Some(PositionEncoding::Utf8),
+                    "utf-16" => Some(PositionEncoding::Utf16),
+                    _ => None,
+                })
+            })
+            .unwrap_or_default()
+    }
+
     pub(super) fn generate_server_capabilities(config: &LspConfig) -> ServerCapabilities {
         ServerCapabilities {
             text_document_sync: Some(TextDocumentSyncCapability::Options(
@@ -392,11 +706,15 @@ mod functions {
                 },
             )),
             code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            code_lens_provider: Some(CodeLensOptions {
+                resolve_provider: Some(true),
+            }),
             completion_provider: Some(CompletionOptions {
                 resolve_provider: Some(true),
                 trigger_characters: None,
                 ..CompletionOptions::default()
             }),
+            declaration_provider: Some(DeclarationCapability::Simple(true)),
             definition_provider: Some(OneOf::Left(true)),
             document_formatting_provider: Some(OneOf::Left(true)),
             document_highlight_provider: Some(OneOf::Left(true)),
@@ -406,6 +724,7 @@ mod functions {
                 None
             },
             hover_provider: Some(HoverProviderCapability::Simple(true)),
+            implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
             references_provider: Some(OneOf::Left(true)),
             rename_provider: Some(OneOf::Right(RenameOptions {
                 prepare_provider: Some(true),
@@ -415,19 +734,30 @@ mod functions {
                 SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
                     legend: SemanticTokensLegend {
                         token_types: vec![
-                            SemanticTokenType::PARAMETER, // 0
-                            SemanticTokenType::VARIABLE,  // 1
-                            SemanticTokenType::FUNCTION,  // 2
-                            SemanticTokenType::MACRO,     // 3
-                            SemanticTokenType::NAMESPACE, // 4
-                            SemanticTokenType::KEYWORD,   // 5
+                            SemanticTokenType::PARAMETER,          // 0
+                            SemanticTokenType::VARIABLE,           // 1
+                            SemanticTokenType::FUNCTION,           // 2
+                            SemanticTokenType::MACRO,              // 3
+                            SemanticTokenType::NAMESPACE,          // 4
+                            SemanticTokenType::KEYWORD,            // 5
+                            SemanticTokenType::new("label"),       // 6
+                            SemanticTokenType::METHOD,             // 7
+                            SemanticTokenType::ENUM_MEMBER,        // 8
+                            SemanticTokenType::STRING,             // 9
+                            SemanticTokenType::NUMBER,             // 10
+                            SemanticTokenType::COMMENT,            // 11
+                            SemanticTokenType::new("dllFunction"), // 12
                         ],
                         token_modifiers: vec![
-                            SemanticTokenModifier::READONLY, // 0b01
-                            SemanticTokenModifier::STATIC,   // 0b10
+                            SemanticTokenModifier::READONLY,        // 0b00001
+                            SemanticTokenModifier::STATIC,          // 0b00010
+                            SemanticTokenModifier::DEFAULT_LIBRARY, // 0b00100
+                            SemanticTokenModifier::DEPRECATED,      // 0b01000
+                            SemanticTokenModifier::DECLARATION,     // 0b10000
                         ],
                     },
-                    full: Some(SemanticTokensFullOptions::Bool(true)),
+                    full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                    range: Some(true),
                     ..Default::default()
                 }),
             ),
@@ -435,16 +765,59 @@ mod functions {
                 trigger_characters: Some(vec![" ".to_string(), "(".to_string(), ",".to_string()]),
                 ..Default::default()
             }),
+            type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+            execute_command_provider: Some(ExecuteCommandOptions {
+                commands: vec![crate::ide::code_lens::RUN_SCRIPT_COMMAND.to_string()],
+                ..Default::default()
+            }),
+            workspace: Some(WorkspaceServerCapabilities {
+                workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                    supported: Some(true),
+                    change_notifications: Some(OneOf::Left(true)),
+                }),
+                file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                    will_rename: Some(FileOperationRegistrationOptions {
+                        filters: vec![
+                            FileOperationFilter {
+                                scheme: Some("file".to_string()),
+                                pattern: FileOperationPattern {
+                                    glob: "**/*.{hsp,as}".to_string(),
+                                    matches: Some(FileOperationPatternKind::File),
+                                    options: None,
+                                },
+                            },
+                            FileOperationFilter {
+                                scheme: Some("file".to_string()),
+                                pattern: FileOperationPattern {
+                                    glob: "**".to_string(),
+                                    matches: Some(FileOperationPatternKind::Folder),
+                                    options: None,
+                                },
+                            },
+                        ],
+                    }),
+                    ..Default::default()
+                }),
+            }),
             workspace_symbol_provider: Some(OneOf::Left(true)),
             ..ServerCapabilities::default()
         }
     }
 
-    pub(super) fn register_file_system_watcher(cx: &lsp_server::Connection) {
+    /// サーバーから送るリクエストのidを生成する
+    fn new_request_id(state: &mut State) -> RequestId {
+        state.last_request_id += 1;
+        RequestId::from(state.last_request_id)
+    }
+
+    pub(super) fn register_file_system_watcher(
+        cx: &lsp_server::Connection,
+        config: &LspConfig,
+        state: &mut State,
+    ) {
         cx.sender
             .send(Message::Request(lsp_server::Request::new(
-                // id 1 しか使わない (ほかのリクエストを送らない)
-                RequestId::from(1),
+                new_request_id(state),
                 // "client/registerCapability"
                 request::RegisterCapability::METHOD.to_string(),
                 RegistrationParams {
@@ -453,12 +826,18 @@ mod functions {
                         method: "workspace/didChangeWatchedFiles".to_string(),
                         register_options: Some(
                             serde_json::to_value(DidChangeWatchedFilesRegistrationOptions {
-                                watchers: vec![FileSystemWatcher {
-                                    kind: Some(
-                                        WatchKind::Create | WatchKind::Change | WatchKind::Delete,
-                                    ),
-                                    glob_pattern: GlobPattern::from("**/*.hsp".to_string()),
-                                }],
+                                watchers: config
+                                    .file_extensions
+                                    .iter()
+                                    .map(|ext| FileSystemWatcher {
+                                        kind: Some(
+                                            WatchKind::Create
+                                                | WatchKind::Change
+                                                | WatchKind::Delete,
+                                        ),
+                                        glob_pattern: GlobPattern::from(format!("**/*.{}", ext)),
+                                    })
+                                    .collect(),
                             })
                             .unwrap(),
                         ),
@@ -468,6 +847,25 @@ mod functions {
             .unwrap();
     }
 
+    /// プル型の診断を使っているとき、クライアントに診断の結果を取得しなおすように要求する
+    ///
+    /// (ワークスペースのファイルがエディタの外で変更されたときに呼ばれる。
+    ///  エディタ上の編集については、クライアントが自ら取得しなおす)
+    pub(super) fn refresh_diagnostics(cx: &lsp_server::Connection, state: &mut State) {
+        if !state.pull_diagnostics || !state.diagnostic_refresh_support {
+            return;
+        }
+
+        cx.sender
+            .send(Message::Request(lsp_server::Request::new(
+                new_request_id(state),
+                // "workspace/diagnostic/refresh"
+                request::WorkspaceDiagnosticRefresh::METHOD.to_string(),
+                (),
+            )))
+            .unwrap();
+    }
+
     /// `diagnostics` の変更があれば再送信する
     ///
     /// (この関数は `initialized`, `didSave` または解析系リクエストの処理後に呼ばれる)
@@ -477,7 +875,8 @@ mod functions {
         an: &mut Analyzer,
         state: &mut State,
     ) {
-        if !lsp_config.lint_enabled {
+        // (プル型の診断を使っているときは、クライアントの要求に応じて返すため、送信しない)
+        if !lsp_config.lint_enabled || state.pull_diagnostics {
             return;
         }
 
@@ -490,6 +889,9 @@ mod functions {
 
         filter_diagnostics(&mut state.diagnostics_cache, &mut diagnostics);
 
+        // (送信する順番を一定にする)
+        diagnostics.sort_by(|(l, ..), (r, ..)| l.cmp(r));
+
         for (uri, version, diagnostics) in diagnostics {
             cx.sender
                 .send(Message::Notification(lsp_server::Notification::new(
@@ -0,0 +1,171 @@
+//! LSPの通信の記録
+//!
+//! クライアントとサーバーの間でやりとりされたメッセージを、1行に1つずつJSONで書き出す。
+//! (記録したファイルはテストで再生される。`src/tests/lsp_replay_tests.rs` を参照)
+//!
+//! 各行は `{"from":"client","message":{...}}` のような形になる。
+//! 記録をほかの環境で再生できるように、ワークスペースのURIは `${workspaceFolder}` に置き換える。
+
+use lsp_server::{Connection, Message};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{self, LineWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+/// 記録の中でワークスペースのURIの代わりに書かれる文字列
+pub(crate) const WORKSPACE_FOLDER_PLACEHOLDER: &str = "${workspaceFolder}";
+
+/// メッセージの送り手
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum Side {
+    Client,
+    Server,
+}
+
+/// 記録の1行
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RecordEntry {
+    pub(crate) from: Side,
+    pub(crate) message: Message,
+}
+
+/// 記録を書き出すスレッド
+pub(crate) struct RecordThreads {
+    in_thread: JoinHandle<()>,
+    out_thread: JoinHandle<()>,
+}
+
+impl RecordThreads {
+    /// スレッドの終了を待つ。
+    ///
+    /// (サーバーが `Connection` を破棄した後、クライアントからの入力が終わったときに終了する)
+    pub(crate) fn join(self) {
+        self.out_thread.join().unwrap();
+        self.in_thread.join().unwrap();
+    }
+}
+
+/// 通信を中継して記録する `Connection` を作る。
+///
+/// サーバーは返された `Connection` を元のものの代わりに使う。
+pub(crate) fn record(cx: Connection, path: &Path) -> io::Result<(Connection, RecordThreads)> {
+    let writer = Arc::new(Mutex::new(Recorder {
+        file: LineWriter::new(File::create(path)?),
+        workspace_uri_opt: None,
+    }));
+
+    let (server_cx, proxy_cx) = Connection::memory();
+    let Connection {
+        sender: client_sender,
+        receiver: client_receiver,
+    } = cx;
+
+    // クライアント → サーバー
+    let in_thread = {
+        let writer = Arc::clone(&writer);
+        let sender = proxy_cx.sender;
+        thread::spawn(move || {
+            for msg in client_receiver {
+                writer.lock().unwrap().write(Side::Client, &msg);
+                if sender.send(msg).is_err() {
+                    break;
+                }
+            }
+        })
+    };
+
+    // サーバー → クライアント
+    let out_thread = {
+        let receiver = proxy_cx.receiver;
+        thread::spawn(move || {
+            for msg in receiver {
+                writer.lock().unwrap().write(Side::Server, &msg);
+                if client_sender.send(msg).is_err() {
+                    break;
+                }
+            }
+        })
+    };
+
+    Ok((
+        server_cx,
+        RecordThreads {
+            in_thread,
+            out_thread,
+        },
+    ))
+}
+
+struct Recorder {
+    file: LineWriter<File>,
+    /// 置き換えるワークスペースのURI (`initialize` リクエストから得る)
+    workspace_uri_opt: Option<String>,
+}
+
+impl Recorder {
+    fn write(&mut self, from: Side, msg: &Message) {
+        if let Message::Request(req) = msg {
+            if req.method == "initialize" {
+                self.workspace_uri_opt = workspace_uri(&req.params);
+            }
+        }
+
+        let mut line = match serde_json::to_string(&RecordEntry {
+            from,
+            message: msg.clone(),
+        }) {
+            Ok(it) => it,
+            Err(err) => {
+                warn!("記録を書き出せません。{:?}", err);
+                return;
+            }
+        };
+        if let Some(uri) = &self.workspace_uri_opt {
+            line = line.replace(uri.as_str(), WORKSPACE_FOLDER_PLACEHOLDER);
+        }
+
+        if let Err(err) = writeln!(self.file, "{}", line) {
+            warn!("記録を書き出せません。{:?}", err);
+        }
+    }
+}
+
+/// `initialize` リクエストのパラメータから、ワークスペースのURIを取り出す。
+///
+/// (最初のワークスペースフォルダを使う。なければ `rootUri` を使う)
+fn workspace_uri(params: &serde_json::Value) -> Option<String> {
+    let uri = params
+        .pointer("/workspaceFolders/0/uri")
+        .or_else(|| params.get("rootUri"))?
+        .as_str()?
+        .trim_end_matches('/');
+    if uri.is_empty() {
+        return None;
+    }
+    Some(uri.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_uri() {
+        let params = serde_json::json!({
+            "rootUri": "file:///root",
+            "workspaceFolders": [{ "uri": "file:///ws/", "name": "ws" }],
+        });
+        assert_eq!(workspace_uri(&params).as_deref(), Some("file:///ws"));
+
+        let params = serde_json::json!({ "rootUri": "file:///root" });
+        assert_eq!(workspace_uri(&params).as_deref(), Some("file:///root"));
+
+        let params = serde_json::json!({ "rootUri": null });
+        assert_eq!(workspace_uri(&params), None);
+    }
+}
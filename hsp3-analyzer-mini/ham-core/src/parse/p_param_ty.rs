@@ -1,7 +1,9 @@
+use serde::{Deserialize, Serialize};
+
 /// Parameter type. パラメータタイプ
 ///
 /// (`#deffunc` や `#func` で宣言されるパラメータのタイプ)
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub(crate) enum PParamTy {
     Str,
     Double,
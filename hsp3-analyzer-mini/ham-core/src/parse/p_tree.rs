@@ -180,7 +180,7 @@ impl Debug for PParenExpr {
     }
 }
 
-/// 前置式 (マイナスの式)
+/// 前置式 (マイナスの式、または `%` で始まる2進数)
 #[must_use]
 pub(crate) struct PPrefixExpr {
     pub(crate) prefix: PToken,
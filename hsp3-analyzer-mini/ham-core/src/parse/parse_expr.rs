@@ -103,6 +103,14 @@ fn parse_prefix_expr(px: &mut Px) -> Option<PExpr> {
             let arg_opt = parse_prefix_expr(px).map(Box::new);
             Some(PExpr::Prefix(PPrefixExpr { prefix, arg_opt }))
         }
+        // 2進数 (`%0101`)
+        TokenKind::Percent
+            if px.nth(1) == TokenKind::Number && px.next_token().trailing.is_empty() =>
+        {
+            let prefix = px.bump();
+            let arg_opt = Some(Box::new(PExpr::Literal(px.bump())));
+            Some(PExpr::Prefix(PPrefixExpr { prefix, arg_opt }))
+        }
         _ => parse_atomic_expr(px),
     }
 }
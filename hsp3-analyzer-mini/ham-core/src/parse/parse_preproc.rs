@@ -26,7 +26,7 @@ impl TokenKind {
 
 impl PToken {
     /// `deffunc` 系の命令の領域を分割するプリプロセッサ命令の名前
-    fn is_deffunc_terminator(&self) -> bool {
+    pub(super) fn is_deffunc_terminator(&self) -> bool {
         self.kind() == TokenKind::Ident
             && (DEFFUNC_LIKE_KEYWORDS.contains(&self.body_text())
                 || self.body_text() == "module"
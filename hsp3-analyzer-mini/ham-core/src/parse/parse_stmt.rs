@@ -68,7 +68,10 @@ fn lookahead_after_paren(mut i: usize, px: &mut Px) -> ExprLikeStmtKind {
             _ if kind.to_op_kind() == Some(POpKind::Assign) => {
                 return ExprLikeStmtKind::Assign;
             }
-            _ if kind.is_end_of_stmt() => break,
+            _ if kind.is_end_of_stmt() => {
+                // カッコが閉じていない。(次の行は見ない。)
+                return ExprLikeStmtKind::Command;
+            }
             _ if i >= LOOKAHEAD_LIMIT => {
                 // 長い文はたぶん命令文。
                 return ExprLikeStmtKind::Command;
@@ -270,3 +273,50 @@ pub(crate) fn parse_root(tokens: Vec<PToken>) -> PRoot {
         eof,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse::{parse_root, PStmt, PToken},
+        token::tokenize,
+    };
+    use expect_test::{expect, Expect};
+
+    /// 文の種類を1行ずつ書き出す
+    fn check_stmts(text: &str, expected: Expect) {
+        let tokens = PToken::from_tokens(tokenize(1, text.into()).into());
+        let root = parse_root(tokens);
+
+        let mut output = String::new();
+        for stmt in &root.stmts {
+            let kind = match stmt {
+                PStmt::Assign(_) => "Assign",
+                PStmt::Command(_) => "Command",
+                PStmt::Invoke(_) => "Invoke",
+                _ => "Other",
+            };
+            output += kind;
+            output += "\n";
+        }
+        expected.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_unclosed_paren_is_command() {
+        // カッコが閉じていない文は命令文とみなす。(次の行は見ない。)
+        check_stmts(
+            "a(1\n-= 2\n",
+            expect![[r#"
+                Command
+            "#]],
+        );
+
+        // 閉じる前にカンマがあれば、これまでどおり添字とみなす。
+        check_stmts(
+            "a(1, 2\n",
+            expect![[r#"
+                Assign
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,1044 @@
+//! 差分の再解析
+//!
+//! HSP3 のスクリプトは行単位の文からなるので、テキストの変更はたいてい変更箇所を含む行の文にしか影響しない。
+//! そこで、変更箇所を含む行 (文末 (`Eos`) で区切られた範囲。以下、窓という) だけを字句解析・構文解析しなおして、
+//! それ以外のトークンと文は前回の結果を再利用する。窓より後ろにあるトークンは位置だけをずらす。
+//!
+//! ブロックの構造 (`#module`, `#deffunc`, `{`, `}` など) が変わりうる変更は、窓の外の文に影響するので扱わない。
+
+use super::*;
+use crate::token::tokenize_at;
+
+/// 前回の解析結果を再利用して、変更後のテキストを解析する
+///
+/// 差分の再解析ができないときは `None` を返す。(その場合は全体を解析しなおすこと。)
+/// 結果は全体を解析しなおしたときと一致する。
+pub(crate) fn reparse(
+    doc: DocId,
+    old_text: &str,
+    old_tokens: &[PToken],
+    mut root: PRoot,
+    new_text: &RcStr,
+) -> Option<(Vec<PToken>, PRoot)> {
+    if old_text == new_text.as_str() {
+        return Some((old_tokens.to_vec(), root));
+    }
+
+    // 変更された範囲を求める。(前後の一致する部分を除く。)
+    let prefix_len = common_prefix_len(old_text, new_text);
+    let suffix_len = common_suffix_len(old_text, new_text, prefix_len);
+    let old_edit_end = old_text.len() - suffix_len;
+
+    // 窓を字句解析しなおす。
+    // 窓の境界で字句解析の結果が変わるときは、窓を後ろに広げて再試行する。
+    let mut edit_end = old_edit_end;
+    let (window, mut window_tokens) = loop {
+        let window = Window::find(old_tokens, prefix_len, edit_end)?;
+        if has_block_structure(&old_tokens[window.start_index..window.end_index])
+            || old_tokens.get(window.end_index).map(|t| t.kind()) == Some(TokenKind::Else)
+        {
+            return None;
+        }
+
+        match relex(doc, old_text, old_tokens, new_text, &window)? {
+            Some(tokens) => break (window, tokens),
+            None if edit_end < old_text.len() && edit_end - old_edit_end < RETRY_LIMIT => {
+                edit_end = window.end.index as usize + 1;
+            }
+            None => return None,
+        }
+    };
+    if has_block_structure(&window_tokens) {
+        return None;
+    }
+
+    // 窓を構文解析しなおす。
+    let window_root = parse_root(window_tokens.clone());
+    let at_eof = window.end_index == old_tokens.len();
+    if !at_eof {
+        // 窓の直後の改行をくっつけた末尾のトークンを取り除く。
+        window_tokens.pop();
+    }
+
+    // 窓を含む文の並びと、その中で窓に含まれる文の範囲を探す。
+    // (位置をずらす前に探す。)
+    let (path, stmt_range) = find_stmts(&root.stmts, &window, &mut vec![])?;
+    let skipped_range = window.range_in(&root.skipped, |t| t);
+
+    // ここから先は失敗しない。
+
+    // 窓より後ろにあるトークンの位置をずらす。
+    let shift = Shift {
+        old_end: window.end,
+        new_end: window_tokens.last().unwrap().body_pos(),
+    };
+    let suffix = old_tokens[window.end_index..]
+        .iter()
+        .map(|t| shift.token(t))
+        .collect::<Vec<_>>();
+
+    let remap = Remap {
+        old_tokens,
+        new_tokens: &suffix,
+        offset: window.end_index,
+        shift: &shift,
+    };
+    remap.root(&mut root);
+
+    // 窓に含まれる文とトークンを置き換える。
+    stmts_at_path(&mut root.stmts, &path).splice(stmt_range, window_root.stmts);
+    root.skipped.splice(skipped_range, window_root.skipped);
+
+    if at_eof {
+        root.eof = window_root.eof;
+    }
+
+    let mut tokens = old_tokens[..window.start_index].to_vec();
+    tokens.extend(window_tokens);
+    tokens.extend(suffix);
+    Some((tokens, root))
+}
+
+/// 窓を広げて再試行する範囲 (バイト数) の上限
+const RETRY_LIMIT: usize = 1000;
+
+/// 窓を字句解析しなおす
+///
+/// 窓の直後にある改行も含めて字句解析して、窓の境界で字句解析の結果が変わらないことを確かめる。
+/// (閉じていない複数行コメントは後ろの行を飲み込み、窓の末尾の空行やコメントは後ろのトークンの先行トリビアになる。)
+/// 変わるときは `Some(None)` を返す。
+///
+/// 結果のトークン列の末尾は EOF である。窓がファイルの末尾で終わらないときは、
+/// 窓の直後の改行を先行トリビアとして持つ EOF になる。
+fn relex(
+    doc: DocId,
+    old_text: &str,
+    old_tokens: &[PToken],
+    new_text: &str,
+    window: &Window,
+) -> Option<Option<Vec<PToken>>> {
+    let next_text = match old_tokens.get(window.end_index) {
+        Some(next) => {
+            let newlines = next.leading.first()?;
+            if newlines.kind != TokenKind::Newlines {
+                return None;
+            }
+            newlines.text.as_str()
+        }
+        None => "",
+    };
+
+    let new_window_end = new_text.len() - (old_text.len() - window.end.index as usize);
+    let window_text = format!(
+        "{}{}",
+        &new_text[window.start.index as usize..new_window_end],
+        next_text
+    );
+    let token_data = tokenize_at(doc, RcStr::from(window_text), window.start);
+    if window.start_index != 0 && token_data[0].kind != TokenKind::Newlines {
+        return None;
+    }
+
+    let tokens = PToken::from_tokens(token_data.into());
+    if !next_text.is_empty() {
+        let n = tokens.len();
+        let eof = &tokens[n - 1];
+        let ok = n >= 2
+            && tokens[n - 2].kind() == TokenKind::Eos
+            && eof.leading.len() == 1
+            && eof.leading[0].kind == TokenKind::Newlines
+            && eof.leading[0].text.as_str() == next_text;
+        if !ok {
+            return Some(None);
+        }
+    }
+    Some(Some(tokens))
+}
+
+fn common_prefix_len(s: &str, t: &str) -> usize {
+    let mut n = s.bytes().zip(t.bytes()).take_while(|(x, y)| x == y).count();
+    while !s.is_char_boundary(n) {
+        n -= 1;
+    }
+    n
+}
+
+/// 後ろから一致する部分の長さ (`prefix_len` と重ならない範囲)
+fn common_suffix_len(s: &str, t: &str, prefix_len: usize) -> usize {
+    let limit = s.len().min(t.len()) - prefix_len;
+    let mut n = s
+        .bytes()
+        .rev()
+        .zip(t.bytes().rev())
+        .take(limit)
+        .take_while(|(x, y)| x == y)
+        .count();
+    while !s.is_char_boundary(s.len() - n) {
+        n -= 1;
+    }
+    n
+}
+
+/// 再解析する範囲
+struct Window {
+    /// 窓に含まれるトークンの範囲 (古いトークン列における位置)
+    start_index: usize,
+    end_index: usize,
+    /// 窓の始まりの位置 (窓の最初のトークンの先行トリビアの開始位置)
+    start: Pos,
+    /// 窓の終わりの位置 (窓の最後のトークンである文末または EOF の位置)
+    end: Pos,
+}
+
+impl Window {
+    /// 古いテキストの `edit_start..edit_end` の範囲が変更されたとき、再解析する範囲を求める。
+    fn find(tokens: &[PToken], edit_start: usize, edit_end: usize) -> Option<Self> {
+        // 変更箇所より前にある最後の文末の直後から始める。
+        // (文末の直後の改行が変更されたら、その前の行も含める。)
+        let start_index = {
+            let i = tokens.partition_point(|t| (t.body_pos().index as usize) < edit_start);
+            tokens[..i]
+                .iter()
+                .rposition(|t| t.kind() == TokenKind::Eos)
+                .map_or(0, |i| i + 1)
+        };
+
+        // 変更箇所より後ろにある最初の文末で終わる。(EOFの直前の文末なら、EOFも含める。)
+        let mut end_index = start_index
+            + tokens[start_index..].iter().position(|t| match t.kind() {
+                TokenKind::Eos => t.body_pos().index as usize >= edit_end,
+                TokenKind::Eof => true,
+                _ => false,
+            })?;
+        if tokens.get(end_index + 1).map(|t| t.kind()) == Some(TokenKind::Eof) {
+            end_index += 1;
+        }
+
+        let start = if start_index == 0 {
+            Pos::default()
+        } else {
+            tokens[start_index].ahead().start()
+        };
+        let end = tokens[end_index].body_pos();
+
+        Some(Window {
+            start_index,
+            end_index: end_index + 1,
+            start,
+            end,
+        })
+    }
+
+    /// トークンが窓の始まりより後ろにあるか
+    fn is_after_start(&self, token: &PToken) -> bool {
+        self.start_index == 0 || token.body_pos().index > self.start.index
+    }
+
+    /// トークンが窓の終わりより前にあるか
+    fn is_before_end(&self, token: &PToken) -> bool {
+        token.body_pos().index <= self.end.index
+    }
+
+    /// 位置の順に並んだ要素のうち、窓に含まれるものの範囲
+    fn range_in<T>(&self, items: &[T], token: impl Fn(&T) -> &PToken) -> std::ops::Range<usize> {
+        let start = items.partition_point(|item| !self.is_after_start(token(item)));
+        let end = items.partition_point(|item| self.is_before_end(token(item)));
+        start..end.max(start)
+    }
+}
+
+/// ブロックの構造に関わるトークンが含まれるか
+fn has_block_structure(tokens: &[PToken]) -> bool {
+    tokens.iter().enumerate().any(|(i, t)| match t.kind() {
+        TokenKind::LeftBrace | TokenKind::RightBrace | TokenKind::Else => true,
+        TokenKind::Hash => matches!(tokens.get(i + 1), Some(t) if t.is_deffunc_terminator()),
+        _ => false,
+    })
+}
+
+fn stmt_first_token(stmt: &PStmt) -> &PToken {
+    match stmt {
+        PStmt::Label(label) => &label.star,
+        PStmt::Assign(stmt) => stmt.left.name(),
+        PStmt::Command(stmt) => &stmt.command,
+        PStmt::Invoke(stmt) => stmt.left.name(),
+        PStmt::If(stmt) => &stmt.command,
+        PStmt::Const(PConstStmt { hash, .. })
+        | PStmt::Define(PDefineStmt { hash, .. })
+        | PStmt::Enum(PEnumStmt { hash, .. })
+        | PStmt::Var(PVarStmt { hash, .. })
+        | PStmt::DefFunc(PDefFuncStmt { hash, .. })
+        | PStmt::UseLib(PUseLibStmt { hash, .. })
+        | PStmt::LibFunc(PLibFuncStmt { hash, .. })
+        | PStmt::UseCom(PUseComStmt { hash, .. })
+        | PStmt::ComFunc(PComFuncStmt { hash, .. })
+        | PStmt::RegCmd(PRegCmdStmt { hash, .. })
+        | PStmt::Cmd(PCmdStmt { hash, .. })
+        | PStmt::Module(PModuleStmt { hash, .. })
+        | PStmt::Global(PGlobalStmt { hash, .. })
+        | PStmt::Include(PIncludeStmt { hash, .. })
+        | PStmt::Use(PUseStmt { hash, .. })
+        | PStmt::UnknownPreProc(PUnknownPreProcStmt { hash, .. }) => hash,
+    }
+}
+
+/// 文の並びの中にある、文の並びを指すもの
+#[derive(Clone, Copy)]
+enum PathStep {
+    /// `#deffunc` または `#module` の中
+    Body(usize),
+    /// `if` 文の本体のブロックの中
+    IfBody(usize),
+    /// `if` 文の `else` 節のブロックの中
+    IfAlt(usize),
+}
+
+/// 窓を含む、最も内側にある文の並びを探す
+///
+/// 文の並びの位置と、その中で窓に含まれる文の範囲を返す。
+fn find_stmts(
+    stmts: &[PStmt],
+    window: &Window,
+    path: &mut Vec<PathStep>,
+) -> Option<(Vec<PathStep>, std::ops::Range<usize>)> {
+    let range = window.range_in(stmts, stmt_first_token);
+
+    // 窓より前から始まる最後の文が窓を含んでいたら、その中を探す。
+    let i = match range.start.checked_sub(1) {
+        Some(i) => i,
+        None => return Some((path.clone(), range)),
+    };
+
+    // (閉じていないブロックはファイルの終わりまで続く。)
+    let contains = |block: &PBlock| {
+        block.left_opt.is_some()
+            && !matches!(&block.right_opt, Some(right) if window.is_before_end(right))
+    };
+
+    let (step, inner) = match &stmts[i] {
+        PStmt::DefFunc(stmt) if stmt.behind.start().index >= window.end.index => {
+            (PathStep::Body(i), &stmt.stmts)
+        }
+        PStmt::Module(stmt) if stmt.behind.start().index >= window.end.index => {
+            (PathStep::Body(i), &stmt.stmts)
+        }
+        PStmt::If(stmt) if contains(&stmt.body) => (PathStep::IfBody(i), &stmt.body.inner_stmts),
+        PStmt::If(stmt) if contains(&stmt.alt) => (PathStep::IfAlt(i), &stmt.alt.inner_stmts),
+        // `if` 文の中に書かれた `#deffunc` や、`if` 文の中の `if` 文のブロックは、
+        // 後続の行を取り込んでいることがある。(諦める。)
+        PStmt::If(stmt) if has_nested_block(&[&stmt.body, &stmt.alt]) => return None,
+        _ => return Some((path.clone(), range)),
+    };
+
+    path.push(step);
+    find_stmts(inner, window, path)
+}
+
+/// ブロックが、後続の行を取り込むことがある文を含むか
+///
+/// (`#deffunc` や `#module` と、`{` の外側に書かれた `if` 文のブロック。)
+fn has_nested_block(blocks: &[&PBlock]) -> bool {
+    blocks.iter().any(|block| {
+        let outer = block.outer_stmts.iter().map(|stmt| (true, stmt));
+        let inner = block.inner_stmts.iter().map(|stmt| (false, stmt));
+        outer.chain(inner).any(|(is_outer, stmt)| match stmt {
+            PStmt::DefFunc(_) | PStmt::Module(_) => true,
+            PStmt::If(stmt) => {
+                (is_outer && (stmt.body.left_opt.is_some() || stmt.alt.left_opt.is_some()))
+                    || has_nested_block(&[&stmt.body, &stmt.alt])
+            }
+            _ => false,
+        })
+    })
+}
+
+fn stmts_at_path<'a>(stmts: &'a mut Vec<PStmt>, path: &[PathStep]) -> &'a mut Vec<PStmt> {
+    let (step, path) = match path.split_first() {
+        Some(it) => it,
+        None => return stmts,
+    };
+
+    let inner = match (*step, stmts) {
+        (PathStep::Body(i), stmts) => match &mut stmts[i] {
+            PStmt::DefFunc(stmt) => &mut stmt.stmts,
+            PStmt::Module(stmt) => &mut stmt.stmts,
+            _ => unreachable!(),
+        },
+        (PathStep::IfBody(i), stmts) => match &mut stmts[i] {
+            PStmt::If(stmt) => &mut stmt.body.inner_stmts,
+            _ => unreachable!(),
+        },
+        (PathStep::IfAlt(i), stmts) => match &mut stmts[i] {
+            PStmt::If(stmt) => &mut stmt.alt.inner_stmts,
+            _ => unreachable!(),
+        },
+    };
+    stmts_at_path(inner, path)
+}
+
+/// 窓より後ろにある位置を、変更後のテキストにおける位置に変換するもの
+struct Shift {
+    /// 変更前の窓の終わりの位置
+    old_end: Pos,
+    /// 変更後の窓の終わりの位置
+    new_end: Pos,
+}
+
+impl Shift {
+    fn pos(&self, pos: Pos) -> Pos {
+        let (old, new) = (self.old_end, self.new_end);
+        if pos.index < old.index {
+            return pos;
+        }
+
+        let index = pos.index - old.index + new.index;
+        if pos.row == old.row {
+            Pos::new(
+                index,
+                new.row,
+                pos.column - old.column + new.column,
+                pos.column16 - old.column16 + new.column16,
+            )
+        } else {
+            Pos::new(index, pos.row - old.row + new.row, pos.column, pos.column16)
+        }
+    }
+
+    fn loc(&self, loc: Loc) -> Loc {
+        Loc::new3(loc.doc, self.pos(loc.start()), self.pos(loc.end()))
+    }
+
+    fn token_data(&self, token: &TokenData) -> TokenData {
+        TokenData {
+            kind: token.kind,
+            text: token.text.clone(),
+            loc: self.loc(token.loc),
+        }
+    }
+
+    fn token(&self, token: &PToken) -> PToken {
+        PToken {
+            leading: RcSlice::from_iter(token.leading.iter().map(|t| self.token_data(t))),
+            body: RcItem::new_single(self.token_data(&token.body)),
+            trailing: RcSlice::from_iter(token.trailing.iter().map(|t| self.token_data(t))),
+        }
+    }
+}
+
+/// 構文木に含まれる、窓より後ろにあるトークンを、位置をずらしたトークンに置き換えるもの
+struct Remap<'a> {
+    old_tokens: &'a [PToken],
+    /// 位置をずらしたトークン (`old_tokens[offset..]` に対応する)
+    new_tokens: &'a [PToken],
+    offset: usize,
+    shift: &'a Shift,
+}
+
+impl Remap<'_> {
+    fn token(&self, token: &mut PToken) {
+        let pos = token.body_pos();
+        if pos.index <= self.shift.old_end.index {
+            return;
+        }
+
+        // 同じ位置にあるトークン (文末など) は参照の一致で区別する。
+        let i = self
+            .old_tokens
+            .partition_point(|t| t.body_pos().index < pos.index);
+        let found = self.old_tokens[i..]
+            .iter()
+            .take_while(|t| t.body_pos().index == pos.index)
+            .position(|t| t.body.ptr_eq(&token.body));
+        if let Some(j) = found {
+            *token = self.new_tokens[i + j - self.offset].clone();
+        }
+    }
+
+    fn token_opt(&self, token_opt: &mut Option<PToken>) {
+        if let Some(token) = token_opt {
+            self.token(token);
+        }
+    }
+
+    fn tokens(&self, tokens: &mut [PToken]) {
+        for token in tokens {
+            self.token(token);
+        }
+    }
+
+    fn pair_opt<T>(&self, pair_opt: &mut Option<(T, PToken)>) {
+        if let Some((_, token)) = pair_opt {
+            self.token(token);
+        }
+    }
+
+    fn loc(&self, loc: &mut Loc) {
+        *loc = self.shift.loc(*loc);
+    }
+
+    fn label(&self, label: &mut PLabel) {
+        let PLabel { star, name_opt } = label;
+        self.token(star);
+        self.token_opt(name_opt);
+    }
+
+    fn compound(&self, compound: &mut PCompound) {
+        match compound {
+            PCompound::Name(name) => self.token(name),
+            PCompound::Paren(PNameParen {
+                name,
+                left_paren,
+                args,
+                right_paren_opt,
+            }) => {
+                self.token(name);
+                self.token(left_paren);
+                self.args(args);
+                self.token_opt(right_paren_opt);
+            }
+            PCompound::Dots(PNameDot { name, args }) => {
+                self.token(name);
+                for PDotArg { dot, expr_opt } in args {
+                    self.token(dot);
+                    self.expr_opt(expr_opt);
+                }
+            }
+        }
+    }
+
+    fn args(&self, args: &mut [PArg]) {
+        for PArg {
+            expr_opt,
+            comma_opt,
+        } in args
+        {
+            self.expr_opt(expr_opt);
+            self.token_opt(comma_opt);
+        }
+    }
+
+    fn expr(&self, expr: &mut PExpr) {
+        match expr {
+            PExpr::Literal(token) => self.token(token),
+            PExpr::Label(label) => self.label(label),
+            PExpr::Compound(compound) => self.compound(compound),
+            PExpr::Paren(PParenExpr {
+                left_paren,
+                body_opt,
+                right_paren_opt,
+            }) => {
+                self.token(left_paren);
+                if let Some(body) = body_opt {
+                    self.expr(body);
+                }
+                self.token_opt(right_paren_opt);
+            }
+            PExpr::Prefix(PPrefixExpr { prefix, arg_opt }) => {
+                self.token(prefix);
+                if let Some(arg) = arg_opt {
+                    self.expr(arg);
+                }
+            }
+            PExpr::Infix(PInfixExpr {
+                left,
+                infix,
+                right_opt,
+            }) => {
+                self.expr(left);
+                self.token(infix);
+                if let Some(right) = right_opt {
+                    self.expr(right);
+                }
+            }
+        }
+    }
+
+    fn expr_opt(&self, expr_opt: &mut Option<PExpr>) {
+        if let Some(expr) = expr_opt {
+            self.expr(expr);
+        }
+    }
+
+    fn params(&self, params: &mut [PParam]) {
+        for PParam {
+            param_ty_opt,
+            name_opt,
+            comma_opt,
+        } in params
+        {
+            self.pair_opt(param_ty_opt);
+            self.token_opt(name_opt);
+            self.token_opt(comma_opt);
+        }
+    }
+
+    fn names(&self, names: &mut [(PToken, Option<PToken>)]) {
+        for (name, comma_opt) in names {
+            self.token(name);
+            self.token_opt(comma_opt);
+        }
+    }
+
+    fn block(&self, block: &mut PBlock) {
+        let PBlock {
+            outer_stmts,
+            left_opt,
+            inner_stmts,
+            right_opt,
+        } = block;
+        self.stmts(outer_stmts);
+        self.token_opt(left_opt);
+        self.stmts(inner_stmts);
+        self.token_opt(right_opt);
+    }
+
+    fn global(&self, global: &mut PGlobalStmt) {
+        let PGlobalStmt { hash, keyword } = global;
+        self.token(hash);
+        self.token(keyword);
+    }
+
+    fn stmt(&self, stmt: &mut PStmt) {
+        match stmt {
+            PStmt::Label(label) => self.label(label),
+            PStmt::Assign(PAssignStmt { left, op_opt, args }) => {
+                self.compound(left);
+                self.token_opt(op_opt);
+                self.args(args);
+            }
+            PStmt::Command(PCommandStmt {
+                command,
+                jump_modifier_opt,
+                args,
+            }) => {
+                self.token(command);
+                self.pair_opt(jump_modifier_opt);
+                self.args(args);
+            }
+            PStmt::Invoke(PInvokeStmt {
+                left,
+                arrow_opt,
+                method_opt,
+                args,
+            }) => {
+                self.compound(left);
+                self.token_opt(arrow_opt);
+                self.expr_opt(method_opt);
+                self.args(args);
+            }
+            PStmt::If(PIfStmt {
+                command,
+                cond_opt,
+                body,
+                else_opt,
+                alt,
+            }) => {
+                self.token(command);
+                self.expr_opt(cond_opt);
+                self.block(body);
+                self.token_opt(else_opt);
+                self.block(alt);
+            }
+            PStmt::Const(PConstStmt {
+                hash,
+                keyword,
+                privacy_opt,
+                ty_opt,
+                name_opt,
+                init_opt,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.pair_opt(privacy_opt);
+                self.pair_opt(ty_opt);
+                self.token_opt(name_opt);
+                self.expr_opt(init_opt);
+            }
+            PStmt::Define(PDefineStmt {
+                hash,
+                keyword,
+                privacy_opt,
+                ctype_opt,
+                name_opt,
+                left_paren_opt,
+                params,
+                right_paren_opt,
+                tokens,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.pair_opt(privacy_opt);
+                self.token_opt(ctype_opt);
+                self.token_opt(name_opt);
+                self.token_opt(left_paren_opt);
+                for PMacroParam {
+                    percent_opt,
+                    number_opt,
+                    equal_opt,
+                    init,
+                    comma_opt,
+                } in params
+                {
+                    self.token_opt(percent_opt);
+                    self.token_opt(number_opt);
+                    self.token_opt(equal_opt);
+                    self.tokens(init);
+                    self.token_opt(comma_opt);
+                }
+                self.token_opt(right_paren_opt);
+                self.tokens(tokens);
+            }
+            PStmt::Enum(PEnumStmt {
+                hash,
+                keyword,
+                privacy_opt,
+                name_opt,
+                equal_opt,
+                init_opt,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.pair_opt(privacy_opt);
+                self.token_opt(name_opt);
+                self.token_opt(equal_opt);
+                self.expr_opt(init_opt);
+            }
+            PStmt::Var(PVarStmt {
+                hash,
+                keyword,
+                names,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.names(names);
+            }
+            PStmt::DefFunc(PDefFuncStmt {
+                hash,
+                keyword,
+                kind: _,
+                privacy_opt,
+                name_opt,
+                params,
+                onexit_opt,
+                stmts,
+                behind,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.pair_opt(privacy_opt);
+                self.token_opt(name_opt);
+                self.params(params);
+                self.token_opt(onexit_opt);
+                self.stmts(stmts);
+                self.loc(behind);
+            }
+            PStmt::UseLib(PUseLibStmt {
+                hash,
+                keyword,
+                file_path_opt,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.token_opt(file_path_opt);
+            }
+            PStmt::LibFunc(PLibFuncStmt {
+                hash,
+                keyword,
+                privacy_opt,
+                name_opt,
+                onexit_opt,
+                func_name_opt,
+                type_id_opt,
+                params,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.pair_opt(privacy_opt);
+                self.token_opt(name_opt);
+                self.token_opt(onexit_opt);
+                self.token_opt(func_name_opt);
+                self.token_opt(type_id_opt);
+                self.params(params);
+            }
+            PStmt::UseCom(PUseComStmt {
+                hash,
+                keyword,
+                privacy_opt,
+                name_opt,
+                args,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.pair_opt(privacy_opt);
+                self.token_opt(name_opt);
+                self.args(args);
+            }
+            PStmt::ComFunc(PComFuncStmt {
+                hash,
+                keyword,
+                privacy_opt,
+                name_opt,
+                index_opt,
+                params,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.pair_opt(privacy_opt);
+                self.token_opt(name_opt);
+                self.token_opt(index_opt);
+                self.params(params);
+            }
+            PStmt::RegCmd(PRegCmdStmt {
+                hash,
+                keyword,
+                args,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.args(args);
+            }
+            PStmt::Cmd(PCmdStmt {
+                hash,
+                keyword,
+                privacy_opt,
+                name_opt,
+                command_id_opt,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.pair_opt(privacy_opt);
+                self.token_opt(name_opt);
+                self.token_opt(command_id_opt);
+            }
+            PStmt::Module(PModuleStmt {
+                hash,
+                keyword,
+                name_opt,
+                fields,
+                stmts,
+                global_opt,
+                behind,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.token_opt(name_opt);
+                self.params(fields);
+                self.stmts(stmts);
+                if let Some(global) = global_opt {
+                    self.global(global);
+                }
+                self.loc(behind);
+            }
+            PStmt::Global(global) => self.global(global),
+            PStmt::Include(PIncludeStmt {
+                hash,
+                keyword,
+                kind: _,
+                file_path_opt,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.token_opt(file_path_opt);
+            }
+            PStmt::Use(PUseStmt {
+                hash,
+                keyword,
+                names,
+            }) => {
+                self.token(hash);
+                self.token(keyword);
+                self.names(names);
+            }
+            PStmt::UnknownPreProc(PUnknownPreProcStmt { hash, tokens }) => {
+                self.token(hash);
+                self.tokens(tokens);
+            }
+        }
+    }
+
+    fn stmts(&self, stmts: &mut [PStmt]) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn root(&self, root: &mut PRoot) {
+        let PRoot {
+            stmts,
+            skipped,
+            eof,
+        } = root;
+        self.stmts(stmts);
+        self.tokens(skipped);
+        self.token(eof);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cst::{build_cst, SyntaxNode},
+        token::tokenize,
+    };
+    use std::fmt::Write as _;
+
+    const TEXT: &str = r#"#include "hsp3utf.as"
+#module m x
+#deffunc f int a, var b
+    if a : b = a + 1 : else : mes "a", , f(a)
+    repeat 3 ; 繰り返し
+        if cnt == 1 {
+            continue
+        }
+    loop
+    return
+#defcfunc g str s
+    return s + "\n"
+#global
+#define ctype twice(%1) ((%1) * 2)
+#const N 10
+#enum E = 1
+/* 複数行の
+コメント */
+*l
+    s = {"ヒアドキュメント
+"} : x(0) = 1, 2
+    goto *l
+"#;
+
+    /// 挿入するテキストの候補
+    const SNIPPETS: &[&str] = &[
+        "",
+        " ",
+        "\n",
+        "\r\n",
+        "a",
+        "1",
+        ":",
+        ",",
+        "(",
+        ")",
+        "=",
+        "\"",
+        ";",
+        "/*",
+        "*/",
+        "{",
+        "}",
+        "{\"",
+        "\"}",
+        "*",
+        "#",
+        "\\",
+        "if ",
+        "else",
+        "mes \"x\"",
+        "#deffunc h",
+        "#global",
+        "#const K 1",
+        "x += 2",
+        "あ",
+    ];
+
+    /// 乱数 (xorshift)
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    fn parse(text: &RcStr) -> (Vec<PToken>, PRoot) {
+        let tokens = PToken::from_tokens(tokenize(1, text.clone()).into());
+        let root = parse_root(tokens.clone());
+        (tokens, root)
+    }
+
+    /// 解析結果の比較用の文字列 (トークンの位置、構文木、具象構文木)
+    fn dump(tokens: &[PToken], root: &PRoot) -> String {
+        let mut w = String::new();
+        for t in tokens.iter().flat_map(|t| t.iter()) {
+            let (s, e) = (t.loc.start(), t.loc.end());
+            let _ = writeln!(
+                w,
+                "{:?} {:?} {},{},{},{}-{},{},{},{}",
+                t.kind,
+                t.text,
+                s.index,
+                s.row,
+                s.column,
+                s.column16,
+                e.index,
+                e.row,
+                e.column,
+                e.column16
+            );
+        }
+        let _ = writeln!(w, "{:#?}", root);
+        let _ = writeln!(w, "{:#?}", SyntaxNode::new_root(build_cst(tokens, root)));
+        w
+    }
+
+    fn random_edit(text: &str, rng: &mut Rng) -> String {
+        let boundaries = (0..=text.len())
+            .filter(|&i| text.is_char_boundary(i))
+            .collect::<Vec<_>>();
+        let i = rng.next(boundaries.len());
+        let j = (i + rng.next(8)).min(boundaries.len() - 1);
+        let snippet = SNIPPETS[rng.next(SNIPPETS.len())];
+        format!(
+            "{}{}{}",
+            &text[..boundaries[i]],
+            snippet,
+            &text[boundaries[j]..]
+        )
+    }
+
+    // ランダムな編集を繰り返して、差分の再解析の結果が全体の再解析の結果に一致することを確かめる。
+    #[test]
+    fn test_reparse_agrees_with_full_parse() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let mut reparsed_count = 0;
+
+        for _ in 0..30 {
+            let mut text = RcStr::from(TEXT);
+            let (mut tokens, mut root) = parse(&text);
+
+            for _ in 0..100 {
+                let new_text = RcStr::from(random_edit(&text, &mut rng));
+                let (full_tokens, full_root) = parse(&new_text);
+                let expected = dump(&full_tokens, &full_root);
+
+                match reparse(1, &text, &tokens, root, &new_text) {
+                    Some((new_tokens, new_root)) => {
+                        let actual = dump(&new_tokens, &new_root);
+                        assert!(
+                            actual == expected,
+                            "old_text={:?}\nnew_text={:?}\nactual:\n{}\nexpected:\n{}",
+                            text.as_str(),
+                            new_text.as_str(),
+                            actual,
+                            expected
+                        );
+                        reparsed_count += 1;
+                        tokens = new_tokens;
+                        root = new_root;
+                    }
+                    None => {
+                        tokens = full_tokens;
+                        root = full_root;
+                    }
+                }
+                text = new_text;
+            }
+        }
+
+        // たいていの編集は差分の再解析で処理できる。
+        assert!(reparsed_count >= 1000, "reparsed_count={}", reparsed_count);
+    }
+}
@@ -0,0 +1,86 @@
+use super::Pos;
+
+/// 列番号の単位 (位置情報のエンコーディング)
+///
+/// 内部では列番号を UTF-16 で扱い、クライアントとの間で受け渡すときに変換する。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum PositionEncoding {
+    /// UTF-8 のバイト数
+    Utf8,
+    /// UTF-16 のコードユニット数 (LSPの既定)
+    #[default]
+    Utf16,
+    /// Shift_JIS のバイト数 (HSPのスクリプトエディタが使う)
+    ShiftJis,
+}
+
+impl PositionEncoding {
+    pub(crate) fn parse(s: &str) -> Option<Self> {
+        let it = match s.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => PositionEncoding::Utf8,
+            "utf-16" | "utf16" => PositionEncoding::Utf16,
+            "shift_jis" | "sjis" | "cp932" => PositionEncoding::ShiftJis,
+            _ => return None,
+        };
+        Some(it)
+    }
+}
+
+/// 文字の Shift_JIS でのバイト数
+///
+/// ASCIIと半角カナは1バイト、それ以外は2バイトとみなす。
+/// (Shift_JIS で表せない文字も2バイトとして数える。)
+fn shift_jis_char_len(c: char) -> u32 {
+    match c {
+        '\0'..='\x7f' | '\u{ff61}'..='\u{ff9f}' => 1,
+        _ => 2,
+    }
+}
+
+/// 文字列の Shift_JIS でのバイト数
+pub(crate) fn shift_jis_len(s: &str) -> u32 {
+    s.chars().map(shift_jis_char_len).sum()
+}
+
+/// 行の途中にある位置 `start` から、Shift_JIS で `len` バイト進んだ位置を求める
+///
+/// (`text` はドキュメント全体のテキスト。
+///  文字の途中を指しているときは、その文字の先頭とみなす。行末を越えるときは行末になる。)
+pub(crate) fn shift_jis_advance(text: &str, start: Pos, len: u32) -> Pos {
+    let rest = &text[start.index as usize..];
+    let mut pos = start;
+    let mut n = 0;
+    for (i, c) in rest.char_indices() {
+        n += shift_jis_char_len(c);
+        if n > len || c == '\n' {
+            break;
+        }
+        pos += Pos::from(&rest[i..i + c.len_utf8()]);
+    }
+    pos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::Pos16;
+
+    #[test]
+    fn test_shift_jis_len() {
+        // 半角カナは1バイト、𠮷 (Shift_JIS にない文字) は2バイトと数える。
+        assert_eq!(shift_jis_len("\tmes \"あ𠮷ｱ\""), 12);
+    }
+
+    #[test]
+    fn test_shift_jis_advance() {
+        let text = "a\nあいう\nb";
+        let start = Pos::from("a\n");
+        let advance = |len: u32| Pos16::from(shift_jis_advance(text, start, len));
+
+        assert_eq!(advance(2), Pos16::new(1, 1));
+        // 文字の途中は文字の先頭になる。
+        assert_eq!(advance(3), Pos16::new(1, 1));
+        // 行末を越えるときは行末になる。
+        assert_eq!(advance(100), Pos16::new(1, 3));
+    }
+}
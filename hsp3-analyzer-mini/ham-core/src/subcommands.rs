@@ -1,5 +1,7 @@
 //! (LSPサーバー以外の) CLIコマンドの処理
 
 pub mod format_comments;
+pub mod gen_builtin;
+pub mod gen_help;
 pub mod parse;
 pub mod profile_parse;
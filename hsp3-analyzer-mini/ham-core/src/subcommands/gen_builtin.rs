@@ -0,0 +1,170 @@
+//! `ham gen-builtin`: hsphelp から標準命令などのデータベース (`data/builtin.hs`) を生成する
+//!
+//! 同梱のデータベースは、`data/hsphelp/*.hs` (hsphelp と同じ書式で必要な項目だけを書いたもの) から生成する。
+//! (`ham --hsp data gen-builtin > data/builtin.hs`)
+
+use crate::{
+    analyzer::search_hsphelp::is_builtin,
+    help_source::{parse_for_symbols, HsSymbol},
+    utils::read_file::read_sjis_file,
+};
+use std::{
+    collections::HashSet,
+    fs,
+    io::{stdout, Write as _},
+    path::Path,
+};
+
+const HEADER: &str = r#";
+; 標準命令・関数などのデータベース (HSPがインストールされていない環境で使う)
+;
+; このファイルは `ham gen-builtin` で生成されたものです。直接編集しないでください。
+; hsphelp/*.hs のうち、標準命令などのヘルプファイルの %index, %prm, %inst, %note を抜き出しています。
+; (同梱のものは data/hsphelp/*.hs から `ham --hsp data gen-builtin > data/builtin.hs` で生成しています。)
+;
+"#;
+
+/// シンボルの種類を表す `%note` の内容
+fn note_of(stem: &str, symbol: &HsSymbol) -> &'static str {
+    let is_func = match symbol.documentation.first() {
+        Some(prm) if symbol.params_opt.is_some() => prm.starts_with('('),
+        _ => false,
+    };
+
+    if symbol.builtin {
+        if is_func {
+            "標準関数"
+        } else {
+            "標準命令"
+        }
+    } else if symbol.name.starts_with('#') {
+        "プリプロセッサ命令"
+    } else if stem == "sysval" {
+        "システム変数"
+    } else {
+        "標準定義マクロ"
+    }
+}
+
+/// データベースに1つの項目を書く。(`note` は `%note` に書く、シンボルの種類)
+fn write_symbol(symbol: &HsSymbol, note: &str, out: &mut String) {
+    out.push_str("\n%index\n");
+    out.push_str(&symbol.name);
+    out.push('\n');
+    if let Some(description) = &symbol.description {
+        for line in description.lines() {
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+    }
+
+    // `%prm` の内容は documentation の先頭に入っている。
+    if let Some(prm) = symbol
+        .documentation
+        .first()
+        .filter(|_| symbol.params_opt.is_some())
+    {
+        out.push_str("%prm\n");
+        for line in prm.lines() {
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+    }
+
+    if let Some(inst) = &symbol.inst_opt {
+        out.push_str("%inst\n");
+        for line in inst.trim_matches(|c| c == '\r' || c == '\n').lines() {
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+    }
+
+    out.push_str("%note\n");
+    out.push_str(note);
+    out.push('\n');
+}
+
+/// hsphelp にある標準命令などのヘルプファイルを解析して、データベースを標準出力に書く。
+pub fn gen_builtin_subcommand(hsp3_root: &Path) {
+    let out = generate(&hsp3_root.join("hsphelp"));
+    stdout().write_all(out.as_bytes()).unwrap();
+}
+
+/// hsphelp ディレクトリにある標準命令などのヘルプファイルから、データベースの内容を生成する。
+fn generate(hsphelp_dir: &Path) -> String {
+    let mut paths = fs::read_dir(hsphelp_dir)
+        .unwrap_or_else(|err| panic!("{hsphelp_dir:?}: {err}"))
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| match (path.file_stem(), path.extension()) {
+            (Some(stem), Some(ext)) => ext == "hs" && is_builtin(&stem.to_string_lossy()),
+            _ => false,
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut out = HEADER.to_string();
+    let mut names = HashSet::new();
+
+    for path in paths {
+        let stem = path.file_stem().unwrap().to_string_lossy().to_string();
+
+        let mut contents = String::new();
+        if !read_sjis_file(&path, &mut contents) {
+            eprintln!("{}: ヘルプソースファイルを開けません。", path.display());
+            continue;
+        }
+
+        let mut symbols = vec![];
+        let mut warnings = vec![];
+        parse_for_symbols(&contents, &mut symbols, &mut warnings);
+        for w in warnings {
+            eprintln!("{}: {}", path.display(), w);
+        }
+
+        for symbol in &symbols {
+            // 同名のものが複数あるときは最初のものを使う。
+            if !names.insert(symbol.name.clone()) {
+                continue;
+            }
+            write_symbol(symbol, note_of(&stem, symbol), &mut out);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLED: &str = include_str!("../../data/builtin.hs");
+
+    #[test]
+    fn test_write_symbol_with_inst() {
+        let mut symbols = vec![];
+        let mut warnings = vec![];
+        parse_for_symbols(
+            "%index\r\nfoo\r\nfooする\r\n%prm\r\np1\r\n%inst\r\n\r\n説明の1行目\r\n説明の2行目  \r\n\r\n%note\r\n標準命令\r\n",
+            &mut symbols,
+            &mut warnings,
+        );
+        assert_eq!(warnings, Vec::<String>::new());
+
+        let mut out = String::new();
+        write_symbol(&symbols[0], note_of("i_foo", &symbols[0]), &mut out);
+        assert_eq!(
+            out,
+            "\n%index\nfoo\nfooする\n%prm\np1\n%inst\n説明の1行目\n説明の2行目\n%note\n標準命令\n"
+        );
+    }
+
+    /// 同梱のデータベースが、元データ (`data/hsphelp`) から生成したものと一致するか検査する。
+    ///
+    /// (一致しないときは `ham --hsp data gen-builtin > data/builtin.hs` で生成しなおす。)
+    #[test]
+    fn test_bundled_database_has_no_drift() {
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data");
+        let generated = generate(&data_dir.join("hsphelp"));
+        assert_eq!(BUNDLED, generated);
+    }
+}
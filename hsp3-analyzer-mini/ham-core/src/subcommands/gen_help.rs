@@ -0,0 +1,550 @@
+//! `ham gen-help`: モジュールの定義からヘルプソースファイル (.hs) を生成する
+
+use crate::{
+    analysis::{
+        calculate_details, collect_comments, module_name_as_ident, new_signature_data_for_deffunc,
+        HspSymbolKind,
+    },
+    help_source::parse_for_symbols,
+    i18n::{tr, tr_fmt, Locale},
+    parse::{parse_root, PConstStmt, PDefFuncKind, PDefFuncStmt, PPrivacy, PStmt, PToken},
+    token::tokenize,
+    utils::{
+        rc_str::RcStr,
+        read_file::{read_file, read_sjis_file},
+    },
+};
+use encoding::{EncoderTrap, Encoding};
+use std::{
+    fs,
+    io::{stdin, stdout, Read, Write as _},
+    mem::replace,
+    path::{Path, PathBuf},
+};
+
+const EOL: &str = "\r\n";
+
+/// ヘルプソースの1つの項目 (`%index` から次の `%index` の手前まで) に書き出す内容
+struct HelpEntry {
+    name: String,
+    kind: HspSymbolKind,
+    /// 定義を含むモジュールの名前
+    group_opt: Option<String>,
+    description: Option<String>,
+    /// パラメータの名前と説明の組 (定数ならNone)
+    params_opt: Option<Vec<(String, String)>>,
+    inst: Vec<String>,
+    href: Vec<String>,
+}
+
+impl HelpEntry {
+    fn is_func(&self) -> bool {
+        self.params_opt.is_some()
+    }
+}
+
+struct Ctx<'a> {
+    text: &'a str,
+    locale: Locale,
+    in_module: bool,
+    group_opt: Option<String>,
+    entries: Vec<HelpEntry>,
+}
+
+/// 行がパラメータの説明か (パラメータ名で始まっているか)
+fn line_describes_param(line: &str, name: &str) -> bool {
+    match line.strip_prefix(name) {
+        Some(rest) => !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'),
+        None => false,
+    }
+}
+
+fn on_deffunc(stmt: &PDefFuncStmt, ctx: &mut Ctx) {
+    let kind = match stmt.kind {
+        PDefFuncKind::DefFunc => HspSymbolKind::DefFunc,
+        PDefFuncKind::DefCFunc => HspSymbolKind::DefCFunc,
+        PDefFuncKind::ModFunc => HspSymbolKind::ModFunc,
+        PDefFuncKind::ModCFunc => HspSymbolKind::ModCFunc,
+        PDefFuncKind::ModInit | PDefFuncKind::ModTerm => return,
+    };
+
+    let is_public = !matches!(stmt.privacy_opt, Some((PPrivacy::Local, _)));
+    if !is_public || stmt.onexit_opt.is_some() {
+        return;
+    }
+
+    let (name, signature) = match (&stmt.name_opt, new_signature_data_for_deffunc(stmt)) {
+        (Some(name), Some(signature)) => (name, signature),
+        _ => return,
+    };
+
+    let details = calculate_details(&collect_comments(&stmt.hash));
+    let mut inst = details.docs;
+
+    let mut params = vec![];
+    for (i, (ty_opt, name_opt, _)) in signature.params.iter().enumerate() {
+        let name = match name_opt {
+            Some(name) => name.to_string(),
+            None => format!("p{}", i + 1),
+        };
+
+        // ドキュメンテーションコメントにパラメータの説明があれば、それを使う。
+        let details = match inst
+            .iter()
+            .position(|line| line_describes_param(line, &name))
+        {
+            Some(row) => inst.remove(row),
+            None => match ty_opt {
+                Some(ty) => format!("{} ({})", name, ty.to_str()),
+                None => name.clone(),
+            },
+        };
+        params.push((name, details));
+    }
+
+    ctx.entries.push(HelpEntry {
+        name: name.body_text().to_string(),
+        kind,
+        group_opt: ctx.group_opt.clone(),
+        description: details.desc.map(|s| s.to_string()),
+        params_opt: Some(params),
+        inst,
+        href: vec![],
+    });
+}
+
+fn on_const(stmt: &PConstStmt, ctx: &mut Ctx) {
+    // モジュールの中の定数は既定でローカルになる。
+    let is_public = match stmt.privacy_opt {
+        Some((PPrivacy::Global, _)) => true,
+        Some(_) => false,
+        None => !ctx.in_module,
+    };
+    if !is_public {
+        return;
+    }
+
+    let name = match &stmt.name_opt {
+        Some(it) => it,
+        None => return,
+    };
+
+    let details = calculate_details(&collect_comments(&stmt.hash));
+    let mut inst = details.docs;
+
+    if let Some(init) = &stmt.init_opt {
+        let range = init.compute_range();
+        if let Some(value) = ctx
+            .text
+            .get(range.start().index as usize..range.end().index as usize)
+        {
+            inst.push(tr_fmt(ctx.locale, "hover.value", &[&value]));
+        }
+    }
+
+    ctx.entries.push(HelpEntry {
+        name: name.body_text().to_string(),
+        kind: HspSymbolKind::Const,
+        group_opt: ctx.group_opt.clone(),
+        description: details.desc.map(|s| s.to_string()),
+        params_opt: None,
+        inst,
+        href: vec![],
+    });
+}
+
+fn on_stmts(stmts: &[PStmt], ctx: &mut Ctx) {
+    for stmt in stmts {
+        match stmt {
+            PStmt::Module(stmt) => {
+                let group_opt = stmt
+                    .name_opt
+                    .as_ref()
+                    .and_then(|name| module_name_as_ident(&name.body))
+                    .map(|name| name.to_string());
+                let parent_group = replace(&mut ctx.group_opt, group_opt);
+                let parent_in_module = replace(&mut ctx.in_module, true);
+
+                on_stmts(&stmt.stmts, ctx);
+
+                ctx.group_opt = parent_group;
+                ctx.in_module = parent_in_module;
+            }
+            PStmt::DefFunc(stmt) => {
+                on_deffunc(stmt, ctx);
+                on_stmts(&stmt.stmts, ctx);
+            }
+            PStmt::Const(stmt) => on_const(stmt, ctx),
+            _ => {}
+        }
+    }
+}
+
+/// スクリプトから、ヘルプソースに書き出す項目を集める。
+fn collect_help_entries(text: &str, locale: Locale) -> Vec<HelpEntry> {
+    let tokens = tokenize(1, RcStr::from(text));
+    let tokens = PToken::from_tokens(tokens.into());
+    let root = parse_root(tokens);
+
+    let mut ctx = Ctx {
+        text,
+        locale,
+        in_module: false,
+        group_opt: None,
+        entries: vec![],
+    };
+    on_stmts(&root.stmts, &mut ctx);
+
+    let mut entries = ctx.entries;
+
+    // 同じモジュールにある同じ種類の項目を関連項目とする。
+    for i in 0..entries.len() {
+        if entries[i].group_opt.is_none() {
+            continue;
+        }
+
+        let href = entries
+            .iter()
+            .enumerate()
+            .filter(|&(j, e)| {
+                j != i && e.group_opt == entries[i].group_opt && e.is_func() == entries[i].is_func()
+            })
+            .map(|(_, e)| e.name.clone())
+            .collect();
+        entries[i].href = href;
+    }
+
+    entries
+}
+
+/// ヘルプソースファイルの内容を生成する。
+fn write_help_source(file_name: &str, entries: &[HelpEntry], locale: Locale) -> String {
+    let stem = Path::new(file_name)
+        .file_stem()
+        .map_or(file_name.into(), |s| s.to_string_lossy());
+
+    let mut lines: Vec<String> = vec![];
+
+    // 既定値のセクション:
+    lines.extend([
+        ";".into(),
+        format!("; {}", tr_fmt(locale, "gen_help.header", &[&file_name])),
+        format!("; {}", tr(locale, "gen_help.generated_by")),
+        ";".into(),
+        "".into(),
+        "%dll".into(),
+        stem.to_string(),
+        "%type".into(),
+        tr(locale, "gen_help.type").to_string(),
+        "%note".into(),
+        tr_fmt(locale, "gen_help.note", &[&file_name]),
+        "%port".into(),
+        "Win".into(),
+    ]);
+
+    for entry in entries {
+        lines.push("".into());
+        lines.push("".into());
+        lines.push("%index".into());
+        lines.push(entry.name.clone());
+        lines.push(match &entry.description {
+            Some(desc) => desc.clone(),
+            None => entry.kind.as_str(locale).to_string(),
+        });
+
+        if let Some(group) = &entry.group_opt {
+            lines.push("".into());
+            lines.push("%group".into());
+            lines.push(group.clone());
+        }
+
+        if let Some(params) = &entry.params_opt {
+            let names = params
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let is_cfunc = matches!(
+                entry.kind,
+                HspSymbolKind::DefCFunc | HspSymbolKind::ModCFunc
+            );
+
+            // 命令はパラメータを並べて、関数はカッコで囲んで書く。
+            if is_cfunc || !params.is_empty() {
+                lines.push("".into());
+                lines.push("%prm".into());
+                lines.push(if is_cfunc {
+                    format!("({})", names)
+                } else {
+                    names
+                });
+                lines.extend(params.iter().map(|(_, details)| details.clone()));
+            }
+        }
+
+        if !entry.inst.is_empty() {
+            lines.push("".into());
+            lines.push("%inst".into());
+            lines.extend(entry.inst.iter().cloned());
+        }
+
+        if !entry.href.is_empty() {
+            lines.push("".into());
+            lines.push("%href".into());
+            lines.extend(entry.href.iter().cloned());
+        }
+    }
+
+    let mut output = lines.join(EOL);
+    output += EOL;
+    output
+}
+
+/// 既存のヘルプソースファイルとコードの食い違いを調べる。
+fn check_help_source(entries: &[HelpEntry], hs_contents: &str, locale: Locale) -> Vec<String> {
+    let mut hs_symbols = vec![];
+    let mut problems = vec![];
+    parse_for_symbols(hs_contents, &mut hs_symbols, &mut problems);
+
+    for entry in entries {
+        let hs_symbol = match hs_symbols.iter().find(|s| s.name == entry.name) {
+            Some(it) => it,
+            None => {
+                problems.push(tr_fmt(locale, "gen_help.missing_in_help", &[&entry.name]));
+                continue;
+            }
+        };
+
+        let expected = entry
+            .params_opt
+            .iter()
+            .flatten()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        let actual = hs_symbol
+            .params_opt
+            .iter()
+            .flatten()
+            .map(|p| p.name.as_str())
+            .filter(|name| !name.is_empty())
+            .collect::<Vec<_>>();
+        if expected != actual {
+            problems.push(tr_fmt(
+                locale,
+                "gen_help.param_mismatch",
+                &[&entry.name, &expected.join(", "), &actual.join(", ")],
+            ));
+        }
+    }
+
+    for hs_symbol in &hs_symbols {
+        if !entries.iter().any(|e| e.name == hs_symbol.name) {
+            problems.push(tr_fmt(
+                locale,
+                "gen_help.missing_in_code",
+                &[&hs_symbol.name],
+            ));
+        }
+    }
+
+    problems
+}
+
+/// `ham gen-help`: スクリプトファイルのモジュールからヘルプソースファイルを生成する
+///
+/// 生成したファイルはスクリプトファイルと同じディレクトリに拡張子 `.hs` で保存する。
+/// (`-` が指定されたら標準入力を読み、標準出力に書く。)
+///
+/// `check` が指定されたら、ファイルを書き込む代わりに、既存のヘルプソースファイルがコードと食い違っている箇所を報告する。
+/// 食い違いがなければ true を返す。
+pub fn gen_help_subcommand(files: Vec<String>, check: bool, locale: Locale) -> bool {
+    assert!(!files.is_empty());
+
+    let mut ok = true;
+
+    for filename in files {
+        if filename == "-" {
+            assert!(!check, "ERROR: {}", tr(locale, "gen_help.check_stdin"));
+
+            let mut text = String::new();
+            stdin().read_to_string(&mut text).unwrap();
+            let entries = collect_help_entries(&text, locale);
+            let output = write_help_source("-", &entries, locale);
+            stdout().write_all(output.as_bytes()).unwrap();
+            continue;
+        }
+
+        let path = PathBuf::from(&filename);
+        let mut text = String::new();
+        if !read_file(&path, &mut text) {
+            panic!("ERROR: Cannot read {filename:?}");
+        }
+
+        let entries = collect_help_entries(&text, locale);
+        let hs_path = path.with_extension("hs");
+
+        if check {
+            let mut hs_contents = String::new();
+            if !read_sjis_file(&hs_path, &mut hs_contents) {
+                eprintln!(
+                    "{}",
+                    tr_fmt(locale, "gen_help.cannot_open", &[&hs_path.display()])
+                );
+                ok = false;
+                continue;
+            }
+
+            for problem in check_help_source(&entries, &hs_contents, locale) {
+                eprintln!("{}: {}", hs_path.display(), problem);
+                ok = false;
+            }
+            continue;
+        }
+
+        let file_name = path
+            .file_name()
+            .map_or(filename.clone(), |s| s.to_string_lossy().to_string());
+        let output = write_help_source(&file_name, &entries, locale);
+
+        // hsphelpのファイルはshift_jisで書く。
+        let contents = encoding::all::WINDOWS_31J
+            .encode(&output, EncoderTrap::NcrEscape)
+            .unwrap();
+        fs::write(&hs_path, contents).expect("write");
+        eprintln!(
+            "{}",
+            tr_fmt(locale, "gen_help.generated", &[&hs_path.display()])
+        );
+    }
+
+    ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::expect;
+
+    const INPUT: &str = r#"
+#module m_stack
+
+;; 値を積む
+;;
+;; x: 積む値
+#deffunc stack_push int x
+    return
+
+#deffunc local stack_helper
+    return
+
+;; 積まれている値の個数
+#defcfunc stack_len
+    return 0
+
+#const CAPACITY 64
+
+;; 最大の深さ
+#const global STACK_MAX_DEPTH CAPACITY * 2
+
+#global
+"#;
+
+    #[test]
+    fn test_write_help_source() {
+        let entries = collect_help_entries(INPUT, Locale::Ja);
+        let output = write_help_source("stack.as", &entries, Locale::Ja).replace(EOL, "\n");
+
+        expect![[r#"
+            ;
+            ; stack.as のヘルプソースファイル
+            ; (`ham gen-help` で生成されました)
+            ;
+
+            %dll
+            stack
+            %type
+            ユーザー拡張命令
+            %note
+            stack.as をインクルードしてください。
+            %port
+            Win
+
+
+            %index
+            stack_push
+            値を積む
+
+            %group
+            m_stack
+
+            %prm
+            x
+            x: 積む値
+
+            %href
+            stack_len
+
+
+            %index
+            stack_len
+            積まれている値の個数
+
+            %group
+            m_stack
+
+            %prm
+            ()
+
+            %href
+            stack_push
+
+
+            %index
+            STACK_MAX_DEPTH
+            最大の深さ
+
+            %group
+            m_stack
+
+            %inst
+            値: CAPACITY * 2
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_generated_help_source_has_no_drift() {
+        let entries = collect_help_entries(INPUT, Locale::Ja);
+        let output = write_help_source("stack.as", &entries, Locale::Ja);
+        assert_eq!(
+            check_help_source(&entries, &output, Locale::Ja),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_check_help_source() {
+        let entries = collect_help_entries(INPUT, Locale::Ja);
+        let hs = r#"
+%index
+stack_push
+値を積む
+%prm
+x, y
+
+%index
+stack_pop
+値を降ろす
+"#;
+
+        expect![[r#"
+            [
+                "stack_push: パラメータが一致しません。(コード: x, ヘルプソース: x, y)",
+                "stack_len: ヘルプソースに記載がありません。",
+                "STACK_MAX_DEPTH: ヘルプソースに記載がありません。",
+                "stack_pop: コードに定義がありません。",
+            ]
+        "#]]
+        .assert_debug_eq(&check_help_source(&entries, hs, Locale::Ja));
+    }
+}
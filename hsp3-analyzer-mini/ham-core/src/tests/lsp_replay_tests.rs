@@ -0,0 +1,180 @@
+#![cfg(test)]
+
+use crate::{
+    analyzer::{vfs::MemoryFs, Analyzer},
+    lsp_server::{
+        lsp_config::LspConfig,
+        lsp_main::serve,
+        lsp_record::{RecordEntry, Side, WORKSPACE_FOLDER_PLACEHOLDER},
+    },
+    test_utils::dummy_path,
+    utils::canonical_uri::CanonicalUri,
+};
+use expect_test::expect_file;
+use lsp_server::{Connection, Message};
+use std::{fmt::Write as _, fs, path::PathBuf, rc::Rc, thread, time::Duration};
+
+/// サーバーの応答を待つ時間の上限
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// 仕組み:
+// `tests/lsp/*.jsonl` は `ham lsp` のセッションの記録 (`HAM_RECORD` で書き出したもの) である。
+// (`tests/lsp/record_session.py` がシナリオのとおりにクライアントとしてメッセージを送って記録を作る。)
+// 記録のうちクライアントが送ったメッセージを、プロセス内で動かしたサーバーに順番に送り、
+// サーバーが送ってきたメッセージを `*.expected.txt` と比較する。
+// (記録の中にあるサーバーのメッセージは使わない。)
+#[test]
+fn lsp_replay_tests() {
+    let tests_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../tests/lsp");
+
+    let mut paths = glob::glob(&format!("{}/*.jsonl", tests_dir.to_string_lossy()))
+        .unwrap()
+        .flatten()
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert_ne!(paths.len(), 0, "記録が見つかりません。{:?}", tests_dir);
+
+    for path in paths {
+        let transcript = fs::read_to_string(&path).expect("read");
+        let output = replay(&transcript);
+        expect_file![path.with_extension("expected.txt")].assert_eq(&output);
+    }
+}
+
+fn replay(transcript: &str) -> String {
+    let workspace_uri = CanonicalUri::from_abs_path(&dummy_path().join("ws"))
+        .unwrap()
+        .into_url()
+        .to_string();
+    let workspace_uri = workspace_uri.trim_end_matches('/');
+
+    let mut messages = transcript
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let line = line.replace(WORKSPACE_FOLDER_PLACEHOLDER, workspace_uri);
+            serde_json::from_str::<RecordEntry>(&line).expect("parse")
+        })
+        .filter(|entry| entry.from == Side::Client)
+        .map(|entry| entry.message)
+        .collect::<Vec<_>>();
+
+    // 記録が途中で終わっているときは、サーバーを終了させるメッセージを補う。
+    let has = |messages: &[Message], method: &str| {
+        messages.iter().any(|msg| match msg {
+            Message::Request(req) => req.method == method,
+            Message::Notification(nn) => nn.method == method,
+            Message::Response(_) => false,
+        })
+    };
+    if !has(&messages, "shutdown") {
+        messages.push(Message::Request(lsp_server::Request::new(
+            i32::MAX.into(),
+            "shutdown".to_string(),
+            serde_json::Value::Null,
+        )));
+    }
+    if !has(&messages, "exit") {
+        messages.push(Message::Notification(lsp_server::Notification::new(
+            "exit".to_string(),
+            serde_json::Value::Null,
+        )));
+    }
+
+    let (client, server) = Connection::memory();
+    let server_thread = thread::spawn(move || {
+        let lsp_config = LspConfig {
+            document_symbol_enabled: true,
+            lint_enabled: true,
+            watcher_enabled: true,
+            ..LspConfig::default()
+        };
+        let an = Analyzer::with_vfs(None, Rc::new(MemoryFs::default()));
+        serve(server, &lsp_config, an)
+    });
+
+    let mut output = String::new();
+    for msg in messages {
+        // サーバーからのリクエストに応答するときは、先にそのリクエストが来るのを待つ。
+        if let Message::Response(resp) = &msg {
+            receive_until(
+                &client,
+                &mut output,
+                |msg| matches!(msg, Message::Request(req) if req.id == resp.id),
+            );
+        }
+
+        write_message(&mut output, Side::Client, &msg);
+
+        let id_opt = match &msg {
+            Message::Request(req) => Some(req.id.clone()),
+            _ => None,
+        };
+        client.sender.send(msg).unwrap();
+
+        // リクエストを送ったら、その応答が来るまでに送られてきたメッセージを記録する。
+        if let Some(id) = id_opt {
+            receive_until(
+                &client,
+                &mut output,
+                |msg| matches!(msg, Message::Response(resp) if resp.id == id),
+            );
+        }
+    }
+
+    server_thread.join().unwrap().expect("serve");
+
+    // 終了するまでに送られてきたメッセージ
+    for msg in client.receiver.iter() {
+        write_message(&mut output, Side::Server, &msg);
+    }
+
+    output.replace(workspace_uri, WORKSPACE_FOLDER_PLACEHOLDER)
+}
+
+/// サーバーから送られてくるメッセージを、条件を満たすものが来るまで記録する。
+fn receive_until(client: &Connection, output: &mut String, done: impl Fn(&Message) -> bool) {
+    loop {
+        let msg = client
+            .receiver
+            .recv_timeout(TIMEOUT)
+            .unwrap_or_else(|err| panic!("メッセージが来ません。{:?}\n{}", err, output));
+        write_message(output, Side::Server, &msg);
+        if done(&msg) {
+            return;
+        }
+    }
+}
+
+fn write_message(output: &mut String, side: Side, msg: &Message) {
+    let arrow = match side {
+        Side::Client => ">>>",
+        Side::Server => "<<<",
+    };
+
+    let body = match msg {
+        Message::Request(req) => {
+            writeln!(output, "{} request #{} {}", arrow, req.id, req.method).unwrap();
+            req.params.clone()
+        }
+        Message::Notification(nn) => {
+            writeln!(output, "{} notification {}", arrow, nn.method).unwrap();
+            nn.params.clone()
+        }
+        Message::Response(resp) => match &resp.error {
+            Some(err) => {
+                writeln!(output, "{} error #{}", arrow, resp.id).unwrap();
+                serde_json::to_value(err).unwrap()
+            }
+            None => {
+                writeln!(output, "{} response #{}", arrow, resp.id).unwrap();
+                resp.result.clone().unwrap_or_default()
+            }
+        },
+    };
+
+    if !body.is_null() {
+        writeln!(output, "{}", serde_json::to_string_pretty(&body).unwrap()).unwrap();
+    }
+    output.push('\n');
+}
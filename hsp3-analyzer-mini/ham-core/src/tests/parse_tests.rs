@@ -1,10 +1,11 @@
 #![cfg(test)]
 
 use crate::{
+    cst::{build_cst, SyntaxNode},
     parse::{parse_root, PToken},
     source::DocId,
     token::{self, TokenKind},
-    utils::{rc_str::RcStr, read_file::read_file},
+    utils::{rc_slice::RcSlice, rc_str::RcStr, read_file::read_file},
 };
 use std::{fs, path::PathBuf, rc::Rc};
 
@@ -61,8 +62,15 @@ fn parse_standard_files() {
                 last_id
             };
             let tokens = token::tokenize(doc, RcStr::from(text.to_string()));
-            let tokens = PToken::from_tokens(tokens.into());
-            let root = parse_root(tokens);
+            let tokens: RcSlice<_> = PToken::from_tokens(tokens.into()).into();
+            let root = parse_root(tokens.to_owned());
+
+            // 具象構文木は元のテキストを復元できる。
+            let cst = SyntaxNode::new_root(build_cst(&tokens, &root));
+            if cst.text() != text.as_str() {
+                eprintln!("path={:?} CST text mismatch", path);
+                ok = false;
+            }
 
             for t in root
                 .skipped
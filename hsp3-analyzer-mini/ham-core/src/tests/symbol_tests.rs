@@ -324,6 +324,45 @@ mod ref_tests {
         }
     }
 
+    #[test]
+    fn test_const_init_does_not_define_var() {
+        let mut an = Analyzer::new_standalone();
+
+        // 定数式の中の未定義の名前は変数にならない。定義済みの名前は参照として解決される。
+        let text = r#"
+            #const A <|B|>B + 1
+                x = 1
+            #const C <|X|>x
+            <|B2|>B = 2
+        "#;
+        let expected_map = vec![("B", None), ("X", Some("x")), ("B2", Some("B"))]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let (text, cursors) = parse_cursor_string(text);
+
+        an.open_doc(dummy_url("a.hsp"), NO_VERSION, text);
+        let doc = an
+            .find_doc_by_uri(&CanonicalUri::from_url(&dummy_url("a.hsp")))
+            .unwrap();
+
+        let an = an.compute_ref();
+
+        for (name, pos) in cursors {
+            let actual = an
+                .locate_symbol(doc, pos.into())
+                .map(|(symbol, _)| symbol.name());
+            assert_eq!(actual.as_deref(), expected_map[name], "name={}", name);
+        }
+
+        // `B` の定義は代入の1箇所だけ
+        let b_defs = an
+            .def_sites
+            .iter()
+            .filter(|(symbol, _)| symbol.name().eq_ignore_ascii_case("b"))
+            .count();
+        assert_eq!(b_defs, 1);
+    }
+
     #[test]
     fn test_it_works() {
         let mut an = Analyzer::new_standalone();
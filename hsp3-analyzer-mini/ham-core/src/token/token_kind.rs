@@ -1,5 +1,6 @@
 /// 字句の種類
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
 #[must_use]
 pub(crate) enum TokenKind {
     // マーカー:
@@ -118,6 +119,9 @@ pub(crate) enum TokenKind {
 }
 
 impl TokenKind {
+    /// 最後の列挙子 (列挙子を追加したら更新すること)
+    pub(crate) const LAST: TokenKind = TokenKind::StarEqual;
+
     /// 先行トリビアか？
     /// 先行トリビアには改行も含まれる。
     /// (空白やコメントなど、構文上の役割を持たないトークンをトリビアと呼ぶ。)
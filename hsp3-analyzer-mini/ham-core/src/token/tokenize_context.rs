@@ -13,13 +13,14 @@ pub(crate) struct TokenizeContext {
 }
 
 impl TokenizeContext {
-    pub(crate) fn new(doc: DocId, source_code: RcStr) -> Self {
+    /// `start`: `source_code` の先頭の位置
+    pub(crate) fn new(doc: DocId, source_code: RcStr, start: Pos) -> Self {
         TokenizeContext {
             doc,
             source_code,
             current_index: 0,
             last_index: 0,
-            last_position: Pos::default(),
+            last_position: start,
             tokens: vec![],
         }
     }
@@ -2,6 +2,7 @@
 
 use super::tokenize_context::TokenizeContext;
 use super::*;
+use crate::i18n::{tr, Locale};
 
 type Tx = TokenizeContext;
 
@@ -232,7 +233,11 @@ fn eat_escaped_text(quote: char, tx: &mut Tx) {
             '\0' | '\n' | '\r' => break,
             '\\' => {
                 tx.bump();
-                tx.bump();
+
+                // ファイルの末尾にある `\` の後ろには何もない。
+                if tx.next() != '\0' {
+                    tx.bump();
+                }
             }
             c if c == quote => break,
             _ => tx.bump(),
@@ -397,11 +402,111 @@ pub(crate) fn do_tokenize(tx: &mut Tx) {
 }
 
 pub(crate) fn tokenize(doc: DocId, text: RcStr) -> Vec<TokenData> {
-    let mut tx = Tx::new(doc, text);
+    tokenize_at(doc, text, Pos::default())
+}
+
+/// ドキュメントの一部を字句解析する
+///
+/// `start` は `text` の先頭がドキュメント上にある位置。トークンの位置はドキュメント上の位置になる。
+pub(crate) fn tokenize_at(doc: DocId, text: RcStr, start: Pos) -> Vec<TokenData> {
+    let mut tx = Tx::new(doc, text, start);
     do_tokenize(&mut tx);
     tx.finish()
 }
 
+/// 字句解析の段階で見つかる、`hspcmp` のエラーの原因になりやすい文字
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TokenLint {
+    /// 全角空白などの特殊な空白文字
+    FullWidthSpace,
+    /// 字句として解釈できない文字
+    BadChar,
+    /// shift_jis で表現できない文字
+    NotShiftJis,
+}
+
+impl TokenLint {
+    pub(crate) fn as_str(self, locale: Locale) -> &'static str {
+        match self {
+            TokenLint::FullWidthSpace => tr(locale, "lint.full_width_space"),
+            TokenLint::BadChar => tr(locale, "lint.bad_char"),
+            TokenLint::NotShiftJis => tr(locale, "lint.not_sjis"),
+        }
+    }
+
+    /// 問題のある文字列 `text` を置き換えるテキスト
+    ///
+    /// (shift_jis で表現できない文字は `?` にする。shift_jis で保存したときに置き換わる文字と同じ)
+    pub(crate) fn fix(self, text: &str) -> String {
+        match self {
+            TokenLint::FullWidthSpace => " ".repeat(text.chars().count()),
+            TokenLint::BadChar => String::new(),
+            TokenLint::NotShiftJis => "?".repeat(text.chars().count()),
+        }
+    }
+}
+
+fn is_special_space(c: char) -> bool {
+    c.is_whitespace() && !matches!(c, ' ' | '\t' | '\r' | '\n')
+}
+
+fn is_not_sjis(c: char) -> bool {
+    use encoding::{EncoderTrap, Encoding};
+
+    !c.is_ascii()
+        && encoding::all::WINDOWS_31J
+            .encode(c.encode_utf8(&mut [0; 4]), EncoderTrap::Strict)
+            .is_err()
+}
+
+/// トークンの中で条件を満たす文字が連続している範囲を列挙する。
+fn lint_chars(
+    token: &TokenData,
+    lint: TokenLint,
+    pred: impl Fn(char) -> bool,
+    lints: &mut Vec<(TokenLint, Loc)>,
+) {
+    let text = token.text.as_str();
+    let start = token.loc.start();
+
+    let mut run_start_opt = None;
+    for (i, c) in text.char_indices().chain(iter::once((text.len(), '\0'))) {
+        match (run_start_opt, i < text.len() && pred(c)) {
+            (None, true) => run_start_opt = Some(i),
+            (Some(run_start), false) => {
+                let range = Range::from(
+                    start + Pos::from(&text[..run_start])..start + Pos::from(&text[..i]),
+                );
+                lints.push((lint, token.loc.with_range(range)));
+                run_start_opt = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// トークン列から、問題のある文字を探す。
+///
+/// `sjis` はソースファイルが shift_jis として扱われるか。(そうなら、文字列などが shift_jis で表現できるか検査する。)
+pub(crate) fn lint_tokens<'a>(
+    tokens: impl IntoIterator<Item = &'a TokenData>,
+    sjis: bool,
+    lints: &mut Vec<(TokenLint, Loc)>,
+) {
+    for token in tokens {
+        match token.kind {
+            TokenKind::Blank | TokenKind::Newlines => {
+                lint_chars(token, TokenLint::FullWidthSpace, is_special_space, lints);
+            }
+            TokenKind::Bad => lints.push((TokenLint::BadChar, token.loc)),
+            TokenKind::Ident | TokenKind::Str | TokenKind::Char if sjis => {
+                lint_chars(token, TokenLint::NotShiftJis, is_not_sjis, lints);
+            }
+            _ => {}
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{tokenize, TokenKind};
@@ -560,6 +665,13 @@ mod tests {
         assert_eq!(tokenize_str_to_kinds("\"你好☺\""), vec![TokenKind::Str]);
     }
 
+    #[test]
+    fn str_with_backslash_at_eof() {
+        // ファイルの末尾にある `\` で終わる文字列は、そこで終わる。
+        assert_eq!(tokenize_str_to_kinds("\"abc\\"), vec![TokenKind::Str]);
+        assert_eq!(tokenize_str_to_kinds("'\\"), vec![TokenKind::Char]);
+    }
+
     #[test]
     fn here_document() {
         assert_eq!(
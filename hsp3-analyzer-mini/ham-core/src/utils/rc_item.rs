@@ -22,6 +22,11 @@ impl<T> RcItem<T> {
     pub(crate) fn new_single(value: T) -> Self {
         RcItem::new(Rc::new([value]), 0)
     }
+
+    /// 2つの参照が同じ要素を指しているか
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.underlying, &other.underlying) && self.index == other.index
+    }
 }
 
 // impl<T: Clone> RcItem<T> {
@@ -1,45 +1,68 @@
 use encoding::{DecoderTrap, Encoding};
 use std::{fs, path::Path, str};
 
-/// テキストファイルを shift_jis または UTF-8 として読む。
-pub(crate) fn read_file(file_path: &Path, out: &mut String) -> bool {
-    // バイナリで読む。
-    let contents = match fs::read(file_path).ok() {
-        None => return false,
-        Some(it) => it,
-    };
+/// テキストファイルの文字コード
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FileEncoding {
+    /// ASCII文字だけからなる (UTF-8 としても shift_jis としても読める)
+    Ascii,
+    Utf8,
+    ShiftJis,
+}
 
+/// ファイルの内容を shift_jis または UTF-8 として読み、その文字コードを返す。
+pub(crate) fn decode_contents(contents: &[u8], out: &mut String) -> Option<FileEncoding> {
     // 可能ならUTF-8として読む。
-    match str::from_utf8(&contents) {
+    match str::from_utf8(contents) {
         Ok(text) => {
             *out += text;
-            return true;
+            let encoding = if text.is_ascii() {
+                FileEncoding::Ascii
+            } else {
+                FileEncoding::Utf8
+            };
+            return Some(encoding);
         }
         Err(_) => {}
     }
 
     // shift_jisから変換する。
     encoding::all::WINDOWS_31J
-        .decode_to(&contents, DecoderTrap::Strict, out)
-        .is_ok()
+        .decode_to(contents, DecoderTrap::Strict, out)
+        .ok()
+        .map(|_| FileEncoding::ShiftJis)
+}
+
+/// テキストファイルを shift_jis または UTF-8 として読む。
+pub(crate) fn read_file(file_path: &Path, out: &mut String) -> bool {
+    // バイナリで読む。
+    match fs::read(file_path).ok() {
+        Some(contents) => decode_contents(&contents, out).is_some(),
+        None => false,
+    }
 }
 
 /// テキストファイルを可能ならshift_jisとして読み、ダメだったらUTF-8として読む。
 pub(crate) fn read_sjis_file(file_path: &Path, out: &mut String) -> bool {
     debug_assert_eq!(out.len(), 0);
 
-    let contents = match fs::read(file_path).ok() {
-        None => return false,
-        Some(it) => it,
-    };
+    match fs::read(file_path).ok() {
+        Some(contents) => decode_sjis_contents(&contents, out),
+        None => false,
+    }
+}
+
+/// ファイルの内容を可能ならshift_jisとして読み、ダメだったらUTF-8として読む。
+pub(crate) fn decode_sjis_contents(contents: &[u8], out: &mut String) -> bool {
+    debug_assert_eq!(out.len(), 0);
 
-    let result = encoding::all::WINDOWS_31J.decode_to(&contents, DecoderTrap::Strict, out);
+    let result = encoding::all::WINDOWS_31J.decode_to(contents, DecoderTrap::Strict, out);
     if result.is_ok() {
         return true;
     }
     out.clear();
 
-    match str::from_utf8(&contents) {
+    match str::from_utf8(contents) {
         Ok(text) => {
             *out += text;
             true
@@ -0,0 +1,195 @@
+// このファイルは ham-core/src/c_api.rs から生成されたものです。(手動で編集しないこと)
+// LICENSE: CC0-1.0
+
+#ifndef HAM_SDK_H
+#define HAM_SDK_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct HamInstance HamInstance;
+
+// ログの出力などを初期化する。
+void ham_init(void);
+
+// インスタンスを作る。
+//
+// `hsp3_root` はHSPのインストールディレクトリ。(空文字列ならなし)
+// `options` はオプションを表す JSON。(空文字列なら既定値)
+//
+// ```json
+// { "lintEnabled": true, "workspaceFolder": "file:///C:/work", "positionEncoding": "utf-8" }
+// ```
+//
+// `positionEncoding` は位置情報の列番号の単位で、
+// `"utf-8"` (既定値), `"utf-16"` または `"shift_jis"` (バイト数) を指定できる。
+//
+// 失敗したらヌルを返す。
+HamInstance *ham_create(
+    const char *hsp3_root,
+    int32_t hsp3_root_len,
+    const char *options,
+    int32_t options_len
+);
+
+// ディスクの代わりにメモリ上のファイルシステムを使うインスタンスを作る。
+//
+// 引数は `ham_create` と同じ。
+// `ham_fs_write` でファイルを置いてから `ham_initialize` を呼ぶこと。
+HamInstance *ham_create_in_memory(
+    const char *hsp3_root,
+    int32_t hsp3_root_len,
+    const char *options,
+    int32_t options_len
+);
+
+// `ham_create_in_memory` で作ったインスタンスの初期化を行う。
+int32_t ham_initialize(
+    HamInstance *instance
+);
+
+// メモリ上のファイルシステムにファイルを置く。(すでにあれば内容を置き換える。)
+int32_t ham_fs_write(
+    HamInstance *instance,
+    const char *path,
+    int32_t path_len,
+    const char *data,
+    int32_t data_len
+);
+
+// メモリ上のファイルシステムからファイルを削除する。
+int32_t ham_fs_remove(
+    HamInstance *instance,
+    const char *path,
+    int32_t path_len
+);
+
+// インスタンスを破棄する。
+int32_t ham_destroy(
+    HamInstance *instance
+);
+
+// テキストドキュメントが開かれたことを通知する。
+int32_t ham_doc_did_open(
+    HamInstance *instance,
+    const char *uri,
+    int32_t uri_len,
+    int32_t version,
+    const char *text,
+    int32_t text_len
+);
+
+// テキストドキュメントが変更されたことを通知する。
+int32_t ham_doc_did_change(
+    HamInstance *instance,
+    const char *uri,
+    int32_t uri_len,
+    int32_t version,
+    const char *text,
+    int32_t text_len
+);
+
+// テキストドキュメントが閉じられたことを通知する。
+int32_t ham_doc_did_close(
+    HamInstance *instance,
+    const char *uri,
+    int32_t uri_len
+);
+
+// カーソル付近のシンボルの情報をテキストで得る。
+//
+// (結果がバッファに収まらないときは切り詰められる。)
+int32_t ham_hover(
+    HamInstance *instance,
+    const char *uri,
+    int32_t uri_len,
+    int32_t position_line,
+    int32_t position_character,
+    char *output,
+    int32_t *output_len
+);
+
+// 入力補完の候補を得る。(結果は `CompletionList` の JSON)
+int32_t ham_completion(
+    HamInstance *instance,
+    const char *uri,
+    int32_t uri_len,
+    int32_t position_line,
+    int32_t position_character,
+    char *output,
+    int32_t *output_len
+);
+
+// 関数呼び出しのシグネチャの情報を得る。(結果は `SignatureHelp` または null の JSON)
+int32_t ham_signature_help(
+    HamInstance *instance,
+    const char *uri,
+    int32_t uri_len,
+    int32_t position_line,
+    int32_t position_character,
+    char *output,
+    int32_t *output_len
+);
+
+// シンボルの定義箇所を得る。(結果は `Location` の配列の JSON)
+int32_t ham_definitions(
+    HamInstance *instance,
+    const char *uri,
+    int32_t uri_len,
+    int32_t position_line,
+    int32_t position_character,
+    char *output,
+    int32_t *output_len
+);
+
+// シンボルの出現箇所を得る。(結果は `Location` の配列の JSON)
+//
+// `include_definition` が真なら定義箇所も含める。
+int32_t ham_references(
+    HamInstance *instance,
+    const char *uri,
+    int32_t uri_len,
+    int32_t position_line,
+    int32_t position_character,
+    int32_t include_definition,
+    char *output,
+    int32_t *output_len
+);
+
+// ドキュメントに含まれるシンボルの一覧を得る。(結果は `DocumentSymbol` の配列または null の JSON)
+int32_t ham_document_symbols(
+    HamInstance *instance,
+    const char *uri,
+    int32_t uri_len,
+    char *output,
+    int32_t *output_len
+);
+
+// ドキュメントの診断 (エラーや警告) を得る。(結果は `Diagnostic` の配列の JSON)
+//
+// オプションで診断が無効になっているときは空の配列になる。
+int32_t ham_diagnostics(
+    HamInstance *instance,
+    const char *uri,
+    int32_t uri_len,
+    char *output,
+    int32_t *output_len
+);
+
+// ドキュメントを整形するための編集を得る。(結果は `TextEdit` の配列または null の JSON)
+int32_t ham_formatting(
+    HamInstance *instance,
+    const char *uri,
+    int32_t uri_len,
+    char *output,
+    int32_t *output_len
+);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // HAM_SDK_H
@@ -1 +1,10 @@
+//! HSP3 のスクリプトを解析するためのライブラリ
+//!
+//! - Rust から使うときは [`AnalysisHost`] などを使う。(`ham_core::api` を参照)
+//! - C言語や HSP3 から使うときは DLL としてビルドして、`ham_sdk.h` にある関数を使う。
+
+pub use ham_core::api::*;
+
+// DLL がエクスポートする関数
+#[doc(hidden)]
 pub use ham_core::c_api::*;
@@ -0,0 +1,342 @@
+#!/usr/bin/env python3
+# LSPの通信の記録 (tests/lsp/<名前>.jsonl) を作るスクリプト
+#
+# 使いかた (hsp3-analyzer-mini ディレクトリで):
+#
+#     cargo build
+#     python3 tests/lsp/record_session.py session pull_diagnostics
+#
+# `ham lsp` を環境変数 HAM_RECORD をつけて起動し、下にあるシナリオのとおりにクライアントとしてメッセージを送る。
+# サーバーが書き出した記録を tests/lsp/<名前>.jsonl に置く。
+# (期待される結果 tests/lsp/<名前>.expected.txt は `UPDATE_EXPECT=1 cargo test` で更新する。)
+#
+# - ワークスペースは一時ディレクトリに作る。(記録の中では `${workspaceFolder}` に置き換えられる)
+# - サーバーからのリクエストには、シナリオの決まった位置で null で応答する。
+#   (記録を再生するときも同じ順番になるように、来ることがわかっているリクエストを待って応答する)
+# - プル型の診断の結果のIDには起動ごとに変わる値 (nonce) が含まれるため、記録の中の nonce を
+#   テストでの値 (`test`) に置き換える。
+# - 使う `ham` の実行ファイルは環境変数 HAM で指定できる。(既定は target/debug/ham)
+
+import json
+import os
+import subprocess
+import sys
+import tempfile
+import threading
+from pathlib import Path
+
+TESTS_DIR = Path(__file__).resolve().parent
+PROJECT_DIR = TESTS_DIR.parent.parent
+
+# 記録の中の nonce を置き換える値 (ham-core/src/ide/diagnose.rs の new_nonce を参照)
+TEST_NONCE = "test"
+
+
+class Client:
+    def __init__(self, ham, workspace, record_path):
+        env = dict(os.environ, HAM_RECORD=str(record_path))
+        self.proc = subprocess.Popen(
+            [str(ham), "lsp"],
+            stdin=subprocess.PIPE,
+            stdout=subprocess.PIPE,
+            cwd=workspace,
+            env=env,
+        )
+        self.workspace_uri = workspace.as_uri()
+        self.cond = threading.Condition()
+        self.responses = {}
+        self.requests = []
+        self.reader = threading.Thread(target=self._read_loop, daemon=True)
+        self.reader.start()
+
+    def uri(self, name):
+        return f"{self.workspace_uri}/{name}"
+
+    def _write(self, msg):
+        body = json.dumps(dict(jsonrpc="2.0", **msg), ensure_ascii=False).encode("utf-8")
+        self.proc.stdin.write(b"Content-Length: %d\r\n\r\n" % len(body) + body)
+        self.proc.stdin.flush()
+
+    def _read_message(self):
+        length = None
+        while True:
+            line = self.proc.stdout.readline()
+            if not line:
+                return None
+            line = line.strip()
+            if not line:
+                break
+            name, _, value = line.decode("ascii").partition(":")
+            if name.lower() == "content-length":
+                length = int(value)
+        return json.loads(self.proc.stdout.read(length))
+
+    def _read_loop(self):
+        while True:
+            msg = self._read_message()
+            if msg is None:
+                return
+            with self.cond:
+                if "method" in msg and "id" in msg:
+                    self.requests.append(msg)
+                elif "method" not in msg:
+                    self.responses[msg["id"]] = msg
+                self.cond.notify_all()
+
+    def request(self, id, method, params=None):
+        msg = {"id": id, "method": method}
+        if params is not None:
+            msg["params"] = params
+        self._write(msg)
+        with self.cond:
+            if not self.cond.wait_for(lambda: id in self.responses, timeout=10):
+                raise TimeoutError(f"応答が来ません。{method}")
+            return self.responses.pop(id)
+
+    def reply(self, method):
+        """サーバーから指定したメソッドのリクエストが来るのを待って、null で応答する。"""
+        with self.cond:
+            found = lambda: next((m for m in self.requests if m["method"] == method), None)
+            if not self.cond.wait_for(lambda: found() is not None, timeout=10):
+                raise TimeoutError(f"リクエストが来ません。{method}")
+            msg = found()
+            self.requests.remove(msg)
+        self._write({"id": msg["id"], "result": None})
+
+    def notify(self, method, params=None):
+        msg = {"method": method}
+        if params is not None:
+            msg["params"] = params
+        self._write(msg)
+
+    def initialize(self, capabilities, **params):
+        self.request(
+            1,
+            "initialize",
+            {
+                "capabilities": capabilities,
+                "clientInfo": {"name": "record_session.py"},
+                "processId": None,
+                "rootUri": self.workspace_uri,
+                "workspaceFolders": [{"name": "ws", "uri": self.workspace_uri}],
+                **params,
+            },
+        )
+        self.notify("initialized", {})
+        self.reply("client/registerCapability")
+
+    def open(self, name, text):
+        self.notify(
+            "textDocument/didOpen",
+            {
+                "textDocument": {
+                    "languageId": "hsp3",
+                    "text": text,
+                    "uri": self.uri(name),
+                    "version": 1,
+                }
+            },
+        )
+
+    def change(self, name, version, text):
+        self.notify(
+            "textDocument/didChange",
+            {
+                "contentChanges": [{"text": text}],
+                "textDocument": {"uri": self.uri(name), "version": version},
+            },
+        )
+
+    def at(self, name, line, character):
+        return {
+            "position": {"character": character, "line": line},
+            "textDocument": {"uri": self.uri(name)},
+        }
+
+    def finish(self, id):
+        if self.requests:
+            raise RuntimeError(f"応答していないリクエストがあります。{self.requests}")
+        self.request(id, "shutdown")
+        self.notify("exit")
+        self.proc.stdin.close()
+        self.proc.wait(timeout=10)
+        self.reader.join()
+
+
+# ------------------------------------------------
+# シナリオ
+# ------------------------------------------------
+
+SESSION_MAIN = """\
+#module
+#deffunc hello str msg
+\tmes "Hello, " + msg
+\treturn
+#global
+#const A 1 / 0
+
+\thello "world"
+\trepeat
+\t\tx = A
+\t\treturn
+\tloop
+"""
+
+
+def session(c, workspace):
+    """基本的なリクエストと、未知のメソッドへのエラー応答"""
+    c.initialize(
+        {
+            "general": {"positionEncodings": ["utf-16"]},
+            "textDocument": {"hover": {"contentFormat": ["markdown", "plaintext"]}},
+            "workspace": {
+                "didChangeWatchedFiles": {"dynamicRegistration": True},
+                "workspaceFolders": True,
+            },
+        },
+        locale="ja",
+    )
+    c.open("main.hsp", SESSION_MAIN)
+    c.request(2, "textDocument/hover", c.at("main.hsp", 7, 2))
+    c.request(3, "textDocument/definition", c.at("main.hsp", 7, 2))
+    c.request(4, "textDocument/documentSymbol", {"textDocument": {"uri": c.uri("main.hsp")}})
+    c.request(5, "hsp3-analyzer-mini/writeReferences", c.at("main.hsp", 9, 2))
+    c.request(6, "textDocument/unknownMethod", {"textDocument": {"uri": c.uri("main.hsp")}})
+    c.change("main.hsp", 2, SESSION_MAIN.replace("1 / 0", "1 / 2"))
+    c.request(7, "textDocument/hover", c.at("main.hsp", 1, 10))
+    c.finish(8)
+
+
+PULL_MAIN = """\
+#module
+#deffunc hello str msg
+\tmes "Hello, " + msg
+\treturn
+#global
+
+\thello "world"
+\trepeat
+\t\treturn
+\tloop
+"""
+
+
+def pull_diagnostics(c, workspace):
+    """プル型の診断 (textDocument/diagnostic, workspace/diagnostic)"""
+    c.initialize(
+        {
+            "general": {"positionEncodings": ["utf-16"]},
+            "textDocument": {"diagnostic": {"relatedDocumentSupport": False}},
+            "workspace": {
+                "diagnostics": {"refreshSupport": True},
+                "didChangeWatchedFiles": {"dynamicRegistration": True},
+                "workspaceFolders": True,
+            },
+        }
+    )
+    c.open("main.hsp", PULL_MAIN)
+    c.open("sub.hsp", "#const B 1 / 0\n")
+
+    main = {"textDocument": {"uri": c.uri("main.hsp")}}
+    first = c.request(2, "textDocument/diagnostic", main)["result"]["resultId"]
+    c.request(3, "textDocument/diagnostic", {"previousResultId": first, **main})
+    c.change("main.hsp", 2, PULL_MAIN.replace("\t\treturn\n\tloop", "\t\tbreak\n\tloop"))
+    second = c.request(4, "textDocument/diagnostic", {"previousResultId": first, **main})
+    second = second["result"]["resultId"]
+    c.request(5, "textDocument/hover", c.at("main.hsp", 6, 2))
+    c.request(
+        6,
+        "workspace/diagnostic",
+        {"previousResultIds": [{"uri": c.uri("main.hsp"), "value": second}]},
+    )
+
+    # ファイルが作られたことを通知すると、サーバーは診断の更新を要求してくる。
+    (workspace / "new.hsp").write_text("", encoding="utf-8")
+    c.notify(
+        "workspace/didChangeWatchedFiles",
+        {"changes": [{"type": 1, "uri": c.uri("new.hsp")}]},
+    )
+    c.reply("workspace/diagnostic/refresh")
+    c.finish(7)
+
+
+POSITION_ENCODING_MAIN = """\
+#deffunc hello str msg
+\tmes msg
+\treturn
+
+\tmes "𠮷野家": hello "world"
+"""
+
+
+def position_encoding(c, workspace):
+    """列番号の単位に UTF-8 を使うクライアント (トークンの前に ASCII 以外の文字がある)"""
+    c.initialize(
+        {
+            "general": {"positionEncodings": ["utf-8", "utf-16"]},
+            "workspace": {
+                "didChangeWatchedFiles": {"dynamicRegistration": True},
+                "workspaceFolders": True,
+            },
+        }
+    )
+    c.open("main.hsp", POSITION_ENCODING_MAIN)
+
+    # 4行目の hello は UTF-8 で 19 バイト目から始まる。(UTF-16 では13番目)
+    c.request(2, "textDocument/hover", c.at("main.hsp", 4, 21))
+    c.request(
+        3,
+        "textDocument/references",
+        {**c.at("main.hsp", 4, 21), "context": {"includeDeclaration": True}},
+    )
+    c.request(4, "textDocument/semanticTokens/full", {"textDocument": {"uri": c.uri("main.hsp")}})
+    c.finish(5)
+
+
+SCENARIOS = {
+    "session": session,
+    "pull_diagnostics": pull_diagnostics,
+    "position_encoding": position_encoding,
+}
+
+
+def record(name, ham):
+    with tempfile.TemporaryDirectory() as tmp:
+        workspace = Path(tmp) / "ws"
+        workspace.mkdir()
+        record_path = Path(tmp) / "record.jsonl"
+
+        c = Client(ham, workspace, record_path)
+        SCENARIOS[name](c, workspace)
+        lines = record_path.read_text(encoding="utf-8").splitlines()
+
+    nonces = set()
+    for line in lines:
+        entry = json.loads(line)
+        result = entry["message"].get("result")
+        items = result.get("items") if isinstance(result, dict) else None
+        for report in [result] + (items if isinstance(items, list) else []):
+            if isinstance(report, dict) and isinstance(report.get("resultId"), str):
+                nonces.add(report["resultId"].rsplit("-", 1)[0])
+    text = "\n".join(lines) + "\n"
+    for nonce in nonces:
+        text = text.replace(f'"{nonce}-', f'"{TEST_NONCE}-')
+
+    out = TESTS_DIR / f"{name}.jsonl"
+    out.write_text(text, encoding="utf-8", newline="\n")
+    print(f"wrote {out}", file=sys.stderr)
+
+
+def main():
+    names = sys.argv[1:] or list(SCENARIOS)
+    for name in names:
+        if name not in SCENARIOS:
+            sys.exit(f"unknown scenario: {name} (expected one of {', '.join(SCENARIOS)})")
+
+    ham = Path(os.environ.get("HAM", PROJECT_DIR / "target" / "debug" / "ham"))
+    for name in names:
+        record(name, ham)
+
+
+if __name__ == "__main__":
+    main()
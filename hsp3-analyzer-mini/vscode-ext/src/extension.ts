@@ -10,7 +10,8 @@
 
 import * as fs from "fs/promises"
 import { watch, FSWatcher } from "fs"
-import { ExtensionContext, workspace, window } from "vscode"
+import * as path from "path"
+import { commands, ExtensionContext, Location, Position, Uri, workspace, window } from "vscode"
 import { Disposable, LanguageClient, LanguageClientOptions, ServerOptions } from "vscode-languageclient/node"
 
 /** 開発モード */
@@ -70,6 +71,75 @@ const isDocumentSymbolEnabled = () =>
 const isLintEnabled = () =>
   workspace.getConfiguration("hsp3-analyzer-mini").get<boolean>("lint-enabled") ?? true
 
+// -----------------------------------------------
+// コードレンズのコマンド
+// -----------------------------------------------
+
+/** スクリプトを実行するコマンド (LSPサーバーの `workspace/executeCommand` をフックする) */
+const RUN_SCRIPT_COMMAND = "hsp3-analyzer-mini.runScript"
+
+/** 参照を表示するコマンド (LSPサーバーのコードレンズから呼ばれる) */
+const SHOW_REFERENCES_COMMAND = "hsp3-analyzer-mini.showReferences"
+
+/**
+ * スクリプトをコンパイルして実行する。
+ *
+ * (HSP3のインストールディレクトリに配置された ginger.exe を使う。見つからなければエラーを表示する)
+ */
+const runScript = async (hsp3Root: string, uri: string): Promise<void> => {
+  const scriptPath = Uri.parse(uri).fsPath
+  const gingerPath = path.join(hsp3Root, "ginger.exe")
+
+  try {
+    await fs.access(gingerPath)
+  } catch {
+    window.showErrorMessage(`ginger.exe が見つからないため、スクリプトを実行できません。(${gingerPath}) HSP3のインストールディレクトリに ginger.exe を配置するか、設定 hsp3-analyzer-mini.hsp3-root を確認してください。`)
+    return
+  }
+
+  const terminal = window.createTerminal({
+    name: "HSP3",
+    shellPath: gingerPath,
+    shellArgs: ["run", scriptPath],
+    cwd: path.dirname(scriptPath),
+  })
+  terminal.show(true)
+}
+
+const registerShowReferencesCommand = (context: ExtensionContext, client: LanguageClient): void => {
+  context.subscriptions.push(commands.registerCommand(SHOW_REFERENCES_COMMAND, (uri: string, position: unknown, locations: unknown[]) => {
+    const c = client.protocol2CodeConverter
+    return commands.executeCommand(
+      "editor.action.showReferences",
+      Uri.parse(uri),
+      c.asPosition(position as any) as Position,
+      locations.map(l => c.asLocation(l as any) as Location),
+    )
+  }))
+}
+
+/** 書き込みの箇所だけを表示するコマンド (LSPサーバーの拡張 `hsp3-analyzer-mini/writeReferences` を使う) */
+const SHOW_WRITE_REFERENCES_COMMAND = "hsp3-analyzer-mini.showWriteReferences"
+
+const registerShowWriteReferencesCommand = (context: ExtensionContext, client: LanguageClient): void => {
+  context.subscriptions.push(commands.registerCommand(SHOW_WRITE_REFERENCES_COMMAND, async () => {
+    const editor = window.activeTextEditor
+    if (editor == null) return
+
+    const c = client.code2ProtocolConverter
+    const locations = await client.sendRequest<unknown[]>("hsp3-analyzer-mini/writeReferences", {
+      textDocument: c.asTextDocumentIdentifier(editor.document),
+      position: c.asPosition(editor.selection.active),
+    })
+    await commands.executeCommand(
+      "editor.action.showReferences",
+      editor.document.uri,
+      editor.selection.active,
+      (locations ?? []).map(l => client.protocol2CodeConverter.asLocation(l as any) as Location),
+    )
+  }))
+}
+
 // -----------------------------------------------
 // LSPクライアント
 // -----------------------------------------------
@@ -91,10 +161,26 @@ const newLspClient = (lspBin: string): LanguageClient => {
   const clientOptions: LanguageClientOptions = {
     documentSelector: [
       { scheme: "file", language: "hsp3" },
+      // ヘルプソースファイル
+      { scheme: "file", pattern: "**/*.hs" },
     ],
     synchronize: {
       // `workspace/didChangeWatchedFiles` のための監視対象
-      fileEvents: workspace.createFileSystemWatcher("**/*.hsp"),
+      fileEvents: [
+        workspace.createFileSystemWatcher("**/*.hsp"),
+        workspace.createFileSystemWatcher("**/*.as"),
+        workspace.createFileSystemWatcher("**/*.hs"),
+      ],
+    },
+    middleware: {
+      executeCommand: async (command, args, next) => {
+        // スクリプトの実行はサーバーではなく拡張機能が行う
+        if (command === RUN_SCRIPT_COMMAND) {
+          await runScript(hsp3Root, args[0] as string)
+          return
+        }
+        return await next(command, args)
+      },
     },
   }
 
@@ -158,6 +244,8 @@ const dev = (context: ExtensionContext): void => {
 
   const client = newLspClient(lspBackupBin)
   context.subscriptions.push({ dispose: () => client.stop() })
+  registerShowReferencesCommand(context, client)
+  registerShowWriteReferencesCommand(context, client)
 
   const waitClientStateChange = () => new Promise<void>(resolve => {
     const h = client.onDidChangeState(() => {
@@ -258,6 +346,8 @@ export const activate = async (context: ExtensionContext): Promise<void> => {
   const lspBin = getLspBin(context)
   const client = newLspClient(lspBin)
   sClient = client
+  registerShowReferencesCommand(context, client)
+  registerShowWriteReferencesCommand(context, client)
   await client.start()
 }
 